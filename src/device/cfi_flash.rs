@@ -0,0 +1,238 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use crate::emu_env::EmuEnv;
+
+// CFI parallel flash (Intel/Sharp command set, 32-bit bank width)
+pub const VIRT_FLASH0_BASE: u64 = 0x2000_0000;
+pub const VIRT_FLASH1_BASE: u64 = 0x2200_0000;
+pub const VIRT_FLASH_SIZE : u64 = 0x0200_0000;
+
+const FLASH_BANK_WIDTH: u64 = 4;
+const FLASH_SECTOR_SIZE: u64 = 0x4_0000;
+const FLASH_WRITE_BUFFER_LOG2: u8 = 6;
+
+const CMD_READ_ARRAY     : u8 = 0xff;
+const CMD_READ_ID        : u8 = 0x90;
+const CMD_READ_QUERY     : u8 = 0x98;
+const CMD_READ_STATUS    : u8 = 0x70;
+const CMD_CLEAR_STATUS   : u8 = 0x50;
+const CMD_PROGRAM        : u8 = 0x40;
+const CMD_PROGRAM_ALT    : u8 = 0x10;
+const CMD_BLOCK_ERASE    : u8 = 0x20;
+const CMD_WRITE_BUFFER   : u8 = 0xe8;
+const CMD_CONFIRM        : u8 = 0xd0;
+const CMD_LOCK_SETUP     : u8 = 0x60;
+
+const STATUS_READY       : u8 = 0x80;
+const STATUS_ERASE_ERROR : u8 = 0x20;
+const STATUS_PROGRAM_ERROR: u8 = 0x10;
+
+const MANUFACTURER_ID: u8 = 0x89;  // Intel
+const DEVICE_ID      : u8 = 0x18;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FlashMode {
+    ReadArray,
+    ReadId,
+    ReadQuery,
+    ReadStatus,
+    Program,
+    Erase,
+    WriteBufferCount,
+    WriteBufferData(u64),   // remaining words
+    WriteBufferConfirm,
+    LockSetup,
+}
+
+pub struct CfiFlash {
+    m_file: File,
+    m_data: Vec<u8>,
+    m_mode: FlashMode,
+    m_status: u8,
+    m_write_buffer: Vec<(u64, u32)>,
+}
+
+impl CfiFlash {
+    // Contents beyond the end of file are treated as erased.
+    pub fn new(filename: &str) -> CfiFlash {
+        let mut data = vec![0xff; VIRT_FLASH_SIZE as usize];
+        let mut file = match OpenOptions::new().read(true).write(true).create(true).open(filename) {
+            Ok(file) => file,
+            Err(error) => panic!("There was a problem opening the flash image: {:?}, {:}", error, filename),
+        };
+        let mut contents = Vec::new();
+        let _ = file.read_to_end(&mut contents);
+        let len = contents.len().min(data.len());
+        data[..len].copy_from_slice(&contents[..len]);
+        let mut flash = CfiFlash {
+            m_file: file,
+            m_data: data,
+            m_mode: FlashMode::ReadArray,
+            m_status: STATUS_READY,
+            m_write_buffer: Vec::new(),
+        };
+        // Extend short image with erased contents so that it persists as is.
+        if (len as u64) < VIRT_FLASH_SIZE {
+            flash.sync(len as u64, VIRT_FLASH_SIZE - len as u64);
+        }
+        flash
+    }
+
+    // Byte range of an access, clamped to the end of flash.
+    fn range(&self, offset: u64, len: u64) -> Range<usize> {
+        let end = self.m_data.len() as u64;
+        offset.min(end) as usize..(offset + len).min(end) as usize
+    }
+
+    // Reflect modified region to the backing file
+    fn sync(&mut self, offset: u64, len: u64) {
+        let range = self.range(offset, len);
+        if self.m_file.seek(SeekFrom::Start(offset)).is_err() || self.m_file.write_all(&self.m_data[range]).is_err() {
+            self.m_status |= STATUS_PROGRAM_ERROR;
+        }
+    }
+
+    fn cfi_query(index: u64) -> u8 {
+        let num_blocks = (VIRT_FLASH_SIZE / FLASH_SECTOR_SIZE - 1) as u16;
+        let block_size = (FLASH_SECTOR_SIZE / 256) as u16;
+        match index {
+            0x10 => b'Q',
+            0x11 => b'R',
+            0x12 => b'Y',
+            0x13 => 0x01,       // Primary command set : Intel
+            0x15 => 0x31,       // Primary extended table address
+            0x1b => 0x45,       // Vcc min
+            0x1c => 0x55,       // Vcc max
+            0x1f => 0x07,       // Typical word program timeout 2^n us
+            0x20 => 0x07,       // Typical buffer write timeout 2^n us
+            0x21 => 0x0a,       // Typical block erase timeout 2^n ms
+            0x23 => 0x04,
+            0x24 => 0x04,
+            0x25 => 0x04,
+            0x27 => VIRT_FLASH_SIZE.trailing_zeros() as u8,
+            0x28 => 0x03,       // Interface : x32
+            0x2a => FLASH_WRITE_BUFFER_LOG2,
+            0x2c => 0x01,       // Number of erase block regions
+            0x2d => num_blocks as u8,
+            0x2e => (num_blocks >> 8) as u8,
+            0x2f => block_size as u8,
+            0x30 => (block_size >> 8) as u8,
+            0x31 => b'P',
+            0x32 => b'R',
+            0x33 => b'I',
+            0x34 => b'1',
+            0x35 => b'0',
+            _ => 0,
+        }
+    }
+
+    fn read(&mut self, offset: u64, size: usize) -> u64 {
+        match self.m_mode {
+            FlashMode::ReadArray => {
+                let mut data = 0;
+                for (i, b) in self.m_data[self.range(offset, size as u64)].iter().enumerate() {
+                    data |= (*b as u64) << (i * 8);
+                }
+                data
+            }
+            FlashMode::ReadId => match offset / FLASH_BANK_WIDTH {
+                0 => MANUFACTURER_ID as u64,
+                1 => DEVICE_ID as u64,
+                _ => 0,
+            },
+            FlashMode::ReadQuery => Self::cfi_query(offset / FLASH_BANK_WIDTH) as u64,
+            _ => self.m_status as u64,
+        }
+    }
+
+    fn write(&mut self, offset: u64, size: usize, data: u64) {
+        let cmd = data as u8;
+        match self.m_mode {
+            FlashMode::Program => {
+                // Programming can only clear bits
+                let range = self.range(offset, size as u64);
+                for (i, b) in self.m_data[range].iter_mut().enumerate() {
+                    *b &= (data >> (i * 8)) as u8;
+                }
+                self.sync(offset, size as u64);
+                self.m_mode = FlashMode::ReadStatus;
+            }
+            FlashMode::Erase => {
+                if cmd == CMD_CONFIRM {
+                    let start = offset & !(FLASH_SECTOR_SIZE - 1);
+                    for b in &mut self.m_data[start as usize..(start + FLASH_SECTOR_SIZE) as usize] {
+                        *b = 0xff;
+                    }
+                    self.sync(start, FLASH_SECTOR_SIZE);
+                } else {
+                    self.m_status |= STATUS_ERASE_ERROR;
+                }
+                self.m_mode = FlashMode::ReadStatus;
+            }
+            FlashMode::WriteBufferCount => {
+                let words = (data & 0xffff) + 1;
+                self.m_write_buffer.clear();
+                self.m_mode = FlashMode::WriteBufferData(words);
+            }
+            FlashMode::WriteBufferData(remaining) => {
+                self.m_write_buffer.push((offset, data as u32));
+                self.m_mode = if remaining > 1 {
+                    FlashMode::WriteBufferData(remaining - 1)
+                } else {
+                    FlashMode::WriteBufferConfirm
+                };
+            }
+            FlashMode::WriteBufferConfirm => {
+                if cmd == CMD_CONFIRM {
+                    let buffer = std::mem::take(&mut self.m_write_buffer);
+                    for (addr, word) in buffer {
+                        let range = self.range(addr, FLASH_BANK_WIDTH);
+                        for (i, b) in self.m_data[range].iter_mut().enumerate() {
+                            *b &= (word >> (i * 8)) as u8;
+                        }
+                        self.sync(addr, FLASH_BANK_WIDTH);
+                    }
+                } else {
+                    self.m_status |= STATUS_PROGRAM_ERROR;
+                }
+                self.m_mode = FlashMode::ReadStatus;
+            }
+            FlashMode::LockSetup => {
+                // Lock / Unlock : blocks are always unlocked
+                self.m_mode = FlashMode::ReadStatus;
+            }
+            _ => {
+                self.m_mode = match cmd {
+                    CMD_READ_ARRAY   => FlashMode::ReadArray,
+                    CMD_READ_ID      => FlashMode::ReadId,
+                    CMD_READ_QUERY   => FlashMode::ReadQuery,
+                    CMD_READ_STATUS  => FlashMode::ReadStatus,
+                    CMD_CLEAR_STATUS => { self.m_status = STATUS_READY; self.m_mode },
+                    CMD_PROGRAM | CMD_PROGRAM_ALT => FlashMode::Program,
+                    CMD_BLOCK_ERASE  => FlashMode::Erase,
+                    CMD_WRITE_BUFFER => FlashMode::WriteBufferCount,
+                    CMD_LOCK_SETUP   => FlashMode::LockSetup,
+                    _ => FlashMode::ReadArray,
+                };
+            }
+        }
+    }
+}
+
+impl EmuEnv {
+    // Banks without backing file are not populated.
+    pub fn cfi_flash_read(&mut self, bank: usize, offset: u64, size: usize) -> u64 {
+        match &mut self.m_flash[bank] {
+            Some(flash) => flash.read(offset, size),
+            None => 0,
+        }
+    }
+
+    pub fn cfi_flash_write(&mut self, bank: usize, offset: u64, size: usize, data: u64) {
+        if let Some(flash) = &mut self.m_flash[bank] {
+            flash.write(offset, size, data);
+        }
+    }
+}
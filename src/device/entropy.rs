@@ -0,0 +1,49 @@
+use std::fs::File;
+use std::io::Read;
+
+use crate::emu_env::RngBackend;
+
+// Entropy shared by virtio-rng and the Zkr seed CSR.
+pub struct EntropySource {
+    m_urandom: Option<File>,
+    m_state: u64,   // PRNG state for seeded mode
+}
+
+impl EntropySource {
+    pub fn new(backend: RngBackend, seed: u64) -> EntropySource {
+        let urandom = match backend {
+            RngBackend::Urandom => match File::open("/dev/urandom") {
+                Ok(file) => Some(file),
+                Err(error) => panic!("There was a problem opening /dev/urandom: {:?}", error),
+            },
+            RngBackend::Seeded => None,
+        };
+        EntropySource {
+            m_urandom: urandom,
+            m_state: seed,
+        }
+    }
+
+    // SplitMix64 : deterministic for a given seed, so that runs are reproducible.
+    fn next_u64(&mut self) -> u64 {
+        self.m_state = self.m_state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.m_state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Returns false when the host entropy source could not be read.
+    pub fn fill(&mut self, data: &mut [u8]) -> bool {
+        match &mut self.m_urandom {
+            Some(file) => file.read_exact(data).is_ok(),
+            None => {
+                for chunk in data.chunks_mut(8) {
+                    let rand = self.next_u64().to_le_bytes();
+                    chunk.copy_from_slice(&rand[..chunk.len()]);
+                }
+                true
+            }
+        }
+    }
+}
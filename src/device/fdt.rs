@@ -0,0 +1,241 @@
+use crate::device::cfi_flash::{VIRT_FLASH0_BASE, VIRT_FLASH1_BASE, VIRT_FLASH_SIZE};
+use crate::device::goldfish_rtc::{GOLDFISH_RTC_BASE, GOLDFISH_RTC_IRQ, GOLDFISH_RTC_SIZE};
+use crate::device::plic::{PLIC_BASE, PLIC_NUM_SOURCES, PLIC_SIZE};
+use crate::device::sifive_test::{SIFIVE_TEST_BASE, SIFIVE_TEST_SIZE};
+use crate::device::virtio_mmio::{VIRTIO_MMIO_NUM_SLOTS, VIRTIO_MMIO_SLOT_SIZE};
+use crate::device::{MemMapDevice, VIRT_MEMMAP, VIRT_VIRTIO_BASE, VIRT_VIRTIO_IRQ};
+use crate::emu_env::EmuEnv;
+
+// Flattened device tree of virt machine, placed at the top of DRAM.
+// At boot a0 holds the hart ID and a1 the address of the device tree, as on QEMU.
+pub const VIRT_FDT_BASE: u64 = 0x8007_e000;
+const VIRT_FDT_MAX_SIZE: usize = 0x2000;
+
+const FDT_MAGIC     : u32 = 0xd00d_feed;
+const FDT_VERSION   : u32 = 17;
+const FDT_LAST_COMP : u32 = 16;
+const FDT_HEADER_SIZE: usize = 40;
+const FDT_RSVMAP_SIZE: usize = 16;   // terminating entry only
+
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE  : u32 = 2;
+const FDT_PROP      : u32 = 3;
+const FDT_END       : u32 = 9;
+
+const PHANDLE_CPU_INTC: u32 = 1;
+const PHANDLE_PLIC    : u32 = 2;
+
+// Hart local interrupt numbers used by PLIC contexts
+const IRQ_M_EXT: u32 = 11;
+const IRQ_S_EXT: u32 = 9;
+
+// Structure block and strings block of the device tree. Values are big endian.
+struct FdtWriter {
+    m_struct: Vec<u8>,
+    m_strings: Vec<u8>,
+}
+
+impl FdtWriter {
+    fn new() -> FdtWriter {
+        FdtWriter { m_struct: Vec::new(), m_strings: Vec::new() }
+    }
+
+    fn put_u32(&mut self, data: u32) { self.m_struct.extend_from_slice(&data.to_be_bytes()); }
+
+    fn put_bytes(&mut self, data: &[u8]) {
+        self.m_struct.extend_from_slice(data);
+        while self.m_struct.len() % 4 != 0 {
+            self.m_struct.push(0);
+        }
+    }
+
+    // Offset of the property name in the strings block, shared by properties with the same name.
+    fn name_offset(&mut self, name: &str) -> u32 {
+        let mut offset = 0;
+        while offset < self.m_strings.len() {
+            let len = self.m_strings[offset..].iter().position(|b| *b == 0).unwrap();
+            if &self.m_strings[offset..offset + len] == name.as_bytes() {
+                return offset as u32;
+            }
+            offset += len + 1;
+        }
+        let offset = self.m_strings.len();
+        self.m_strings.extend_from_slice(name.as_bytes());
+        self.m_strings.push(0);
+        offset as u32
+    }
+
+    fn begin_node(&mut self, name: &str) {
+        self.put_u32(FDT_BEGIN_NODE);
+        let mut data = name.as_bytes().to_vec();
+        data.push(0);
+        self.put_bytes(&data);
+    }
+
+    fn end_node(&mut self) { self.put_u32(FDT_END_NODE); }
+
+    fn prop(&mut self, name: &str, value: &[u8]) {
+        let name_offset = self.name_offset(name);
+        self.put_u32(FDT_PROP);
+        self.put_u32(value.len() as u32);
+        self.put_u32(name_offset);
+        self.put_bytes(value);
+    }
+
+    fn prop_empty(&mut self, name: &str) { self.prop(name, &[]); }
+
+    fn prop_cells(&mut self, name: &str, cells: &[u32]) {
+        let value: Vec<u8> = cells.iter().flat_map(|c| c.to_be_bytes()).collect();
+        self.prop(name, &value);
+    }
+
+    fn prop_u32(&mut self, name: &str, data: u32) { self.prop_cells(name, &[data]); }
+
+    fn prop_strs(&mut self, name: &str, strs: &[&str]) {
+        let mut value = Vec::new();
+        for s in strs {
+            value.extend_from_slice(s.as_bytes());
+            value.push(0);
+        }
+        self.prop(name, &value);
+    }
+
+    fn prop_str(&mut self, name: &str, data: &str) { self.prop_strs(name, &[data]); }
+
+    // reg with #address-cells = 2 and #size-cells = 2
+    fn prop_reg(&mut self, regions: &[(u64, u64)]) {
+        let cells: Vec<u32> = regions.iter().flat_map(|(base, size)| {
+            [(base >> 32) as u32, *base as u32, (size >> 32) as u32, *size as u32]
+        }).collect();
+        self.prop_cells("reg", &cells);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.put_u32(FDT_END);
+        let off_rsvmap = FDT_HEADER_SIZE;
+        let off_struct = off_rsvmap + FDT_RSVMAP_SIZE;
+        let off_strings = off_struct + self.m_struct.len();
+        let total = off_strings + self.m_strings.len();
+
+        let mut fdt = Vec::with_capacity(total);
+        for data in [FDT_MAGIC, total as u32, off_struct as u32, off_strings as u32, off_rsvmap as u32,
+                     FDT_VERSION, FDT_LAST_COMP, 0, self.m_strings.len() as u32, self.m_struct.len() as u32] {
+            fdt.extend_from_slice(&data.to_be_bytes());
+        }
+        fdt.extend_from_slice(&[0; FDT_RSVMAP_SIZE]);
+        fdt.extend_from_slice(&self.m_struct);
+        fdt.extend_from_slice(&self.m_strings);
+        fdt
+    }
+}
+
+impl EmuEnv {
+    fn virt_isa_string(&self) -> String {
+        let mut isa = "rv64imafdcv".to_string();
+        if self.m_arg_config.hypervisor {
+            isa.push('h');
+        }
+        isa + "_zicbom_zicboz_zicbop"
+    }
+
+    pub fn virt_fdt(&self) -> Vec<u8> {
+        let cbo_block_size = self.m_arg_config.cbo_block_size as u32;
+        let mut fdt = FdtWriter::new();
+
+        fdt.begin_node("");
+        fdt.prop_u32("#address-cells", 2);
+        fdt.prop_u32("#size-cells", 2);
+        fdt.prop_str("compatible", "riscv-virtio");
+        fdt.prop_str("model", "riscv-virtio,dydra");
+
+        fdt.begin_node("chosen");
+        fdt.end_node();
+
+        for entry in VIRT_MEMMAP.iter().filter(|e| e.dev == MemMapDevice::Dram) {
+            fdt.begin_node(&format!("memory@{:x}", entry.base));
+            fdt.prop_str("device_type", "memory");
+            fdt.prop_reg(&[(entry.base, entry.size)]);
+            fdt.end_node();
+        }
+
+        fdt.begin_node("cpus");
+        fdt.prop_u32("#address-cells", 1);
+        fdt.prop_u32("#size-cells", 0);
+        fdt.begin_node("cpu@0");
+        fdt.prop_str("device_type", "cpu");
+        fdt.prop_u32("reg", 0);
+        fdt.prop_str("status", "okay");
+        fdt.prop_str("compatible", "riscv");
+        fdt.prop_str("riscv,isa", &self.virt_isa_string());
+        fdt.prop_str("mmu-type", "riscv,sv57");
+        fdt.prop_u32("riscv,cbom-block-size", cbo_block_size);
+        fdt.prop_u32("riscv,cboz-block-size", cbo_block_size);
+        fdt.begin_node("interrupt-controller");
+        fdt.prop_u32("#interrupt-cells", 1);
+        fdt.prop_empty("interrupt-controller");
+        fdt.prop_str("compatible", "riscv,cpu-intc");
+        fdt.prop_u32("phandle", PHANDLE_CPU_INTC);
+        fdt.end_node();
+        fdt.end_node();
+        fdt.end_node();
+
+        fdt.begin_node("soc");
+        fdt.prop_u32("#address-cells", 2);
+        fdt.prop_u32("#size-cells", 2);
+        fdt.prop_str("compatible", "simple-bus");
+        fdt.prop_empty("ranges");
+
+        fdt.begin_node(&format!("test@{:x}", SIFIVE_TEST_BASE));
+        fdt.prop_strs("compatible", &["sifive,test1", "sifive,test0", "syscon"]);
+        fdt.prop_reg(&[(SIFIVE_TEST_BASE, SIFIVE_TEST_SIZE)]);
+        fdt.end_node();
+
+        fdt.begin_node(&format!("rtc@{:x}", GOLDFISH_RTC_BASE));
+        fdt.prop_str("compatible", "google,goldfish-rtc");
+        fdt.prop_reg(&[(GOLDFISH_RTC_BASE, GOLDFISH_RTC_SIZE)]);
+        fdt.prop_u32("interrupts", GOLDFISH_RTC_IRQ as u32);
+        fdt.prop_u32("interrupt-parent", PHANDLE_PLIC);
+        fdt.end_node();
+
+        fdt.begin_node(&format!("plic@{:x}", PLIC_BASE));
+        fdt.prop_strs("compatible", &["sifive,plic-1.0.0", "riscv,plic0"]);
+        fdt.prop_reg(&[(PLIC_BASE, PLIC_SIZE)]);
+        fdt.prop_u32("#interrupt-cells", 1);
+        fdt.prop_empty("interrupt-controller");
+        fdt.prop_cells("interrupts-extended", &[PHANDLE_CPU_INTC, IRQ_M_EXT, PHANDLE_CPU_INTC, IRQ_S_EXT]);
+        fdt.prop_u32("riscv,ndev", PLIC_NUM_SOURCES as u32 - 1);
+        fdt.prop_u32("phandle", PHANDLE_PLIC);
+        fdt.end_node();
+
+        for slot in 0..VIRTIO_MMIO_NUM_SLOTS {
+            let base = VIRT_VIRTIO_BASE + slot as u64 * VIRTIO_MMIO_SLOT_SIZE;
+            fdt.begin_node(&format!("virtio_mmio@{:x}", base));
+            fdt.prop_str("compatible", "virtio,mmio");
+            fdt.prop_reg(&[(base, VIRTIO_MMIO_SLOT_SIZE)]);
+            fdt.prop_u32("interrupts", (VIRT_VIRTIO_IRQ + slot) as u32);
+            fdt.prop_u32("interrupt-parent", PHANDLE_PLIC);
+            fdt.end_node();
+        }
+
+        fdt.begin_node(&format!("flash@{:x}", VIRT_FLASH0_BASE));
+        fdt.prop_str("compatible", "cfi-flash");
+        fdt.prop_reg(&[(VIRT_FLASH0_BASE, VIRT_FLASH_SIZE), (VIRT_FLASH1_BASE, VIRT_FLASH_SIZE)]);
+        fdt.prop_u32("bank-width", 4);
+        fdt.end_node();
+
+        fdt.end_node();
+        fdt.end_node();
+        fdt.finish()
+    }
+
+    // Copies the device tree to DRAM and sets the boot arguments. Called after the program is loaded.
+    pub fn load_virt_fdt(&mut self) {
+        let fdt = self.virt_fdt();
+        assert!(fdt.len() <= VIRT_FDT_MAX_SIZE);
+        for (offset, data) in fdt.iter().enumerate() {
+            self.write_mem_1byte(VIRT_FDT_BASE + offset as u64, *data);
+        }
+        self.m_iregs[10] = 0;
+        self.m_iregs[11] = VIRT_FDT_BASE;
+    }
+}
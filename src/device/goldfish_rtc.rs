@@ -0,0 +1,118 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::emu_env::EmuEnv;
+
+// Goldfish RTC : time in nanoseconds since the epoch
+pub const GOLDFISH_RTC_BASE: u64 = 0x10_1000;
+pub const GOLDFISH_RTC_SIZE: u64 = 0x1000;
+pub const GOLDFISH_RTC_IRQ: usize = 11;
+
+const RTC_TIME_LOW       : u64 = 0x00;
+const RTC_TIME_HIGH      : u64 = 0x04;
+const RTC_ALARM_LOW      : u64 = 0x08;
+const RTC_ALARM_HIGH     : u64 = 0x0c;
+const RTC_IRQ_ENABLED    : u64 = 0x10;
+const RTC_CLEAR_ALARM    : u64 = 0x14;
+const RTC_ALARM_STATUS   : u64 = 0x18;
+const RTC_CLEAR_INTERRUPT: u64 = 0x1c;
+
+pub struct GoldfishRtc {
+    m_fixed_epoch: Option<u64>,   // seconds. Time does not advance when specified.
+    m_offset: i64,                // guest written time - source time
+    m_time_high: u32,             // latched at TIME_LOW read
+    m_alarm: Option<u64>,
+    m_irq_enabled: bool,
+    m_irq_pending: bool,
+}
+
+impl GoldfishRtc {
+    pub fn new(fixed_epoch: Option<u64>) -> GoldfishRtc {
+        GoldfishRtc {
+            m_fixed_epoch: fixed_epoch,
+            m_offset: 0,
+            m_time_high: 0,
+            m_alarm: None,
+            m_irq_enabled: false,
+            m_irq_pending: false,
+        }
+    }
+
+    fn source_time(&self) -> u64 {
+        match self.m_fixed_epoch {
+            Some(epoch) => epoch * 1_000_000_000,
+            None => SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0),
+        }
+    }
+
+    fn time(&self) -> u64 {
+        self.source_time().wrapping_add(self.m_offset as u64)
+    }
+
+    fn read(&mut self, offset: u64) -> u32 {
+        match offset {
+            RTC_TIME_LOW => {
+                let time = self.time();
+                self.m_time_high = (time >> 32) as u32;
+                time as u32
+            }
+            RTC_TIME_HIGH => self.m_time_high,
+            RTC_ALARM_LOW => self.m_alarm.map(|a| a as u32).unwrap_or(0),
+            RTC_ALARM_HIGH => self.m_alarm.map(|a| (a >> 32) as u32).unwrap_or(0),
+            RTC_IRQ_ENABLED => self.m_irq_enabled as u32,
+            RTC_ALARM_STATUS => self.m_alarm.is_some() as u32,
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, offset: u64, data: u32) {
+        match offset {
+            RTC_TIME_LOW => {
+                let time = (self.time() & !0xffff_ffff) | data as u64;
+                self.m_offset = time.wrapping_sub(self.source_time()) as i64;
+            }
+            RTC_TIME_HIGH => {
+                let time = (self.time() & 0xffff_ffff) | ((data as u64) << 32);
+                self.m_offset = time.wrapping_sub(self.source_time()) as i64;
+            }
+            RTC_ALARM_LOW => {
+                // Writing ALARM_LOW arms the alarm with latched ALARM_HIGH
+                self.m_alarm = Some(((self.m_time_high as u64) << 32) | data as u64);
+            }
+            RTC_ALARM_HIGH => { self.m_time_high = data; }
+            RTC_IRQ_ENABLED => { self.m_irq_enabled = data & 1 != 0; }
+            RTC_CLEAR_ALARM => { self.m_alarm = None; }
+            RTC_CLEAR_INTERRUPT => { self.m_irq_pending = false; }
+            _ => {}
+        }
+    }
+
+    fn poll(&mut self) {
+        if let Some(alarm) = self.m_alarm {
+            if self.time() >= alarm {
+                self.m_alarm = None;
+                self.m_irq_pending = true;
+            }
+        }
+    }
+}
+
+impl EmuEnv {
+    pub fn goldfish_rtc_read(&mut self, offset: u64, _size: usize) -> u64 {
+        return self.m_rtc.read(offset) as u64;
+    }
+
+    pub fn goldfish_rtc_write(&mut self, offset: u64, _size: usize, data: u64) {
+        self.m_rtc.write(offset, data as u32);
+        self.goldfish_rtc_update_irq();
+    }
+
+    pub fn goldfish_rtc_poll(&mut self) {
+        self.m_rtc.poll();
+        self.goldfish_rtc_update_irq();
+    }
+
+    fn goldfish_rtc_update_irq(&mut self) {
+        let level = self.m_rtc.m_irq_enabled && self.m_rtc.m_irq_pending;
+        self.plic_set_irq(GOLDFISH_RTC_IRQ, level);
+    }
+}
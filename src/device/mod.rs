@@ -0,0 +1,134 @@
+pub mod cfi_flash;
+pub mod entropy;
+pub mod fdt;
+pub mod goldfish_rtc;
+pub mod plic;
+pub mod virtio_9p;
+pub mod sifive_test;
+pub mod virtio_blk;
+pub mod virtio_console;
+pub mod virtio_mmio;
+pub mod virtio_rng;
+
+use crate::emu_env::{EmuEnv, MachineEnum};
+use crate::device::cfi_flash::{VIRT_FLASH0_BASE, VIRT_FLASH1_BASE, VIRT_FLASH_SIZE};
+use crate::device::goldfish_rtc::{GOLDFISH_RTC_BASE, GOLDFISH_RTC_SIZE};
+use crate::device::plic::{PLIC_BASE, PLIC_SIZE};
+use crate::device::sifive_test::{SIFIVE_TEST_BASE, SIFIVE_TEST_SIZE};
+use crate::device::virtio_mmio::{VIRTIO_MMIO_NUM_SLOTS, VIRTIO_MMIO_SLOT_SIZE};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MemMapDevice {
+    Test,
+    Rtc,
+    Plic,
+    VirtioMmio,
+    Flash(usize),
+    Dram,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct MemMapEntry {
+    pub dev: MemMapDevice,
+    pub base: u64,
+    pub size: u64,
+}
+
+// virtio-mmio slots on virt : IRQ = VIRT_VIRTIO_IRQ + slot
+pub const VIRT_VIRTIO_BASE: u64 = 0x1000_1000;
+pub const VIRT_VIRTIO_IRQ: usize = 1;
+
+// Slot assignment of virtio-mmio devices
+pub const VIRTIO_SLOT_BLK: usize = 0;
+pub const VIRTIO_SLOT_CONSOLE: usize = 1;
+pub const VIRTIO_SLOT_RNG: usize = 2;
+pub const VIRTIO_SLOT_9P: usize = 3;
+
+pub const VIRT_MEMMAP: [MemMapEntry; 7] = [
+    MemMapEntry { dev: MemMapDevice::Test,       base: SIFIVE_TEST_BASE, size: SIFIVE_TEST_SIZE },
+    MemMapEntry { dev: MemMapDevice::Rtc,        base: GOLDFISH_RTC_BASE, size: GOLDFISH_RTC_SIZE },
+    MemMapEntry { dev: MemMapDevice::Plic,       base: PLIC_BASE,        size: PLIC_SIZE },
+    MemMapEntry { dev: MemMapDevice::VirtioMmio, base: VIRT_VIRTIO_BASE, size: VIRTIO_MMIO_SLOT_SIZE * VIRTIO_MMIO_NUM_SLOTS as u64 },
+    MemMapEntry { dev: MemMapDevice::Flash(0),   base: VIRT_FLASH0_BASE, size: VIRT_FLASH_SIZE },
+    MemMapEntry { dev: MemMapDevice::Flash(1),   base: VIRT_FLASH1_BASE, size: VIRT_FLASH_SIZE },
+    MemMapEntry { dev: MemMapDevice::Dram,       base: 0x8000_0000,      size: 0x8_0000 },
+];
+
+pub const SIFIVE_U_MEMMAP: [MemMapEntry; 3] = [
+    MemMapEntry { dev: MemMapDevice::Test,       base: SIFIVE_TEST_BASE, size: SIFIVE_TEST_SIZE },
+    MemMapEntry { dev: MemMapDevice::Plic,       base: PLIC_BASE,        size: PLIC_SIZE },
+    MemMapEntry { dev: MemMapDevice::Dram,       base: 0x8000_0000,      size: 0x8_0000 },
+];
+
+pub fn machine_memmap(machine: MachineEnum) -> &'static [MemMapEntry] {
+    match machine {
+        MachineEnum::RiscvVirt    => &VIRT_MEMMAP,
+        MachineEnum::RiscvSiFiveU => &SIFIVE_U_MEMMAP,
+    }
+}
+
+fn find_memmap_entry(machine: MachineEnum, guest_phy_addr: u64) -> Option<MemMapEntry> {
+    machine_memmap(machine).iter().find(|e| e.base <= guest_phy_addr && guest_phy_addr < e.base + e.size).copied()
+}
+
+impl EmuEnv {
+    // Memory mapped device access.
+    // Returns None when the physical address does not belong to any device,
+    // so that caller falls back to the guest memory access.
+    pub fn mmio_read(&mut self, guest_phy_addr: u64, size: usize) -> Option<u64> {
+        let entry = find_memmap_entry(self.m_arg_config.machine, guest_phy_addr)?;
+        let offset = guest_phy_addr - entry.base;
+        match entry.dev {
+            MemMapDevice::Test => Some(self.sifive_test_read(offset, size)),
+            MemMapDevice::Rtc  => Some(self.goldfish_rtc_read(offset, size)),
+            MemMapDevice::Plic => Some(self.plic_read(offset, size)),
+            MemMapDevice::VirtioMmio => {
+                let slot = (offset / VIRTIO_MMIO_SLOT_SIZE) as usize;
+                Some(self.virtio_mmio_read(slot, offset % VIRTIO_MMIO_SLOT_SIZE, size))
+            }
+            MemMapDevice::Flash(bank) => Some(self.cfi_flash_read(bank, offset, size)),
+            MemMapDevice::Dram => None,
+        }
+    }
+
+    pub fn mmio_write(&mut self, guest_phy_addr: u64, size: usize, data: u64) -> bool {
+        let entry = match find_memmap_entry(self.m_arg_config.machine, guest_phy_addr) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let offset = guest_phy_addr - entry.base;
+        match entry.dev {
+            MemMapDevice::Test => self.sifive_test_write(offset, size, data),
+            MemMapDevice::Rtc  => self.goldfish_rtc_write(offset, size, data),
+            MemMapDevice::Plic => self.plic_write(offset, size, data),
+            MemMapDevice::VirtioMmio => {
+                let slot = (offset / VIRTIO_MMIO_SLOT_SIZE) as usize;
+                self.virtio_mmio_write(slot, offset % VIRTIO_MMIO_SLOT_SIZE, size, data);
+            }
+            MemMapDevice::Flash(bank) => self.cfi_flash_write(bank, offset, size, data),
+            MemMapDevice::Dram => return false,
+        }
+        return true;
+    }
+
+    pub fn is_dram_addr(&self, guest_phy_addr: u64, size: u64) -> bool {
+        match find_memmap_entry(self.m_arg_config.machine, guest_phy_addr) {
+            Some(entry) => entry.dev == MemMapDevice::Dram && guest_phy_addr + size <= entry.base + entry.size,
+            None => false,
+        }
+    }
+
+    // Host side events (console input, RTC alarm etc.)
+    pub fn poll_devices(&mut self) {
+        if self.m_arg_config.machine == MachineEnum::RiscvVirt {
+            self.virtio_poll();
+            self.goldfish_rtc_poll();
+        }
+    }
+
+    pub fn dump_memmap(&self) {
+        for entry in machine_memmap(self.m_arg_config.machine) {
+            eprintln!("{:<12}: {:08x} - {:08x}", format!("{:?}", entry.dev), entry.base, entry.base + entry.size - 1);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::emu_env::EmuEnv;
+
+// Platform-Level Interrupt Controller
+pub const PLIC_BASE: u64 = 0x0c00_0000;
+pub const PLIC_SIZE: u64 = 0x0060_0000;
+
+pub const PLIC_NUM_SOURCES: usize = 64;
+// Context 0 : Hart0 M-mode, Context 1 : Hart0 S-mode
+const PLIC_NUM_CONTEXTS: usize = 2;
+
+const PLIC_PRIORITY_BASE : u64 = 0x00_0000;
+const PLIC_PENDING_BASE  : u64 = 0x00_1000;
+const PLIC_ENABLE_BASE   : u64 = 0x00_2000;
+const PLIC_ENABLE_STRIDE : u64 = 0x80;
+const PLIC_CONTEXT_BASE  : u64 = 0x20_0000;
+const PLIC_CONTEXT_STRIDE: u64 = 0x1000;
+
+const MIP_SEIP: i64 = 1 << 9;
+const MIP_MEIP: i64 = 1 << 11;
+
+pub struct Plic {
+    m_priority: [u32; PLIC_NUM_SOURCES],
+    m_pending: u64,
+    m_claimed: u64,
+    m_level: u64,       // Interrupt lines asserted by the devices
+    m_enable: [u64; PLIC_NUM_CONTEXTS],
+    m_threshold: [u32; PLIC_NUM_CONTEXTS],
+}
+
+impl Plic {
+    pub fn new() -> Plic {
+        Plic {
+            m_priority: [0; PLIC_NUM_SOURCES],
+            m_pending: 0,
+            m_claimed: 0,
+            m_level: 0,
+            m_enable: [0; PLIC_NUM_CONTEXTS],
+            m_threshold: [0; PLIC_NUM_CONTEXTS],
+        }
+    }
+
+    // Highest priority pending interrupt for the context, 0 if nothing.
+    fn best_irq(&self, ctx: usize) -> u32 {
+        let mut best_irq = 0;
+        let mut best_prio = self.m_threshold[ctx];
+        for irq in 1..PLIC_NUM_SOURCES {
+            let bit = 1u64 << irq;
+            if self.m_pending & bit != 0 && self.m_claimed & bit == 0 && self.m_enable[ctx] & bit != 0 &&
+                self.m_priority[irq] > best_prio {
+                best_irq = irq as u32;
+                best_prio = self.m_priority[irq];
+            }
+        }
+        return best_irq;
+    }
+
+    fn read(&mut self, offset: u64) -> u32 {
+        match offset {
+            // Priority slots above the implemented sources read as 0 and ignore writes.
+            _ if offset < PLIC_PRIORITY_BASE + 4 * PLIC_NUM_SOURCES as u64 => self.m_priority[((offset - PLIC_PRIORITY_BASE) / 4) as usize],
+            PLIC_PENDING_BASE => self.m_pending as u32,
+            0x1004 => (self.m_pending >> 32) as u32,
+            _ if offset >= PLIC_ENABLE_BASE && offset < PLIC_ENABLE_BASE + PLIC_ENABLE_STRIDE * PLIC_NUM_CONTEXTS as u64 => {
+                let ctx = ((offset - PLIC_ENABLE_BASE) / PLIC_ENABLE_STRIDE) as usize;
+                match offset & (PLIC_ENABLE_STRIDE - 1) {
+                    0 => self.m_enable[ctx] as u32,
+                    4 => (self.m_enable[ctx] >> 32) as u32,
+                    _ => 0,
+                }
+            }
+            _ if offset >= PLIC_CONTEXT_BASE && offset < PLIC_CONTEXT_BASE + PLIC_CONTEXT_STRIDE * PLIC_NUM_CONTEXTS as u64 => {
+                let ctx = ((offset - PLIC_CONTEXT_BASE) / PLIC_CONTEXT_STRIDE) as usize;
+                match offset & (PLIC_CONTEXT_STRIDE - 1) {
+                    0 => self.m_threshold[ctx],
+                    4 => {
+                        // Claim
+                        let irq = self.best_irq(ctx);
+                        if irq != 0 {
+                            self.m_pending &= !(1u64 << irq);
+                            self.m_claimed |= 1u64 << irq;
+                        }
+                        irq
+                    }
+                    _ => 0,
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, offset: u64, data: u32) {
+        match offset {
+            _ if offset < PLIC_PRIORITY_BASE + 4 * PLIC_NUM_SOURCES as u64 => { self.m_priority[((offset - PLIC_PRIORITY_BASE) / 4) as usize] = data & 0x7; }
+            _ if offset >= PLIC_ENABLE_BASE && offset < PLIC_ENABLE_BASE + PLIC_ENABLE_STRIDE * PLIC_NUM_CONTEXTS as u64 => {
+                let ctx = ((offset - PLIC_ENABLE_BASE) / PLIC_ENABLE_STRIDE) as usize;
+                match offset & (PLIC_ENABLE_STRIDE - 1) {
+                    0 => { self.m_enable[ctx] = (self.m_enable[ctx] & !0xffff_ffff) | (data as u64 & !1); }
+                    4 => { self.m_enable[ctx] = (self.m_enable[ctx] & 0xffff_ffff) | ((data as u64) << 32); }
+                    _ => {}
+                }
+            }
+            _ if offset >= PLIC_CONTEXT_BASE && offset < PLIC_CONTEXT_BASE + PLIC_CONTEXT_STRIDE * PLIC_NUM_CONTEXTS as u64 => {
+                let ctx = ((offset - PLIC_CONTEXT_BASE) / PLIC_CONTEXT_STRIDE) as usize;
+                match offset & (PLIC_CONTEXT_STRIDE - 1) {
+                    0 => { self.m_threshold[ctx] = data & 0x7; }
+                    4 => {
+                        // Complete : the gateway forwards the source again if its line is still asserted.
+                        if (data as usize) < PLIC_NUM_SOURCES {
+                            self.m_claimed &= !(1u64 << data);
+                            self.m_pending |= self.m_level & (1u64 << data);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl EmuEnv {
+    pub fn plic_read(&mut self, offset: u64, _size: usize) -> u64 {
+        let data = self.m_plic.read(offset);
+        self.plic_update_mip();
+        return data as u64;
+    }
+
+    pub fn plic_write(&mut self, offset: u64, _size: usize, data: u64) {
+        self.m_plic.write(offset, data as u32);
+        self.plic_update_mip();
+    }
+
+    // Interrupt line from devices. Level-triggered.
+    pub fn plic_set_irq(&mut self, irq: usize, level: bool) {
+        assert!(irq != 0 && irq < PLIC_NUM_SOURCES);
+        if level {
+            self.m_plic.m_level |= 1u64 << irq;
+            self.m_plic.m_pending |= 1u64 << irq;
+        } else {
+            self.m_plic.m_level &= !(1u64 << irq);
+            self.m_plic.m_pending &= !(1u64 << irq);
+        }
+        self.plic_update_mip();
+    }
+
+    fn plic_update_mip(&mut self) {
+        let mip = &mut self.m_csr.m_mip.m_csr;
+        *mip &= !(MIP_MEIP | MIP_SEIP);
+        if self.m_plic.best_irq(0) != 0 {
+            *mip |= MIP_MEIP;
+        }
+        if self.m_plic.best_irq(1) != 0 {
+            *mip |= MIP_SEIP;
+        }
+    }
+}
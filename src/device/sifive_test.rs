@@ -0,0 +1,32 @@
+use crate::emu_env::{EmuEnv, MachineStatus};
+
+// SiFive Test Finisher (compatible = "sifive,test0" / "syscon-poweroff")
+pub const SIFIVE_TEST_BASE: u64 = 0x10_0000;
+pub const SIFIVE_TEST_SIZE: u64 = 0x1000;
+
+const FINISHER_FAIL : u64 = 0x3333;
+const FINISHER_PASS : u64 = 0x5555;
+const FINISHER_RESET: u64 = 0x7777;
+
+impl EmuEnv {
+    pub fn sifive_test_read(&mut self, _offset: u64, _size: usize) -> u64 {
+        return 0;
+    }
+
+    pub fn sifive_test_write(&mut self, offset: u64, _size: usize, data: u64) {
+        if offset != 0 {
+            return;
+        }
+        let code = ((data >> 16) & 0xffff) as u16;
+        match data & 0xffff {
+            FINISHER_FAIL  => { self.m_machine_status = MachineStatus::Fail(code); },
+            FINISHER_PASS  => { self.m_machine_status = MachineStatus::Pass; },
+            FINISHER_RESET => { self.m_machine_status = MachineStatus::Reset; },
+            _ => {
+                if self.m_arg_config.debug {
+                    eprintln!("sifive_test : unknown command {:08x}", data);
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,613 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+
+use crate::device::virtio_mmio::{virtq_read_chain, virtq_writable_len, virtq_write_chain, VirtioDevice, VirtqDesc};
+use crate::emu_env::EmuEnv;
+
+// virtio-9p : 9P2000.L server exporting a host directory
+const VIRTIO_ID_9P: u32 = 9;
+
+const VIRTIO_9P_MOUNT_TAG: u64 = 1 << 0;
+
+const P9_MAX_MSIZE: u32 = 0x2_0000;
+const P9_MIN_MSIZE: u32 = 0x1000;
+const P9_IOHDRSZ: u32 = 24;
+
+// Message types (T-message, R-message is T + 1)
+const P9_RLERROR   : u8 = 7;
+const P9_TSTATFS   : u8 = 8;
+const P9_TLOPEN    : u8 = 12;
+const P9_TLCREATE  : u8 = 14;
+const P9_TSYMLINK  : u8 = 16;
+const P9_TRENAME   : u8 = 20;
+const P9_TREADLINK : u8 = 22;
+const P9_TGETATTR  : u8 = 24;
+const P9_TSETATTR  : u8 = 26;
+const P9_TXATTRWALK: u8 = 30;
+const P9_TREADDIR  : u8 = 40;
+const P9_TFSYNC    : u8 = 50;
+const P9_TLOCK     : u8 = 52;
+const P9_TGETLOCK  : u8 = 54;
+const P9_TLINK     : u8 = 70;
+const P9_TMKDIR    : u8 = 72;
+const P9_TRENAMEAT : u8 = 74;
+const P9_TUNLINKAT : u8 = 76;
+const P9_TVERSION  : u8 = 100;
+const P9_TATTACH   : u8 = 104;
+const P9_TFLUSH    : u8 = 108;
+const P9_TWALK     : u8 = 110;
+const P9_TREAD     : u8 = 116;
+const P9_TWRITE    : u8 = 118;
+const P9_TCLUNK    : u8 = 120;
+const P9_TREMOVE   : u8 = 122;
+
+const P9_QTDIR    : u8 = 0x80;
+const P9_QTSYMLINK: u8 = 0x02;
+const P9_QTFILE   : u8 = 0x00;
+
+const P9_GETATTR_BASIC: u64 = 0x7ff;
+
+const P9_SETATTR_MODE: u32 = 1 << 0;
+const P9_SETATTR_SIZE: u32 = 1 << 3;
+
+// Linux open flags / errno used by 9P2000.L
+const L_O_ACCMODE: u32 = 0o3;
+const L_O_WRONLY : u32 = 0o1;
+const L_O_RDWR   : u32 = 0o2;
+const L_O_CREAT  : u32 = 0o100;
+const L_O_EXCL   : u32 = 0o200;
+const L_O_TRUNC  : u32 = 0o1000;
+const L_O_APPEND : u32 = 0o2000;
+
+const L_AT_REMOVEDIR: u32 = 0x200;
+
+const EIO     : u32 = 5;
+const EBADF   : u32 = 9;
+const EACCES  : u32 = 13;
+const EINVAL  : u32 = 22;
+const EROFS   : u32 = 30;
+const ENOTSUP : u32 = 95;
+
+struct P9Fid {
+    m_path: PathBuf,   // relative to the export root
+    m_file: Option<File>,
+    m_dir_entries: Option<Vec<(String, PathBuf)>>,
+}
+
+pub struct Virtio9p {
+    m_root: PathBuf,
+    m_tag: String,
+    m_read_only: bool,
+    m_msize: u32,
+    m_fids: HashMap<u32, P9Fid>,
+}
+
+// Request message reader
+struct P9Reader<'a> {
+    m_buf: &'a [u8],
+    m_pos: usize,
+}
+
+impl<'a> P9Reader<'a> {
+    fn get_bytes(&mut self, len: usize) -> Result<&'a [u8], u32> {
+        if self.m_pos + len > self.m_buf.len() {
+            return Err(EINVAL);
+        }
+        let bytes = &self.m_buf[self.m_pos..self.m_pos + len];
+        self.m_pos += len;
+        Ok(bytes)
+    }
+    fn get_u8 (&mut self) -> Result<u8 , u32> { Ok(self.get_bytes(1)?[0]) }
+    fn get_u16(&mut self) -> Result<u16, u32> { Ok(u16::from_le_bytes(self.get_bytes(2)?.try_into().unwrap())) }
+    fn get_u32(&mut self) -> Result<u32, u32> { Ok(u32::from_le_bytes(self.get_bytes(4)?.try_into().unwrap())) }
+    fn get_u64(&mut self) -> Result<u64, u32> { Ok(u64::from_le_bytes(self.get_bytes(8)?.try_into().unwrap())) }
+    fn get_str(&mut self) -> Result<String, u32> {
+        let len = self.get_u16()? as usize;
+        Ok(String::from_utf8_lossy(self.get_bytes(len)?).into_owned())
+    }
+}
+
+// Response message writer
+struct P9Writer {
+    m_buf: Vec<u8>,
+}
+
+impl P9Writer {
+    fn put_u8 (&mut self, data: u8 ) { self.m_buf.push(data); }
+    fn put_u16(&mut self, data: u16) { self.m_buf.extend_from_slice(&data.to_le_bytes()); }
+    fn put_u32(&mut self, data: u32) { self.m_buf.extend_from_slice(&data.to_le_bytes()); }
+    fn put_u64(&mut self, data: u64) { self.m_buf.extend_from_slice(&data.to_le_bytes()); }
+    fn put_str(&mut self, data: &str) {
+        self.put_u16(data.len() as u16);
+        self.m_buf.extend_from_slice(data.as_bytes());
+    }
+    fn put_qid(&mut self, meta: &fs::Metadata) {
+        let qid_type = if meta.is_dir() {
+            P9_QTDIR
+        } else if meta.file_type().is_symlink() {
+            P9_QTSYMLINK
+        } else {
+            P9_QTFILE
+        };
+        self.put_u8(qid_type);
+        self.put_u32(meta.mtime() as u32);  // version
+        self.put_u64(meta.ino());
+    }
+}
+
+fn io_errno(error: io::Error) -> u32 {
+    match error.raw_os_error() {
+        Some(errno) => errno as u32,
+        None => EIO,
+    }
+}
+
+fn is_modify_request(req_type: u8) -> bool {
+    matches!(req_type, P9_TLCREATE | P9_TSYMLINK | P9_TRENAME | P9_TSETATTR | P9_TLINK |
+                       P9_TMKDIR | P9_TRENAMEAT | P9_TUNLINKAT | P9_TWRITE | P9_TREMOVE)
+}
+
+impl Virtio9p {
+    pub fn new(root: &str, tag: &str, read_only: bool) -> Virtio9p {
+        let root = match fs::canonicalize(root) {
+            Ok(root) if root.is_dir() => root,
+            _ => panic!("virtio-9p : shared path is not a directory: {:}", root),
+        };
+        Virtio9p {
+            m_root: root,
+            m_tag: tag.to_string(),
+            m_read_only: read_only,
+            m_msize: P9_MAX_MSIZE,
+            m_fids: HashMap::new(),
+        }
+    }
+
+    // Join name to the relative path. ".." never goes above the export root.
+    fn join_path(base: &Path, name: &str) -> Result<PathBuf, u32> {
+        let mut path = base.to_path_buf();
+        for comp in Path::new(name).components() {
+            match comp {
+                Component::Normal(c) => path.push(c),
+                Component::ParentDir => { path.pop(); },
+                Component::CurDir => {},
+                _ => return Err(EINVAL),
+            }
+        }
+        Ok(path)
+    }
+
+    // Host path of the entry itself (not followed if it is a symlink). The directory
+    // holding it is resolved on the host and must stay inside the export root, so a
+    // symlink in the middle of the path can't lead out of it.
+    fn host_path(&self, path: &Path) -> Result<PathBuf, u32> {
+        let name = match path.file_name() {
+            Some(name) => name,
+            None => return Ok(self.m_root.clone()),
+        };
+        let dir = fs::canonicalize(self.m_root.join(path.parent().unwrap())).map_err(io_errno)?;
+        if !dir.starts_with(&self.m_root) {
+            return Err(EACCES);
+        }
+        Ok(dir.join(name))
+    }
+
+    // Host path with all symlinks resolved, for the calls which follow them.
+    fn resolve_path(&self, path: &Path) -> Result<PathBuf, u32> {
+        let host_path = fs::canonicalize(self.m_root.join(path)).map_err(io_errno)?;
+        if !host_path.starts_with(&self.m_root) {
+            return Err(EACCES);
+        }
+        Ok(host_path)
+    }
+
+    fn fid_path(&self, fid: u32) -> Result<PathBuf, u32> {
+        match self.m_fids.get(&fid) {
+            Some(f) => Ok(f.m_path.clone()),
+            None => Err(EBADF),
+        }
+    }
+
+    fn new_fid(&mut self, fid: u32, path: PathBuf) {
+        self.m_fids.insert(fid, P9Fid { m_path: path, m_file: None, m_dir_entries: None });
+    }
+
+    fn handle_request(&mut self, req_type: u8, r: &mut P9Reader, w: &mut P9Writer, max_len: usize) -> Result<(), u32> {
+        if self.m_read_only && is_modify_request(req_type) {
+            return Err(EROFS);
+        }
+        match req_type {
+            P9_TVERSION => {
+                let msize = r.get_u32()?;
+                let version = r.get_str()?;
+                self.m_fids.clear();
+                // An msize too small for the I/O header is refused like an unknown version, and the current one is kept.
+                if version == "9P2000.L" && msize >= P9_MIN_MSIZE {
+                    self.m_msize = msize.min(P9_MAX_MSIZE);
+                    w.put_u32(self.m_msize);
+                    w.put_str("9P2000.L");
+                } else {
+                    w.put_u32(msize.min(P9_MAX_MSIZE));
+                    w.put_str("unknown");
+                }
+            }
+            P9_TATTACH => {
+                let fid = r.get_u32()?;
+                let _afid = r.get_u32()?;
+                let _uname = r.get_str()?;
+                let _aname = r.get_str()?;
+                self.new_fid(fid, PathBuf::new());
+                let meta = fs::symlink_metadata(&self.m_root).map_err(io_errno)?;
+                w.put_qid(&meta);
+            }
+            P9_TWALK => {
+                let fid = r.get_u32()?;
+                let newfid = r.get_u32()?;
+                let nwname = r.get_u16()?;
+                let mut path = self.fid_path(fid)?;
+                let mut qids = P9Writer { m_buf: Vec::new() };
+                let mut nwqid = 0;
+                for _ in 0..nwname {
+                    let name = r.get_str()?;
+                    let next = Self::join_path(&path, &name)?;
+                    match self.host_path(&next).and_then(|p| fs::symlink_metadata(p).map_err(io_errno)) {
+                        Ok(meta) => { qids.put_qid(&meta); nwqid += 1; path = next; }
+                        Err(errno) => {
+                            if nwqid == 0 {
+                                return Err(errno);
+                            }
+                            break;
+                        }
+                    }
+                }
+                if nwqid == nwname {
+                    self.new_fid(newfid, path);
+                }
+                w.put_u16(nwqid);
+                w.m_buf.extend_from_slice(&qids.m_buf);
+            }
+            P9_TGETATTR => {
+                let fid = r.get_u32()?;
+                let _request_mask = r.get_u64()?;
+                let meta = fs::symlink_metadata(self.host_path(&self.fid_path(fid)?)?).map_err(io_errno)?;
+                w.put_u64(P9_GETATTR_BASIC);
+                w.put_qid(&meta);
+                w.put_u32(meta.mode());
+                w.put_u32(meta.uid());
+                w.put_u32(meta.gid());
+                w.put_u64(meta.nlink());
+                w.put_u64(meta.rdev());
+                w.put_u64(meta.size());
+                w.put_u64(meta.blksize());
+                w.put_u64(meta.blocks());
+                w.put_u64(meta.atime() as u64);
+                w.put_u64(meta.atime_nsec() as u64);
+                w.put_u64(meta.mtime() as u64);
+                w.put_u64(meta.mtime_nsec() as u64);
+                w.put_u64(meta.ctime() as u64);
+                w.put_u64(meta.ctime_nsec() as u64);
+                w.put_u64(0);   // btime
+                w.put_u64(0);
+                w.put_u64(0);   // gen
+                w.put_u64(0);   // data_version
+            }
+            P9_TSETATTR => {
+                let fid = r.get_u32()?;
+                let valid = r.get_u32()?;
+                let mode = r.get_u32()?;
+                let _uid = r.get_u32()?;
+                let _gid = r.get_u32()?;
+                let size = r.get_u64()?;
+                let host_path = self.resolve_path(&self.fid_path(fid)?)?;
+                if valid & P9_SETATTR_MODE != 0 {
+                    fs::set_permissions(&host_path, fs::Permissions::from_mode(mode & 0o7777)).map_err(io_errno)?;
+                }
+                if valid & P9_SETATTR_SIZE != 0 {
+                    let file = OpenOptions::new().write(true).open(&host_path).map_err(io_errno)?;
+                    file.set_len(size).map_err(io_errno)?;
+                }
+            }
+            P9_TSTATFS => {
+                let _fid = r.get_u32()?;
+                w.put_u32(0x0102_1997);     // V9FS_MAGIC
+                w.put_u32(4096);            // bsize
+                w.put_u64(1 << 20);         // blocks
+                w.put_u64(1 << 19);         // bfree
+                w.put_u64(1 << 19);         // bavail
+                w.put_u64(1 << 20);         // files
+                w.put_u64(1 << 19);         // ffree
+                w.put_u64(0);               // fsid
+                w.put_u32(255);             // namelen
+            }
+            P9_TLOPEN => {
+                let fid = r.get_u32()?;
+                let flags = r.get_u32()?;
+                let path = self.fid_path(fid)?;
+                let host_path = self.resolve_path(&path)?;
+                let meta = fs::metadata(&host_path).map_err(io_errno)?;
+                if self.m_read_only && (flags & L_O_ACCMODE != 0 || flags & L_O_TRUNC != 0) {
+                    return Err(EROFS);
+                }
+                let f = self.m_fids.get_mut(&fid).unwrap();
+                if meta.is_dir() {
+                    f.m_dir_entries = None;
+                } else {
+                    f.m_file = Some(Self::open_file(&host_path, flags, None)?);
+                }
+                w.put_qid(&meta);
+                w.put_u32(self.m_msize.saturating_sub(P9_IOHDRSZ));
+            }
+            P9_TLCREATE => {
+                let fid = r.get_u32()?;
+                let name = r.get_str()?;
+                let flags = r.get_u32()?;
+                let mode = r.get_u32()?;
+                let _gid = r.get_u32()?;
+                let path = Self::join_path(&self.fid_path(fid)?, &name)?;
+                let host_path = self.host_path(&path)?;
+                let file = Self::open_file(&host_path, flags | L_O_CREAT, Some(mode))?;
+                let meta = fs::symlink_metadata(&host_path).map_err(io_errno)?;
+                let f = self.m_fids.get_mut(&fid).unwrap();
+                f.m_path = path;
+                f.m_file = Some(file);
+                w.put_qid(&meta);
+                w.put_u32(self.m_msize.saturating_sub(P9_IOHDRSZ));
+            }
+            P9_TREAD => {
+                let fid = r.get_u32()?;
+                let offset = r.get_u64()?;
+                let count = r.get_u32()?;
+                let count = (count as usize).min(max_len.saturating_sub(4));
+                let file = match self.m_fids.get_mut(&fid).and_then(|f| f.m_file.as_mut()) {
+                    Some(file) => file,
+                    None => return Err(EBADF),
+                };
+                let mut data = vec![0u8; count];
+                file.seek(SeekFrom::Start(offset)).map_err(io_errno)?;
+                let mut len = 0;
+                while len < count {
+                    match file.read(&mut data[len..]) {
+                        Ok(0) => break,
+                        Ok(n) => len += n,
+                        Err(error) => return Err(io_errno(error)),
+                    }
+                }
+                w.put_u32(len as u32);
+                w.m_buf.extend_from_slice(&data[..len]);
+            }
+            P9_TWRITE => {
+                let fid = r.get_u32()?;
+                let offset = r.get_u64()?;
+                let count = r.get_u32()?;
+                let data = r.get_bytes(count as usize)?;
+                let file = match self.m_fids.get_mut(&fid).and_then(|f| f.m_file.as_mut()) {
+                    Some(file) => file,
+                    None => return Err(EBADF),
+                };
+                file.seek(SeekFrom::Start(offset)).map_err(io_errno)?;
+                file.write_all(data).map_err(io_errno)?;
+                w.put_u32(count);
+            }
+            P9_TREADDIR => {
+                let fid = r.get_u32()?;
+                let offset = r.get_u64()?;
+                let count = r.get_u32()?;
+                let count = (count as usize).min(max_len.saturating_sub(4));
+                let path = self.fid_path(fid)?;
+                let host_path = self.resolve_path(&path)?;
+                let f = self.m_fids.get_mut(&fid).unwrap();
+                if f.m_dir_entries.is_none() || offset == 0 {
+                    let mut entries = vec![(".".to_string(), path.clone()), ("..".to_string(), Self::join_path(&path, "..")?)];
+                    let mut names: Vec<String> = fs::read_dir(&host_path).map_err(io_errno)?
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.file_name().to_string_lossy().into_owned())
+                        .collect();
+                    names.sort();
+                    for name in names {
+                        let entry_path = path.join(&name);
+                        entries.push((name, entry_path));
+                    }
+                    f.m_dir_entries = Some(entries);
+                }
+                let mut dirents = P9Writer { m_buf: Vec::new() };
+                for (idx, (name, entry_path)) in f.m_dir_entries.as_ref().unwrap().iter().enumerate().skip(offset as usize) {
+                    let meta = match fs::symlink_metadata(self.m_root.join(entry_path)) {
+                        Ok(meta) => meta,
+                        Err(_) => continue,
+                    };
+                    // qid[13] offset[8] type[1] name[s]
+                    if dirents.m_buf.len() + 24 + name.len() > count {
+                        break;
+                    }
+                    dirents.put_qid(&meta);
+                    dirents.put_u64(idx as u64 + 1);
+                    dirents.put_u8((meta.mode() >> 12) as u8);  // DT_* = S_IFMT >> 12
+                    dirents.put_str(name);
+                }
+                w.put_u32(dirents.m_buf.len() as u32);
+                w.m_buf.extend_from_slice(&dirents.m_buf);
+            }
+            P9_TMKDIR => {
+                let dfid = r.get_u32()?;
+                let name = r.get_str()?;
+                let mode = r.get_u32()?;
+                let _gid = r.get_u32()?;
+                let host_path = self.host_path(&Self::join_path(&self.fid_path(dfid)?, &name)?)?;
+                fs::create_dir(&host_path).map_err(io_errno)?;
+                let _ = fs::set_permissions(&host_path, fs::Permissions::from_mode(mode & 0o7777));
+                w.put_qid(&fs::symlink_metadata(&host_path).map_err(io_errno)?);
+            }
+            P9_TSYMLINK => {
+                let fid = r.get_u32()?;
+                let name = r.get_str()?;
+                let target = r.get_str()?;
+                let _gid = r.get_u32()?;
+                let host_path = self.host_path(&Self::join_path(&self.fid_path(fid)?, &name)?)?;
+                std::os::unix::fs::symlink(&target, &host_path).map_err(io_errno)?;
+                w.put_qid(&fs::symlink_metadata(&host_path).map_err(io_errno)?);
+            }
+            P9_TREADLINK => {
+                let fid = r.get_u32()?;
+                let target = fs::read_link(self.host_path(&self.fid_path(fid)?)?).map_err(io_errno)?;
+                w.put_str(&target.to_string_lossy());
+            }
+            P9_TLINK => {
+                let dfid = r.get_u32()?;
+                let fid = r.get_u32()?;
+                let name = r.get_str()?;
+                let src = self.host_path(&self.fid_path(fid)?)?;
+                let dst = self.host_path(&Self::join_path(&self.fid_path(dfid)?, &name)?)?;
+                fs::hard_link(src, dst).map_err(io_errno)?;
+            }
+            P9_TRENAME => {
+                let fid = r.get_u32()?;
+                let dfid = r.get_u32()?;
+                let name = r.get_str()?;
+                let new_path = Self::join_path(&self.fid_path(dfid)?, &name)?;
+                fs::rename(self.host_path(&self.fid_path(fid)?)?, self.host_path(&new_path)?).map_err(io_errno)?;
+                self.m_fids.get_mut(&fid).unwrap().m_path = new_path;
+            }
+            P9_TRENAMEAT => {
+                let olddirfid = r.get_u32()?;
+                let oldname = r.get_str()?;
+                let newdirfid = r.get_u32()?;
+                let newname = r.get_str()?;
+                let old_path = self.host_path(&Self::join_path(&self.fid_path(olddirfid)?, &oldname)?)?;
+                let new_path = self.host_path(&Self::join_path(&self.fid_path(newdirfid)?, &newname)?)?;
+                fs::rename(old_path, new_path).map_err(io_errno)?;
+            }
+            P9_TUNLINKAT => {
+                let dirfid = r.get_u32()?;
+                let name = r.get_str()?;
+                let flags = r.get_u32()?;
+                let host_path = self.host_path(&Self::join_path(&self.fid_path(dirfid)?, &name)?)?;
+                if flags & L_AT_REMOVEDIR != 0 {
+                    fs::remove_dir(host_path).map_err(io_errno)?;
+                } else {
+                    fs::remove_file(host_path).map_err(io_errno)?;
+                }
+            }
+            P9_TREMOVE => {
+                let fid = r.get_u32()?;
+                let host_path = self.host_path(&self.fid_path(fid)?)?;
+                self.m_fids.remove(&fid);
+                if fs::symlink_metadata(&host_path).map_err(io_errno)?.is_dir() {
+                    fs::remove_dir(host_path).map_err(io_errno)?;
+                } else {
+                    fs::remove_file(host_path).map_err(io_errno)?;
+                }
+            }
+            P9_TFSYNC => {
+                let fid = r.get_u32()?;
+                if let Some(file) = self.m_fids.get_mut(&fid).and_then(|f| f.m_file.as_mut()) {
+                    file.sync_all().map_err(io_errno)?;
+                }
+            }
+            P9_TLOCK => {
+                // No other client : locks always succeed.
+                w.put_u8(0);
+            }
+            P9_TGETLOCK => {
+                let _fid = r.get_u32()?;
+                let _lock_type = r.get_u8()?;
+                let start = r.get_u64()?;
+                let length = r.get_u64()?;
+                let proc_id = r.get_u32()?;
+                let client_id = r.get_str()?;
+                w.put_u8(2);    // F_UNLCK
+                w.put_u64(start);
+                w.put_u64(length);
+                w.put_u32(proc_id);
+                w.put_str(&client_id);
+            }
+            P9_TCLUNK => {
+                let fid = r.get_u32()?;
+                if self.m_fids.remove(&fid).is_none() {
+                    return Err(EBADF);
+                }
+            }
+            P9_TFLUSH => {}
+            P9_TXATTRWALK => return Err(ENOTSUP),
+            _ => return Err(ENOTSUP),
+        }
+        Ok(())
+    }
+
+    fn open_file(host_path: &Path, flags: u32, mode: Option<u32>) -> Result<File, u32> {
+        let mut options = OpenOptions::new();
+        match flags & L_O_ACCMODE {
+            L_O_WRONLY => { options.write(true); },
+            L_O_RDWR   => { options.read(true).write(true); },
+            _          => { options.read(true); },
+        }
+        if flags & L_O_APPEND != 0 {
+            options.append(true);
+        }
+        if flags & L_O_TRUNC != 0 {
+            options.write(true).truncate(true);
+        }
+        if flags & L_O_CREAT != 0 {
+            options.write(true);
+            if flags & L_O_EXCL != 0 {
+                options.create_new(true);
+            } else {
+                options.create(true);
+            }
+        }
+        if let Some(mode) = mode {
+            options.mode(mode & 0o7777);
+        }
+        // A symlink left at the end of the path (e.g. dangling one for O_CREAT) is not followed.
+        options.custom_flags(libc::O_NOFOLLOW);
+        options.open(host_path).map_err(io_errno)
+    }
+}
+
+impl VirtioDevice for Virtio9p {
+    fn device_id(&self) -> u32 { VIRTIO_ID_9P }
+
+    fn device_features(&self) -> u64 { VIRTIO_9P_MOUNT_TAG }
+
+    fn num_queues(&self) -> usize { 1 }
+
+    // config : tag_len[2] tag[tag_len]
+    fn read_config(&self, offset: u64) -> u8 {
+        let tag = self.m_tag.as_bytes();
+        match offset {
+            0..=1 => ((tag.len() as u16) >> (offset * 8)) as u8,
+            _ => *tag.get(offset as usize - 2).unwrap_or(&0),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.m_fids.clear();
+    }
+
+    fn process_chain(&mut self, emu: &EmuEnv, _queue_idx: usize, chain: &[VirtqDesc]) -> u32 {
+        let req = virtq_read_chain(emu, chain);
+        let max_len = virtq_writable_len(chain).min(self.m_msize as usize);
+        let mut r = P9Reader { m_buf: &req, m_pos: 0 };
+        let (req_type, tag) = match (r.get_u32(), r.get_u8(), r.get_u16()) {
+            (Ok(_size), Ok(req_type), Ok(tag)) => (req_type, tag),
+            _ => return 0,
+        };
+
+        // size[4] type[1] tag[2] body
+        let mut w = P9Writer { m_buf: vec![0; 7] };
+        let resp_type = match self.handle_request(req_type, &mut r, &mut w, max_len.saturating_sub(7)) {
+            Ok(()) => req_type + 1,
+            Err(ecode) => {
+                w.m_buf.truncate(7);
+                w.put_u32(ecode);
+                P9_RLERROR
+            }
+        };
+        let len = w.m_buf.len() as u32;
+        w.m_buf[0..4].copy_from_slice(&len.to_le_bytes());
+        w.m_buf[4] = resp_type;
+        w.m_buf[5..7].copy_from_slice(&tag.to_le_bytes());
+        return virtq_write_chain(emu, chain, &w.m_buf);
+    }
+}
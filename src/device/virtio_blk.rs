@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::device::virtio_mmio::{virtq_read_chain, virtq_writable_len, virtq_write_chain, virtq_write_last, VirtioDevice, VirtqDesc};
+use crate::emu_env::{DriveMode, EmuEnv};
+
+const VIRTIO_ID_BLOCK: u32 = 2;
+
+const VIRTIO_BLK_F_RO   : u64 = 1 << 5;
+const VIRTIO_BLK_F_FLUSH: u64 = 1 << 9;
+
+const VIRTIO_BLK_T_IN    : u32 = 0;
+const VIRTIO_BLK_T_OUT   : u32 = 1;
+const VIRTIO_BLK_T_FLUSH : u32 = 4;
+const VIRTIO_BLK_T_GET_ID: u32 = 8;
+
+const VIRTIO_BLK_S_OK    : u8 = 0;
+const VIRTIO_BLK_S_IOERR : u8 = 1;
+const VIRTIO_BLK_S_UNSUPP: u8 = 2;
+
+const SECTOR_SIZE: u64 = 512;
+const VIRTIO_BLK_ID_BYTES: usize = 20;
+
+pub struct VirtioBlk {
+    m_file: File,
+    m_mode: DriveMode,
+    m_capacity: u64,    // in 512-byte sectors
+    // Copy-on-write overlay : modified sectors are kept here, image file is never written.
+    m_overlay: HashMap<u64, Vec<u8>>,
+}
+
+impl VirtioBlk {
+    pub fn new(filename: &str, mode: DriveMode) -> VirtioBlk {
+        let file = match OpenOptions::new().read(true).write(mode == DriveMode::ReadWrite).open(filename) {
+            Ok(file) => file,
+            Err(error) => panic!("There was a problem opening the drive image: {:?}, {:}", error, filename),
+        };
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        VirtioBlk {
+            m_file: file,
+            m_mode: mode,
+            m_capacity: size / SECTOR_SIZE,
+            m_overlay: HashMap::new(),
+        }
+    }
+
+    fn read_sector(&mut self, sector: u64, buf: &mut [u8]) -> bool {
+        if let Some(data) = self.m_overlay.get(&sector) {
+            buf.copy_from_slice(data);
+            return true;
+        }
+        match sector.checked_mul(SECTOR_SIZE) {
+            Some(offset) => self.m_file.seek(SeekFrom::Start(offset)).is_ok() && self.m_file.read_exact(buf).is_ok(),
+            None => false,
+        }
+    }
+
+    fn write_sector(&mut self, sector: u64, buf: &[u8]) -> bool {
+        match self.m_mode {
+            DriveMode::ReadOnly => false,
+            DriveMode::Cow => { self.m_overlay.insert(sector, buf.to_vec()); true },
+            DriveMode::ReadWrite => match sector.checked_mul(SECTOR_SIZE) {
+                Some(offset) => self.m_file.seek(SeekFrom::Start(offset)).is_ok() && self.m_file.write_all(buf).is_ok(),
+                None => false,
+            },
+        }
+    }
+
+    // Whole sectors within the capacity. The sector comes from the guest, so the end must not wrap around.
+    fn sector_range_ok(&self, sector: u64, bytes: u64) -> bool {
+        bytes % SECTOR_SIZE == 0 && matches!(sector.checked_add(bytes / SECTOR_SIZE), Some(end) if end <= self.m_capacity)
+    }
+}
+
+impl VirtioDevice for VirtioBlk {
+    fn device_id(&self) -> u32 { VIRTIO_ID_BLOCK }
+
+    fn device_features(&self) -> u64 {
+        match self.m_mode {
+            DriveMode::ReadOnly => VIRTIO_BLK_F_RO,
+            _ => VIRTIO_BLK_F_FLUSH,
+        }
+    }
+
+    fn num_queues(&self) -> usize { 1 }
+
+    fn read_config(&self, offset: u64) -> u8 {
+        match offset {
+            0..=7 => (self.m_capacity >> (offset * 8)) as u8,
+            _ => 0,
+        }
+    }
+
+    fn process_chain(&mut self, emu: &EmuEnv, _queue_idx: usize, chain: &[VirtqDesc]) -> u32 {
+        let req = virtq_read_chain(emu, chain);
+        let writable_len = virtq_writable_len(chain);
+        if req.len() < 16 || writable_len < 1 {
+            return 0;
+        }
+        let req_type = u32::from_le_bytes(req[0..4].try_into().unwrap());
+        let sector = u64::from_le_bytes(req[8..16].try_into().unwrap());
+
+        // Response : data (device-writable) followed by the status byte
+        let mut resp = Vec::new();
+        let status = match req_type {
+            VIRTIO_BLK_T_IN => {
+                let len = (writable_len - 1) as u64;
+                if !self.sector_range_ok(sector, len) {
+                    VIRTIO_BLK_S_IOERR
+                } else {
+                    resp.resize(len as usize, 0);
+                    let mut ok = true;
+                    for (i, buf) in resp.chunks_mut(SECTOR_SIZE as usize).enumerate() {
+                        ok &= self.read_sector(sector + i as u64, buf);
+                    }
+                    if ok { VIRTIO_BLK_S_OK } else { VIRTIO_BLK_S_IOERR }
+                }
+            }
+            VIRTIO_BLK_T_OUT => {
+                let data = &req[16..];
+                if !self.sector_range_ok(sector, data.len() as u64) {
+                    VIRTIO_BLK_S_IOERR
+                } else {
+                    let mut ok = true;
+                    for (i, buf) in data.chunks(SECTOR_SIZE as usize).enumerate() {
+                        ok &= self.write_sector(sector + i as u64, buf);
+                    }
+                    if ok { VIRTIO_BLK_S_OK } else { VIRTIO_BLK_S_IOERR }
+                }
+            }
+            VIRTIO_BLK_T_FLUSH => {
+                if self.m_mode == DriveMode::ReadWrite && self.m_file.sync_data().is_err() {
+                    VIRTIO_BLK_S_IOERR
+                } else {
+                    VIRTIO_BLK_S_OK
+                }
+            }
+            VIRTIO_BLK_T_GET_ID => {
+                let mut id = b"dydra-virtio-blk".to_vec();
+                id.resize(VIRTIO_BLK_ID_BYTES.min(writable_len - 1), 0);
+                resp = id;
+                VIRTIO_BLK_S_OK
+            }
+            _ => VIRTIO_BLK_S_UNSUPP,
+        };
+        // Status byte is always placed at the end of the writable buffers.
+        // Rejected requests write only the status, without a buffer of the guest described length.
+        virtq_write_chain(emu, chain, &resp);
+        virtq_write_last(emu, chain, status);
+        return writable_len as u32;
+    }
+}
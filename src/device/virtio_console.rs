@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::device::virtio_mmio::{virtq_read_chain, virtq_writable_len, virtq_write_chain, VirtioDevice, VirtqDesc};
+use crate::emu_env::{ConsoleBackend, EmuEnv};
+
+const VIRTIO_ID_CONSOLE: u32 = 3;
+
+const VIRTIO_CONSOLE_F_SIZE: u64 = 1 << 0;
+
+const RECEIVEQ : usize = 0;
+const TRANSMITQ: usize = 1;
+
+const CONSOLE_COLS: u16 = 80;
+const CONSOLE_ROWS: u16 = 25;
+
+pub struct VirtioConsole {
+    m_input: Receiver<u8>,
+    m_input_buf: VecDeque<u8>,
+    // None for stdio, socket is set when a client connected.
+    m_socket: Option<Arc<Mutex<Option<UnixStream>>>>,
+}
+
+impl VirtioConsole {
+    pub fn new(backend: ConsoleBackend, socket_path: Option<&String>) -> VirtioConsole {
+        let (tx, rx) = channel();
+        let socket = match backend {
+            ConsoleBackend::Stdio => {
+                thread::spawn(move || {
+                    let mut buf = [0u8; 1];
+                    while let Ok(1) = std::io::stdin().read(&mut buf) {
+                        if tx.send(buf[0]).is_err() {
+                            break;
+                        }
+                    }
+                });
+                None
+            }
+            ConsoleBackend::Socket => {
+                let path = match socket_path {
+                    Some(path) => path.clone(),
+                    None => panic!("virtio-console : --console-socket is required for socket backend"),
+                };
+                let _ = std::fs::remove_file(&path);
+                let listener = match UnixListener::bind(&path) {
+                    Ok(listener) => listener,
+                    Err(error) => panic!("There was a problem binding the console socket: {:?}, {:}", error, path),
+                };
+                let stream_out = Arc::new(Mutex::new(None));
+                let stream_accepted = stream_out.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let mut stream = match stream {
+                            Ok(stream) => stream,
+                            Err(_) => continue,
+                        };
+                        *stream_accepted.lock().unwrap() = stream.try_clone().ok();
+                        let mut buf = [0u8; 1];
+                        while let Ok(1) = stream.read(&mut buf) {
+                            if tx.send(buf[0]).is_err() {
+                                return;
+                            }
+                        }
+                        *stream_accepted.lock().unwrap() = None;
+                    }
+                });
+                Some(stream_out)
+            }
+        };
+        VirtioConsole {
+            m_input: rx,
+            m_input_buf: VecDeque::new(),
+            m_socket: socket,
+        }
+    }
+
+    fn output(&mut self, data: &[u8]) {
+        match &self.m_socket {
+            None => {
+                let mut stdout = std::io::stdout();
+                let _ = stdout.write_all(data);
+                let _ = stdout.flush();
+            }
+            Some(socket) => {
+                // Output is dropped while no client is connected.
+                if let Some(stream) = socket.lock().unwrap().as_mut() {
+                    let _ = stream.write_all(data);
+                }
+            }
+        }
+    }
+}
+
+impl VirtioDevice for VirtioConsole {
+    fn device_id(&self) -> u32 { VIRTIO_ID_CONSOLE }
+
+    fn device_features(&self) -> u64 { VIRTIO_CONSOLE_F_SIZE }
+
+    fn num_queues(&self) -> usize { 2 }
+
+    fn read_config(&self, offset: u64) -> u8 {
+        match offset {
+            0..=1 => (CONSOLE_COLS >> (offset * 8)) as u8,
+            2..=3 => (CONSOLE_ROWS >> ((offset - 2) * 8)) as u8,
+            _ => 0,
+        }
+    }
+
+    fn queue_ready(&mut self, queue_idx: usize) -> bool {
+        match queue_idx {
+            RECEIVEQ => !self.m_input_buf.is_empty(),
+            _ => true,
+        }
+    }
+
+    fn poll(&mut self) -> Option<usize> {
+        while let Ok(c) = self.m_input.try_recv() {
+            self.m_input_buf.push_back(c);
+        }
+        if self.m_input_buf.is_empty() { None } else { Some(RECEIVEQ) }
+    }
+
+    fn process_chain(&mut self, emu: &EmuEnv, queue_idx: usize, chain: &[VirtqDesc]) -> u32 {
+        match queue_idx {
+            RECEIVEQ => {
+                let len = virtq_writable_len(chain).min(self.m_input_buf.len());
+                let data: Vec<u8> = self.m_input_buf.drain(..len).collect();
+                virtq_write_chain(emu, chain, &data)
+            }
+            TRANSMITQ => {
+                let data = virtq_read_chain(emu, chain);
+                self.output(&data);
+                0
+            }
+            _ => 0,
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use crate::emu_env::EmuEnv;
+
+// virtio-mmio transport, version 2 (Virtio 1.x, "modern" interface)
+pub const VIRTIO_MMIO_SLOT_SIZE: u64 = 0x1000;
+pub const VIRTIO_MMIO_NUM_SLOTS: usize = 8;
+
+const VIRTIO_MMIO_MAGIC_VALUE        : u64 = 0x000;
+const VIRTIO_MMIO_VERSION            : u64 = 0x004;
+const VIRTIO_MMIO_DEVICE_ID          : u64 = 0x008;
+const VIRTIO_MMIO_VENDOR_ID          : u64 = 0x00c;
+const VIRTIO_MMIO_DEVICE_FEATURES    : u64 = 0x010;
+const VIRTIO_MMIO_DEVICE_FEATURES_SEL: u64 = 0x014;
+const VIRTIO_MMIO_DRIVER_FEATURES    : u64 = 0x020;
+const VIRTIO_MMIO_DRIVER_FEATURES_SEL: u64 = 0x024;
+const VIRTIO_MMIO_QUEUE_SEL          : u64 = 0x030;
+const VIRTIO_MMIO_QUEUE_NUM_MAX      : u64 = 0x034;
+const VIRTIO_MMIO_QUEUE_NUM          : u64 = 0x038;
+const VIRTIO_MMIO_QUEUE_READY        : u64 = 0x044;
+const VIRTIO_MMIO_QUEUE_NOTIFY       : u64 = 0x050;
+const VIRTIO_MMIO_INTERRUPT_STATUS   : u64 = 0x060;
+const VIRTIO_MMIO_INTERRUPT_ACK      : u64 = 0x064;
+const VIRTIO_MMIO_STATUS             : u64 = 0x070;
+const VIRTIO_MMIO_QUEUE_DESC_LOW     : u64 = 0x080;
+const VIRTIO_MMIO_QUEUE_DESC_HIGH    : u64 = 0x084;
+const VIRTIO_MMIO_QUEUE_DRIVER_LOW   : u64 = 0x090;
+const VIRTIO_MMIO_QUEUE_DRIVER_HIGH  : u64 = 0x094;
+const VIRTIO_MMIO_QUEUE_DEVICE_LOW   : u64 = 0x0a0;
+const VIRTIO_MMIO_QUEUE_DEVICE_HIGH  : u64 = 0x0a4;
+const VIRTIO_MMIO_CONFIG_GENERATION  : u64 = 0x0fc;
+const VIRTIO_MMIO_CONFIG             : u64 = 0x100;
+
+const VIRT_MAGIC : u32 = 0x7472_6976; // "virt"
+const VIRT_VENDOR: u32 = 0x554d_4551; // "QEMU"
+
+pub const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+
+const VIRTQ_DESC_F_NEXT : u16 = 1;
+const VIRTQ_DESC_F_WRITE: u16 = 2;
+
+// Device status : the device hit an error (e.g. a ring outside of DRAM) and must be reset by the driver
+const VIRTIO_STATUS_DEVICE_NEEDS_RESET: u32 = 0x40;
+
+const VIRTIO_MMIO_INT_VRING: u32 = 1 << 0;
+const VIRTIO_MMIO_INT_CONFIG: u32 = 1 << 1;
+
+const VIRTQ_NUM_MAX: u32 = 256;
+
+#[derive(Debug, Copy, Clone)]
+pub struct VirtqDesc {
+    pub addr: u64,
+    pub len: u32,
+    pub flags: u16,
+}
+
+impl VirtqDesc {
+    pub fn is_write(&self) -> bool {
+        self.flags & VIRTQ_DESC_F_WRITE != 0
+    }
+}
+
+// Device side of virtio. The transport calls process_chain() for each
+// descriptor chain made available by the driver.
+pub trait VirtioDevice {
+    fn device_id(&self) -> u32;
+    fn device_features(&self) -> u64;
+    fn num_queues(&self) -> usize;
+    fn read_config(&self, offset: u64) -> u8;
+    fn write_config(&mut self, _offset: u64, _data: u8) {}
+    fn reset(&mut self) {}
+    // Whether the device can consume a chain of the queue now
+    // (e.g. receive queue waits until host input arrives).
+    fn queue_ready(&mut self, _queue_idx: usize) -> bool { true }
+    // Called periodically from the main loop. Returns queue index which has data to deliver.
+    fn poll(&mut self) -> Option<usize> { None }
+    // Returns number of bytes written into device-writable buffers.
+    fn process_chain(&mut self, emu: &EmuEnv, queue_idx: usize, chain: &[VirtqDesc]) -> u32;
+}
+
+#[derive(Debug, Copy, Clone)]
+struct VirtQueue {
+    num: u32,
+    ready: bool,
+    desc_addr: u64,
+    driver_addr: u64,
+    device_addr: u64,
+    last_avail_idx: u16,
+}
+
+impl VirtQueue {
+    fn new() -> VirtQueue {
+        VirtQueue {
+            num: 0,
+            ready: false,
+            desc_addr: 0,
+            driver_addr: 0,
+            device_addr: 0,
+            last_avail_idx: 0,
+        }
+    }
+}
+
+pub struct VirtioMmio {
+    pub m_irq: usize,
+    m_device: Option<Box<dyn VirtioDevice>>,
+    m_device_features_sel: u32,
+    m_driver_features: u64,
+    m_driver_features_sel: u32,
+    m_queue_sel: u32,
+    m_queues: Vec<VirtQueue>,
+    m_interrupt_status: u32,
+    m_status: u32,
+    m_config_generation: u32,
+}
+
+impl VirtioMmio {
+    pub fn new(irq: usize, device: Option<Box<dyn VirtioDevice>>) -> VirtioMmio {
+        let num_queues = match &device {
+            Some(dev) => dev.num_queues(),
+            None => 0,
+        };
+        VirtioMmio {
+            m_irq: irq,
+            m_device: device,
+            m_device_features_sel: 0,
+            m_driver_features: 0,
+            m_driver_features_sel: 0,
+            m_queue_sel: 0,
+            m_queues: vec![VirtQueue::new(); num_queues],
+            m_interrupt_status: 0,
+            m_status: 0,
+            m_config_generation: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.m_device_features_sel = 0;
+        self.m_driver_features = 0;
+        self.m_driver_features_sel = 0;
+        self.m_queue_sel = 0;
+        for q in self.m_queues.iter_mut() {
+            *q = VirtQueue::new();
+        }
+        self.m_interrupt_status = 0;
+        self.m_status = 0;
+        if let Some(dev) = &mut self.m_device {
+            dev.reset();
+        }
+    }
+
+    fn curr_queue(&mut self) -> Option<&mut VirtQueue> {
+        self.m_queues.get_mut(self.m_queue_sel as usize)
+    }
+
+    fn read(&mut self, offset: u64, size: usize) -> u64 {
+        let device = match &self.m_device {
+            Some(dev) => dev,
+            None => {
+                // Unpopulated slot : Device ID 0
+                return match offset {
+                    VIRTIO_MMIO_MAGIC_VALUE => VIRT_MAGIC as u64,
+                    VIRTIO_MMIO_VERSION     => 2,
+                    VIRTIO_MMIO_VENDOR_ID   => VIRT_VENDOR as u64,
+                    _ => 0,
+                };
+            }
+        };
+        if offset >= VIRTIO_MMIO_CONFIG {
+            let mut data = 0;
+            for i in 0..size as u64 {
+                data |= (device.read_config(offset - VIRTIO_MMIO_CONFIG + i) as u64) << (i * 8);
+            }
+            return data;
+        }
+        let data: u32 = match offset {
+            VIRTIO_MMIO_MAGIC_VALUE => VIRT_MAGIC,
+            VIRTIO_MMIO_VERSION     => 2,
+            VIRTIO_MMIO_DEVICE_ID   => device.device_id(),
+            VIRTIO_MMIO_VENDOR_ID   => VIRT_VENDOR,
+            VIRTIO_MMIO_DEVICE_FEATURES => {
+                let features = device.device_features() | VIRTIO_F_VERSION_1;
+                match self.m_device_features_sel {
+                    0 => features as u32,
+                    1 => (features >> 32) as u32,
+                    _ => 0,
+                }
+            }
+            VIRTIO_MMIO_QUEUE_NUM_MAX => match self.curr_queue() {
+                Some(_) => VIRTQ_NUM_MAX,
+                None => 0,
+            },
+            VIRTIO_MMIO_QUEUE_READY => match self.curr_queue() {
+                Some(q) => q.ready as u32,
+                None => 0,
+            },
+            VIRTIO_MMIO_INTERRUPT_STATUS => self.m_interrupt_status,
+            VIRTIO_MMIO_STATUS => self.m_status,
+            VIRTIO_MMIO_CONFIG_GENERATION => self.m_config_generation,
+            _ => 0,
+        };
+        return data as u64;
+    }
+
+    // Returns queue index to be processed when the driver notified.
+    fn write(&mut self, offset: u64, size: usize, data: u64) -> Option<usize> {
+        if self.m_device.is_none() {
+            return None;
+        }
+        if offset >= VIRTIO_MMIO_CONFIG {
+            let device = self.m_device.as_mut().unwrap();
+            for i in 0..size as u64 {
+                device.write_config(offset - VIRTIO_MMIO_CONFIG + i, (data >> (i * 8)) as u8);
+            }
+            return None;
+        }
+        let data = data as u32;
+        match offset {
+            VIRTIO_MMIO_DEVICE_FEATURES_SEL => { self.m_device_features_sel = data; }
+            VIRTIO_MMIO_DRIVER_FEATURES => {
+                match self.m_driver_features_sel {
+                    0 => { self.m_driver_features = (self.m_driver_features & !0xffff_ffff) | data as u64; }
+                    1 => { self.m_driver_features = (self.m_driver_features & 0xffff_ffff) | ((data as u64) << 32); }
+                    _ => {}
+                }
+            }
+            VIRTIO_MMIO_DRIVER_FEATURES_SEL => { self.m_driver_features_sel = data; }
+            VIRTIO_MMIO_QUEUE_SEL => { self.m_queue_sel = data; }
+            VIRTIO_MMIO_QUEUE_NUM => { if let Some(q) = self.curr_queue() { q.num = data.min(VIRTQ_NUM_MAX); } }
+            VIRTIO_MMIO_QUEUE_READY => { if let Some(q) = self.curr_queue() { q.ready = data & 1 != 0; } }
+            VIRTIO_MMIO_QUEUE_NOTIFY => {
+                if (data as usize) < self.m_queues.len() {
+                    return Some(data as usize);
+                }
+            }
+            VIRTIO_MMIO_INTERRUPT_ACK => { self.m_interrupt_status &= !data; }
+            VIRTIO_MMIO_STATUS => {
+                if data == 0 {
+                    self.reset();
+                } else {
+                    self.m_status = data;
+                }
+            }
+            VIRTIO_MMIO_QUEUE_DESC_LOW    => { if let Some(q) = self.curr_queue() { q.desc_addr   = (q.desc_addr   & !0xffff_ffff) | data as u64; } }
+            VIRTIO_MMIO_QUEUE_DESC_HIGH   => { if let Some(q) = self.curr_queue() { q.desc_addr   = (q.desc_addr   &  0xffff_ffff) | ((data as u64) << 32); } }
+            VIRTIO_MMIO_QUEUE_DRIVER_LOW  => { if let Some(q) = self.curr_queue() { q.driver_addr = (q.driver_addr & !0xffff_ffff) | data as u64; } }
+            VIRTIO_MMIO_QUEUE_DRIVER_HIGH => { if let Some(q) = self.curr_queue() { q.driver_addr = (q.driver_addr &  0xffff_ffff) | ((data as u64) << 32); } }
+            VIRTIO_MMIO_QUEUE_DEVICE_LOW  => { if let Some(q) = self.curr_queue() { q.device_addr = (q.device_addr & !0xffff_ffff) | data as u64; } }
+            VIRTIO_MMIO_QUEUE_DEVICE_HIGH => { if let Some(q) = self.curr_queue() { q.device_addr = (q.device_addr &  0xffff_ffff) | ((data as u64) << 32); } }
+            _ => {}
+        }
+        return None;
+    }
+
+    // Descriptor table, available ring and used ring (with the event index fields) are placed on DRAM.
+    fn rings_valid(emu: &EmuEnv, q: &VirtQueue) -> bool {
+        let num = q.num as u64;
+        emu.is_dram_addr(q.desc_addr, 16 * num) &&
+            emu.is_dram_addr(q.driver_addr, 6 + 2 * num) &&
+            emu.is_dram_addr(q.device_addr, 6 + 8 * num)
+    }
+
+    // Read the descriptor chain from head. None if an index or a buffer is out of range.
+    fn read_chain(emu: &EmuEnv, q: &VirtQueue, head: u16) -> Option<Vec<VirtqDesc>> {
+        let mut chain = Vec::new();
+        let mut desc_idx = head;
+        loop {
+            if desc_idx as u32 >= q.num {
+                return None;
+            }
+            let desc_addr = q.desc_addr + (desc_idx as u64) * 16;
+            let desc = VirtqDesc {
+                addr : emu.read_mem_8byte(desc_addr),
+                len  : emu.read_mem_4byte(desc_addr + 8),
+                flags: emu.read_mem_2byte(desc_addr + 12),
+            };
+            if !emu.is_dram_addr(desc.addr, desc.len as u64) {
+                return None;
+            }
+            chain.push(desc);
+            if desc.flags & VIRTQ_DESC_F_NEXT == 0 || chain.len() >= q.num as usize {
+                return Some(chain);
+            }
+            desc_idx = emu.read_mem_2byte(desc_addr + 14);
+        }
+    }
+
+    // Consume all available descriptor chains of the queue.
+    // Returns true if any buffer is returned to the used ring.
+    // Rings and buffers given by the driver are checked before any access,
+    // and the device stops with DEVICE_NEEDS_RESET when they are out of DRAM.
+    fn process_queue(&mut self, emu: &EmuEnv, queue_idx: usize) -> bool {
+        if self.m_status & VIRTIO_STATUS_DEVICE_NEEDS_RESET != 0 {
+            return false;
+        }
+        let q = &mut self.m_queues[queue_idx];
+        let device = self.m_device.as_mut().unwrap();
+        if !q.ready || q.num == 0 {
+            return false;
+        }
+        if !Self::rings_valid(emu, q) {
+            self.set_needs_reset();
+            return false;
+        }
+        let mut processed = false;
+        let mut broken = false;
+        let avail_idx = emu.read_mem_2byte(q.driver_addr + 2);
+        while q.last_avail_idx != avail_idx && device.queue_ready(queue_idx) {
+            let ring_idx = (q.last_avail_idx as u32 % q.num) as u64;
+            let head = emu.read_mem_2byte(q.driver_addr + 4 + ring_idx * 2);
+
+            let chain = match Self::read_chain(emu, q, head) {
+                Some(chain) => chain,
+                None => {
+                    broken = true;
+                    break;
+                }
+            };
+
+            let used_len = device.process_chain(emu, queue_idx, &chain);
+
+            let used_idx = emu.read_mem_2byte(q.device_addr + 2);
+            let used_elem = q.device_addr + 4 + (used_idx as u32 % q.num) as u64 * 8;
+            emu.write_mem_4byte(used_elem, head as u32);
+            emu.write_mem_4byte(used_elem + 4, used_len);
+            emu.write_mem_2byte(q.device_addr + 2, used_idx.wrapping_add(1));
+
+            q.last_avail_idx = q.last_avail_idx.wrapping_add(1);
+            processed = true;
+        }
+        if broken {
+            self.set_needs_reset();
+        }
+        return processed;
+    }
+
+    // Report the error with configuration change notification.
+    fn set_needs_reset(&mut self) {
+        self.m_status |= VIRTIO_STATUS_DEVICE_NEEDS_RESET;
+        self.m_interrupt_status |= VIRTIO_MMIO_INT_CONFIG;
+    }
+}
+
+// Gather device-readable part of the descriptor chain. Buffers are checked by process_queue.
+pub fn virtq_read_chain(emu: &EmuEnv, chain: &[VirtqDesc]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for desc in chain.iter().filter(|d| !d.is_write()) {
+        for i in 0..desc.len as u64 {
+            data.push(emu.read_mem_1byte(desc.addr + i));
+        }
+    }
+    return data;
+}
+
+// Scatter data into device-writable part of the descriptor chain.
+pub fn virtq_write_chain(emu: &EmuEnv, chain: &[VirtqDesc], data: &[u8]) -> u32 {
+    let mut pos = 0;
+    for desc in chain.iter().filter(|d| d.is_write()) {
+        for i in 0..desc.len as u64 {
+            if pos >= data.len() {
+                return pos as u32;
+            }
+            emu.write_mem_1byte(desc.addr + i, data[pos]);
+            pos += 1;
+        }
+    }
+    return pos as u32;
+}
+
+// Write the last byte of device-writable part of the descriptor chain, e.g. the status of virtio-blk.
+pub fn virtq_write_last(emu: &EmuEnv, chain: &[VirtqDesc], data: u8) {
+    if let Some(desc) = chain.iter().filter(|d| d.is_write() && d.len > 0).last() {
+        emu.write_mem_1byte(desc.addr + desc.len as u64 - 1, data);
+    }
+}
+
+// Total length of device-writable buffers.
+pub fn virtq_writable_len(chain: &[VirtqDesc]) -> usize {
+    chain.iter().filter(|d| d.is_write()).map(|d| d.len as usize).sum()
+}
+
+impl EmuEnv {
+    pub fn virtio_mmio_read(&mut self, slot: usize, offset: u64, size: usize) -> u64 {
+        return self.m_virtio[slot].read(offset, size);
+    }
+
+    pub fn virtio_mmio_write(&mut self, slot: usize, offset: u64, size: usize, data: u64) {
+        if let Some(queue_idx) = self.m_virtio[slot].write(offset, size, data) {
+            self.virtio_notify(slot, queue_idx);
+        }
+        if offset == VIRTIO_MMIO_INTERRUPT_ACK || offset == VIRTIO_MMIO_STATUS {
+            self.virtio_update_irq(slot);
+        }
+    }
+
+    fn virtio_notify(&mut self, slot: usize, queue_idx: usize) {
+        // Take the device out so that it can access guest memory through EmuEnv.
+        let mut virtio = std::mem::take(&mut self.m_virtio);
+        if virtio[slot].process_queue(self, queue_idx) {
+            virtio[slot].m_interrupt_status |= VIRTIO_MMIO_INT_VRING;
+        }
+        self.m_virtio = virtio;
+        self.virtio_update_irq(slot);
+    }
+
+    pub fn virtio_poll(&mut self) {
+        for slot in 0..self.m_virtio.len() {
+            let queue_idx = match &mut self.m_virtio[slot].m_device {
+                Some(dev) => dev.poll(),
+                None => None,
+            };
+            if let Some(queue_idx) = queue_idx {
+                self.virtio_notify(slot, queue_idx);
+            }
+        }
+    }
+
+    pub fn virtio_config_changed(&mut self, slot: usize) {
+        self.m_virtio[slot].m_config_generation += 1;
+        self.m_virtio[slot].m_interrupt_status |= VIRTIO_MMIO_INT_CONFIG;
+        self.virtio_update_irq(slot);
+    }
+
+    fn virtio_update_irq(&mut self, slot: usize) {
+        let irq = self.m_virtio[slot].m_irq;
+        let level = self.m_virtio[slot].m_interrupt_status != 0;
+        self.plic_set_irq(irq, level);
+    }
+}
@@ -0,0 +1,35 @@
+use crate::device::entropy::EntropySource;
+use crate::device::virtio_mmio::{virtq_writable_len, virtq_write_chain, VirtioDevice, VirtqDesc};
+use crate::emu_env::{EmuEnv, RngBackend};
+
+const VIRTIO_ID_RNG: u32 = 4;
+
+pub struct VirtioRng {
+    m_entropy: EntropySource,
+}
+
+impl VirtioRng {
+    pub fn new(backend: RngBackend, seed: u64) -> VirtioRng {
+        VirtioRng {
+            m_entropy: EntropySource::new(backend, seed),
+        }
+    }
+}
+
+impl VirtioDevice for VirtioRng {
+    fn device_id(&self) -> u32 { VIRTIO_ID_RNG }
+
+    fn device_features(&self) -> u64 { 0 }
+
+    fn num_queues(&self) -> usize { 1 }
+
+    fn read_config(&self, _offset: u64) -> u8 { 0 }
+
+    fn process_chain(&mut self, emu: &EmuEnv, _queue_idx: usize, chain: &[VirtqDesc]) -> u32 {
+        let mut data = vec![0u8; virtq_writable_len(chain)];
+        if !self.m_entropy.fill(&mut data) {
+            return 0;
+        }
+        return virtq_write_chain(emu, chain, &data);
+    }
+}
@@ -5,16 +5,30 @@ use arr_macro::arr;
 use std::mem;
 use clap::{Parser, ValueEnum};
 
+use crate::device::{VIRT_VIRTIO_IRQ, VIRTIO_SLOT_BLK, VIRTIO_SLOT_CONSOLE, VIRTIO_SLOT_RNG, VIRTIO_SLOT_9P};
+use crate::device::cfi_flash::CfiFlash;
+use crate::device::entropy::EntropySource;
+use crate::device::goldfish_rtc::GoldfishRtc;
+use crate::device::plic::Plic;
+use crate::device::virtio_9p::Virtio9p;
+use crate::device::virtio_blk::VirtioBlk;
+use crate::device::virtio_console::VirtioConsole;
+use crate::device::virtio_rng::VirtioRng;
+use crate::device::virtio_mmio::{VirtioDevice, VirtioMmio, VIRTIO_MMIO_NUM_SLOTS};
 use crate::elf_loader::{ELFLoader};
 use crate::elf_loader::ProgramHeader;
 use crate::elf_loader::SectionHeader;
 
+use crate::target::riscv::debug::DebugCause;
+use crate::target::riscv::hpm::{HpmBlock, HpmEvent, TbInst};
+use crate::target::riscv::interrupt::CAUSE_INTERRUPT;
 use crate::target::riscv::riscv::{ExceptCode, PrivMode, TranslateRiscv};
 use crate::target::riscv::riscv_csr::{CsrAddr, RiscvCsr};
 use crate::target::riscv::riscv_csr_def;
 use crate::target::riscv::riscv_decoder::decode_inst;
+use crate::target::riscv::riscv_decoder_extra::{decode_inst_bitmanip, decode_inst_crypto, decode_inst_ext, decode_inst_vector, decode_inst_zcmp, decode_inst_zfa, decode_inst_zfh};
 use crate::target::riscv::riscv_inst_id::RiscvInstId;
-use crate::target::riscv::mmu::{MemAccType};
+use crate::target::riscv::mmu::{MemAccType, TlbContext, TlbEntry};
 use crate::target::riscv::riscv_disassemble::{disassemble_riscv};
 
 use crate::tcg::tcg::{TCGOp, TCG, TCGOpcode};
@@ -30,7 +44,25 @@ fn calc_hash_func(addr: u64) -> usize {
     ((addr >> 1) & 0x3ff) as usize
 }
 
-const TLB_SIZE: usize = 4096;
+pub const TLB_SIZE: usize = 4096;
+
+// Inline TLB : index = VA[23:12], tag = VA[63:24].
+// Tag keeps all upper bits of VA, so that Sv48/Sv57 addresses never alias each other.
+pub const TLB_IDX_SHIFT: u64 = 12;
+pub const TLB_TAG_SHIFT: u64 = 24;
+pub const TLB_INVALID_TAG: u64 = 0xdeadbeef_01234567;   // Larger than any VA >> TLB_TAG_SHIFT
+
+#[inline]
+pub fn tlb_index(addr: u64) -> usize {
+    ((addr >> TLB_IDX_SHIFT) as usize) & (TLB_SIZE - 1)
+}
+
+#[inline]
+pub fn tlb_tag(addr: u64) -> u64 {
+    addr >> TLB_TAG_SHIFT
+}
+
+const DEVICE_POLL_INTERVAL: usize = 0x1000;
 
 #[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
 pub enum MachineEnum {
@@ -40,6 +72,59 @@ pub enum MachineEnum {
     RiscvSiFiveU,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum DriveMode {
+    #[clap(name = "rw")]
+    ReadWrite,
+    #[clap(name = "ro")]
+    ReadOnly,
+    #[clap(name = "cow")]
+    Cow,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum ConsoleBackend {
+    #[clap(name = "stdio")]
+    Stdio,
+    #[clap(name = "socket")]
+    Socket,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum RngBackend {
+    #[clap(name = "urandom")]
+    Urandom,
+    #[clap(name = "seeded")]
+    Seeded,
+}
+
+// Machine state reported by the test finisher device
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MachineStatus {
+    Running,
+    Pass,
+    Fail(u16),
+    Reset,
+    Halted,     // Hart is halted in Debug Mode, waiting for the debugger
+}
+
+impl MachineStatus {
+    // Process exit code: the OS keeps only the low 8 bits, so a Fail code with low byte 0
+    // (including 0 itself) is still reported as failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            MachineStatus::Running => 0,
+            MachineStatus::Pass    => 0,
+            MachineStatus::Fail(code) => match *code & 0xff {
+                0 => 1,
+                low => low as i32,
+            },
+            MachineStatus::Reset   => 0,
+            MachineStatus::Halted  => 0,
+        }
+    }
+}
+
 #[derive(Parser, Debug, Clone)]
 #[clap(
     name = "Hydra",
@@ -78,22 +163,103 @@ pub struct ArgConfig {
     #[arg(long = "opt-reg-fwd", help = "Apply optimization : Register Forwarding")]
     pub opt_reg_fwd: bool,
 
+    #[arg(long = "no-host-bitmanip", help = "Don't use LZCNT / TZCNT / POPCNT / PCLMULQDQ of the host for Zbb / Zbc")]
+    pub no_host_bitmanip: bool,
+
+    #[arg(long = "no-host-crypto", help = "Don't use AES-NI of the host for Zkne / Zknd")]
+    pub no_host_crypto: bool,
+
+    #[arg(long = "drive", help = "Raw disk image for virtio-blk")]
+    pub drive: Option<String>,
+
+    #[arg(value_enum, long = "drive-mode", default_value = "rw", help = "virtio-blk image access mode (cow: writes are discarded at exit)")]
+    pub drive_mode: DriveMode,
+
+    #[arg(value_enum, long = "virtio-console", help = "Add virtio-console with specified backend")]
+    pub virtio_console: Option<ConsoleBackend>,
+
+    #[arg(long = "console-socket", help = "Unix socket path for virtio-console socket backend")]
+    pub console_socket: Option<String>,
+
+    #[arg(value_enum, long = "virtio-rng", help = "Add virtio-rng with specified entropy source")]
+    pub virtio_rng: Option<RngBackend>,
+
+    #[arg(long = "rng-seed", default_value_t = 0, help = "Seed for seeded backend of virtio-rng and Zkr")]
+    pub rng_seed: u64,
+
+    #[arg(long = "virtfs", help = "Host directory exported to the guest by virtio-9p")]
+    pub virtfs: Option<String>,
+
+    #[arg(long = "virtfs-tag", default_value = "hostshare", help = "Mount tag of virtio-9p")]
+    pub virtfs_tag: String,
+
+    #[arg(long = "virtfs-ro", help = "Export virtio-9p directory as read-only")]
+    pub virtfs_ro: bool,
+
+    #[arg(long = "rtc-epoch", help = "Goldfish RTC reports fixed time (seconds since 1970) instead of host time")]
+    pub rtc_epoch: Option<u64>,
+
+    #[arg(long = "pflash0", help = "Backing file of CFI flash bank 0")]
+    pub pflash0: Option<String>,
+
+    #[arg(long = "pflash1", help = "Backing file of CFI flash bank 1")]
+    pub pflash1: Option<String>,
+
+    #[arg(long = "svadu", help = "Update PTE A/D bits by hardware instead of raising page fault")]
+    pub svadu: bool,
+
+    #[arg(long = "svpbmt", help = "Enable Svpbmt : PTE memory types (menvcfg.PBMTE)")]
+    pub svpbmt: bool,
+
+    #[arg(long = "svnapot", help = "Enable Svnapot : 64KiB NAPOT pages")]
+    pub svnapot: bool,
+
+    #[arg(long = "hypervisor", help = "Enable H extension : VS/VU-mode and two-stage address translation")]
+    pub hypervisor: bool,
+
+    #[arg(long = "vector-table", help = "Enable CLIC-style vector table : xtvec.MODE = 3 jumps to the handler address in mtvt / stvt")]
+    pub vector_table: bool,
+
+    #[arg(long = "zcmp", help = "Enable Zcmp : cm.push / cm.pop / cm.mvsa01 etc. take over the C.FSDSP encodings")]
+    pub zcmp: bool,
+
+    #[arg(value_enum, long = "zkr", help = "Enable Zkr : seed CSR with specified entropy source")]
+    pub zkr: Option<RngBackend>,
+
+    #[arg(long = "vlen", default_value_t = 128, help = "V extension : bits of a vector register (VLEN), power of 2 from 32 to 65536")]
+    pub vlen: usize,
+
+    #[arg(long = "elen", default_value_t = 64, help = "V extension : maximum element bits (ELEN), 32 or 64")]
+    pub elen: usize,
+
+    #[arg(long = "cbo-block-size", default_value_t = 64, help = "Zicbom / Zicboz : bytes of a cache block, power of 2 from 8 to 4096 (virt device tree reports it as riscv,cbom-block-size / riscv,cboz-block-size)")]
+    pub cbo_block_size: u64,
+
+    #[arg(long = "cycle-per-inst", default_value_t = 1, help = "Cycle model : cycles counted for each retired instruction")]
+    pub cycle_per_inst: u64,
+
+    #[arg(long = "tlb-miss-cycles", default_value_t = 0, help = "Cycle model : extra cycles counted for each page table walk")]
+    pub tlb_miss_cycles: u64,
+
     #[arg(long = "elf-file", required = true)]
     pub elf_file: String,
 }
 
+#[repr(C)]  // head must be placed at the top of struct : helper functions receive &head as &mut EmuEnv.
 pub struct EmuEnv {
     pub head: [u64; 1], // pointer of this struct. Do not move.
 
     pub m_priv: PrivMode,
+    pub m_virt: bool,       // Virtualization mode (V). VS-mode / VU-mode when set.
 
     pub m_iregs: [u64; 32],  // Integer Registers
     pub m_fregs: [u64; 32], // Floating Point Registers
+    pub m_vregs: Vec<u8>,   // Vector Registers : 32 x VLENB bytes, element 0 at the lowest address
     pub m_pc: [u64; 1],
 
     pub m_csr: RiscvCsr<i64>, // CSR implementation
 
-    helper_func: [fn(emu: &mut EmuEnv, arg0: u64, arg1: u64, arg2: u64, arg3: u64) -> usize; 59],
+    helper_func: [fn(emu: &mut EmuEnv, arg0: u64, arg1: u64, arg2: u64, arg3: u64) -> usize; 108],
 
     pub m_riscv_trans: TranslateRiscv,
 
@@ -107,18 +273,36 @@ pub struct EmuEnv {
     pub m_tb_text_hash_address: [u64; TCG_HASH_SIZE],
     pub m_tb_text_hash_inst_size: [usize; TCG_HASH_SIZE],
     pub m_tb_text_hash_memmap: [MemoryMap; TCG_HASH_SIZE],
+    pub m_tb_text_hash_ctx: [u64; TCG_HASH_SIZE],
+    pub m_tb_text_hash_page_mask: [u64; TCG_HASH_SIZE],
+    pub m_tb_text_hash_insts: Vec<Vec<TbInst>>,  // Retired instructions and their events, for performance counters
+    pub m_hpm_block: HpmBlock,
 
     pub m_host_prologue: [u8; 15],
     pub m_host_epilogue: [u8; 11],
 
     m_updated_pc : bool,
 
-    pub m_tlb_vec: [u64; TLB_SIZE],
+    pub m_tlb_vec: [u64; TLB_SIZE],         // Tags for loads
+    pub m_tlb_wr_vec: [u64; TLB_SIZE],      // Tags for stores
     pub m_tlb_addr_vec: [u64; TLB_SIZE],
+    pub m_tlb_entry_vec: [TlbEntry; TLB_SIZE],
+    pub m_tlb_fill: TlbEntry,               // Result of the last translation
+    pub m_tlb_ctx: TlbContext,
+    // Extra information of the exception being raised, consumed by generate_exception
+    pub m_excp_gva: bool,                   // tval is a guest virtual address
+    pub m_excp_tval2: u64,                  // Guest physical address >> 2 of guest page fault
     // Configuration
     pub m_arg_config: ArgConfig,
 
-    pub m_notify_exit: bool,
+    pub m_machine_status: MachineStatus,
+    pub m_debug_haltreq: bool,              // Halt request from the debugger, taken at the next block boundary
+
+    // Devices
+    pub m_plic: Plic,
+    pub m_virtio: Vec<VirtioMmio>,
+    pub m_rtc: GoldfishRtc,
+    pub m_flash: [Option<CfiFlash>; 2],
 
     pub m_gpr_usage_list: [Option<X86TargetRM>; 32],
     pub m_x86reg_usage_list: [Option<u64>; X86TargetRM::SENTINEL as usize],  // Sentinel
@@ -127,12 +311,14 @@ pub struct EmuEnv {
 
 impl EmuEnv {
     pub fn new(arg_config: ArgConfig) -> EmuEnv {
-        EmuEnv {
+        let mut emu = EmuEnv {
             head: [0xdeadbeef; 1],
             m_priv: PrivMode::Machine,
+            m_virt: false,
 
             m_iregs: [0; 32],
             m_fregs: [0; 32],
+            m_vregs: vec![],
             m_pc: [0x8000_0000; 1],
             m_csr: RiscvCsr::new(),
 
@@ -196,6 +382,55 @@ impl EmuEnv {
                 Self::helper_func_float_store32,
                 Self::helper_func_sfence_vma,
                 Self::helper_func_fcvt,
+                Self::helper_func_hlv,
+                Self::helper_func_hsv,
+                Self::helper_func_hfence_vvma,
+                Self::helper_func_hfence_gvma,
+                Self::helper_func_ebreak,
+                Self::helper_func_dret,
+                Self::helper_func_float_load16,
+                Self::helper_func_float_store16,
+                Self::helper_func_fadd_h,
+                Self::helper_func_fsub_h,
+                Self::helper_func_fmul_h,
+                Self::helper_func_fdiv_h,
+                Self::helper_func_fmadd_h,
+                Self::helper_func_fmsub_h,
+                Self::helper_func_fnmsub_h,
+                Self::helper_func_fnmadd_h,
+                Self::helper_func_fsqrt_h,
+                Self::helper_func_feq_h,
+                Self::helper_func_flt_h,
+                Self::helper_func_fle_h,
+                Self::helper_func_fclass_h,
+                Self::helper_func_fmax_h,
+                Self::helper_func_fmin_h,
+                Self::helper_func_fsgnj_h,
+                Self::helper_func_fsgnjn_h,
+                Self::helper_func_fsgnjx_h,
+                Self::helper_func_fmv_x_h,
+                Self::helper_func_fmv_h_x,
+                Self::helper_func_fli_s,
+                Self::helper_func_fminmaxm_s,
+                Self::helper_func_fround_s,
+                Self::helper_func_fcmpq_s,
+                Self::helper_func_fli_d,
+                Self::helper_func_fminmaxm_d,
+                Self::helper_func_fround_d,
+                Self::helper_func_fcmpq_d,
+                Self::helper_func_fcvtmod_w_d,
+                Self::helper_func_fmvh_x_d,
+                Self::helper_func_fmvp_d_x,
+                Self::helper_func_fli_h,
+                Self::helper_func_fminmaxm_h,
+                Self::helper_func_fround_h,
+                Self::helper_func_fcmpq_h,
+                Self::helper_func_vsetvl,
+                Self::helper_func_vldst,
+                Self::helper_func_vop,
+                Self::helper_func_vfop,
+                Self::helper_func_crypto,
+            Self::helper_func_cbo,
             ],
             m_riscv_trans: TranslateRiscv::new(),
 
@@ -208,6 +443,10 @@ impl EmuEnv {
             // m_tb_text_hashmap: FnvHashMap::with_capacity_and_hasher (0, Default::default()),
             m_tb_text_hash_address: [0; TCG_HASH_SIZE],
             m_tb_text_hash_inst_size: [0; TCG_HASH_SIZE],
+            m_tb_text_hash_ctx: [0; TCG_HASH_SIZE],
+            m_tb_text_hash_page_mask: [!0xfff; TCG_HASH_SIZE],
+            m_tb_text_hash_insts: vec![vec![]; TCG_HASH_SIZE],
+            m_hpm_block: HpmBlock::new(),
             m_tb_text_hash_memmap: arr![MemoryMap::new(0x2000, &[
                 MapOption::MapReadable,
                 MapOption::MapWritable,
@@ -245,15 +484,74 @@ impl EmuEnv {
 
             m_curr_hash_key: 0,
             // TLB format
-            m_tlb_vec: [0xdeadbeef_01234567; TLB_SIZE],
+            m_tlb_vec: [TLB_INVALID_TAG; TLB_SIZE],
+            m_tlb_wr_vec: [TLB_INVALID_TAG; TLB_SIZE],
             m_tlb_addr_vec: [0x0; TLB_SIZE],
+            m_tlb_entry_vec: [TlbEntry::invalid(); TLB_SIZE],
+            m_tlb_fill: TlbEntry::invalid(),
+            m_tlb_ctx: TlbContext::machine(),
+            m_excp_gva: false,
+            m_excp_tval2: 0,
             m_arg_config: arg_config,
 
             loop_idx: 0,
-            m_notify_exit: false,
+            m_machine_status: MachineStatus::Running,
+            m_debug_haltreq: false,
+
+            m_plic: Plic::new(),
+            m_virtio: Vec::new(),
+            m_rtc: GoldfishRtc::new(None),
+            m_flash: [None, None],
 
             m_gpr_usage_list: [None; 32],
             m_x86reg_usage_list: [None; X86TargetRM::SENTINEL as usize],
+        };
+        if emu.m_arg_config.svpbmt {
+            emu.m_csr.m_menvcfg_wmask |= 1 << riscv_csr_def::SYSREG_MENVCFG_PBMTE_LSB;
+        }
+        emu.m_csr.m_tvt_enabled = emu.m_arg_config.vector_table;
+        emu.m_csr.m_entropy = emu.m_arg_config.zkr.map(|backend| EntropySource::new(backend, emu.m_arg_config.rng_seed));
+        if emu.m_arg_config.hypervisor {
+            emu.m_csr.m_misa.m_csr |= 1 << ('H' as u8 - 'A' as u8);
+        }
+        let (vlen, elen) = (emu.m_arg_config.vlen, emu.m_arg_config.elen);
+        if !vlen.is_power_of_two() || !(32..=65536).contains(&vlen) || !(elen == 32 || elen == 64) || vlen < elen {
+            panic!("Error: unsupported vector configuration VLEN = {}, ELEN = {}", vlen, elen);
+        }
+        emu.m_vregs = vec![0; 32 * vlen / 8];
+        emu.m_csr.m_vlenb.m_csr = (vlen / 8) as i64;
+        emu.m_csr.m_misa.m_csr |= 1 << ('V' as u8 - 'A' as u8);
+        let cbo_block_size = emu.m_arg_config.cbo_block_size;
+        if !cbo_block_size.is_power_of_two() || !(8..=4096).contains(&cbo_block_size) {
+            panic!("Error: unsupported cache block size {}", cbo_block_size);
+        }
+        emu.init_devices();
+        emu
+    }
+
+    fn init_devices(&mut self) {
+        if self.m_arg_config.machine == MachineEnum::RiscvVirt {
+            self.m_rtc = GoldfishRtc::new(self.m_arg_config.rtc_epoch);
+            self.m_flash = [self.m_arg_config.pflash0.as_ref().map(|f| CfiFlash::new(f)),
+                            self.m_arg_config.pflash1.as_ref().map(|f| CfiFlash::new(f))];
+            for slot in 0..VIRTIO_MMIO_NUM_SLOTS {
+                let device: Option<Box<dyn VirtioDevice>> = match slot {
+                    VIRTIO_SLOT_BLK => self.m_arg_config.drive.as_ref().map(|drive| {
+                        Box::new(VirtioBlk::new(drive, self.m_arg_config.drive_mode)) as Box<dyn VirtioDevice>
+                    }),
+                    VIRTIO_SLOT_CONSOLE => self.m_arg_config.virtio_console.map(|backend| {
+                        Box::new(VirtioConsole::new(backend, self.m_arg_config.console_socket.as_ref())) as Box<dyn VirtioDevice>
+                    }),
+                    VIRTIO_SLOT_RNG => self.m_arg_config.virtio_rng.map(|backend| {
+                        Box::new(VirtioRng::new(backend, self.m_arg_config.rng_seed)) as Box<dyn VirtioDevice>
+                    }),
+                    VIRTIO_SLOT_9P => self.m_arg_config.virtfs.as_ref().map(|path| {
+                        Box::new(Virtio9p::new(path, &self.m_arg_config.virtfs_tag, self.m_arg_config.virtfs_ro)) as Box<dyn VirtioDevice>
+                    }),
+                    _ => None,
+                };
+                self.m_virtio.push(VirtioMmio::new(VIRT_VIRTIO_IRQ + slot, device));
+            }
         }
     }
 
@@ -299,7 +597,7 @@ impl EmuEnv {
         return self.m_iregs;
     }
 
-    pub fn run(&mut self) {
+    pub fn run(&mut self) -> MachineStatus {
         let loader = match ELFLoader::new(&self.m_arg_config.elf_file) {
             Ok(loader) => loader,
             Err(error) => panic!("There was a problem opening the file: {:?}, {:}", error, &self.m_arg_config.elf_file),
@@ -308,6 +606,10 @@ impl EmuEnv {
         let elf_header = loader.get_elf_header();
         elf_header.dump();
 
+        if self.m_arg_config.debug {
+            self.dump_memmap();
+        }
+
         let mut ph_headers = Vec::new();
         for ph_idx in 0..elf_header.e_phnum {
             let phdr: ProgramHeader = loader.get_program_header(
@@ -354,6 +656,10 @@ impl EmuEnv {
             }
         }
 
+        if self.m_arg_config.machine == MachineEnum::RiscvVirt {
+            self.load_virt_fdt();
+        }
+
         // Emit Prologue
         for b in &self.m_host_prologue {
             self.m_tcg_raw_vec.push(*b);
@@ -370,6 +676,8 @@ impl EmuEnv {
         };
 
         self.run_loop();
+
+        return self.m_machine_status;
     }
 
     fn execute_func(&self, tb_text: *mut u8) {
@@ -385,31 +693,56 @@ impl EmuEnv {
         }
     }
 
-    fn run_loop (&mut self) {
+    pub fn run_loop (&mut self) {
         let start = Instant::now();
         let loop_max = 10000000;
+        let mut poll_count: usize = 0;
         self.loop_idx = 5;
         while self.loop_idx < loop_max {
+            poll_count += 1;
+            if poll_count % DEVICE_POLL_INTERVAL == 0 {
+                self.poll_devices();
+            }
+
+            if self.debug_check_halt() {
+                break;
+            }
+            if !self.debug_mask_interrupts() {
+                if let Some(irq) = self.pending_interrupt() {
+                    self.generate_interrupt(irq);
+                }
+            }
             if self.m_arg_config.debug {
                 eprintln!("========= BLOCK START =========");
             }
 
             assert!(self.m_pc[0] >= 0x8000_0000);
             self.m_curr_hash_key = calc_hash_func(self.m_pc[0]);
-            if self.m_arg_config.debug {
-                self.decode_and_run();
+            // Single step executes a block of one instruction and halts again.
+            let stepping = self.debug_single_step();
+            let translated = if self.m_arg_config.debug || stepping {
+                self.decode_and_run()
             } else {
-                if self.m_tb_text_hash_address[self.m_curr_hash_key] == self.m_pc[0] {
+                if self.m_tb_text_hash_address[self.m_curr_hash_key] == self.m_pc[0] &&
+                    self.tb_context_match(self.m_tb_text_hash_ctx[self.m_curr_hash_key]) {
                     let inst_size = self.m_tb_text_hash_inst_size[self.m_curr_hash_key];
                     // let mem_map = &self.m_tb_text_hash_memmap[hash_key];
 
                     self.m_pc[0] = self.m_pc[0] + inst_size as u64;
+                    true
                 } else {
-                    self.decode_and_run();
+                    self.decode_and_run()
                 }
             };
 
-            self.execute_func(self.m_tb_text_hash_memmap[self.m_curr_hash_key].data());
+            if translated {
+                self.hpm_block_start(self.m_curr_hash_key);
+                self.execute_func(self.m_tb_text_hash_memmap[self.m_curr_hash_key].data());
+                self.hpm_block_end();
+            }
+            if stepping && !self.m_csr.m_debug_mode {
+                self.enter_debug_mode(self.m_pc[0], DebugCause::Step);
+            }
 
             // if self.m_arg_config.dump_gpr {
             //     self.dump_gpr();
@@ -425,7 +758,7 @@ impl EmuEnv {
             //     self.write_mem_4byte(0x80001000, 0);
             //     self.write_mem_4byte(0x80001040, 1);
             // }
-            if self.m_arg_config.machine == MachineEnum::RiscvSiFiveU && self.m_notify_exit {
+            if self.m_machine_status != MachineStatus::Running {
                 break;
             }
             // if self.get_mem(0x3000) != 0 {
@@ -560,6 +893,13 @@ impl EmuEnv {
         diff
     }
 
+    pub fn calc_tlb_wr_relat_address(&self) -> isize {
+        let tlb_ptr = self.m_tlb_wr_vec.as_ptr() as *const u8;
+        let self_ptr = self.head.as_ptr() as *const u8;
+        let diff = unsafe { tlb_ptr.offset_from(self_ptr) };
+        diff
+    }
+
     pub fn calc_tlb_addr_relat_address(&self) -> isize {
         let tlb_ptr = self.m_tlb_addr_vec.as_ptr() as *const u8;
         let self_ptr = self.head.as_ptr() as *const u8;
@@ -585,39 +925,74 @@ impl EmuEnv {
                 code as u32, tval, guest_pc
             );
         }
-        let epc = guest_pc;
+        self.hpm_block_stop(guest_pc);
+        self.take_trap(guest_pc, code as u64, false, tval);
+    }
 
+    // Trap entry shared by exceptions and interrupts. Traps taken in M-mode are never delegated.
+    pub fn take_trap(&mut self, epc: u64, code: u64, interrupt: bool, tval: i64) {
         let curr_priv: PrivMode = self.m_priv;
+        let curr_virt: bool = self.m_virt;
+        let gva = self.m_excp_gva as i64;
+        let tval2 = self.m_excp_tval2;
+        self.m_excp_gva = false;
+        self.m_excp_tval2 = 0;
 
         let mut mstatus: i64;
         let mut sstatus: i64;
         let tvec: i64;
-        let medeleg = self.m_csr.csrrs(CsrAddr::Medeleg, 0);
+        let tvt: i64;
+        // Interrupts are delegated by mideleg / hideleg, exceptions by medeleg / hedeleg
+        let (deleg, hdeleg) = if interrupt {
+            (self.m_csr.csrrs(CsrAddr::Mideleg, 0), self.m_csr.csrrs(CsrAddr::Hideleg, 0))
+        } else {
+            (self.m_csr.csrrs(CsrAddr::Medeleg, 0), self.m_csr.csrrs(CsrAddr::Hedeleg, 0))
+        };
         let mut next_priv: PrivMode = PrivMode::Machine;
 
         self.m_priv = next_priv;
 
-        if (medeleg & (1 << (code as u32))) != 0 {
+        let deleg_hs = curr_priv != PrivMode::Machine && (deleg & (1 << code)) != 0;
+        // Traps from VS/VU-mode can be delegated again to VS-mode
+        let deleg_vs = deleg_hs && curr_virt && (hdeleg & (1 << code)) != 0;
+        // VS-level interrupts are seen as S-level interrupts in VS-mode
+        let code = if interrupt && deleg_vs { code - 1 } else { code };
+        let cause = code as i64 | if interrupt { CAUSE_INTERRUPT } else { 0 };
+
+        if deleg_vs {
+            self.m_csr.csrrw(CsrAddr::Vsepc, epc as i64);
+            self.m_csr.csrrw(CsrAddr::Vscause, cause);
+            self.m_csr.csrrw(CsrAddr::Vstval, tval as i64);
+
+            tvec = self.m_csr.csrrs(CsrAddr::Vstvec, 0 as i64);
+            tvt = 0;
+            next_priv = PrivMode::Supervisor;
+        } else if deleg_hs {
             // Delegation
             self.m_csr.csrrw(CsrAddr::Sepc, epc as i64);
-            self.m_csr.csrrw(CsrAddr::Scause, code as i64);
+            self.m_csr.csrrw(CsrAddr::Scause, cause);
             self.m_csr.csrrw(CsrAddr::Stval, tval as i64);
+            self.m_csr.csrrw(CsrAddr::Htval, tval2 as i64);
 
             tvec = self.m_csr.csrrs(CsrAddr::Stvec, 0 as i64);
+            tvt = self.m_csr.csrrs(CsrAddr::Stvt, 0 as i64);
             next_priv = PrivMode::Supervisor;
         } else {
             self.m_csr.csrrw(CsrAddr::Mepc, epc as i64);
-            self.m_csr.csrrw(CsrAddr::Mcause, code as i64);
+            self.m_csr.csrrw(CsrAddr::Mcause, cause);
             self.m_csr.csrrw(CsrAddr::Mtval, tval as i64);
+            self.m_csr.csrrw(CsrAddr::Mtval2, tval2 as i64);
+            self.m_csr.trigger_trap_to_machine();
 
             tvec = self.m_csr.csrrs(CsrAddr::Mtvec, 0 as i64);
-            eprint!("tvec = {:016x}\n", tvec);
+            tvt = self.m_csr.csrrs(CsrAddr::Mtvt, 0 as i64);
         }
 
         // Update status CSR
-        if (medeleg & (1 << (code as u32))) != 0 {
-            // Delegation
-            sstatus = self.m_csr.csrrs(CsrAddr::Sstatus, 0 as i64);
+        if deleg_hs {
+            // Delegation : vsstatus for VS-mode, sstatus for HS-mode
+            let status_addr = if deleg_vs { CsrAddr::Vsstatus } else { CsrAddr::Sstatus };
+            sstatus = self.m_csr.csrrs(status_addr, 0 as i64);
             sstatus = Self::set_bit_field(
                 sstatus,
                 Self::extract_bit_field(
@@ -640,7 +1015,7 @@ impl EmuEnv {
                 riscv_csr_def::SYSREG_SSTATUS_SIE_MSB,
                 riscv_csr_def::SYSREG_SSTATUS_SIE_LSB,
             );
-            self.m_csr.csrrw(CsrAddr::Sstatus, sstatus as i64);
+            self.m_csr.csrrw(status_addr, sstatus as i64);
         } else {
             mstatus = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
             mstatus = Self::set_bit_field(
@@ -665,24 +1040,41 @@ impl EmuEnv {
                 riscv_csr_def::SYSREG_MSTATUS_MIE_MSB,
                 riscv_csr_def::SYSREG_MSTATUS_MIE_LSB,
             );
+            if self.m_arg_config.hypervisor {
+                mstatus = Self::set_bit_field(mstatus, curr_virt as i64, riscv_csr_def::SYSREG_MSTATUS_MPV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPV_LSB);
+                mstatus = Self::set_bit_field(mstatus, gva, riscv_csr_def::SYSREG_MSTATUS_GVA_MSB, riscv_csr_def::SYSREG_MSTATUS_GVA_LSB);
+            }
 
             self.m_csr.csrrw(CsrAddr::Mstatus, mstatus);
         }
 
+        // Trap to HS-mode records where it came from in hstatus
+        if deleg_hs && !deleg_vs && self.m_arg_config.hypervisor {
+            let mut hstatus = self.m_csr.csrrs(CsrAddr::Hstatus, 0);
+            hstatus = Self::set_bit_field(hstatus, curr_virt as i64, riscv_csr_def::SYSREG_HSTATUS_SPV_MSB, riscv_csr_def::SYSREG_HSTATUS_SPV_LSB);
+            if curr_virt {
+                hstatus = Self::set_bit_field(hstatus, curr_priv as i64, riscv_csr_def::SYSREG_HSTATUS_SPVP_MSB, riscv_csr_def::SYSREG_HSTATUS_SPVP_LSB);
+            }
+            hstatus = Self::set_bit_field(hstatus, gva, riscv_csr_def::SYSREG_HSTATUS_GVA_MSB, riscv_csr_def::SYSREG_HSTATUS_GVA_LSB);
+            self.m_csr.csrrw(CsrAddr::Hstatus, hstatus);
+        }
+
         // self.set_priv_mode(next_priv);
         self.m_priv = next_priv;
+        self.m_virt = deleg_vs;
         // self.set_pc(tvec as u64);
         // self.set_update_pc(true);
-        self.m_pc[0] = tvec as u64;
+        self.m_pc[0] = self.trap_vector(tvec, tvt, code, interrupt);
 
         if self.m_arg_config.debug {
             eprintln!(
-                "<Info: Exception. ChangeMode from {} to {}>",
+                "<Info: Trap. ChangeMode from {} to {}>",
                 curr_priv as u32, next_priv as u32
             );
             eprintln!("<Info: Set Program Counter = 0x{:16x}>", self.m_pc[0]);
         }
         self.m_updated_pc = true;
+        self.check_tlb_context();
 
         return;
     }
@@ -754,7 +1146,9 @@ impl EmuEnv {
         self.write_mem_4byte(guest_phy_addr + 4, data1 as u32);
     }
 
-    fn decode_and_run(&mut self) {
+    // Returns false when the block head raised fetch exception,
+    // in that case nothing is translated and m_pc points to the trap vector.
+    fn decode_and_run(&mut self) -> bool {
         let mut tcg_vec = vec![];
         if self.m_arg_config.debug {
             eprint!("{:}: Guest PC Address = {:08x}\n", self.loop_idx, self.m_pc[0]);
@@ -762,23 +1156,72 @@ impl EmuEnv {
 
         let mut total_inst_byte = 0;
         let init_pc = self.m_pc[0];
+        let mut tb_ctx = 0;
+        let mut tb_page_mask = !0xfff;
+        let mut tb_insts = vec![];
         #[allow(while_true)]
         while true {
+            // Block never crosses guest page (a 32-bit instruction at the end of page is translated
+            // as a block of its own, see below), so fetch fault is always raised at the block head
+            // and all the preceding instructions are committed.
+            if (self.m_pc[0] ^ init_pc) >> 12 != 0 {
+                break;
+            }
             self.loop_idx += 1;
             #[allow(unused_assignments)]
             let mut guest_phy_addr = 0;
+            // Execute address trigger fires before the instruction is fetched.
+            // Block is closed just before the instruction so that it is raised at the block head.
+            if let Some(chain) = self.exec_trigger_match(self.m_pc[0]) {
+                if !tcg_vec.is_empty() {
+                    break;
+                }
+                self.fire_trigger(self.m_pc[0], self.m_pc[0], chain);
+                return false;
+            }
             match self.convert_physical_address(self.m_pc[0], self.m_pc[0], MemAccType::Fetch) {
                 Ok(addr) => guest_phy_addr = addr,
                 Err(_error) => {
-                    continue;
+                    return false;
                 }
             };
+            if tcg_vec.is_empty() {
+                tb_ctx = self.tb_context(self.m_tlb_fill.is_global());
+                tb_page_mask = self.m_tlb_fill.page_mask;
+            }
             if self.m_arg_config.mmu_debug {
                 eprint!("  converted physical address = {:08x}\n", guest_phy_addr);
             }
-            let guest_inst = self.read_mem_4byte(guest_phy_addr);
+            let mut guest_inst = if self.m_pc[0] & 0xfff == 0xffe {
+                self.read_mem_2byte(guest_phy_addr) as u32
+            } else {
+                self.read_mem_4byte(guest_phy_addr)
+            };
+            // 32-bit instruction crossing the page : upper half is fetched through the next page.
+            // It depends on two translations, so the block is flushed by any SFENCE.VMA for its ASID.
+            if self.m_pc[0] & 0xfff == 0xffe && guest_inst & 0x3 == 0x3 {
+                if !tcg_vec.is_empty() {
+                    break;
+                }
+                match self.convert_physical_address(self.m_pc[0], self.m_pc[0] + 2, MemAccType::Fetch) {
+                    Ok(addr) => guest_inst |= (self.read_mem_2byte(addr) as u32) << 16,
+                    Err(_error) => {
+                        return false;
+                    }
+                };
+                tb_ctx = self.tb_context(false);
+                tb_page_mask = 0;
+            }
         
-            let (id, inst_byte) = match decode_inst(guest_inst) {
+            let zcmp_inst = if self.m_arg_config.zcmp { decode_inst_zcmp(guest_inst) } else { None };
+            let (id, inst_byte) = match zcmp_inst
+                .or_else(|| decode_inst_bitmanip(guest_inst))
+                .or_else(|| decode_inst_crypto(guest_inst))
+                .or_else(|| decode_inst_zfh(guest_inst))
+                .or_else(|| decode_inst_zfa(guest_inst))
+                .or_else(|| decode_inst_vector(guest_inst))
+                .or_else(|| decode_inst(guest_inst))
+                .or_else(|| decode_inst_ext(guest_inst)) {
                 Some((id, inst_byte)) => (id, inst_byte),
                 _ => panic!("Decode Failed. {:08x}", guest_inst),
             };
@@ -797,6 +1240,7 @@ impl EmuEnv {
             if self.m_arg_config.dump_guest {
                 eprint!(" {:016x}:{:016x} Hostcode {:08x} : {}\n",  self.m_pc[0], guest_phy_addr, inst_info.inst, disassemble_riscv(guest_inst));
             }
+            tb_insts.push(TbInst { offset: (self.m_pc[0] - init_pc) as u16, events: HpmEvent::of_inst(id) });
             total_inst_byte += inst_byte;
 
             if id == RiscvInstId::JALR
@@ -810,23 +1254,31 @@ impl EmuEnv {
                 || id == RiscvInstId::ECALL
                 || id == RiscvInstId::MRET
                 || id == RiscvInstId::SRET
+                || id == RiscvInstId::DRET
+                || id == RiscvInstId::EBREAK
+                || id == RiscvInstId::C_EBREAK
                 || id == RiscvInstId::C_J
                 || id == RiscvInstId::C_JAL
                 || id == RiscvInstId::C_JALR
                 || id == RiscvInstId::C_JR
+                || id == RiscvInstId::CM_POPRET
+                || id == RiscvInstId::CM_POPRETZ
             {
                 break;
             }
             self.m_pc[0] = self.m_pc[0] + inst_byte as u64;
 
-            if id == RiscvInstId::FENCE_I {
+            if id == RiscvInstId::FENCE_I || id == RiscvInstId::SFENCE_VMA ||
+                id == RiscvInstId::HFENCE_VVMA || id == RiscvInstId::HFENCE_GVMA {
                 break;
             }
         
-            if self.m_arg_config.step {
+            if self.m_arg_config.step || self.debug_single_step() {
                 break;      // When self.m_arg_config.debug Mode, break for each instruction
             }
         }
+        // Block may end without branch (page boundary, fences), do not run into the rest of TB memory.
+        tcg_vec.push(TCGOp::new_0op(TCGOpcode::EXIT_TB, None));
         
         let mut pc_address = 0;
         
@@ -856,6 +1308,9 @@ impl EmuEnv {
         }
         self.m_tb_text_hash_address[hash_key] = init_pc;
         self.m_tb_text_hash_inst_size[hash_key] = total_inst_byte;
+        self.m_tb_text_hash_ctx[hash_key] = tb_ctx;
+        self.m_tb_text_hash_page_mask[hash_key] = tb_page_mask;
+        self.m_tb_text_hash_insts[hash_key] = tb_insts;
 
         for tcg in tcg_vec.iter_mut() {
             match tcg.op {
@@ -900,6 +1355,7 @@ impl EmuEnv {
     
             disassemble_x86(self.m_tcg_tb_vec.as_slice(), self.m_tb_text_hash_memmap[hash_key].data());
         }
+        return true;
     }
 
 }
@@ -1,5 +1,7 @@
 use std::{env, process};
+use clap::Parser;
 
+pub mod device;
 pub mod elf_loader;
 pub mod emu_env;
 pub mod instr_info;
@@ -8,12 +10,16 @@ pub mod tcg;
 pub mod op_helper;
 pub mod op_helper_fp_d;
 pub mod op_helper_fp_s;
+pub mod op_helper_fp_h;
+pub mod op_helper_vector;
+pub mod op_helper_vector_fp;
+pub mod op_helper_crypto;
 pub mod op_helper_mem;
 pub mod op_helper_fcvt;
 
-use emu_env::MachineEnum;
+use emu_env::{DriveMode, MachineEnum};
 
-use crate::emu_env::{EmuEnv, ArgConfig};
+use crate::emu_env::{EmuEnv, ArgConfig, MachineStatus};
 
 pub fn run(filename: String, step: bool, exp_gpr: &[u64; 32]) -> usize {
     let arg_config = ArgConfig {
@@ -27,6 +33,32 @@ pub fn run(filename: String, step: bool, exp_gpr: &[u64; 32]) -> usize {
         dump_host: false,
         machine : MachineEnum::RiscvVirt,
         opt_reg_fwd: false,
+        no_host_bitmanip: false,
+        no_host_crypto: false,
+        drive: None,
+        drive_mode: DriveMode::ReadWrite,
+        virtio_console: None,
+        console_socket: None,
+        virtio_rng: None,
+        rng_seed: 0,
+        virtfs: None,
+        virtfs_tag: "hostshare".to_string(),
+        virtfs_ro: false,
+        rtc_epoch: None,
+        pflash0: None,
+        pflash1: None,
+        svadu: false,
+        svpbmt: false,
+        svnapot: false,
+        hypervisor: false,
+        vector_table: false,
+        zcmp: false,
+        zkr: None,
+        vlen: 128,
+        elen: 64,
+        cbo_block_size: 64,
+        cycle_per_inst: 1,
+        tlb_miss_cycles: 0,
         elf_file: filename.clone(),
     };
 
@@ -62,6 +94,32 @@ pub fn run_riscv_test(filename: String, opt_step: bool) -> u64 {
         dump_host: false,
         machine : MachineEnum::RiscvVirt,
         opt_reg_fwd: false,
+        no_host_bitmanip: false,
+        no_host_crypto: false,
+        drive: None,
+        drive_mode: DriveMode::ReadWrite,
+        virtio_console: None,
+        console_socket: None,
+        virtio_rng: None,
+        rng_seed: 0,
+        virtfs: None,
+        virtfs_tag: "hostshare".to_string(),
+        virtfs_ro: false,
+        rtc_epoch: None,
+        pflash0: None,
+        pflash1: None,
+        svadu: false,
+        svpbmt: false,
+        svnapot: false,
+        hypervisor: false,
+        vector_table: false,
+        zcmp: false,
+        zkr: None,
+        vlen: 128,
+        elen: 64,
+        cbo_block_size: 64,
+        cycle_per_inst: 1,
+        tlb_miss_cycles: 0,
         elf_file: riscv_path + &filename,
     };
 
@@ -69,3 +127,16 @@ pub fn run_riscv_test(filename: String, opt_step: bool) -> u64 {
     emu.run();
     return emu.get_mem(0x1000) as u64;
 }
+
+// Virt machine for a test program with the command line options. The program is loaded by run().
+pub fn virt_test_env(filename: &str, options: &[&str]) -> EmuEnv {
+    let args = ["dydra", "--machine", "virt", "--elf-file", filename];
+    let arg_config = ArgConfig::parse_from(args.iter().chain(options.iter()));
+
+    EmuEnv::new(arg_config)
+}
+
+// Run a test program on virt machine with the command line options, and return the status reported by the test finisher.
+pub fn run_virt_test(filename: &str, options: &[&str]) -> MachineStatus {
+    virt_test_env(filename, options).run()
+}
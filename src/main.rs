@@ -1,9 +1,11 @@
 use crate::clap::Parser;
-use emu_env::MachineEnum;
+use emu_env::{MachineEnum, MachineStatus};
+use std::process;
 
 extern crate clap;
 extern crate mmap;
 
+pub mod device;
 pub mod elf_loader;
 pub mod emu_env;
 pub mod instr_info;
@@ -11,6 +13,10 @@ pub mod op_helper;
 pub mod op_helper_fcvt;
 pub mod op_helper_fp_d;
 pub mod op_helper_fp_s;
+pub mod op_helper_fp_h;
+pub mod op_helper_vector;
+pub mod op_helper_vector_fp;
+pub mod op_helper_crypto;
 pub mod op_helper_mem;
 pub mod target;
 pub mod tcg;
@@ -26,9 +32,16 @@ fn main() {
     }
 
     let mut emu = EmuEnv::new(cfg);
-    emu.run();
+    let status = emu.run();
 
     println!("Result: MEM[0x1000] = {:08x}", emu.get_mem(0x1000));
+    match status {
+        MachineStatus::Running => {},
+        MachineStatus::Pass    => println!("Result: power-off (pass)"),
+        MachineStatus::Fail(code) => println!("Result: power-off (fail, code = {})", code),
+        MachineStatus::Reset   => println!("Result: reboot requested"),
+        MachineStatus::Halted  => println!("Result: halted in Debug Mode (dpc = {:016x})", emu.m_csr.m_dpc.m_csr),
+    }
 
-    return;
+    process::exit(status.exit_code());
 }
@@ -1,7 +1,8 @@
-use crate::target::riscv::riscv_csr::{CsrAddr};
+use crate::target::riscv::riscv_csr::{CsrAddr, RiscvCsr};
 use crate::target::riscv::riscv_csr_def;
 use crate::emu_env::EmuEnv;
 use crate::target::riscv::riscv::{ExceptCode, PrivMode};
+use crate::target::riscv::debug::DebugCause;
 
 impl EmuEnv {
     pub fn helper_func_csrrw(
@@ -9,15 +10,18 @@ impl EmuEnv {
         dest: u64,
         source: u64,
         csr_addr: u64,
-        _dummy: u64,
+        guest_pc: u64,
     ) -> usize {
+        let csr = match emu.csr_access(csr_addr, guest_pc, true) {
+            Ok(csr) => csr,
+            Err(code) => return code as usize,
+        };
         let data = emu.m_iregs[source as usize];
-        let reg_data = emu
-            .m_csr
-            .csrrw(CsrAddr::from_u64(csr_addr as u64), data as i64);
+        let reg_data = emu.csr_op(csr, guest_pc, RiscvCsr::csrrw, data as i64);
         if dest != 0 {
             emu.m_iregs[dest as usize] = reg_data as u64;
         }
+        emu.check_tlb_context();
         return 0;
     }
 
@@ -26,15 +30,18 @@ impl EmuEnv {
         dest: u64,
         source: u64,
         csr_addr: u64,
-        _dummy: u64,
+        guest_pc: u64,
     ) -> usize {
+        let csr = match emu.csr_access(csr_addr, guest_pc, source != 0) {
+            Ok(csr) => csr,
+            Err(code) => return code as usize,
+        };
         let data = emu.m_iregs[source as usize];
-        let reg_data = emu
-            .m_csr
-            .csrrs(CsrAddr::from_u64(csr_addr as u64), data as i64);
+        let reg_data = emu.csr_op(csr, guest_pc, RiscvCsr::csrrs, data as i64);
         if dest != 0 {
             emu.m_iregs[dest as usize] = reg_data as u64;
         }
+        emu.check_tlb_context();
         return 0;
     }
 
@@ -43,15 +50,18 @@ impl EmuEnv {
         dest: u64,
         source: u64,
         csr_addr: u64,
-        _dummy: u64,
+        guest_pc: u64,
     ) -> usize {
+        let csr = match emu.csr_access(csr_addr, guest_pc, source != 0) {
+            Ok(csr) => csr,
+            Err(code) => return code as usize,
+        };
         let data = emu.m_iregs[source as usize];
-        let reg_data = emu
-            .m_csr
-            .csrrc(CsrAddr::from_u64(csr_addr as u64), data as i64);
+        let reg_data = emu.csr_op(csr, guest_pc, RiscvCsr::csrrc, data as i64);
         if dest != 0 {
             emu.m_iregs[dest as usize] = reg_data as u64;
         }
+        emu.check_tlb_context();
         return 0;
     }
 
@@ -60,14 +70,17 @@ impl EmuEnv {
         dest: u64,
         imm: u64,
         csr_addr: u64,
-        _dummy: u64,
+        guest_pc: u64,
     ) -> usize {
-        let reg_data = emu
-            .m_csr
-            .csrrw(CsrAddr::from_u64(csr_addr as u64), imm as i64);
+        let csr = match emu.csr_access(csr_addr, guest_pc, true) {
+            Ok(csr) => csr,
+            Err(code) => return code as usize,
+        };
+        let reg_data = emu.csr_op(csr, guest_pc, RiscvCsr::csrrw, imm as i64);
         if dest != 0 {
             emu.m_iregs[dest as usize] = reg_data as u64;
         }
+        emu.check_tlb_context();
         return 0;
     }
 
@@ -76,14 +89,17 @@ impl EmuEnv {
         dest: u64,
         imm: u64,
         csr_addr: u64,
-        _dummy: u64,
+        guest_pc: u64,
     ) -> usize {
-        let reg_data = emu
-            .m_csr
-            .csrrs(CsrAddr::from_u64(csr_addr as u64), imm as i64);
+        let csr = match emu.csr_access(csr_addr, guest_pc, imm != 0) {
+            Ok(csr) => csr,
+            Err(code) => return code as usize,
+        };
+        let reg_data = emu.csr_op(csr, guest_pc, RiscvCsr::csrrs, imm as i64);
         if dest != 0 {
             emu.m_iregs[dest as usize] = reg_data as u64;
         }
+        emu.check_tlb_context();
         return 0;
     }
 
@@ -92,83 +108,282 @@ impl EmuEnv {
         dest: u64,
         imm: u64,
         csr_addr: u64,
-        _dummy: u64,
+        guest_pc: u64,
     ) -> usize {
-        let reg_data = emu
-            .m_csr
-            .csrrc(CsrAddr::from_u64(csr_addr as u64), imm as i64);
+        let csr = match emu.csr_access(csr_addr, guest_pc, imm != 0) {
+            Ok(csr) => csr,
+            Err(code) => return code as usize,
+        };
+        let reg_data = emu.csr_op(csr, guest_pc, RiscvCsr::csrrc, imm as i64);
         if dest != 0 {
             emu.m_iregs[dest as usize] = reg_data as u64;
         }
+        emu.check_tlb_context();
         return 0;
     }
 
     pub fn helper_func_ecall(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, guest_pc: u64) -> usize {
-        emu.m_csr.csrrw(CsrAddr::Mepc, emu.m_pc[0] as i64); // MEPC
-
         let current_priv: PrivMode = emu.m_priv;
         match current_priv {
             PrivMode::User       => emu.generate_exception(guest_pc, ExceptCode::EcallFromUMode, 0),
-            PrivMode::Supervisor => emu.generate_exception(guest_pc, ExceptCode::EcallFromSMode, 0),
-            PrivMode::Hypervisor => emu.generate_exception(guest_pc, ExceptCode::EcallFromHMode, 0),
+            PrivMode::Supervisor => if emu.m_virt {
+                emu.generate_exception(guest_pc, ExceptCode::EcallFromVSMode, 0)
+            } else {
+                emu.generate_exception(guest_pc, ExceptCode::EcallFromSMode, 0)
+            },
+            PrivMode::Hypervisor => emu.generate_exception(guest_pc, ExceptCode::EcallFromVSMode, 0),
             PrivMode::Machine    => emu.generate_exception(guest_pc, ExceptCode::EcallFromMMode, 0),
         }
 
         return 0;
     }
 
-    pub fn helper_func_mret(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, _dummy: u64) -> usize {
-        emu.m_pc[0] = emu.m_csr.csrrc(CsrAddr::Mepc, 0 as i64) as u64;
+    pub fn helper_func_ebreak(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        if emu.ebreak_enters_debug_mode() {
+            emu.enter_debug_mode(guest_pc, DebugCause::Ebreak);
+        } else {
+            emu.generate_exception(guest_pc, ExceptCode::Breakpoint, guest_pc as i64);
+        }
+        return 0;
+    }
+
+    // DRET is only legal in Debug Mode.
+    pub fn helper_func_dret(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        if !emu.m_csr.m_debug_mode {
+            emu.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return ExceptCode::IllegalInst as usize;
+        }
+        emu.leave_debug_mode();
+        return 0;
+    }
+
+    // MRET : MIE = MPIE, MPIE = 1, privilege = MPP, MPP = U, and V = MPV with H extension.
+    pub fn helper_func_mret(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        if emu.m_priv != PrivMode::Machine {
+            emu.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return ExceptCode::IllegalInst as usize;
+        }
+        let mstatus: i64 = emu.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let next_priv = PrivMode::from_u8(Self::extract_bit_field(
+            mstatus, riscv_csr_def::SYSREG_MSTATUS_MPP_MSB, riscv_csr_def::SYSREG_MSTATUS_MPP_LSB) as u8);
+        let mpie = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MPIE_MSB, riscv_csr_def::SYSREG_MSTATUS_MPIE_LSB);
+        let mpv = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MPV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPV_LSB);
+        let mut next_mstatus = Self::set_bit_field(mstatus, mpie, riscv_csr_def::SYSREG_MSTATUS_MIE_MSB, riscv_csr_def::SYSREG_MSTATUS_MIE_LSB);
+        next_mstatus = Self::set_bit_field(next_mstatus, 1, riscv_csr_def::SYSREG_MSTATUS_MPIE_MSB, riscv_csr_def::SYSREG_MSTATUS_MPIE_LSB);
+        next_mstatus = Self::set_bit_field(next_mstatus, PrivMode::User as i64, riscv_csr_def::SYSREG_MSTATUS_MPP_MSB, riscv_csr_def::SYSREG_MSTATUS_MPP_LSB);
+        next_mstatus = Self::set_bit_field(next_mstatus, 0, riscv_csr_def::SYSREG_MSTATUS_MPV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPV_LSB);
+        if next_priv != PrivMode::Machine {
+            next_mstatus = Self::set_bit_field(next_mstatus, 0, riscv_csr_def::SYSREG_MSTATUS_MPRV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPRV_LSB);
+        }
+        emu.m_csr.csrrw(CsrAddr::Mstatus, next_mstatus);
+
+        emu.m_priv = next_priv;
+        emu.m_virt = emu.m_arg_config.hypervisor && next_priv != PrivMode::Machine && mpv != 0;
+        emu.m_pc[0] = emu.m_csr.csrrs(CsrAddr::Mepc, 0) as u64;
+        emu.m_csr.trigger_mret();
+        emu.check_tlb_context();
         return 0;
     }
-    
-    pub fn helper_func_sret(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, _dummy: u64) -> usize {
-        let mstatus: i64 = emu.m_csr.csrrs(CsrAddr::Mstatus, PrivMode::Machine as i64);
-        let next_priv_uint: i64 = Self::extract_bit_field( mstatus, riscv_csr_def::SYSREG_MSTATUS_SPP_MSB, riscv_csr_def::SYSREG_MSTATUS_SPP_LSB,
-        );
-        let next_priv: PrivMode = PrivMode::from_u8(next_priv_uint as u8);
-        let mut next_mstatus: i64 = mstatus;
-        next_mstatus = Self::set_bit_field(
-            next_mstatus,
-            Self::extract_bit_field(
-                mstatus,
-                riscv_csr_def::SYSREG_MSTATUS_SPIE_MSB,
-                riscv_csr_def::SYSREG_MSTATUS_SPIE_LSB,
-            ),
-            riscv_csr_def::SYSREG_MSTATUS_SIE_MSB,
-            riscv_csr_def::SYSREG_MSTATUS_SIE_LSB,
-        );
-        next_mstatus = Self::set_bit_field(
-            next_mstatus,
-            1,
-            riscv_csr_def::SYSREG_MSTATUS_SPIE_MSB,
-            riscv_csr_def::SYSREG_MSTATUS_SPIE_LSB,
-        );
-        next_mstatus = Self::set_bit_field(
-            next_mstatus,
-            PrivMode::User as i64,
-            riscv_csr_def::SYSREG_MSTATUS_SPP_MSB,
-            riscv_csr_def::SYSREG_MSTATUS_SPP_LSB,
-        );
 
+    // SRET : SIE = SPIE, SPIE = 1, privilege = SPP, SPP = U, and V = hstatus.SPV with H extension.
+    // Not allowed in U-mode, and in S-mode when mstatus.TSR is set.
+    pub fn helper_func_sret(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        if emu.m_virt {
+            let hstatus = emu.m_csr.csrrs(CsrAddr::Hstatus, 0);
+            let vtsr = Self::extract_bit_field(hstatus, riscv_csr_def::SYSREG_HSTATUS_VTSR_MSB, riscv_csr_def::SYSREG_HSTATUS_VTSR_LSB);
+            if emu.m_priv == PrivMode::User || vtsr != 0 {
+                emu.generate_exception(guest_pc, ExceptCode::VirtualInst, 0);
+                return ExceptCode::VirtualInst as usize;
+            }
+            return Self::sret_from_vs(emu);
+        }
+
+        let mstatus: i64 = emu.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let tsr = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_TSR_MSB, riscv_csr_def::SYSREG_MSTATUS_TSR_LSB);
+        if emu.m_priv == PrivMode::User || (emu.m_priv == PrivMode::Supervisor && tsr != 0) {
+            emu.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return ExceptCode::IllegalInst as usize;
+        }
+        let next_priv = PrivMode::from_u8(Self::extract_bit_field(
+            mstatus, riscv_csr_def::SYSREG_MSTATUS_SPP_MSB, riscv_csr_def::SYSREG_MSTATUS_SPP_LSB) as u8);
+        let spie = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_SPIE_MSB, riscv_csr_def::SYSREG_MSTATUS_SPIE_LSB);
+        let mut next_mstatus = Self::set_bit_field(mstatus, spie, riscv_csr_def::SYSREG_MSTATUS_SIE_MSB, riscv_csr_def::SYSREG_MSTATUS_SIE_LSB);
+        next_mstatus = Self::set_bit_field(next_mstatus, 1, riscv_csr_def::SYSREG_MSTATUS_SPIE_MSB, riscv_csr_def::SYSREG_MSTATUS_SPIE_LSB);
+        next_mstatus = Self::set_bit_field(next_mstatus, PrivMode::User as i64, riscv_csr_def::SYSREG_MSTATUS_SPP_MSB, riscv_csr_def::SYSREG_MSTATUS_SPP_LSB);
+        next_mstatus = Self::set_bit_field(next_mstatus, 0, riscv_csr_def::SYSREG_MSTATUS_MPRV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPRV_LSB);
         emu.m_csr.csrrw(CsrAddr::Mstatus, next_mstatus);
+
         let ret_pc = emu.m_csr.csrrs(CsrAddr::Sepc, 0);
         emu.m_priv = next_priv;
 
+        // Return to the guest when the trap was taken from VS/VU-mode.
+        if emu.m_arg_config.hypervisor {
+            let hstatus = emu.m_csr.csrrs(CsrAddr::Hstatus, 0);
+            emu.m_virt = Self::extract_bit_field(hstatus, riscv_csr_def::SYSREG_HSTATUS_SPV_MSB, riscv_csr_def::SYSREG_HSTATUS_SPV_LSB) != 0;
+            let hstatus = Self::set_bit_field(hstatus, 0, riscv_csr_def::SYSREG_HSTATUS_SPV_MSB, riscv_csr_def::SYSREG_HSTATUS_SPV_LSB);
+            emu.m_csr.csrrw(CsrAddr::Hstatus, hstatus);
+        }
+
         emu.m_pc[0] = ret_pc as u64;
+        emu.check_tlb_context();
         
         return 0;
     }
 
-    pub fn helper_func_sfence_vma(emu: &mut EmuEnv, _dest: u64, _imm: u64, _csr_addr: u64, _dummy: u64) -> usize {
-        // Clear TLB
-        for idx in 0..4096 {
-            emu.m_tlb_vec[idx] = 0xdeadbeef_01234567;
+    // SRET in VS-mode uses vsstatus / vsepc and stays in the guest.
+    fn sret_from_vs(emu: &mut EmuEnv) -> usize {
+        let vsstatus: i64 = emu.m_csr.csrrs(CsrAddr::Vsstatus, 0);
+        let next_priv = PrivMode::from_u8(Self::extract_bit_field(
+            vsstatus, riscv_csr_def::SYSREG_SSTATUS_SPP_MSB, riscv_csr_def::SYSREG_SSTATUS_SPP_LSB) as u8);
+        let spie = Self::extract_bit_field(vsstatus, riscv_csr_def::SYSREG_SSTATUS_SPIE_MSB, riscv_csr_def::SYSREG_SSTATUS_SPIE_LSB);
+        let mut next_vsstatus = Self::set_bit_field(vsstatus, spie, riscv_csr_def::SYSREG_SSTATUS_SIE_MSB, riscv_csr_def::SYSREG_SSTATUS_SIE_LSB);
+        next_vsstatus = Self::set_bit_field(next_vsstatus, 1, riscv_csr_def::SYSREG_SSTATUS_SPIE_MSB, riscv_csr_def::SYSREG_SSTATUS_SPIE_LSB);
+        next_vsstatus = Self::set_bit_field(next_vsstatus, PrivMode::User as i64, riscv_csr_def::SYSREG_SSTATUS_SPP_MSB, riscv_csr_def::SYSREG_SSTATUS_SPP_LSB);
+        emu.m_csr.csrrw(CsrAddr::Vsstatus, next_vsstatus);
+
+        emu.m_priv = next_priv;
+        emu.m_pc[0] = emu.m_csr.csrrs(CsrAddr::Vsepc, 0) as u64;
+        emu.check_tlb_context();
+        return 0;
+    }
+
+    // CSR seen from the current mode.
+    // VS-mode accesses to supervisor CSRs are redirected to the vs* CSRs.
+    // CSRs not implemented, writes to read-only CSRs (including CSRRS / CSRRC with a non-zero source)
+    // and accesses from lower privilege raise illegal instruction exception.
+    fn csr_access(&mut self, csr_addr: u64, guest_pc: u64, write: bool) -> Result<CsrAddr, ExceptCode> {
+        // Debug CSRs are only accessible in Debug Mode.
+        if (0x7b0..=0x7bf).contains(&csr_addr) && !self.m_csr.m_debug_mode {
+            self.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return Err(ExceptCode::IllegalInst);
+        }
+        if let Some(code) = self.counter_access_fault(csr_addr) {
+            self.generate_exception(guest_pc, code, 0);
+            return Err(code);
+        }
+        if csr_addr == CsrAddr::Seed as u64 {
+            if let Some(code) = self.seed_access_fault(write) {
+                self.generate_exception(guest_pc, code, 0);
+                return Err(code);
+            }
+        }
+        let csr_priv = (csr_addr >> 8) & 0x3;
+        let read_only = (csr_addr >> 10) & 0x3 == 0x3;
+        let desc = self.m_csr.csr_desc(CsrAddr::from_u64(csr_addr));
+        let illegal = match desc {
+            None => true,
+            Some(desc) => {
+                let mstatus = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+                let tvm = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_TVM_MSB, riscv_csr_def::SYSREG_MSTATUS_TVM_LSB);
+                (write && read_only) ||
+                    (!self.m_virt && (self.m_priv as u8) < (desc.priv_mode as u8)) ||
+                    (!self.m_virt && self.m_priv == PrivMode::Supervisor && csr_addr == CsrAddr::Satp as u64 && tvm != 0)
+            }
+        };
+        if illegal {
+            self.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return Err(ExceptCode::IllegalInst);
+        }
+        if !self.m_virt {
+            return Ok(CsrAddr::from_u64(csr_addr));
+        }
+        let hstatus = self.m_csr.csrrs(CsrAddr::Hstatus, 0);
+        let vtvm = Self::extract_bit_field(hstatus, riscv_csr_def::SYSREG_HSTATUS_VTVM_MSB, riscv_csr_def::SYSREG_HSTATUS_VTVM_LSB);
+        let code = if csr_priv == PrivMode::Machine as u64 {
+            Some(ExceptCode::IllegalInst)
+        } else if csr_priv == PrivMode::Hypervisor as u64 ||
+            (csr_priv == PrivMode::Supervisor as u64 && self.m_priv == PrivMode::User) ||
+            (csr_addr == CsrAddr::Satp as u64 && vtvm != 0) {
+            Some(ExceptCode::VirtualInst)
+        } else {
+            None
+        };
+        if let Some(code) = code {
+            self.generate_exception(guest_pc, code, 0);
+            return Err(code);
+        }
+        let addr = match csr_addr {
+            0x100 | 0x104 | 0x105 | 0x140..=0x144 | 0x180 => csr_addr + 0x100,
+            _ => csr_addr,
+        };
+        Ok(CsrAddr::from_u64(addr))
+    }
+
+    // Raise illegal instruction for the address translation fences in S-mode when mstatus.TVM is set.
+    fn check_tvm(emu: &mut EmuEnv, guest_pc: u64) -> Result<(), ExceptCode> {
+        let mstatus = emu.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let tvm = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_TVM_MSB, riscv_csr_def::SYSREG_MSTATUS_TVM_LSB);
+        if emu.m_priv == PrivMode::Supervisor && tvm != 0 {
+            emu.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return Err(ExceptCode::IllegalInst);
+        }
+        Ok(())
+    }
+
+    // SFENCE.VMA : not allowed in U-mode, and in S-mode when mstatus.TVM is set.
+    pub fn helper_func_sfence_vma(emu: &mut EmuEnv, rs1: u64, rs2: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        let vaddr = if rs1 != 0 { Some(emu.m_iregs[rs1 as usize]) } else { None };
+        let asid = if rs2 != 0 { Some(emu.m_iregs[rs2 as usize] as u16) } else { None };
+        if emu.m_virt {
+            let hstatus = emu.m_csr.csrrs(CsrAddr::Hstatus, 0);
+            let vtvm = Self::extract_bit_field(hstatus, riscv_csr_def::SYSREG_HSTATUS_VTVM_MSB, riscv_csr_def::SYSREG_HSTATUS_VTVM_LSB);
+            if emu.m_priv == PrivMode::User || vtvm != 0 {
+                emu.generate_exception(guest_pc, ExceptCode::VirtualInst, 0);
+                return ExceptCode::VirtualInst as usize;
+            }
+            // SFENCE.VMA in VS-mode only affects the current guest.
+            emu.hfence_vvma(vaddr, asid);
+            return 0;
         }
+        if emu.m_priv == PrivMode::User {
+            emu.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+            return ExceptCode::IllegalInst as usize;
+        }
+        if let Err(code) = Self::check_tvm(emu, guest_pc) {
+            return code as usize;
+        }
+        emu.sfence_vma(vaddr, asid);
+        return 0;
+    }
+
+    // HFENCE.VVMA / HFENCE.GVMA are only allowed in HS-mode and M-mode.
+    fn check_hfence(emu: &mut EmuEnv, guest_pc: u64) -> Result<(), ExceptCode> {
+        let code = if !emu.m_arg_config.hypervisor {
+            Some(ExceptCode::IllegalInst)
+        } else if emu.m_virt {
+            Some(ExceptCode::VirtualInst)
+        } else if emu.m_priv == PrivMode::User {
+            Some(ExceptCode::IllegalInst)
+        } else {
+            None
+        };
+        match code {
+            Some(code) => {
+                emu.generate_exception(guest_pc, code, 0);
+                Err(code)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn helper_func_hfence_vvma(emu: &mut EmuEnv, rs1: u64, rs2: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        if let Err(code) = Self::check_hfence(emu, guest_pc) {
+            return code as usize;
+        }
+        let vaddr = if rs1 != 0 { Some(emu.m_iregs[rs1 as usize]) } else { None };
+        let asid = if rs2 != 0 { Some(emu.m_iregs[rs2 as usize] as u16) } else { None };
+        emu.hfence_vvma(vaddr, asid);
+        return 0;
+    }
 
-        for addr in emu.m_tb_text_hash_address.iter_mut() {
-            *addr = 0xdeadbeef;
+    // HFENCE.GVMA is also trapped by mstatus.TVM in HS-mode.
+    pub fn helper_func_hfence_gvma(emu: &mut EmuEnv, _rs1: u64, rs2: u64, _csr_addr: u64, guest_pc: u64) -> usize {
+        if let Err(code) = Self::check_hfence(emu, guest_pc).and_then(|_| Self::check_tvm(emu, guest_pc)) {
+            return code as usize;
         }
+        // Guest physical address is not kept in TLB : flush all the guest entries of VMID.
+        let vmid = if rs2 != 0 { Some(emu.m_iregs[rs2 as usize] as u16) } else { None };
+        emu.hfence_gvma(vmid);
         return 0;
     }
 
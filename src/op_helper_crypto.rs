@@ -0,0 +1,242 @@
+use std::arch::x86_64::{_mm_aesdec_si128, _mm_aesdeclast_si128, _mm_aesenc_si128, _mm_aesenclast_si128, _mm_aesimc_si128};
+use std::arch::x86_64::{_mm_cvtsi128_si64, _mm_set_epi64x, _mm_setzero_si128};
+
+use crate::emu_env::EmuEnv;
+use crate::target::riscv::riscv::{CallCryptoIdx, ExceptCode, PrivMode};
+use crate::target::riscv::riscv_csr_def;
+
+const AES_SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+const AES_INV_SBOX: [u8; 256] = [
+    0x52, 0x09, 0x6a, 0xd5, 0x30, 0x36, 0xa5, 0x38, 0xbf, 0x40, 0xa3, 0x9e, 0x81, 0xf3, 0xd7, 0xfb,
+    0x7c, 0xe3, 0x39, 0x82, 0x9b, 0x2f, 0xff, 0x87, 0x34, 0x8e, 0x43, 0x44, 0xc4, 0xde, 0xe9, 0xcb,
+    0x54, 0x7b, 0x94, 0x32, 0xa6, 0xc2, 0x23, 0x3d, 0xee, 0x4c, 0x95, 0x0b, 0x42, 0xfa, 0xc3, 0x4e,
+    0x08, 0x2e, 0xa1, 0x66, 0x28, 0xd9, 0x24, 0xb2, 0x76, 0x5b, 0xa2, 0x49, 0x6d, 0x8b, 0xd1, 0x25,
+    0x72, 0xf8, 0xf6, 0x64, 0x86, 0x68, 0x98, 0x16, 0xd4, 0xa4, 0x5c, 0xcc, 0x5d, 0x65, 0xb6, 0x92,
+    0x6c, 0x70, 0x48, 0x50, 0xfd, 0xed, 0xb9, 0xda, 0x5e, 0x15, 0x46, 0x57, 0xa7, 0x8d, 0x9d, 0x84,
+    0x90, 0xd8, 0xab, 0x00, 0x8c, 0xbc, 0xd3, 0x0a, 0xf7, 0xe4, 0x58, 0x05, 0xb8, 0xb3, 0x45, 0x06,
+    0xd0, 0x2c, 0x1e, 0x8f, 0xca, 0x3f, 0x0f, 0x02, 0xc1, 0xaf, 0xbd, 0x03, 0x01, 0x13, 0x8a, 0x6b,
+    0x3a, 0x91, 0x11, 0x41, 0x4f, 0x67, 0xdc, 0xea, 0x97, 0xf2, 0xcf, 0xce, 0xf0, 0xb4, 0xe6, 0x73,
+    0x96, 0xac, 0x74, 0x22, 0xe7, 0xad, 0x35, 0x85, 0xe2, 0xf9, 0x37, 0xe8, 0x1c, 0x75, 0xdf, 0x6e,
+    0x47, 0xf1, 0x1a, 0x71, 0x1d, 0x29, 0xc5, 0x89, 0x6f, 0xb7, 0x62, 0x0e, 0xaa, 0x18, 0xbe, 0x1b,
+    0xfc, 0x56, 0x3e, 0x4b, 0xc6, 0xd2, 0x79, 0x20, 0x9a, 0xdb, 0xc0, 0xfe, 0x78, 0xcd, 0x5a, 0xf4,
+    0x1f, 0xdd, 0xa8, 0x33, 0x88, 0x07, 0xc7, 0x31, 0xb1, 0x12, 0x10, 0x59, 0x27, 0x80, 0xec, 0x5f,
+    0x60, 0x51, 0x7f, 0xa9, 0x19, 0xb5, 0x4a, 0x0d, 0x2d, 0xe5, 0x7a, 0x9f, 0x93, 0xc9, 0x9c, 0xef,
+    0xa0, 0xe0, 0x3b, 0x4d, 0xae, 0x2a, 0xf5, 0xb0, 0xc8, 0xeb, 0xbb, 0x3c, 0x83, 0x53, 0x99, 0x61,
+    0x17, 0x2b, 0x04, 0x7e, 0xba, 0x77, 0xd6, 0x26, 0xe1, 0x69, 0x14, 0x63, 0x55, 0x21, 0x0c, 0x7d,
+];
+
+const SM4_SBOX: [u8; 256] = [
+    0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+    0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+    0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+    0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+    0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+    0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+    0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+    0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+    0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+    0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+    0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+    0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+    0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+    0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+    0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+    0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48,
+];
+
+// Round constants of aes64ks1i, rnum = 0xA is used for the odd words of AES-256 and has none
+const AES_RCON: [u8; 11] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36, 0x00];
+
+fn aes_xtime(a: u8) -> u8 {
+    (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+fn aes_gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut res = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            res ^= a;
+        }
+        a = aes_xtime(a);
+        b >>= 1;
+    }
+    res
+}
+
+fn aes_subword(x: u32) -> u32 {
+    u32::from_le_bytes(x.to_le_bytes().map(|b| AES_SBOX[b as usize]))
+}
+
+// The AES state is {rs2, rs1} : byte i of the state is byte i of rs1 for i < 8, column c is bytes 4c .. 4c+3.
+// The 64-bit instructions produce columns 0 and 1 of a round, i.e. the lower half of the new state.
+fn aes_shift_sub(rs1: u64, rs2: u64, inverse: bool) -> [u8; 8] {
+    let mut state = [0u8; 16];
+    state[..8].copy_from_slice(&rs1.to_le_bytes());
+    state[8..].copy_from_slice(&rs2.to_le_bytes());
+    let mut out = [0u8; 8];
+    for c in 0..2 {
+        for r in 0..4 {
+            let src = if inverse { (c + 4 - r) % 4 } else { (c + r) % 4 };
+            let b = state[4 * src + r] as usize;
+            out[4 * c + r] = if inverse { AES_INV_SBOX[b] } else { AES_SBOX[b] };
+        }
+    }
+    out
+}
+
+fn aes_mix_columns(mut half: [u8; 8], inverse: bool) -> [u8; 8] {
+    let m: [u8; 4] = if inverse { [14, 11, 13, 9] } else { [2, 3, 1, 1] };
+    for col in half.chunks_mut(4) {
+        let a = [col[0], col[1], col[2], col[3]];
+        for r in 0..4 {
+            col[r] = (0..4).fold(0, |acc, k| acc ^ aes_gf_mul(m[(k + 4 - r) % 4], a[k]));
+        }
+    }
+    half
+}
+
+fn aes64_soft(op: CallCryptoIdx, rs1: u64, rs2: u64) -> u64 {
+    let half = match op {
+        CallCryptoIdx::AES64ES  => aes_shift_sub(rs1, rs2, false),
+        CallCryptoIdx::AES64ESM => aes_mix_columns(aes_shift_sub(rs1, rs2, false), false),
+        CallCryptoIdx::AES64DS  => aes_shift_sub(rs1, rs2, true),
+        CallCryptoIdx::AES64DSM => aes_mix_columns(aes_shift_sub(rs1, rs2, true), true),
+        _ => aes_mix_columns(rs1.to_le_bytes(), true),
+    };
+    u64::from_le_bytes(half)
+}
+
+// AESENC etc. do a whole round with the round key. The key is zero here and the lower half is taken.
+#[target_feature(enable = "aes")]
+unsafe fn aes64_host(op: CallCryptoIdx, rs1: u64, rs2: u64) -> u64 {
+    let state = _mm_set_epi64x(rs2 as i64, rs1 as i64);
+    let zero = _mm_setzero_si128();
+    let res = match op {
+        CallCryptoIdx::AES64ES  => _mm_aesenclast_si128(state, zero),
+        CallCryptoIdx::AES64ESM => _mm_aesenc_si128(state, zero),
+        CallCryptoIdx::AES64DS  => _mm_aesdeclast_si128(state, zero),
+        CallCryptoIdx::AES64DSM => _mm_aesdec_si128(state, zero),
+        _ => _mm_aesimc_si128(state),
+    };
+    _mm_cvtsi128_si64(res) as u64
+}
+
+fn aes64_ks1i(rs1: u64, rnum: usize) -> u64 {
+    let tmp1 = (rs1 >> 32) as u32;
+    let tmp2 = if rnum == 0xa { tmp1 } else { tmp1.rotate_right(8) };
+    let tmp3 = aes_subword(tmp2) ^ AES_RCON[rnum] as u32;
+    (tmp3 as u64) << 32 | tmp3 as u64
+}
+
+fn aes64_ks2(rs1: u64, rs2: u64) -> u64 {
+    let w0 = (rs1 >> 32) as u32 ^ rs2 as u32;
+    let w1 = w0 ^ (rs2 >> 32) as u32;
+    (w1 as u64) << 32 | w0 as u64
+}
+
+// Byte bs of rs2 goes through the S-box and the linear transform of the round (sm4ed) or key schedule (sm4ks).
+fn sm4_round(rs1: u64, rs2: u64, bs: u32, key_schedule: bool) -> u64 {
+    let x = SM4_SBOX[((rs2 >> (8 * bs)) & 0xff) as usize] as u32;
+    let y = if key_schedule {
+        x ^ x.rotate_left(13) ^ x.rotate_left(23)
+    } else {
+        x ^ x.rotate_left(2) ^ x.rotate_left(10) ^ x.rotate_left(18) ^ x.rotate_left(24)
+    };
+    sext32(y.rotate_left(8 * bs) ^ rs1 as u32)
+}
+
+fn sext32(x: u32) -> u64 {
+    x as i32 as i64 as u64
+}
+
+// Each byte (xperm8) or nibble (xperm4) of rs2 selects an element of rs1, out of range indices give zero.
+fn xperm(rs1: u64, rs2: u64, bits: u32) -> u64 {
+    let mask = (1u64 << bits) - 1;
+    (0..64 / bits).fold(0, |acc, i| {
+        let idx = (rs2 >> (i * bits)) & mask;
+        let elem = if idx < (64 / bits) as u64 { (rs1 >> (idx as u32 * bits)) & mask } else { 0 };
+        acc | elem << (i * bits)
+    })
+}
+
+impl EmuEnv {
+    fn host_has_aes(&self) -> bool {
+        !self.m_arg_config.no_host_crypto && is_x86_feature_detected!("aes")
+    }
+
+    pub fn helper_func_crypto(emu: &mut EmuEnv, op: u64, rd: u64, rs1: u64, rs2: u64) -> usize {
+        let imm = (op >> 8) & 0xf;
+        let op = CallCryptoIdx::from_u64(op & 0xff);
+        let rs1 = emu.m_iregs[rs1 as usize];
+        let rs2 = emu.m_iregs[rs2 as usize];
+        let res = match op {
+            CallCryptoIdx::AES64ES | CallCryptoIdx::AES64ESM | CallCryptoIdx::AES64DS |
+            CallCryptoIdx::AES64DSM | CallCryptoIdx::AES64IM => {
+                if emu.host_has_aes() {
+                    unsafe { aes64_host(op, rs1, rs2) }
+                } else {
+                    aes64_soft(op, rs1, rs2)
+                }
+            }
+            CallCryptoIdx::AES64KS1I => aes64_ks1i(rs1, imm as usize),
+            CallCryptoIdx::AES64KS2 => aes64_ks2(rs1, rs2),
+            CallCryptoIdx::SHA256SIG0 => { let x = rs1 as u32; sext32(x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)) }
+            CallCryptoIdx::SHA256SIG1 => { let x = rs1 as u32; sext32(x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)) }
+            CallCryptoIdx::SHA256SUM0 => { let x = rs1 as u32; sext32(x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)) }
+            CallCryptoIdx::SHA256SUM1 => { let x = rs1 as u32; sext32(x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)) }
+            CallCryptoIdx::SHA512SIG0 => rs1.rotate_right(1) ^ rs1.rotate_right(8) ^ (rs1 >> 7),
+            CallCryptoIdx::SHA512SIG1 => rs1.rotate_right(19) ^ rs1.rotate_right(61) ^ (rs1 >> 6),
+            CallCryptoIdx::SHA512SUM0 => rs1.rotate_right(28) ^ rs1.rotate_right(34) ^ rs1.rotate_right(39),
+            CallCryptoIdx::SHA512SUM1 => rs1.rotate_right(14) ^ rs1.rotate_right(18) ^ rs1.rotate_right(41),
+            CallCryptoIdx::SM3P0 => { let x = rs1 as u32; sext32(x ^ x.rotate_left(9) ^ x.rotate_left(17)) }
+            CallCryptoIdx::SM3P1 => { let x = rs1 as u32; sext32(x ^ x.rotate_left(15) ^ x.rotate_left(23)) }
+            CallCryptoIdx::SM4ED => sm4_round(rs1, rs2, imm as u32, false),
+            CallCryptoIdx::SM4KS => sm4_round(rs1, rs2, imm as u32, true),
+            CallCryptoIdx::BREV8 => u64::from_le_bytes(rs1.to_le_bytes().map(|b| b.reverse_bits())),
+            CallCryptoIdx::XPERM4 => xperm(rs1, rs2, 4),
+            CallCryptoIdx::XPERM8 => xperm(rs1, rs2, 8),
+        };
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = res;
+        }
+        return 0;
+    }
+
+    // seed is accessible from M-mode, and from S-mode / U-mode when mseccfg.SSEED / USEED is set.
+    // Read-only access (csrrs / csrrc with x0) is illegal so that entropy is not read and dropped.
+    pub fn seed_access_fault(&self, write: bool) -> Option<ExceptCode> {
+        if self.m_csr.m_entropy.is_none() || !write {
+            return Some(ExceptCode::IllegalInst);
+        }
+        let mseccfg = self.m_csr.m_mseccfg.m_csr;
+        let sseed = Self::extract_bit_field(mseccfg, riscv_csr_def::SYSREG_MSECCFG_SSEED_MSB, riscv_csr_def::SYSREG_MSECCFG_SSEED_LSB) != 0;
+        let useed = Self::extract_bit_field(mseccfg, riscv_csr_def::SYSREG_MSECCFG_USEED_MSB, riscv_csr_def::SYSREG_MSECCFG_USEED_LSB) != 0;
+        if self.m_priv == PrivMode::Machine {
+            None
+        } else if self.m_virt {
+            Some(if sseed { ExceptCode::VirtualInst } else { ExceptCode::IllegalInst })
+        } else if (self.m_priv == PrivMode::Supervisor && sseed) || (self.m_priv == PrivMode::User && useed) {
+            None
+        } else {
+            Some(ExceptCode::IllegalInst)
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use softfloat_wrapper::{ExceptionFlags, Float, F64, F32, RoundingMode};
+use softfloat_wrapper::{ExceptionFlags, Float, F64, F32, F16, RoundingMode};
 use crate::target::riscv::riscv_csr::{CsrAddr};
 use crate::target::riscv::riscv::CallFcvtIdx;
 use crate::emu_env::EmuEnv;
 
 impl EmuEnv {
-    pub fn helper_func_fcvt(emu: &mut EmuEnv, call_idx: u64, rd: u64, rs1: u64, _: u64) -> usize {
+    pub fn helper_func_fcvt(emu: &mut EmuEnv, call_idx: u64, rd: u64, rs1: u64, rm: u64) -> usize {
+        // Only the Zfh conversions pass the rm field of the instruction, the others use fixed modes.
+        let rnd = Self::get_rounding_mode(emu, rm);
         let mut flag = ExceptionFlags::default();
         flag.set();
         let helper_idx = CallFcvtIdx::from_u64(call_idx);
@@ -27,6 +29,18 @@ impl EmuEnv {
             CallFcvtIdx::LU_D => { let to_data = F64::from_bits(emu.m_fregs[rs1 as usize] as u64).to_u64(RoundingMode::TowardZero, true); emu.m_iregs[rd as usize] = to_data        as u64; },
             CallFcvtIdx::D_L  => { let to_data = F64::from_i64 (emu.m_iregs[rs1 as usize] as i64, RoundingMode::TowardZero)     ; emu.m_fregs[rd as usize] = to_data.bits() as u64; },
             CallFcvtIdx::D_LU => { let to_data = F64::from_u64 (emu.m_iregs[rs1 as usize] as u64, RoundingMode::TiesToEven)     ; emu.m_fregs[rd as usize] = to_data.bits() as u64; },
+            CallFcvtIdx::S_H  => { let to_data = Self::get_freg_h(emu, rs1).to_f32(rnd)                                                 ; emu.m_fregs[rd as usize] = to_data.to_bits() as u64 | 0xffffffff_00000000; },
+            CallFcvtIdx::H_S  => { let to_data = F32::from_bits(Self::convert_nan_boxing(emu.m_fregs[rs1 as usize]) as u32).to_f16(rnd) ; emu.m_fregs[rd as usize] = Self::nan_boxing_h(to_data.to_bits()); },
+            CallFcvtIdx::D_H  => { let to_data = Self::get_freg_h(emu, rs1).to_f64(rnd)                                                 ; emu.m_fregs[rd as usize] = to_data.to_bits() as u64; },
+            CallFcvtIdx::H_D  => { let to_data = F64::from_bits(emu.m_fregs[rs1 as usize] as u64).to_f16(rnd)                           ; emu.m_fregs[rd as usize] = Self::nan_boxing_h(to_data.to_bits()); },
+            CallFcvtIdx::W_H  => { let to_data = Self::get_freg_h(emu, rs1).to_i32(rnd, true)                                           ; emu.m_iregs[rd as usize] = to_data        as u64; },
+            CallFcvtIdx::WU_H => { let to_data = Self::get_freg_h(emu, rs1).to_u32(rnd, true)                                           ; emu.m_iregs[rd as usize] = to_data        as i32 as u64; },
+            CallFcvtIdx::L_H  => { let to_data = Self::get_freg_h(emu, rs1).to_i64(rnd, true)                                           ; emu.m_iregs[rd as usize] = to_data        as u64; },
+            CallFcvtIdx::LU_H => { let to_data = Self::get_freg_h(emu, rs1).to_u64(rnd, true)                                           ; emu.m_iregs[rd as usize] = to_data        as u64; },
+            CallFcvtIdx::H_W  => { let to_data = F16::from_i32 (emu.m_iregs[rs1 as usize] as i32, rnd)                                  ; emu.m_fregs[rd as usize] = Self::nan_boxing_h(to_data.to_bits()); },
+            CallFcvtIdx::H_WU => { let to_data = F16::from_u32 (emu.m_iregs[rs1 as usize] as u32, rnd)                                  ; emu.m_fregs[rd as usize] = Self::nan_boxing_h(to_data.to_bits()); },
+            CallFcvtIdx::H_L  => { let to_data = F16::from_i64 (emu.m_iregs[rs1 as usize] as i64, rnd)                                  ; emu.m_fregs[rd as usize] = Self::nan_boxing_h(to_data.to_bits()); },
+            CallFcvtIdx::H_LU => { let to_data = F16::from_u64 (emu.m_iregs[rs1 as usize] as u64, rnd)                                  ; emu.m_fregs[rd as usize] = Self::nan_boxing_h(to_data.to_bits()); },
         };
 
         flag.get();
@@ -2,6 +2,24 @@ use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F64};
 use crate::target::riscv::riscv_csr::{CsrAddr};
 use crate::emu_env::EmuEnv;
 
+pub const FFLAGS_NX: u8 = 1 << 0;
+pub const FFLAGS_OF: u8 = 1 << 2;
+pub const FFLAGS_DZ: u8 = 1 << 3;
+pub const FFLAGS_NV: u8 = 1 << 4;
+
+// Constants of fli.s / fli.d / fli.h. Entry 1 (minimum positive normal) and 31 (canonical NaN)
+// depend on the format and are filled in by each helper.
+pub const FLI_TABLE: [f64; 32] = [
+    -1.0, 0.0, 1.52587890625e-05, 3.0517578125e-05,
+    0.00390625, 0.0078125, 0.0625, 0.125,
+    0.25, 0.3125, 0.375, 0.4375,
+    0.5, 0.625, 0.75, 0.875,
+    1.0, 1.25, 1.5, 1.75,
+    2.0, 2.5, 3.0, 4.0,
+    8.0, 16.0, 128.0, 256.0,
+    32768.0, 65536.0, f64::INFINITY, f64::NAN,
+];
+
 impl EmuEnv {
     pub fn helper_func_fadd_d(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
         let fs1_data = F64::from_bits(emu.m_fregs[fs1 as usize]);
@@ -288,4 +306,129 @@ impl EmuEnv {
         emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
         return 0;
     }
+
+    // rm field of the instruction, 0b111 takes the dynamic rounding mode in frm.
+    pub fn get_rounding_mode(emu: &mut EmuEnv, rm: u64) -> RoundingMode {
+        let rm = if rm == 0b111 { emu.m_csr.csrrs(CsrAddr::Frm, 0) as u64 } else { rm };
+        match rm {
+            0b001 => RoundingMode::TowardZero,
+            0b010 => RoundingMode::TowardNegative,
+            0b011 => RoundingMode::TowardPositive,
+            0b100 => RoundingMode::TiesToAway,
+            _     => RoundingMode::TiesToEven,
+        }
+    }
+
+    pub fn helper_func_fli_d(emu: &mut EmuEnv, fd: u64, idx: u64, _: u64, _: u64) -> usize {
+        emu.m_fregs[fd as usize] = match idx {
+            1  => f64::MIN_POSITIVE.to_bits(),
+            31 => F64::quiet_nan().to_bits(),
+            _  => FLI_TABLE[idx as usize].to_bits(),
+        };
+        return 0;
+    }
+
+    // fminm / fmaxm : same as fmin / fmax except that any NaN input gives the canonical NaN.
+    pub fn helper_func_fminmaxm_d(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, is_max: u64) -> usize {
+        let fs1_data = F64::from_bits(emu.m_fregs[fs1 as usize]);
+        let fs2_data = F64::from_bits(emu.m_fregs[fs2 as usize]);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let (lhs, rhs) = if is_max != 0 { (fs2_data, fs1_data) } else { (fs1_data, fs2_data) };
+        let take_fs1 = lhs.lt_quiet(rhs) || lhs.eq(rhs) && lhs.is_negative();
+        let fd_data = if fs1_data.is_nan() || fs2_data.is_nan() {
+            F64::quiet_nan()
+        } else if take_fs1 {
+            fs1_data
+        } else {
+            fs2_data
+        };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        emu.m_fregs[fd as usize] = fd_data.to_bits();
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    // fround / froundnx : NX is raised only by froundnx, when the result differs from the input.
+    pub fn helper_func_fround_d(emu: &mut EmuEnv, fd: u64, fs1: u64, rm: u64, exact: u64) -> usize {
+        let fs1_data = F64::from_bits(emu.m_fregs[fs1 as usize]);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.round_to_integral(rnd);
+        flag.get();
+        let mut ret_flag = flag.to_bits();
+        if exact != 0 && !fs1_data.is_nan() && fd_data.to_bits() != fs1_data.to_bits() {
+            ret_flag |= FFLAGS_NX;
+        }
+        emu.m_fregs[fd as usize] = fd_data.to_bits();
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    // fleq / fltq : quiet comparisons, only signaling NaN raises NV.
+    pub fn helper_func_fcmpq_d(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, is_lt: u64) -> usize {
+        let fs1_data = F64::from_bits(emu.m_fregs[fs1 as usize]);
+        let fs2_data = F64::from_bits(emu.m_fregs[fs2 as usize]);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = if is_lt != 0 { fs1_data.lt_quiet(fs2_data) } else { fs1_data.le_quiet(fs2_data) };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result as u64;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    // fcvtmod.w.d : truncate toward zero and keep the lower 32 bits of the integer.
+    pub fn helper_func_fcvtmod_w_d(emu: &mut EmuEnv, rd: u64, fs1: u64, _: u64, _: u64) -> usize {
+        let bits = emu.m_fregs[fs1 as usize];
+        let negative = (bits >> 63) != 0;
+        let exp = ((bits >> 52) & 0x7ff) as i64;
+        let frac = bits & ((1 << 52) - 1);
+
+        let (result, ret_flag) = if exp == 0x7ff {
+            (0, FFLAGS_NV)
+        } else if exp == 0 && frac == 0 {
+            (0, 0)
+        } else {
+            let mant = if exp == 0 { frac } else { frac | (1 << 52) };
+            let shift = exp - 1075;
+            let (int, inexact) = if shift >= 64 {
+                (0, false)
+            } else if shift >= 0 {
+                (mant << shift, false)
+            } else if shift > -64 {
+                (mant >> -shift, mant & ((1 << -shift) - 1) != 0)
+            } else {
+                (0, true)
+            };
+            let int = if negative { int.wrapping_neg() } else { int };
+            let unbiased = exp - 1023;
+            let invalid = unbiased > 31 || unbiased == 31 && !(negative && frac == 0);
+            let flag = if invalid { FFLAGS_NV } else if inexact { FFLAGS_NX } else { 0 };
+            (int as u32 as i32 as i64 as u64, flag)
+        };
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    // fmvh.x.d / fmvp.d.x : upper half of a double register and a double from an integer pair.
+    pub fn helper_func_fmvh_x_d(emu: &mut EmuEnv, rd: u64, fs1: u64, _: u64, _: u64) -> usize {
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = (emu.m_fregs[fs1 as usize] >> 32) as u32 as i32 as i64 as u64;
+        }
+        return 0;
+    }
+
+    pub fn helper_func_fmvp_d_x(emu: &mut EmuEnv, fd: u64, rs1: u64, rs2: u64, _: u64) -> usize {
+        emu.m_fregs[fd as usize] = (emu.m_iregs[rs2 as usize] << 32) | (emu.m_iregs[rs1 as usize] & 0xffffffff);
+        return 0;
+    }
 }
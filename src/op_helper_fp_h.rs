@@ -0,0 +1,356 @@
+use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F16, F64};
+use crate::target::riscv::riscv_csr::{CsrAddr};
+use crate::emu_env::EmuEnv;
+use crate::op_helper_fp_d::{FFLAGS_NX, FLI_TABLE};
+
+impl EmuEnv {
+    // A half value is valid only when the upper 48 bits are all 1, otherwise it is the canonical NaN.
+    #[inline]
+    pub fn convert_nan_boxing_h (i: u64) -> u16 {
+        if i & 0xffffffff_ffff0000 == 0xffffffff_ffff0000 {
+            (i & 0xffff) as u16
+        } else {
+            0x7e00
+        }
+    }
+
+    #[inline]
+    pub fn nan_boxing_h (h: u16) -> u64 {
+        h as u64 | 0xffffffff_ffff0000
+    }
+
+    #[inline]
+    pub fn get_freg_h (emu: &EmuEnv, fs: u64) -> F16 {
+        F16::from_bits(Self::convert_nan_boxing_h(emu.m_fregs[fs as usize]))
+    }
+
+    pub fn helper_func_fadd_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, rm: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.add(fs2_data, rnd);
+        flag.get();
+        let ret_flag = flag.to_bits();
+
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+
+        return 0;
+    }
+
+    pub fn helper_func_fsub_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, rm: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.sub(fs2_data, rnd);
+        flag.get();
+        let ret_flag = flag.to_bits();
+
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+
+        return 0;
+    }
+
+    pub fn helper_func_fmul_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, rm: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.mul(fs2_data, rnd);
+        flag.get();
+        let ret_flag = flag.to_bits();
+
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+
+        return 0;
+    }
+
+    pub fn helper_func_fdiv_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, rm: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.div(fs2_data, rnd);
+        flag.get();
+        let ret_flag = flag.to_bits();
+
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+
+        return 0;
+    }
+
+    // fs1 * fs2 + fs3 with a single rounding, signs of the product / addend are flipped for the variants.
+    // The rm field comes above the fs3 index.
+    fn fused_mul_add_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, fs3_rm: u64, neg_mul: bool, neg_add: bool) -> usize {
+        let mut fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut fs3_data = Self::get_freg_h(emu, fs3_rm & 0x1f);
+        let rnd = Self::get_rounding_mode(emu, fs3_rm >> 5);
+        if neg_mul {
+            fs1_data = fs1_data.neg();
+        }
+        if neg_add {
+            fs3_data = fs3_data.neg();
+        }
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.fused_mul_add(fs2_data, fs3_data, rnd);
+        flag.get();
+        let ret_flag = flag.to_bits();
+
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+
+        return 0;
+    }
+
+    pub fn helper_func_fmadd_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, fs3_rm: u64) -> usize {
+        Self::fused_mul_add_h(emu, fd, fs1, fs2, fs3_rm, false, false)
+    }
+
+    pub fn helper_func_fmsub_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, fs3_rm: u64) -> usize {
+        Self::fused_mul_add_h(emu, fd, fs1, fs2, fs3_rm, false, true)
+    }
+
+    pub fn helper_func_fnmsub_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, fs3_rm: u64) -> usize {
+        Self::fused_mul_add_h(emu, fd, fs1, fs2, fs3_rm, true, false)
+    }
+
+    pub fn helper_func_fnmadd_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, fs3_rm: u64) -> usize {
+        Self::fused_mul_add_h(emu, fd, fs1, fs2, fs3_rm, true, true)
+    }
+
+    pub fn helper_func_fsqrt_h(emu: &mut EmuEnv, fd: u64, fs1: u64, rm: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.sqrt(rnd);
+        flag.get();
+        let ret_flag = flag.to_bits();
+
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+
+        return 0;
+    }
+
+    pub fn helper_func_feq_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = fs1_data.eq(fs2_data) as u64;
+        flag.get();
+        let ret_flag = flag.to_bits();
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_flt_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = fs1_data.lt(fs2_data) as u64;
+        flag.get();
+        let ret_flag = flag.to_bits();
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fle_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = fs1_data.le(fs2_data) as u64;
+        flag.get();
+        let ret_flag = flag.to_bits();
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fclass_h(emu: &mut EmuEnv, rd: u64, fs1: u64, _fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let result = if fs1_data.is_negative_infinity() {
+            1 << 0
+        } else if fs1_data.is_negative_normal() {
+            1 << 1
+        } else if fs1_data.is_negative_subnormal() {
+            1 << 2
+        } else if fs1_data.is_negative_zero() {
+            1 << 3
+        } else if fs1_data.is_positive_zero() {
+            1 << 4
+        } else if fs1_data.is_positive_subnormal() {
+            1 << 5
+        } else if fs1_data.is_positive_normal() {
+            1 << 6
+        } else if fs1_data.is_positive_infinity() {
+            1 << 7
+        } else if fs1_data.is_signaling_nan() {
+            1 << 8
+        } else {
+            1 << 9
+        };
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result as u64;
+        }
+        return 0;
+    }
+
+    pub fn helper_func_fmax_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data =
+        if fs1_data.is_nan() && fs2_data.is_nan() {
+            F16::quiet_nan()
+        } else if fs2_data.lt_quiet(fs1_data) || fs2_data.is_nan() || fs1_data.eq(fs2_data) && fs2_data.is_negative() {
+            fs1_data
+        } else {
+            fs2_data
+        };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        emu.m_fregs[rd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fmin_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data =
+        if fs1_data.is_nan() && fs2_data.is_nan() {
+            F16::quiet_nan()
+        } else if fs1_data.lt_quiet(fs2_data) || fs2_data.is_nan() || fs1_data.eq(fs2_data) && fs1_data.is_negative() {
+            fs1_data
+        } else {
+            fs2_data
+        };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        emu.m_fregs[rd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fsgnj_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::convert_nan_boxing_h(emu.m_fregs[fs1 as usize]);
+        let fs2_data = Self::convert_nan_boxing_h(emu.m_fregs[fs2 as usize]);
+        emu.m_fregs[rd as usize] = Self::nan_boxing_h(fs1_data & 0x7fff | fs2_data & 0x8000);
+        return 0;
+    }
+
+    pub fn helper_func_fsgnjn_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::convert_nan_boxing_h(emu.m_fregs[fs1 as usize]);
+        let fs2_data = Self::convert_nan_boxing_h(emu.m_fregs[fs2 as usize]);
+        emu.m_fregs[rd as usize] = Self::nan_boxing_h(fs1_data & 0x7fff | !fs2_data & 0x8000);
+        return 0;
+    }
+
+    pub fn helper_func_fsgnjx_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, _: u64) -> usize {
+        let fs1_data = Self::convert_nan_boxing_h(emu.m_fregs[fs1 as usize]);
+        let fs2_data = Self::convert_nan_boxing_h(emu.m_fregs[fs2 as usize]);
+        emu.m_fregs[rd as usize] = Self::nan_boxing_h(fs1_data & 0x7fff | (fs1_data ^ fs2_data) & 0x8000);
+        return 0;
+    }
+
+    // FMV.X.H sign-extends the raw lower 16 bits, FMV.H.X NaN-boxes them.
+    pub fn helper_func_fmv_x_h(emu: &mut EmuEnv, rd: u64, fs1: u64, _: u64, _: u64) -> usize {
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = emu.m_fregs[fs1 as usize] as u16 as i16 as i64 as u64;
+        }
+        return 0;
+    }
+
+    pub fn helper_func_fmv_h_x(emu: &mut EmuEnv, fd: u64, rs1: u64, _: u64, _: u64) -> usize {
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(emu.m_iregs[rs1 as usize] as u16);
+        return 0;
+    }
+
+    pub fn helper_func_fli_h(emu: &mut EmuEnv, fd: u64, idx: u64, _: u64, _: u64) -> usize {
+        let bits = match idx {
+            1  => 0x0400,
+            31 => F16::quiet_nan().to_bits(),
+            _  => F64::from_bits(FLI_TABLE[idx as usize].to_bits()).to_f16(RoundingMode::TiesToEven).to_bits(),
+        };
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(bits);
+        return 0;
+    }
+
+    pub fn helper_func_fminmaxm_h(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, is_max: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let (lhs, rhs) = if is_max != 0 { (fs2_data, fs1_data) } else { (fs1_data, fs2_data) };
+        let take_fs1 = lhs.lt_quiet(rhs) || lhs.eq(rhs) && lhs.is_negative();
+        let fd_data = if fs1_data.is_nan() || fs2_data.is_nan() {
+            F16::quiet_nan()
+        } else if take_fs1 {
+            fs1_data
+        } else {
+            fs2_data
+        };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fround_h(emu: &mut EmuEnv, fd: u64, fs1: u64, rm: u64, exact: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.round_to_integral(rnd);
+        flag.get();
+        let mut ret_flag = flag.to_bits();
+        if exact != 0 && !fs1_data.is_nan() && fd_data.to_bits() != fs1_data.to_bits() {
+            ret_flag |= FFLAGS_NX;
+        }
+        emu.m_fregs[fd as usize] = Self::nan_boxing_h(fd_data.to_bits());
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fcmpq_h(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, is_lt: u64) -> usize {
+        let fs1_data = Self::get_freg_h(emu, fs1);
+        let fs2_data = Self::get_freg_h(emu, fs2);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = if is_lt != 0 { fs1_data.lt_quiet(fs2_data) } else { fs1_data.le_quiet(fs2_data) };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result as u64;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+}
@@ -1,10 +1,11 @@
 use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F32};
 use crate::target::riscv::riscv_csr::{CsrAddr};
 use crate::emu_env::EmuEnv;
+use crate::op_helper_fp_d::{FFLAGS_NX, FLI_TABLE};
 
 impl EmuEnv {
     #[inline]
-    fn convert_nan_boxing (i: u64) -> u32 {
+    pub fn convert_nan_boxing (i: u64) -> u32 {
         if i & 0xffffffff_00000000 == 0xffffffff_00000000 {
             (i & 0xffffffff) as u32
         } else {
@@ -338,4 +339,66 @@ impl EmuEnv {
         emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
         return 0;
     }
+
+    pub fn helper_func_fli_s(emu: &mut EmuEnv, fd: u64, idx: u64, _: u64, _: u64) -> usize {
+        let bits = match idx {
+            1  => f32::MIN_POSITIVE.to_bits(),
+            31 => F32::quiet_nan().to_bits(),
+            _  => (FLI_TABLE[idx as usize] as f32).to_bits(),
+        };
+        emu.m_fregs[fd as usize] = bits as u64 | 0xffffffff_00000000;
+        return 0;
+    }
+
+    pub fn helper_func_fminmaxm_s(emu: &mut EmuEnv, fd: u64, fs1: u64, fs2: u64, is_max: u64) -> usize {
+        let fs1_data = F32::from_bits(Self::convert_nan_boxing(emu.m_fregs[fs1 as usize]));
+        let fs2_data = F32::from_bits(Self::convert_nan_boxing(emu.m_fregs[fs2 as usize]));
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let (lhs, rhs) = if is_max != 0 { (fs2_data, fs1_data) } else { (fs1_data, fs2_data) };
+        let take_fs1 = lhs.lt_quiet(rhs) || lhs.eq(rhs) && lhs.is_negative();
+        let fd_data = if fs1_data.is_nan() || fs2_data.is_nan() {
+            F32::quiet_nan()
+        } else if take_fs1 {
+            fs1_data
+        } else {
+            fs2_data
+        };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        emu.m_fregs[fd as usize] = fd_data.to_bits() as u64 | 0xffffffff_00000000;
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fround_s(emu: &mut EmuEnv, fd: u64, fs1: u64, rm: u64, exact: u64) -> usize {
+        let fs1_data = F32::from_bits(Self::convert_nan_boxing(emu.m_fregs[fs1 as usize]));
+        let rnd = Self::get_rounding_mode(emu, rm);
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let fd_data = fs1_data.round_to_integral(rnd);
+        flag.get();
+        let mut ret_flag = flag.to_bits();
+        if exact != 0 && !fs1_data.is_nan() && fd_data.to_bits() != fs1_data.to_bits() {
+            ret_flag |= FFLAGS_NX;
+        }
+        emu.m_fregs[fd as usize] = fd_data.to_bits() as u64 | 0xffffffff_00000000;
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
+
+    pub fn helper_func_fcmpq_s(emu: &mut EmuEnv, rd: u64, fs1: u64, fs2: u64, is_lt: u64) -> usize {
+        let fs1_data = F32::from_bits(Self::convert_nan_boxing(emu.m_fregs[fs1 as usize]));
+        let fs2_data = F32::from_bits(Self::convert_nan_boxing(emu.m_fregs[fs2 as usize]));
+        let mut flag = ExceptionFlags::default();
+        flag.set();
+        let result = if is_lt != 0 { fs1_data.lt_quiet(fs2_data) } else { fs1_data.le_quiet(fs2_data) };
+        flag.get();
+        let ret_flag = flag.to_bits();
+        if rd != 0 {
+            emu.m_iregs[rd as usize] = result as u64;
+        }
+        emu.m_csr.csrrw(CsrAddr::FFlags, ret_flag as i64);
+        return 0;
+    }
 }
@@ -1,6 +1,8 @@
-use crate::emu_env::{EmuEnv, MachineEnum};
+use crate::emu_env::{tlb_index, tlb_tag, EmuEnv, MachineEnum};
 use crate::target::riscv::mmu::{MemAccType, MemResult};
-use crate::target::riscv::riscv::ExceptCode;
+use crate::target::riscv::riscv::{ExceptCode, PrivMode, CBO_INVAL, CBO_ZERO, HYP_LDST_EXEC, HYP_LDST_UNSIGNED};
+use crate::target::riscv::riscv_csr::CsrAddr;
+use crate::target::riscv::riscv_csr_def;
 
 impl EmuEnv {
     pub fn helper_func_load64(emu: &mut EmuEnv,rd: u64,rs1: u64,imm: u64,guest_pc: u64) -> usize {
@@ -9,19 +11,22 @@ impl EmuEnv {
         match emu.convert_physical_address(guest_pc, addr, MemAccType::Read) {
             Ok(guest_phy_addr) => { 
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
+                }
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 8) {
+                    emu.m_iregs[rd as usize] = data;
+                    return MemResult::NoExcept as usize;
                 }
                 // Update TLB List
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_8byte(guest_phy_addr) as u64;
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -50,17 +55,19 @@ impl EmuEnv {
                     }                  
                     return MemResult::NoExcept as usize;
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 4) {
+                    emu.m_iregs[rd as usize] = data as i32 as u64;
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_4byte(guest_phy_addr) as i32 as u64; 
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -75,17 +82,19 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("load16 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 2) {
+                    emu.m_iregs[rd as usize] = data as i16 as u64;
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_2byte(guest_phy_addr) as i16 as u64; 
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -100,17 +109,19 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("load8 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 1) {
+                    emu.m_iregs[rd as usize] = data as i8 as u64;
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_1byte(guest_phy_addr) as i8 as u64; 
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -125,17 +136,19 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("loadu32 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 4) {
+                    emu.m_iregs[rd as usize] = data as u32 as u64;
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_4byte(guest_phy_addr) as u64; 
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -150,17 +163,19 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("loadu16 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 2) {
+                    emu.m_iregs[rd as usize] = data as u16 as u64;
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_2byte(guest_phy_addr) as u64;
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -175,17 +190,19 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("loadu8 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if let Some(data) = emu.mmio_read(guest_phy_addr, 1) {
+                    emu.m_iregs[rd as usize] = data as u8 as u64;
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_iregs[rd as usize] = emu.read_mem_1byte(guest_phy_addr) as u64; 
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -207,17 +224,18 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("store64 : converted address: {:016x} --> {:016x} <= {:016x}", addr, guest_phy_addr, rs2_data);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if emu.mmio_write(guest_phy_addr, 8, rs2_data) {
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.write_mem_8byte(guest_phy_addr, rs2_data); 
                 return MemResult::NoExcept as usize; 
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -247,21 +265,18 @@ impl EmuEnv {
                     }                  
                     return MemResult::NoExcept as usize;
                 }
-                if emu.m_arg_config.machine == MachineEnum::RiscvSiFiveU && (guest_phy_addr & !0xfff) == 0x10_0000 {
-                    emu.m_notify_exit = true;
+                if emu.mmio_write(guest_phy_addr, 4, rs2_data as u32 as u64) {
                     return MemResult::NoExcept as usize;
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.write_mem_4byte(guest_phy_addr, rs2_data as u32);
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -283,17 +298,18 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("store16 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if emu.mmio_write(guest_phy_addr, 2, rs2_data as u16 as u64) {
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.write_mem_2byte(guest_phy_addr, rs2_data as u16);
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -315,17 +331,18 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("store8 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                if emu.mmio_write(guest_phy_addr, 1, rs2_data as u8 as u64) {
+                    return MemResult::NoExcept as usize;
+                }
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.write_mem_1byte(guest_phy_addr, rs2_data as u8);
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -340,16 +357,15 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("loadf64 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_fregs[rd as usize] = emu.read_mem_8byte(guest_phy_addr) as u64;
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -364,17 +380,15 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("loadf32 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                emu.update_tlb(addr, guest_phy_addr);
                 if emu.m_arg_config.mmu_debug {
-                    println!("update tlb_vec[{:}] = {:016x}", ((addr >> 12) & 0xfff) as usize, addr >> (12 + 12));
+                    println!("update tlb_vec[{:}] = {:016x}", tlb_index(addr), tlb_tag(addr));
                 }
                 emu.m_fregs[rd as usize] = emu.read_mem_4byte(guest_phy_addr) as u64 | 0xffffffff00000000;  // NaN Boxing 
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::LoadPageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -390,14 +404,12 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("storef64 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                emu.update_tlb(addr, guest_phy_addr);
                 emu.write_mem_8byte(guest_phy_addr, rs2_data); 
                 return MemResult::NoExcept as usize; 
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
@@ -413,17 +425,205 @@ impl EmuEnv {
                 if emu.m_arg_config.mmu_debug {
                     println!("storef32 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
                 }
-                emu.m_tlb_vec[((addr >> 12) & 0xfff) as usize] = addr >> (12 + 12);
-                emu.m_tlb_addr_vec[((addr >> 12) & 0xfff) as usize] = guest_phy_addr & !0xfff;
+                emu.update_tlb(addr, guest_phy_addr);
                 emu.write_mem_4byte(guest_phy_addr, rs2_data as u32);
                 return MemResult::NoExcept as usize;
             }
             Err(error) => {
-                print!("Read Error: {:?}\n", error);
-                emu.generate_exception(guest_pc, ExceptCode::StorePageFault, addr as i64);
+                // Page / access fault is raised while walking page table.
+                return error as usize;
+            }
+        };
+    }
+
+    pub fn helper_func_float_load16(emu: &mut EmuEnv, rd: u64, rs1: u64, imm: u64, guest_pc: u64) -> usize {
+        let rs1_data = emu.m_iregs[rs1 as usize];
+        let addr = rs1_data.wrapping_add(imm as i32 as u64);
+
+        match emu.convert_physical_address(guest_pc, addr, MemAccType::Read) {
+            Ok(guest_phy_addr) => { 
+                if emu.m_arg_config.mmu_debug {
+                    println!("loadf16 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
+                }
+                emu.update_tlb(addr, guest_phy_addr);
+                emu.m_fregs[rd as usize] = Self::nan_boxing_h(emu.read_mem_2byte(guest_phy_addr));
+                return MemResult::NoExcept as usize;
+            }
+            Err(error) => {
+                // Page / access fault is raised while walking page table.
+                return error as usize;
+            }
+        };
+    }
+
+    pub fn helper_func_float_store16(emu: &mut EmuEnv, rs2: u64, rs1: u64, imm: u64, guest_pc: u64) -> usize {
+        let rs1_data = emu.m_iregs[rs1 as usize];
+        let rs2_data = emu.m_fregs[rs2 as usize];
+        let addr = rs1_data.wrapping_add(imm as i32 as u64);
+
+        match emu.convert_physical_address(guest_pc, addr, MemAccType::Write) {
+            Ok(guest_phy_addr) => { 
+                if emu.m_arg_config.mmu_debug {
+                    println!("storef16 : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
+                }
+                emu.update_tlb(addr, guest_phy_addr);
+                emu.write_mem_2byte(guest_phy_addr, rs2_data as u16);
+                return MemResult::NoExcept as usize;
+            }
+            Err(error) => {
+                // Page / access fault is raised while walking page table.
                 return error as usize;
             }
         };
     }
 
+    // HLV / HSV are allowed in HS-mode and M-mode, and in U-mode when hstatus.HU = 1.
+    fn check_hyp_ldst(emu: &mut EmuEnv, guest_pc: u64) -> Result<(), ExceptCode> {
+        let hstatus = emu.m_csr.csrrs(CsrAddr::Hstatus, 0);
+        let hu = Self::extract_bit_field(hstatus, riscv_csr_def::SYSREG_HSTATUS_HU_MSB, riscv_csr_def::SYSREG_HSTATUS_HU_LSB);
+        let code = if !emu.m_arg_config.hypervisor {
+            Some(ExceptCode::IllegalInst)
+        } else if emu.m_virt {
+            Some(ExceptCode::VirtualInst)
+        } else if emu.m_priv == PrivMode::User && hu == 0 {
+            Some(ExceptCode::IllegalInst)
+        } else {
+            None
+        };
+        match code {
+            Some(code) => {
+                emu.generate_exception(guest_pc, code, 0);
+                Err(code)
+            }
+            None => Ok(()),
+        }
+    }
+
+    // HLV.* / HLVX.* : kind = [3:0] size, HYP_LDST_UNSIGNED, HYP_LDST_EXEC
+    pub fn helper_func_hlv(emu: &mut EmuEnv, rd: u64, rs1: u64, kind: u64, guest_pc: u64) -> usize {
+        if let Err(code) = Self::check_hyp_ldst(emu, guest_pc) {
+            return code as usize;
+        }
+        let addr = emu.m_iregs[rs1 as usize];
+        let size = (kind & 0xf) as usize;
+        let hlvx = (kind & HYP_LDST_EXEC) != 0;
+
+        match emu.convert_guest_physical_address(guest_pc, addr, MemAccType::Read, hlvx) {
+            Ok(guest_phy_addr) => {
+                if emu.m_arg_config.mmu_debug {
+                    println!("hlv : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
+                }
+                let data = match emu.mmio_read(guest_phy_addr, size) {
+                    Some(data) => data,
+                    None => match size {
+                        1 => emu.read_mem_1byte(guest_phy_addr) as u64,
+                        2 => emu.read_mem_2byte(guest_phy_addr) as u64,
+                        4 => emu.read_mem_4byte(guest_phy_addr) as u64,
+                        _ => emu.read_mem_8byte(guest_phy_addr),
+                    },
+                };
+                let shift = 64 - size * 8;
+                emu.m_iregs[rd as usize] = if (kind & HYP_LDST_UNSIGNED) != 0 {
+                    (data << shift) >> shift
+                } else {
+                    (((data << shift) as i64) >> shift) as u64
+                };
+                return MemResult::NoExcept as usize;
+            }
+            Err(error) => {
+                // Page / access fault is raised while walking page table.
+                return error as usize;
+            }
+        };
+    }
+
+    // HSV.* : kind = [3:0] size
+    pub fn helper_func_hsv(emu: &mut EmuEnv, rs2: u64, rs1: u64, kind: u64, guest_pc: u64) -> usize {
+        if let Err(code) = Self::check_hyp_ldst(emu, guest_pc) {
+            return code as usize;
+        }
+        let addr = emu.m_iregs[rs1 as usize];
+        let rs2_data = emu.m_iregs[rs2 as usize];
+        let size = (kind & 0xf) as usize;
+
+        match emu.convert_guest_physical_address(guest_pc, addr, MemAccType::Write, false) {
+            Ok(guest_phy_addr) => {
+                if emu.m_arg_config.mmu_debug {
+                    println!("hsv : converted address: {:016x} --> {:016x} <= {:016x}", addr, guest_phy_addr, rs2_data);
+                }
+                if emu.mmio_write(guest_phy_addr, size, rs2_data) {
+                    return MemResult::NoExcept as usize;
+                }
+                match size {
+                    1 => emu.write_mem_1byte(guest_phy_addr, rs2_data as u8),
+                    2 => emu.write_mem_2byte(guest_phy_addr, rs2_data as u16),
+                    4 => emu.write_mem_4byte(guest_phy_addr, rs2_data as u32),
+                    _ => emu.write_mem_8byte(guest_phy_addr, rs2_data),
+                }
+                return MemResult::NoExcept as usize;
+            }
+            Err(error) => {
+                // Page / access fault is raised while walking page table.
+                return error as usize;
+            }
+        };
+    }
+
+    // cbo.* below M-mode are enabled by menvcfg, henvcfg (VS / VU-mode) and senvcfg (U / VU-mode).
+    fn cbo_access_fault(&self, op: u64) -> Option<ExceptCode> {
+        let (msb, lsb) = match op {
+            CBO_ZERO => (riscv_csr_def::SYSREG_MENVCFG_CBZE_MSB, riscv_csr_def::SYSREG_MENVCFG_CBZE_LSB),
+            CBO_INVAL => (riscv_csr_def::SYSREG_MENVCFG_CBIE_MSB, riscv_csr_def::SYSREG_MENVCFG_CBIE_LSB),
+            _ => (riscv_csr_def::SYSREG_MENVCFG_CBCFE_MSB, riscv_csr_def::SYSREG_MENVCFG_CBCFE_LSB),
+        };
+        if self.m_priv == PrivMode::Machine {
+            return None;
+        }
+        let enabled = |envcfg: i64| Self::extract_bit_field(envcfg, msb, lsb) != 0;
+        if !enabled(self.m_csr.m_menvcfg.m_csr) {
+            Some(ExceptCode::IllegalInst)
+        } else if self.m_virt && !enabled(self.m_csr.m_henvcfg.m_csr) {
+            Some(ExceptCode::VirtualInst)
+        } else if self.m_priv == PrivMode::User && !enabled(self.m_csr.m_senvcfg.m_csr) {
+            Some(if self.m_virt { ExceptCode::VirtualInst } else { ExceptCode::IllegalInst })
+        } else {
+            None
+        }
+    }
+
+    // cbo.inval / clean / flush / zero : op = CBO_* (funct12)
+    // Without caches, clean / flush / inval (also inval executed as flush by CBIE = 1) only check the block address.
+    pub fn helper_func_cbo(emu: &mut EmuEnv, rs1: u64, op: u64, _: u64, guest_pc: u64) -> usize {
+        if let Some(code) = emu.cbo_access_fault(op) {
+            emu.generate_exception(guest_pc, code, 0);
+            return code as usize;
+        }
+        let block_size = emu.m_arg_config.cbo_block_size;
+        let addr = emu.m_iregs[rs1 as usize] & !(block_size - 1);
+
+        if op != CBO_ZERO {
+            return match emu.convert_cbo_address(guest_pc, addr) {
+                Ok(_) => MemResult::NoExcept as usize,
+                // Store page / access fault is raised while walking page table.
+                Err(error) => error as usize,
+            };
+        }
+        match emu.convert_physical_address(guest_pc, addr, MemAccType::Write) {
+            Ok(guest_phy_addr) => {
+                if emu.m_arg_config.mmu_debug {
+                    println!("cbo.zero : converted address: {:016x} --> {:016x}", addr, guest_phy_addr);
+                }
+                for offset in (0..block_size).step_by(8) {
+                    if !emu.mmio_write(guest_phy_addr + offset, 8, 0) {
+                        emu.write_mem_8byte(guest_phy_addr + offset, 0);
+                    }
+                }
+                return MemResult::NoExcept as usize;
+            }
+            Err(error) => {
+                // Page / access fault is raised while walking page table.
+                return error as usize;
+            }
+        };
+    }
 }
@@ -0,0 +1,976 @@
+use std::cmp;
+
+use crate::emu_env::EmuEnv;
+use crate::target::riscv::mmu::{MemAccType, MemResult};
+use crate::target::riscv::riscv::ExceptCode;
+use crate::target::riscv::riscv_csr::VTYPE_VILL;
+use crate::target::riscv::riscv_csr_def;
+
+// funct3 of OP-V : operand types
+pub const OPIVV: u32 = 0b000;
+pub const OPFVV: u32 = 0b001;
+pub const OPMVV: u32 = 0b010;
+pub const OPIVI: u32 = 0b011;
+pub const OPIVX: u32 = 0b100;
+pub const OPFVF: u32 = 0b101;
+pub const OPMVX: u32 = 0b110;
+
+// Fields of an OP-V instruction
+#[derive(Clone, Copy)]
+pub struct VInst {
+    pub vd: usize,          // also rd of scalar results
+    pub vs1: usize,         // also rs1 / imm5
+    pub vs2: usize,
+    pub vm: bool,           // unmasked when set
+    pub funct3: u32,
+    pub funct6: u32,
+}
+
+impl VInst {
+    pub fn new(inst: u64) -> VInst {
+        let inst = inst as u32;
+        VInst {
+            vd: ((inst >> 7) & 0x1f) as usize,
+            vs1: ((inst >> 15) & 0x1f) as usize,
+            vs2: ((inst >> 20) & 0x1f) as usize,
+            vm: (inst >> 25) & 0x1 != 0,
+            funct3: (inst >> 12) & 0x7,
+            funct6: inst >> 26,
+        }
+    }
+
+    pub fn simm5(&self) -> u64 {
+        (((self.vs1 as i64) << 59) >> 59) as u64
+    }
+}
+
+// vtype in effect
+#[derive(Clone, Copy)]
+pub struct VConfig {
+    pub sew: usize,         // element bits
+    pub lmul8: usize,       // LMUL x 8 : 1 (mf8) to 64 (m8)
+    pub vl: usize,
+    pub vlmax: usize,
+}
+
+#[inline]
+pub fn vmask_sew(sew: usize) -> u64 {
+    if sew >= 64 { !0 } else { (1 << sew) - 1 }
+}
+
+#[inline]
+pub fn vsext(x: u64, sew: usize) -> i64 {
+    ((x << (64 - sew)) as i64) >> (64 - sew)
+}
+
+// Register group of EMUL (x 8) is aligned to EMUL and fits in v0-v31. Fractional EMUL takes one register.
+pub fn vreg_group_ok(reg: usize, emul8: usize) -> bool {
+    if !(1..=64).contains(&emul8) {
+        return false;
+    }
+    let regs = cmp::max(emul8 / 8, 1);
+    reg % regs == 0 && reg + regs <= 32
+}
+
+// Rounding increment of a fixed-point right shift by d bits (vxrm : 0 rnu, 1 rne, 2 rdn, 3 rod)
+fn vround_inc(v: u128, d: u32, vxrm: u64) -> u128 {
+    if d == 0 {
+        return 0;
+    }
+    let bit = |n: u32| (v >> n) & 1;
+    let below = |n: u32| v & ((1u128 << n) - 1) != 0;
+    match vxrm {
+        0 => bit(d - 1),
+        1 => bit(d - 1) & (below(d - 1) as u128 | bit(d)),
+        2 => 0,
+        _ => (bit(d) == 0 && below(d)) as u128,
+    }
+}
+
+fn vroundoff_u(v: u128, d: u32, vxrm: u64) -> u128 {
+    (v >> d) + vround_inc(v, d, vxrm)
+}
+
+fn vroundoff_s(v: i128, d: u32, vxrm: u64) -> i128 {
+    (v >> d) + vround_inc(v as u128, d, vxrm) as i128
+}
+
+// Fixed-point state of an instruction : vxrm for rounding, and whether any result saturated.
+struct VFixState {
+    sew: usize,
+    vxrm: u64,
+    sat: bool,
+}
+
+impl VFixState {
+    fn clamp_u(&mut self, v: i128) -> u64 {
+        let max = vmask_sew(self.sew) as i128;
+        if v > max {
+            self.sat = true;
+            max as u64
+        } else if v < 0 {
+            self.sat = true;
+            0
+        } else {
+            v as u64
+        }
+    }
+
+    fn clamp_s(&mut self, v: i128) -> u64 {
+        let max = (1i128 << (self.sew - 1)) - 1;
+        let min = -(1i128 << (self.sew - 1));
+        let v = if v > max {
+            self.sat = true;
+            max
+        } else if v < min {
+            self.sat = true;
+            min
+        } else {
+            v
+        };
+        v as u64 & vmask_sew(self.sew)
+    }
+}
+
+// Single-width integer operations : vd = op(vs2, vs1 / rs1 / imm), multiply-add also takes vd.
+#[derive(Clone, Copy, PartialEq)]
+enum VIntOp {
+    Add, Sub, Rsub, Minu, Min, Maxu, Max, And, Or, Xor, Sll, Srl, Sra,
+    Saddu, Sadd, Ssubu, Ssub, Smul, Ssrl, Ssra, Aaddu, Aadd, Asubu, Asub,
+    Divu, Div, Remu, Rem, Mulhu, Mul, Mulhsu, Mulh, Madd, Nmsub, Macc, Nmsac,
+}
+
+fn vint_op(funct3: u32, funct6: u32) -> Option<VIntOp> {
+    use VIntOp::*;
+    let opi = matches!(funct3, OPIVV | OPIVX | OPIVI);
+    let (vv, vi) = (matches!(funct3, OPIVV | OPMVV), funct3 == OPIVI);
+    let op = match (opi, funct6) {
+        (true, 0b000000) => Add,
+        (true, 0b000010) if !vi => Sub,
+        (true, 0b000011) if !vv => Rsub,
+        (true, 0b000100) if !vi => Minu,
+        (true, 0b000101) if !vi => Min,
+        (true, 0b000110) if !vi => Maxu,
+        (true, 0b000111) if !vi => Max,
+        (true, 0b001001) => And,
+        (true, 0b001010) => Or,
+        (true, 0b001011) => Xor,
+        (true, 0b100000) => Saddu,
+        (true, 0b100001) => Sadd,
+        (true, 0b100010) if !vi => Ssubu,
+        (true, 0b100011) if !vi => Ssub,
+        (true, 0b100101) => Sll,
+        (true, 0b100111) if !vi => Smul,
+        (true, 0b101000) => Srl,
+        (true, 0b101001) => Sra,
+        (true, 0b101010) => Ssrl,
+        (true, 0b101011) => Ssra,
+        (false, 0b001000) => Aaddu,
+        (false, 0b001001) => Aadd,
+        (false, 0b001010) => Asubu,
+        (false, 0b001011) => Asub,
+        (false, 0b100000) => Divu,
+        (false, 0b100001) => Div,
+        (false, 0b100010) => Remu,
+        (false, 0b100011) => Rem,
+        (false, 0b100100) => Mulhu,
+        (false, 0b100101) => Mul,
+        (false, 0b100110) => Mulhsu,
+        (false, 0b100111) => Mulh,
+        (false, 0b101001) => Madd,
+        (false, 0b101011) => Nmsub,
+        (false, 0b101101) => Macc,
+        (false, 0b101111) => Nmsac,
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn vint_apply(op: VIntOp, a: u64, b: u64, d: u64, x: &mut VFixState) -> u64 {
+    use VIntOp::*;
+    let sew = x.sew;
+    let (sa, sb, sd) = (vsext(a, sew) as i128, vsext(b, sew) as i128, vsext(d, sew) as i128);
+    let (ua, ub) = (a as i128, b as i128);
+    let shamt = (b & (sew as u64 - 1)) as u32;
+    let result = match op {
+        Add => a.wrapping_add(b),
+        Sub => a.wrapping_sub(b),
+        Rsub => b.wrapping_sub(a),
+        Minu => cmp::min(a, b),
+        Min => if sa < sb { a } else { b },
+        Maxu => cmp::max(a, b),
+        Max => if sa > sb { a } else { b },
+        And => a & b,
+        Or => a | b,
+        Xor => a ^ b,
+        Sll => a << shamt,
+        Srl => a >> shamt,
+        Sra => (sa >> shamt) as u64,
+        Saddu => x.clamp_u(ua + ub),
+        Sadd => x.clamp_s(sa + sb),
+        Ssubu => x.clamp_u(ua - ub),
+        Ssub => x.clamp_s(sa - sb),
+        // Only (-1.0) x (-1.0) overflows
+        Smul => x.clamp_s(vroundoff_s(sa * sb, sew as u32 - 1, x.vxrm)),
+        Ssrl => vroundoff_u(a as u128, shamt, x.vxrm) as u64,
+        Ssra => vroundoff_s(sa, shamt, x.vxrm) as u64,
+        Aaddu => vroundoff_s(ua + ub, 1, x.vxrm) as u64,
+        Aadd => vroundoff_s(sa + sb, 1, x.vxrm) as u64,
+        Asubu => vroundoff_s(ua - ub, 1, x.vxrm) as u64,
+        Asub => vroundoff_s(sa - sb, 1, x.vxrm) as u64,
+        // Division by zero gives all ones (quotient) / the dividend (remainder), overflow gives the dividend / zero.
+        Divu => if b == 0 { !0 } else { a / b },
+        Div => if sb == 0 { !0 } else { (sa / sb) as u64 },
+        Remu => if b == 0 { a } else { a % b },
+        Rem => if sb == 0 { a } else { (sa % sb) as u64 },
+        Mulhu => ((a as u128 * b as u128) >> sew) as u64,
+        Mul => a.wrapping_mul(b),
+        Mulhsu => ((sa * ub) >> sew) as u64,
+        Mulh => ((sa * sb) >> sew) as u64,
+        Madd => d.wrapping_mul(b).wrapping_add(a),
+        Nmsub => a.wrapping_sub(d.wrapping_mul(b)),
+        Macc => b.wrapping_mul(a).wrapping_add(d),
+        Nmsac => (sd - sb * sa) as u64,
+    };
+    result & vmask_sew(sew)
+}
+
+impl EmuEnv {
+    // Vector instructions are illegal while mstatus.VS (and vsstatus.VS in VS/VU-mode) is Off.
+    pub fn vector_enabled(&self) -> bool {
+        let vs = |status: i64| Self::extract_bit_field(status, riscv_csr_def::SYSREG_MSTATUS_VS_MSB, riscv_csr_def::SYSREG_MSTATUS_VS_LSB);
+        vs(self.m_csr.m_mstatus.m_csr) != 0 && (!self.m_virt || vs(self.m_csr.m_vsstatus.m_csr) != 0)
+    }
+
+    pub fn vector_illegal(&mut self, guest_pc: u64) -> usize {
+        self.generate_exception(guest_pc, ExceptCode::IllegalInst, 0);
+        ExceptCode::IllegalInst as usize
+    }
+
+    // Instruction completed : vstart goes back to zero and the vector state becomes dirty.
+    pub fn vector_done(&mut self) -> usize {
+        self.m_csr.m_vstart.m_csr = 0;
+        self.m_csr.set_vs_dirty();
+        0
+    }
+
+    pub fn vlenb(&self) -> usize {
+        self.m_csr.m_vlenb.m_csr as usize
+    }
+
+    fn vstart(&self) -> usize {
+        self.m_csr.m_vstart.m_csr as usize
+    }
+
+    // Configuration of vtype with vl, None for vtype which is not supported (vill).
+    fn vtype_config(&self, vtype: u64, vl: usize) -> Option<VConfig> {
+        let vsew = (vtype >> 3) & 0x7;
+        let vlmul = vtype & 0x7;
+        if vtype >> 8 != 0 || vlmul == 4 || vsew > 3 {
+            return None;
+        }
+        let sew = 8 << vsew;
+        let lmul8 = if vlmul < 4 { 8 << vlmul } else { 8 >> (8 - vlmul) };
+        let elen = self.m_arg_config.elen;
+        // SEW is up to ELEN, and up to LMUL x ELEN with fractional LMUL
+        if sew > elen || sew * 8 > lmul8 * elen {
+            return None;
+        }
+        let vlmax = self.vlenb() * lmul8 / sew;
+        if vlmax == 0 {
+            return None;
+        }
+        Some(VConfig { sew, lmul8, vl: cmp::min(vl, vlmax), vlmax })
+    }
+
+    pub fn vconfig(&self) -> Option<VConfig> {
+        let vtype = self.m_csr.m_vtype.m_csr;
+        if vtype & VTYPE_VILL != 0 {
+            return None;
+        }
+        self.vtype_config(vtype as u64, self.m_csr.m_vl.m_csr as usize)
+    }
+
+    // Element idx of the register group starting at reg. Elements are little endian.
+    pub fn vreg_get(&self, reg: usize, idx: usize, sew: usize) -> u64 {
+        let base = reg * self.vlenb() + idx * (sew / 8);
+        self.m_vregs[base..base + sew / 8].iter().rev().fold(0, |v, b| (v << 8) | *b as u64)
+    }
+
+    pub fn vreg_set(&mut self, reg: usize, idx: usize, sew: usize, data: u64) {
+        let base = reg * self.vlenb() + idx * (sew / 8);
+        for (n, b) in self.m_vregs[base..base + sew / 8].iter_mut().enumerate() {
+            *b = (data >> (n * 8)) as u8;
+        }
+    }
+
+    pub fn vmask_get(&self, reg: usize, idx: usize) -> bool {
+        (self.m_vregs[reg * self.vlenb() + idx / 8] >> (idx % 8)) & 0x1 != 0
+    }
+
+    pub fn vmask_set(&mut self, reg: usize, idx: usize, bit: bool) {
+        let pos = reg * self.vlenb() + idx / 8;
+        let byte = &mut self.m_vregs[pos];
+        if bit {
+            *byte |= 1 << (idx % 8);
+        } else {
+            *byte &= !(1 << (idx % 8));
+        }
+    }
+
+    // Element is active : unmasked, or v0.mask[idx] is set
+    pub fn vactive(&self, vm: bool, idx: usize) -> bool {
+        vm || self.vmask_get(0, idx)
+    }
+
+    // Results are computed for all elements before any write, so that sources overlapping vd read the old values.
+    pub fn vwrite(&mut self, vd: usize, sew: usize, res: Vec<(usize, u64)>) {
+        for (idx, data) in res {
+            self.vreg_set(vd, idx, sew, data);
+        }
+    }
+
+    pub fn vwrite_mask(&mut self, vd: usize, res: Vec<(usize, bool)>) {
+        for (idx, bit) in res {
+            self.vmask_set(vd, idx, bit);
+        }
+    }
+
+    // Second operand : vs1 element, rs1 / fs1 truncated to SEW, or the immediate (sign-extended unless uimm).
+    pub fn vop_src1(&self, v: &VInst, idx: usize, sew: usize, uimm: bool) -> u64 {
+        match v.funct3 {
+            OPIVV | OPMVV | OPFVV => self.vreg_get(v.vs1, idx, sew),
+            OPIVX | OPMVX => self.m_iregs[v.vs1] & vmask_sew(sew),
+            OPFVF => self.vf_scalar(v.vs1, sew),
+            _ if uimm => v.vs1 as u64,
+            _ => v.simm5() & vmask_sew(sew),
+        }
+    }
+
+    // Single-width operands : vd, vs2 (and vs1) are groups of LMUL, and a masked operation does not write v0.
+    pub fn vcheck_single(&self, v: &VInst, cfg: &VConfig) -> bool {
+        vreg_group_ok(v.vd, cfg.lmul8) && vreg_group_ok(v.vs2, cfg.lmul8) &&
+            (!matches!(v.funct3, OPIVV | OPMVV | OPFVV) || vreg_group_ok(v.vs1, cfg.lmul8)) &&
+            (v.vm || v.vd != 0)
+    }
+
+    // vsetvli / vsetivli / vsetvl : vl = min(AVL, VLMAX) is written to rd.
+    // AVL is rs1, or VLMAX with rs1 = x0, or the current vl with rs1 = rd = x0.
+    pub fn helper_func_vsetvl(emu: &mut EmuEnv, inst: u64, _: u64, _: u64, guest_pc: u64) -> usize {
+        if !emu.vector_enabled() {
+            return emu.vector_illegal(guest_pc);
+        }
+        let inst = inst as u32;
+        let rd = ((inst >> 7) & 0x1f) as usize;
+        let rs1 = ((inst >> 15) & 0x1f) as usize;
+        let (vtype, uimm) = match inst >> 30 {
+            0b11 => (((inst >> 20) & 0x3ff) as u64, true),                 // vsetivli : AVL is uimm5
+            0b10 => (emu.m_iregs[((inst >> 20) & 0x1f) as usize], false),  // vsetvl
+            _ => (((inst >> 20) & 0x7ff) as u64, false),                   // vsetvli
+        };
+        let avl = if uimm {
+            rs1
+        } else if rs1 != 0 {
+            cmp::min(emu.m_iregs[rs1], usize::MAX as u64) as usize
+        } else if rd != 0 {
+            usize::MAX
+        } else {
+            emu.m_csr.m_vl.m_csr as usize
+        };
+        let (vtype, vl) = match emu.vtype_config(vtype, avl) {
+            Some(cfg) => (vtype as i64, cfg.vl),
+            None => (VTYPE_VILL, 0),
+        };
+        emu.m_csr.m_vtype.m_csr = vtype;
+        emu.m_csr.m_vl.m_csr = vl as i64;
+        if rd != 0 {
+            emu.m_iregs[rd] = vl as u64;
+        }
+        emu.vector_done()
+    }
+
+    fn vmem_read(&mut self, guest_pc: u64, addr: u64, bytes: usize) -> Result<u64, MemResult> {
+        let guest_phy_addr = self.convert_physical_address(guest_pc, addr, MemAccType::Read)?;
+        if let Some(data) = self.mmio_read(guest_phy_addr, bytes) {
+            return Ok(data);
+        }
+        Ok(match bytes {
+            1 => self.read_mem_1byte(guest_phy_addr) as u64,
+            2 => self.read_mem_2byte(guest_phy_addr) as u64,
+            4 => self.read_mem_4byte(guest_phy_addr) as u64,
+            _ => self.read_mem_8byte(guest_phy_addr),
+        })
+    }
+
+    fn vmem_write(&mut self, guest_pc: u64, addr: u64, bytes: usize, data: u64) -> Result<(), MemResult> {
+        let guest_phy_addr = self.convert_physical_address(guest_pc, addr, MemAccType::Write)?;
+        if self.mmio_write(guest_phy_addr, bytes, data) {
+            return Ok(());
+        }
+        match bytes {
+            1 => self.write_mem_1byte(guest_phy_addr, data as u8),
+            2 => self.write_mem_2byte(guest_phy_addr, data as u16),
+            4 => self.write_mem_4byte(guest_phy_addr, data as u32),
+            _ => self.write_mem_8byte(guest_phy_addr, data),
+        }
+        Ok(())
+    }
+
+    // Vector loads / stores : unit-stride (with segment, mask, whole register and fault-only-first), strided and indexed.
+    // Elements are accessed in order from vstart. A faulting element leaves its index in vstart.
+    pub fn helper_func_vldst(emu: &mut EmuEnv, inst: u64, _: u64, _: u64, guest_pc: u64) -> usize {
+        if !emu.vector_enabled() {
+            return emu.vector_illegal(guest_pc);
+        }
+        let inst = inst as u32;
+        let is_store = inst & 0x7f == 0x27;
+        let vd = ((inst >> 7) & 0x1f) as usize;       // vs3 of stores
+        let rs1 = ((inst >> 15) & 0x1f) as usize;
+        let vs2 = ((inst >> 20) & 0x1f) as usize;     // rs2 of strided, lumop / sumop of unit-stride
+        let vm = (inst >> 25) & 0x1 != 0;
+        let mop = (inst >> 26) & 0x3;
+        let mut nf = ((inst >> 29) + 1) as usize;
+        let eew = match (inst >> 12) & 0x7 {
+            0b000 => 8,
+            0b101 => 16,
+            0b110 => 32,
+            _ => 64,
+        };
+        if eew > emu.m_arg_config.elen {
+            return emu.vector_illegal(guest_pc);
+        }
+
+        // Data EEW, EMUL (x 8) and the number of elements
+        let (data_eew, emul8, evl) = if mop == 0b00 && vs2 == 0b01000 {
+            // Whole register : NFIELDS registers regardless of vtype
+            if !vm || !nf.is_power_of_two() || vd % nf != 0 {
+                return emu.vector_illegal(guest_pc);
+            }
+            let regs = nf;
+            nf = 1;
+            (eew, regs * 8, regs * emu.vlenb() * 8 / eew)
+        } else {
+            let cfg = match emu.vconfig() {
+                Some(cfg) => cfg,
+                None => return emu.vector_illegal(guest_pc),
+            };
+            match mop {
+                // vlm.v / vsm.v : ceil(vl / 8) bytes
+                0b00 if vs2 == 0b01011 => (8, 8, (cfg.vl + 7) / 8),
+                // Indexed : data is SEW / LMUL, and vs2 holds offsets of EEW
+                0b01 | 0b11 => {
+                    let index_emul8 = eew * cfg.lmul8 / cfg.sew;
+                    if eew * cfg.lmul8 < cfg.sew || !vreg_group_ok(vs2, index_emul8) {
+                        return emu.vector_illegal(guest_pc);
+                    }
+                    (cfg.sew, cfg.lmul8, cfg.vl)
+                }
+                _ => {
+                    if eew * cfg.lmul8 < cfg.sew {
+                        return emu.vector_illegal(guest_pc);
+                    }
+                    (eew, eew * cfg.lmul8 / cfg.sew, cfg.vl)
+                }
+            }
+        };
+        let regs_per_field = cmp::max(emul8 / 8, 1);
+        if !vreg_group_ok(vd, emul8) || nf * regs_per_field > 8 || vd + nf * regs_per_field > 32 || (!is_store && !vm && vd == 0) {
+            return emu.vector_illegal(guest_pc);
+        }
+
+        let fault_first = !is_store && mop == 0b00 && vs2 == 0b10000;
+        let base = emu.m_iregs[rs1];
+        let bytes = data_eew / 8;
+        for idx in emu.vstart()..evl {
+            if !emu.vactive(vm, idx) {
+                continue;
+            }
+            for field in 0..nf {
+                let addr = match mop {
+                    0b00 => base.wrapping_add(((idx * nf + field) * bytes) as u64),
+                    0b10 => base.wrapping_add(emu.m_iregs[vs2].wrapping_mul(idx as u64)).wrapping_add((field * bytes) as u64),
+                    _ => base.wrapping_add(emu.vreg_get(vs2, idx, eew)).wrapping_add((field * bytes) as u64),
+                };
+                // Fault-only-first : vl is trimmed at the first element in another page than element 0,
+                // which covers all elements that could fault.
+                if fault_first && idx > 0 && (addr ^ base) >> 12 != 0 {
+                    emu.m_csr.m_vl.m_csr = idx as i64;
+                    return emu.vector_done();
+                }
+                let reg = vd + field * regs_per_field;
+                let result = if is_store {
+                    let data = emu.vreg_get(reg, idx, data_eew);
+                    emu.vmem_write(guest_pc, addr, bytes, data)
+                } else {
+                    emu.vmem_read(guest_pc, addr, bytes).map(|data| emu.vreg_set(reg, idx, data_eew, data))
+                };
+                if let Err(error) = result {
+                    // Page / access fault is raised while walking page table.
+                    emu.m_csr.m_vstart.m_csr = idx as i64;
+                    emu.m_csr.set_vs_dirty();
+                    return error as usize;
+                }
+            }
+        }
+        emu.vector_done()
+    }
+
+    // Integer, fixed-point, mask and permutation instructions (OPIVV / OPIVX / OPIVI / OPMVV / OPMVX).
+    // Non-zero vstart is not supported by arithmetic instructions and raises illegal instruction.
+    pub fn helper_func_vop(emu: &mut EmuEnv, inst: u64, _: u64, _: u64, guest_pc: u64) -> usize {
+        let v = VInst::new(inst);
+        let legal = emu.vector_enabled() && emu.vstart() == 0 && if v.funct3 == OPIVI && v.funct6 == 0b100111 {
+            emu.vop_move_whole(&v)
+        } else {
+            match emu.vconfig() {
+                Some(cfg) => emu.vop_int(&v, &cfg),
+                None => false,
+            }
+        };
+        if legal { emu.vector_done() } else { emu.vector_illegal(guest_pc) }
+    }
+
+    fn vop_int(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let opi = matches!(v.funct3, OPIVV | OPIVX | OPIVI);
+        let vv = matches!(v.funct3, OPIVV | OPMVV);
+        match (opi, v.funct6) {
+            (true, 0b010000..=0b010011) => self.vop_carry(v, cfg),
+            (true, 0b010111) => self.vop_merge(v, cfg),
+            (true, 0b011000..=0b011111) => self.vop_compare(v, cfg),
+            (true, 0b101100..=0b101111) => self.vop_narrow(v, cfg),
+            (true, 0b110000 | 0b110001) if vv => self.vop_reduce(v, cfg),
+            (true, 0b001100 | 0b001110 | 0b001111) => self.vop_gather_slide(v, cfg),
+            (false, 0b000000..=0b000111) if vv => self.vop_reduce(v, cfg),
+            (false, 0b001110 | 0b001111) if !vv => self.vop_slide1(v, cfg),
+            (false, 0b010000) => self.vop_move_scalar(v, cfg),
+            (false, 0b010010) if vv => self.vop_extend(v, cfg),
+            (false, 0b010100) if vv => self.vop_mask_unary(v, cfg),
+            (false, 0b010111) if vv => self.vop_compress(v, cfg),
+            (false, 0b011000..=0b011111) if vv => self.vop_mask_logical(v, cfg),
+            (false, 0b110000..=0b111111) => self.vop_widen(v, cfg),
+            _ => self.vop_single(v, cfg),
+        }
+    }
+
+    fn vop_single(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let op = match vint_op(v.funct3, v.funct6) {
+            Some(op) => op,
+            None => return false,
+        };
+        if !self.vcheck_single(v, cfg) {
+            return false;
+        }
+        let uimm = matches!(op, VIntOp::Sll | VIntOp::Srl | VIntOp::Sra | VIntOp::Ssrl | VIntOp::Ssra);
+        let mut x = VFixState { sew: cfg.sew, vxrm: self.m_csr.m_vxrm.m_csr as u64, sat: false };
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if self.vactive(v.vm, idx) {
+                let a = self.vreg_get(v.vs2, idx, cfg.sew);
+                let b = self.vop_src1(v, idx, cfg.sew, uimm);
+                let d = self.vreg_get(v.vd, idx, cfg.sew);
+                res.push((idx, vint_apply(op, a, b, d, &mut x)));
+            }
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        if x.sat {
+            self.m_csr.m_vxsat.m_csr = 1;
+        }
+        true
+    }
+
+    // vadc / vsbc write sums of all body elements with carry-in from v0.
+    // vmadc / vmsbc write carry-out to the mask, with carry-in only when masked.
+    fn vop_carry(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let sub = v.funct6 & 0b10 != 0;
+        let mask_out = v.funct6 & 0b1 != 0;
+        if (sub && v.funct3 == OPIVI) || !vreg_group_ok(v.vs2, cfg.lmul8) ||
+            (v.funct3 == OPIVV && !vreg_group_ok(v.vs1, cfg.lmul8)) ||
+            (!mask_out && (v.vm || v.vd == 0 || !vreg_group_ok(v.vd, cfg.lmul8))) {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        let mut carry = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            let c = (!v.vm && self.vmask_get(0, idx)) as i128;
+            let a = self.vreg_get(v.vs2, idx, cfg.sew) as i128;
+            let b = self.vop_src1(v, idx, cfg.sew, false) as i128;
+            let r = if sub { a - b - c } else { a + b + c };
+            res.push((idx, r as u64 & vmask_sew(cfg.sew)));
+            carry.push((idx, if sub { r < 0 } else { r >> cfg.sew != 0 }));
+        }
+        if mask_out {
+            self.vwrite_mask(v.vd, carry);
+        } else {
+            self.vwrite(v.vd, cfg.sew, res);
+        }
+        true
+    }
+
+    // vmerge takes vs1 / rs1 / imm where v0.mask is set and vs2 elsewhere. Unmasked, vmv.v.* copies the operand.
+    pub fn vop_merge(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        if !self.vcheck_single(v, cfg) || (v.vm && v.vs2 != 0) {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            let data = if self.vactive(v.vm, idx) {
+                self.vop_src1(v, idx, cfg.sew, false)
+            } else {
+                self.vreg_get(v.vs2, idx, cfg.sew)
+            };
+            res.push((idx, data));
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+
+    fn vop_compare(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let vv = v.funct3 == OPIVV;
+        let vi = v.funct3 == OPIVI;
+        let valid = match v.funct6 {
+            0b011010 | 0b011011 => !vi,
+            0b011110 | 0b011111 => !vv,
+            _ => true,
+        };
+        if !valid || !vreg_group_ok(v.vs2, cfg.lmul8) || (vv && !vreg_group_ok(v.vs1, cfg.lmul8)) {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let a = self.vreg_get(v.vs2, idx, cfg.sew);
+            let b = self.vop_src1(v, idx, cfg.sew, false);
+            let (sa, sb) = (vsext(a, cfg.sew), vsext(b, cfg.sew));
+            let bit = match v.funct6 {
+                0b011000 => a == b,     // vmseq
+                0b011001 => a != b,     // vmsne
+                0b011010 => a < b,      // vmsltu
+                0b011011 => sa < sb,    // vmslt
+                0b011100 => a <= b,     // vmsleu
+                0b011101 => sa <= sb,   // vmsle
+                0b011110 => a > b,      // vmsgtu
+                _ => sa > sb,           // vmsgt
+            };
+            res.push((idx, bit));
+        }
+        self.vwrite_mask(v.vd, res);
+        true
+    }
+
+    // vnsrl / vnsra / vnclipu / vnclip : vs2 is 2 x SEW and shifted right by vs1 / rs1 / uimm.
+    fn vop_narrow(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let wsew = cfg.sew * 2;
+        if wsew > self.m_arg_config.elen || !vreg_group_ok(v.vd, cfg.lmul8) || !vreg_group_ok(v.vs2, cfg.lmul8 * 2) ||
+            (v.funct3 == OPIVV && !vreg_group_ok(v.vs1, cfg.lmul8)) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        let mut x = VFixState { sew: cfg.sew, vxrm: self.m_csr.m_vxrm.m_csr as u64, sat: false };
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let a = self.vreg_get(v.vs2, idx, wsew);
+            let shamt = (self.vop_src1(v, idx, cfg.sew, true) & (wsew as u64 - 1)) as u32;
+            let data = match v.funct6 {
+                0b101100 => a >> shamt,
+                0b101101 => (vsext(a, wsew) >> shamt) as u64,
+                0b101110 => x.clamp_u(vroundoff_u(a as u128, shamt, x.vxrm) as i128),
+                _ => x.clamp_s(vroundoff_s(vsext(a, wsew) as i128, shamt, x.vxrm)),
+            };
+            res.push((idx, data & vmask_sew(cfg.sew)));
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        if x.sat {
+            self.m_csr.m_vxsat.m_csr = 1;
+        }
+        true
+    }
+
+    // Widening add / subtract / multiply (-add) : vd is 2 x SEW, vs2 too with the .w forms.
+    fn vop_widen(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let wsew = cfg.sew * 2;
+        let wide_vs2 = v.funct6 & 0b111100 == 0b110100;
+        if v.funct6 == 0b111001 || (v.funct6 == 0b111110 && v.funct3 == OPMVV) {
+            return false;
+        }
+        if wsew > self.m_arg_config.elen || !vreg_group_ok(v.vd, cfg.lmul8 * 2) ||
+            !vreg_group_ok(v.vs2, if wide_vs2 { cfg.lmul8 * 2 } else { cfg.lmul8 }) ||
+            (v.funct3 == OPMVV && !vreg_group_ok(v.vs1, cfg.lmul8)) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        let ext = |x: u64, signed: bool| if signed { vsext(x, cfg.sew) as i128 } else { x as i128 };
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let a = self.vreg_get(v.vs2, idx, if wide_vs2 { wsew } else { cfg.sew });
+            let b = self.vop_src1(v, idx, cfg.sew, false);
+            let d = vsext(self.vreg_get(v.vd, idx, wsew), wsew) as i128;
+            let signed = v.funct6 & 0b1 != 0;
+            let wa = if wide_vs2 { vsext(a, wsew) as i128 } else { ext(a, signed) };
+            let data = match v.funct6 {
+                0b110000 | 0b110001 | 0b110100 | 0b110101 => wa + ext(b, signed),     // vwaddu / vwadd (.w)
+                0b110010 | 0b110011 | 0b110110 | 0b110111 => wa - ext(b, signed),     // vwsubu / vwsub (.w)
+                0b111000 => ext(a, false) * ext(b, false),                            // vwmulu
+                0b111010 => ext(a, true) * ext(b, false),                             // vwmulsu
+                0b111011 => ext(a, true) * ext(b, true),                              // vwmul
+                0b111100 => ext(a, false) * ext(b, false) + d,                        // vwmaccu
+                0b111101 => ext(a, true) * ext(b, true) + d,                          // vwmacc
+                0b111110 => ext(a, true) * ext(b, false) + d,                         // vwmaccus
+                _ => ext(a, false) * ext(b, true) + d,                                // vwmaccsu
+            };
+            res.push((idx, data as u64 & vmask_sew(wsew)));
+        }
+        self.vwrite(v.vd, wsew, res);
+        true
+    }
+
+    // vred* : vd[0] = vs1[0] op active elements of vs2. vwredsum(u) accumulates in 2 x SEW.
+    fn vop_reduce(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let widen = v.funct3 == OPIVV;
+        let dsew = if widen { cfg.sew * 2 } else { cfg.sew };
+        if dsew > self.m_arg_config.elen || !vreg_group_ok(v.vs2, cfg.lmul8) {
+            return false;
+        }
+        let op = match (widen, v.funct6 & 0b111) {
+            (true, _) | (false, 0b000) => VIntOp::Add,
+            (false, 0b001) => VIntOp::And,
+            (false, 0b010) => VIntOp::Or,
+            (false, 0b011) => VIntOp::Xor,
+            (false, 0b100) => VIntOp::Minu,
+            (false, 0b101) => VIntOp::Min,
+            (false, 0b110) => VIntOp::Maxu,
+            _ => VIntOp::Max,
+        };
+        let mut x = VFixState { sew: dsew, vxrm: 0, sat: false };
+        let mut acc = self.vreg_get(v.vs1, 0, dsew);
+        for idx in 0..cfg.vl {
+            if self.vactive(v.vm, idx) {
+                let mut elem = self.vreg_get(v.vs2, idx, cfg.sew);
+                if widen && v.funct6 & 0b1 != 0 {
+                    elem = vsext(elem, cfg.sew) as u64 & vmask_sew(dsew);
+                }
+                acc = vint_apply(op, elem, acc, 0, &mut x);
+            }
+        }
+        if cfg.vl > 0 {
+            self.vreg_set(v.vd, 0, dsew, acc);
+        }
+        true
+    }
+
+    // vrgather / vrgatherei16 / vslideup / vslidedown
+    fn vop_gather_slide(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let vv = v.funct3 == OPIVV;
+        if (v.funct6 == 0b001111 && vv) || !vreg_group_ok(v.vd, cfg.lmul8) || !vreg_group_ok(v.vs2, cfg.lmul8) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        // vrgatherei16 takes 16 bit indices
+        let index_sew = if v.funct6 == 0b001110 { 16 } else { cfg.sew };
+        if vv && (index_sew * cfg.lmul8 < cfg.sew || !vreg_group_ok(v.vs1, index_sew * cfg.lmul8 / cfg.sew)) {
+            return false;
+        }
+        let scalar = match v.funct3 {
+            OPIVX => self.m_iregs[v.vs1],
+            _ => v.vs1 as u64,
+        };
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let src = match v.funct6 {
+                0b001110 if !vv => {
+                    // Elements below the offset are not changed by vslideup
+                    if (idx as u64) < scalar {
+                        continue;
+                    }
+                    Some(idx - scalar as usize)
+                }
+                0b001111 => (idx as u64).checked_add(scalar).map(|i| i as usize),
+                _ => Some(if vv { self.vreg_get(v.vs1, idx, index_sew) } else { scalar } as usize),
+            };
+            let data = match src {
+                Some(i) if i < cfg.vlmax => self.vreg_get(v.vs2, i, cfg.sew),
+                _ => 0,
+            };
+            res.push((idx, data));
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+
+    // vslide1up / vslide1down (and vfslide1up / vfslide1down) : the scalar fills the vacated element.
+    pub fn vop_slide1(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        if !self.vcheck_single(v, cfg) {
+            return false;
+        }
+        let scalar = self.vop_src1(v, 0, cfg.sew, false);
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let data = match v.funct6 {
+                0b001110 if idx == 0 => scalar,
+                0b001110 => self.vreg_get(v.vs2, idx - 1, cfg.sew),
+                _ if idx + 1 == cfg.vl => scalar,
+                _ => self.vreg_get(v.vs2, idx + 1, cfg.sew),
+            };
+            res.push((idx, data));
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+
+    // vmv.x.s / vcpop.m / vfirst.m write rd, vmv.s.x writes element 0 of vd.
+    fn vop_move_scalar(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let result = match (v.funct3, v.vs1) {
+            (OPMVV, 0b00000) if v.vm => vsext(self.vreg_get(v.vs2, 0, cfg.sew), cfg.sew) as u64,
+            (OPMVV, 0b10000) => (0..cfg.vl).filter(|&i| self.vactive(v.vm, i) && self.vmask_get(v.vs2, i)).count() as u64,
+            (OPMVV, 0b10001) => match (0..cfg.vl).find(|&i| self.vactive(v.vm, i) && self.vmask_get(v.vs2, i)) {
+                Some(i) => i as u64,
+                None => !0,
+            },
+            (OPMVX, _) if v.vm && v.vs2 == 0 => {
+                if cfg.vl > 0 {
+                    let data = self.m_iregs[v.vs1] & vmask_sew(cfg.sew);
+                    self.vreg_set(v.vd, 0, cfg.sew, data);
+                }
+                return true;
+            }
+            _ => return false,
+        };
+        if v.vd != 0 {
+            self.m_iregs[v.vd] = result;
+        }
+        true
+    }
+
+    // vzext.vf2/4/8 / vsext.vf2/4/8 : vs2 is SEW / factor
+    fn vop_extend(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let (factor, signed) = match v.vs1 {
+            0b00010 => (8, false),
+            0b00011 => (8, true),
+            0b00100 => (4, false),
+            0b00101 => (4, true),
+            0b00110 => (2, false),
+            0b00111 => (2, true),
+            _ => return false,
+        };
+        let ssew = cfg.sew / factor;
+        if ssew < 8 || cfg.lmul8 < factor || !vreg_group_ok(v.vd, cfg.lmul8) ||
+            !vreg_group_ok(v.vs2, cfg.lmul8 / factor) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if self.vactive(v.vm, idx) {
+                let a = self.vreg_get(v.vs2, idx, ssew);
+                res.push((idx, if signed { vsext(a, ssew) as u64 & vmask_sew(cfg.sew) } else { a }));
+            }
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+
+    // vmsbf / vmsof / vmsif / viota / vid
+    fn vop_mask_unary(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        match v.vs1 {
+            0b00001 | 0b00010 | 0b00011 => {
+                if v.vd == v.vs2 || (!v.vm && v.vd == 0) {
+                    return false;
+                }
+                let mut found = false;
+                let mut res = Vec::with_capacity(cfg.vl);
+                for idx in 0..cfg.vl {
+                    if !self.vactive(v.vm, idx) {
+                        continue;
+                    }
+                    let bit = self.vmask_get(v.vs2, idx);
+                    let out = match v.vs1 {
+                        0b00001 => !found && !bit,  // vmsbf : before the first set bit
+                        0b00010 => !found && bit,   // vmsof : only the first set bit
+                        _ => !found,                // vmsif : including the first set bit
+                    };
+                    found |= bit;
+                    res.push((idx, out));
+                }
+                self.vwrite_mask(v.vd, res);
+                true
+            }
+            0b10000 | 0b10001 => {
+                if !vreg_group_ok(v.vd, cfg.lmul8) || (!v.vm && v.vd == 0) || (v.vs1 == 0b10001 && v.vs2 != 0) {
+                    return false;
+                }
+                // viota : number of set bits in active elements below
+                let mut count = 0;
+                let mut res = Vec::with_capacity(cfg.vl);
+                for idx in 0..cfg.vl {
+                    if !self.vactive(v.vm, idx) {
+                        continue;
+                    }
+                    if v.vs1 == 0b10001 {
+                        res.push((idx, idx as u64));
+                    } else {
+                        res.push((idx, count));
+                        count += self.vmask_get(v.vs2, idx) as u64;
+                    }
+                }
+                self.vwrite(v.vd, cfg.sew, res);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // vcompress.vm : elements of vs2 selected by vs1.mask are packed from element 0 of vd.
+    fn vop_compress(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        if !v.vm || !vreg_group_ok(v.vd, cfg.lmul8) || !vreg_group_ok(v.vs2, cfg.lmul8) {
+            return false;
+        }
+        let res: Vec<(usize, u64)> = (0..cfg.vl).filter(|&i| self.vmask_get(v.vs1, i))
+            .enumerate().map(|(j, i)| (j, self.vreg_get(v.vs2, i, cfg.sew))).collect();
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+
+    fn vop_mask_logical(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        if !v.vm {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            let a = self.vmask_get(v.vs2, idx);
+            let b = self.vmask_get(v.vs1, idx);
+            let bit = match v.funct6 {
+                0b011000 => a && !b,        // vmandn
+                0b011001 => a && b,         // vmand
+                0b011010 => a || b,         // vmor
+                0b011011 => a ^ b,          // vmxor
+                0b011100 => a || !b,        // vmorn
+                0b011101 => !(a && b),      // vmnand
+                0b011110 => !(a || b),      // vmnor
+                _ => a == b,                // vmxnor
+            };
+            res.push((idx, bit));
+        }
+        self.vwrite_mask(v.vd, res);
+        true
+    }
+
+    // vmv<nr>r.v : copies nr whole registers regardless of vtype
+    fn vop_move_whole(&mut self, v: &VInst) -> bool {
+        let nr = v.vs1 + 1;
+        if !v.vm || !nr.is_power_of_two() || nr > 8 || v.vd % nr != 0 || v.vs2 % nr != 0 {
+            return false;
+        }
+        let vlenb = self.vlenb();
+        self.m_vregs.copy_within(v.vs2 * vlenb..(v.vs2 + nr) * vlenb, v.vd * vlenb);
+        true
+    }
+}
@@ -0,0 +1,576 @@
+use std::mem::size_of;
+
+use num::traits::{NumCast, ToPrimitive};
+use softfloat_wrapper::{ExceptionFlags, Float, RoundingMode, F16, F32, F64};
+
+use crate::emu_env::EmuEnv;
+use crate::op_helper_fp_d::{FFLAGS_DZ, FFLAGS_NV, FFLAGS_NX, FFLAGS_OF};
+use crate::op_helper_vector::{vmask_sew, vreg_group_ok, vsext, VConfig, VInst, OPFVF};
+use crate::target::riscv::riscv_csr::CsrAddr;
+
+// vfrsqrt7.v : 7 bit estimate of 1 / sqrt(x), indexed by exponent LSB and 6 MSBs of the significand
+const RSQRT7_TABLE: [u64; 128] = [
+    52, 51, 50, 48, 47, 46, 44, 43, 42, 41, 40, 39, 38, 36, 35, 34,
+    33, 32, 31, 30, 30, 29, 28, 27, 26, 25, 24, 23, 23, 22, 21, 20,
+    19, 19, 18, 17, 16, 16, 15, 14, 14, 13, 12, 12, 11, 10, 10, 9,
+    9, 8, 7, 7, 6, 6, 5, 4, 4, 3, 3, 2, 2, 1, 1, 0,
+    127, 125, 123, 121, 119, 118, 116, 114, 113, 111, 109, 108, 106, 105, 103, 102,
+    100, 99, 97, 96, 95, 93, 92, 91, 90, 88, 87, 86, 85, 84, 83, 82,
+    80, 79, 78, 77, 76, 75, 74, 73, 72, 71, 70, 70, 69, 68, 67, 66,
+    65, 64, 63, 63, 62, 61, 60, 59, 59, 58, 57, 56, 56, 55, 54, 53,
+];
+
+// vfrec7.v : 7 bit estimate of 1 / x, indexed by 7 MSBs of the significand
+const REC7_TABLE: [u64; 128] = [
+    127, 125, 123, 121, 119, 117, 116, 114, 112, 110, 109, 107, 105, 104, 102, 100,
+    99, 97, 96, 94, 93, 91, 90, 88, 87, 85, 84, 83, 81, 80, 79, 77,
+    76, 75, 74, 72, 71, 70, 69, 68, 66, 65, 64, 63, 62, 61, 60, 59,
+    58, 57, 56, 55, 54, 53, 52, 51, 50, 49, 48, 47, 46, 45, 44, 43,
+    42, 41, 40, 40, 39, 38, 37, 36, 35, 35, 34, 33, 32, 31, 31, 30,
+    29, 28, 28, 27, 26, 25, 25, 24, 23, 23, 22, 21, 21, 20, 19, 19,
+    18, 17, 17, 16, 15, 15, 14, 14, 13, 12, 12, 11, 11, 10, 9, 9,
+    8, 8, 7, 7, 6, 5, 5, 4, 4, 3, 3, 2, 2, 1, 1, 0,
+];
+
+// Calls a function generic over Float with F16 / F32 / F64 selected by the element width.
+macro_rules! vf_sew {
+    ($sew:expr, $func:ident ( $($arg:expr),* )) => {
+        match $sew {
+            16 => $func::<F16>($($arg),*),
+            32 => $func::<F32>($($arg),*),
+            _  => $func::<F64>($($arg),*),
+        }
+    };
+}
+
+// Floating-point element width : half, single or double within ELEN
+fn vf_width_ok(sew: usize, elen: usize) -> bool {
+    matches!(sew, 16 | 32 | 64) && sew <= elen
+}
+
+fn vf_from<T: Float>(x: u64) -> T {
+    let bits = size_of::<T::Payload>() * 8;
+    T::from_bits(NumCast::from(x & vmask_sew(bits)).unwrap())
+}
+
+fn vf_bits<T: Float>(x: T) -> u64 {
+    x.to_bits().to_u64().unwrap()
+}
+
+// Adds flags to the accrued softfloat flags of the instruction.
+fn vf_raise(flags: u8) {
+    let mut accrued = ExceptionFlags::default();
+    accrued.get();
+    ExceptionFlags::from_bits(accrued.to_bits() | flags).set();
+}
+
+// Runs op with the softfloat flags cleared and returns the flags it raised. The flags raised before are kept.
+fn vf_local_flags<R>(op: impl FnOnce() -> R) -> (R, u8) {
+    let mut accrued = ExceptionFlags::default();
+    accrued.get();
+    ExceptionFlags::default().set();
+    let result = op();
+    let mut local = ExceptionFlags::default();
+    local.get();
+    accrued.set();
+    (result, local.to_bits())
+}
+
+#[derive(Clone, Copy)]
+enum VFOp {
+    Add, Sub, Rsub, Mul, Div, Rdiv, Min, Max, Sgnj, Sgnjn, Sgnjx,
+    Madd, Nmadd, Msub, Nmsub, Macc, Nmacc, Msac, Nmsac,
+}
+
+fn vf_op(funct6: u32, vf: bool) -> Option<VFOp> {
+    use VFOp::*;
+    let op = match funct6 {
+        0b000000 => Add,
+        0b000010 => Sub,
+        0b000100 => Min,
+        0b000110 => Max,
+        0b001000 => Sgnj,
+        0b001001 => Sgnjn,
+        0b001010 => Sgnjx,
+        0b100000 => Div,
+        0b100001 if vf => Rdiv,
+        0b100100 => Mul,
+        0b100111 if vf => Rsub,
+        0b101000 => Madd,
+        0b101001 => Nmadd,
+        0b101010 => Msub,
+        0b101011 => Nmsub,
+        0b101100 => Macc,
+        0b101101 => Nmacc,
+        0b101110 => Msac,
+        0b101111 => Nmsac,
+        _ => return None,
+    };
+    Some(op)
+}
+
+// IEEE 754-2019 minimumNumber / maximumNumber : a NaN operand gives the other one, and -0 is less than +0.
+fn vf_minmax<T: Float>(a: u64, b: u64, is_max: bool) -> u64 {
+    let (x, y) = (vf_from::<T>(a), vf_from::<T>(b));
+    // Quiet compare raises invalid for signaling NaN
+    let lt = x.lt_quiet(&y) || (x.is_negative_zero() && y.is_positive_zero());
+    if x.is_nan() && y.is_nan() {
+        vf_bits(T::quiet_nan())
+    } else if x.is_nan() {
+        b
+    } else if y.is_nan() || lt != is_max {
+        a
+    } else {
+        b
+    }
+}
+
+// vd = op(vs2, vs1 / fs1). The multiply-add family also takes vd, and negation flips the sign bit.
+fn vf_arith<T: Float>(op: VFOp, a: u64, b: u64, d: u64, rm: RoundingMode) -> u64 {
+    use VFOp::*;
+    let sign = 1 << (size_of::<T::Payload>() * 8 - 1);
+    let f = vf_from::<T>;
+    let fma = |x: u64, y: u64, z: u64| vf_bits(f(x).fused_mul_add(f(y), f(z), rm));
+    match op {
+        Add => vf_bits(f(a).add(f(b), rm)),
+        Sub => vf_bits(f(a).sub(f(b), rm)),
+        Rsub => vf_bits(f(b).sub(f(a), rm)),
+        Mul => vf_bits(f(a).mul(f(b), rm)),
+        Div => vf_bits(f(a).div(f(b), rm)),
+        Rdiv => vf_bits(f(b).div(f(a), rm)),
+        Min => vf_minmax::<T>(a, b, false),
+        Max => vf_minmax::<T>(a, b, true),
+        Sgnj => (a & !sign) | (b & sign),
+        Sgnjn => (a & !sign) | (!b & sign),
+        Sgnjx => a ^ (b & sign),
+        Madd => fma(b, d, a),                   // (vs1 * vd) + vs2
+        Nmadd => fma(b ^ sign, d, a ^ sign),    // -(vs1 * vd) - vs2
+        Msub => fma(b, d, a ^ sign),            // (vs1 * vd) - vs2
+        Nmsub => fma(b ^ sign, d, a),           // -(vs1 * vd) + vs2
+        Macc => fma(b, a, d),                   // (vs1 * vs2) + vd
+        Nmacc => fma(b ^ sign, a, d ^ sign),    // -(vs1 * vs2) - vd
+        Msac => fma(b, a, d ^ sign),            // (vs1 * vs2) - vd
+        Nmsac => fma(b ^ sign, a, d),           // -(vs1 * vs2) + vd
+    }
+}
+
+fn vf_compare<T: Float>(funct6: u32, a: u64, b: u64) -> bool {
+    let (x, y) = (vf_from::<T>(a), vf_from::<T>(b));
+    match funct6 {
+        0b011000 => x.eq(&y),       // vmfeq
+        0b011001 => x.le(&y),       // vmfle
+        0b011011 => x.lt(&y),       // vmflt
+        0b011100 => !x.eq(&y),      // vmfne
+        0b011101 => y.lt(&x),       // vmfgt
+        _ => y.le(&x),              // vmfge
+    }
+}
+
+// Float to integer of bits. Out of range values saturate and raise only invalid, NaN gives the maximum.
+fn vf_to_int<T: Float>(x: u64, signed: bool, bits: usize, rm: RoundingMode) -> u64 {
+    let f = vf_from::<T>(x);
+    let (result, flags) = vf_local_flags(|| {
+        if signed { f.to_i64(rm, true) as i128 } else { f.to_u64(rm, true) as i128 }
+    });
+    let (min, max) = if signed { (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1) } else { (0, (1i128 << bits) - 1) };
+    let result = if result > max {
+        vf_raise(FFLAGS_NV);
+        max
+    } else if result < min {
+        vf_raise(FFLAGS_NV);
+        min
+    } else {
+        vf_raise(flags);
+        result
+    };
+    result as u64 & vmask_sew(bits)
+}
+
+// Integer of bits to float
+fn vf_from_int<T: Float>(x: u64, bits: usize, signed: bool, rm: RoundingMode) -> u64 {
+    if signed {
+        vf_bits(T::from_i64(vsext(x, bits), rm))
+    } else {
+        vf_bits(T::from_u64(x, rm))
+    }
+}
+
+// Float to float of bits. Round-to-odd truncates and sets the LSB of an inexact result.
+fn vf_to_float<T: Float>(x: u64, bits: usize, rm: RoundingMode, odd: bool) -> u64 {
+    let f = vf_from::<T>(x);
+    let rm = if odd { RoundingMode::TowardZero } else { rm };
+    let (result, flags) = vf_local_flags(|| match bits {
+        16 => vf_bits(f.to_f16(rm)),
+        32 => vf_bits(f.to_f32(rm)),
+        _ => vf_bits(f.to_f64(rm)),
+    });
+    vf_raise(flags);
+    if odd && flags & FFLAGS_NX != 0 { result | 1 } else { result }
+}
+
+fn vf_class<T: Float>(x: u64) -> u64 {
+    let f = vf_from::<T>(x);
+    let bit = if f.is_negative_infinity() {
+        0
+    } else if f.is_negative_normal() {
+        1
+    } else if f.is_negative_subnormal() {
+        2
+    } else if f.is_negative_zero() {
+        3
+    } else if f.is_positive_zero() {
+        4
+    } else if f.is_positive_subnormal() {
+        5
+    } else if f.is_positive_normal() {
+        6
+    } else if f.is_positive_infinity() {
+        7
+    } else if f.is_signaling_nan() {
+        8
+    } else {
+        9
+    };
+    1 << bit
+}
+
+// Fields of an IEEE binary of sew bits : exponent bits, significand bits, sign, biased exponent, significand
+fn vf_fields(x: u64, sew: usize) -> (u32, u32, u64, i64, u64) {
+    let (e, s) = match sew {
+        16 => (5, 10),
+        32 => (8, 23),
+        _ => (11, 52),
+    };
+    (e, s, (x >> (sew - 1)) & 0x1, ((x >> s) & ((1 << e) - 1)) as i64, x & ((1 << s) - 1))
+}
+
+// Subnormal significand is normalized with the exponent going below zero.
+fn vf_normalize(exp: i64, sig: u64, s: u32) -> (i64, u64) {
+    if exp != 0 {
+        return (exp, sig);
+    }
+    let (mut exp, mut sig) = (exp, sig);
+    while sig & (1 << (s - 1)) == 0 {
+        exp -= 1;
+        sig <<= 1;
+    }
+    (exp, (sig << 1) & ((1 << s) - 1))
+}
+
+// vfrsqrt7.v
+fn vf_rsqrt7(x: u64, sew: usize) -> u64 {
+    let (e, s, sign, exp, sig) = vf_fields(x, sew);
+    let exp_max = (1 << e) - 1;
+    let inf = (exp_max as u64) << s;
+    let qnan = inf | (1 << (s - 1));
+    if exp == exp_max && sig != 0 {
+        if sig & (1 << (s - 1)) == 0 {
+            vf_raise(FFLAGS_NV);
+        }
+        return qnan;
+    }
+    if exp == 0 && sig == 0 {
+        vf_raise(FFLAGS_DZ);
+        return (sign << (sew - 1)) | inf;
+    }
+    if sign != 0 {
+        vf_raise(FFLAGS_NV);
+        return qnan;
+    }
+    if exp == exp_max {
+        return 0;
+    }
+    let (exp, sig) = vf_normalize(exp, sig, s);
+    let bias = (1 << (e - 1)) - 1;
+    let idx = (((exp & 0x1) << 6) as u64 | (sig >> (s - 6))) as usize;
+    let out_exp = (3 * bias - 1 - exp) / 2;
+    ((out_exp as u64) << s) | (RSQRT7_TABLE[idx] << (s - 7))
+}
+
+// vfrec7.v
+fn vf_rec7(x: u64, sew: usize, rm: RoundingMode) -> u64 {
+    let (e, s, sign, exp, sig) = vf_fields(x, sew);
+    let exp_max = (1 << e) - 1;
+    let sign = sign << (sew - 1);
+    let inf = (exp_max as u64) << s;
+    if exp == exp_max {
+        if sig == 0 {
+            return sign;
+        }
+        if sig & (1 << (s - 1)) == 0 {
+            vf_raise(FFLAGS_NV);
+        }
+        return inf | (1 << (s - 1));
+    }
+    if exp == 0 && sig == 0 {
+        vf_raise(FFLAGS_DZ);
+        return sign | inf;
+    }
+    let (exp, sig) = vf_normalize(exp, sig, s);
+    // Too small subnormal overflows : the largest finite value when rounding toward zero
+    if exp < -1 {
+        vf_raise(FFLAGS_OF | FFLAGS_NX);
+        let to_max = match rm {
+            RoundingMode::TowardZero => true,
+            RoundingMode::TowardNegative => sign == 0,
+            RoundingMode::TowardPositive => sign != 0,
+            _ => false,
+        };
+        return if to_max { sign | (inf - 1) } else { sign | inf };
+    }
+    let bias = (1 << (e - 1)) - 1;
+    let mut out_sig = REC7_TABLE[(sig >> (s - 7)) as usize] << (s - 7);
+    let mut out_exp = 2 * bias - 1 - exp;
+    // Result is subnormal
+    if out_exp <= 0 {
+        out_sig = (out_sig >> 1) | (1 << (s - 1));
+        if out_exp == -1 {
+            out_sig >>= 1;
+        }
+        out_exp = 0;
+    }
+    sign | ((out_exp as u64) << s) | out_sig
+}
+
+impl EmuEnv {
+    // Scalar operand fs1 of SEW. Values not NaN-boxed are the canonical NaN.
+    pub fn vf_scalar(&self, fs: usize, sew: usize) -> u64 {
+        match sew {
+            16 => Self::convert_nan_boxing_h(self.m_fregs[fs]) as u64,
+            32 => Self::convert_nan_boxing(self.m_fregs[fs]) as u64,
+            _ => self.m_fregs[fs],
+        }
+    }
+
+    // Floating-point instructions (OPFVV / OPFVF). frm is reserved with a value over 4.
+    // Flags of all elements are accrued to fflags.
+    pub fn helper_func_vfop(emu: &mut EmuEnv, inst: u64, _: u64, _: u64, guest_pc: u64) -> usize {
+        let v = VInst::new(inst);
+        let frm = emu.m_csr.m_frm.m_csr as u64;
+        let cfg = match emu.vconfig() {
+            Some(cfg) if emu.vector_enabled() && emu.m_csr.m_vstart.m_csr == 0 && frm <= 4 => cfg,
+            _ => return emu.vector_illegal(guest_pc),
+        };
+        let rm = Self::get_rounding_mode(emu, frm);
+        ExceptionFlags::default().set();
+        if !emu.vfop(&v, &cfg, rm) {
+            return emu.vector_illegal(guest_pc);
+        }
+        let mut flags = ExceptionFlags::default();
+        flags.get();
+        emu.m_csr.csrrs(CsrAddr::FFlags, flags.to_bits() as i64);
+        emu.vector_done()
+    }
+
+    fn vfop(&mut self, v: &VInst, cfg: &VConfig, rm: RoundingMode) -> bool {
+        let vf = v.funct3 == OPFVF;
+        // Conversions check the widths of their own
+        if v.funct6 == 0b010010 && !vf {
+            return self.vfop_convert(v, cfg, rm);
+        }
+        if !vf_width_ok(cfg.sew, self.m_arg_config.elen) {
+            return false;
+        }
+        match v.funct6 {
+            0b000001 | 0b000011 | 0b000101 | 0b000111 | 0b110001 | 0b110011 if !vf => self.vfop_reduce(v, cfg, rm),
+            0b001110 | 0b001111 if vf => self.vop_slide1(v, cfg),
+            0b010000 => self.vfop_move_scalar(v, cfg),
+            0b010011 if !vf => self.vfop_unary(v, cfg, rm),
+            0b010111 if vf => self.vop_merge(v, cfg),
+            0b011000..=0b011111 => self.vfop_compare(v, cfg),
+            0b110000..=0b111111 => self.vfop_widen(v, cfg, rm),
+            _ => self.vfop_single(v, cfg, rm),
+        }
+    }
+
+    fn vfop_single(&mut self, v: &VInst, cfg: &VConfig, rm: RoundingMode) -> bool {
+        let op = match vf_op(v.funct6, v.funct3 == OPFVF) {
+            Some(op) => op,
+            None => return false,
+        };
+        if !self.vcheck_single(v, cfg) {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if self.vactive(v.vm, idx) {
+                let a = self.vreg_get(v.vs2, idx, cfg.sew);
+                let b = self.vop_src1(v, idx, cfg.sew, false);
+                let d = self.vreg_get(v.vd, idx, cfg.sew);
+                res.push((idx, vf_sew!(cfg.sew, vf_arith(op, a, b, d, rm))));
+            }
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+
+    fn vfop_compare(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        let vf = v.funct3 == OPFVF;
+        let valid = match v.funct6 {
+            0b011000 | 0b011001 | 0b011011 | 0b011100 => true,
+            0b011101 | 0b011111 => vf,
+            _ => false,
+        };
+        if !valid || !vreg_group_ok(v.vs2, cfg.lmul8) || (!vf && !vreg_group_ok(v.vs1, cfg.lmul8)) {
+            return false;
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if self.vactive(v.vm, idx) {
+                let a = self.vreg_get(v.vs2, idx, cfg.sew);
+                let b = self.vop_src1(v, idx, cfg.sew, false);
+                res.push((idx, vf_sew!(cfg.sew, vf_compare(v.funct6, a, b))));
+            }
+        }
+        self.vwrite_mask(v.vd, res);
+        true
+    }
+
+    // Widening add / subtract / multiply (-add) : operands of SEW are converted to 2 x SEW first, which is exact.
+    fn vfop_widen(&mut self, v: &VInst, cfg: &VConfig, rm: RoundingMode) -> bool {
+        let wsew = cfg.sew * 2;
+        let op = match v.funct6 {
+            0b110000 | 0b110100 => VFOp::Add,
+            0b110010 | 0b110110 => VFOp::Sub,
+            0b111000 => VFOp::Mul,
+            0b111100 => VFOp::Macc,
+            0b111101 => VFOp::Nmacc,
+            0b111110 => VFOp::Msac,
+            0b111111 => VFOp::Nmsac,
+            _ => return false,
+        };
+        let wide_vs2 = v.funct6 & 0b111100 == 0b110100;
+        if !vf_width_ok(wsew, self.m_arg_config.elen) || !vreg_group_ok(v.vd, cfg.lmul8 * 2) ||
+            !vreg_group_ok(v.vs2, if wide_vs2 { cfg.lmul8 * 2 } else { cfg.lmul8 }) ||
+            (v.funct3 != OPFVF && !vreg_group_ok(v.vs1, cfg.lmul8)) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        let widen = |x: u64| vf_sew!(cfg.sew, vf_to_float(x, wsew, rm, false));
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let a = if wide_vs2 { self.vreg_get(v.vs2, idx, wsew) } else { widen(self.vreg_get(v.vs2, idx, cfg.sew)) };
+            let b = widen(self.vop_src1(v, idx, cfg.sew, false));
+            let d = self.vreg_get(v.vd, idx, wsew);
+            res.push((idx, vf_sew!(wsew, vf_arith(op, a, b, d, rm))));
+        }
+        self.vwrite(v.vd, wsew, res);
+        true
+    }
+
+    // vfred(u/o)sum / vfredmin / vfredmax / vfwred(u/o)sum : sums are always computed in element order.
+    fn vfop_reduce(&mut self, v: &VInst, cfg: &VConfig, rm: RoundingMode) -> bool {
+        let widen = v.funct6 & 0b110000 != 0;
+        let dsew = if widen { cfg.sew * 2 } else { cfg.sew };
+        if !vf_width_ok(dsew, self.m_arg_config.elen) || !vreg_group_ok(v.vs2, cfg.lmul8) {
+            return false;
+        }
+        let op = match v.funct6 & 0b111 {
+            0b101 => VFOp::Min,
+            0b111 => VFOp::Max,
+            _ => VFOp::Add,
+        };
+        let mut acc = self.vreg_get(v.vs1, 0, dsew);
+        for idx in 0..cfg.vl {
+            if self.vactive(v.vm, idx) {
+                let mut elem = self.vreg_get(v.vs2, idx, cfg.sew);
+                if widen {
+                    elem = vf_sew!(cfg.sew, vf_to_float(elem, dsew, rm, false));
+                }
+                acc = vf_sew!(dsew, vf_arith(op, acc, elem, 0, rm));
+            }
+        }
+        if cfg.vl > 0 {
+            self.vreg_set(v.vd, 0, dsew, acc);
+        }
+        true
+    }
+
+    // vfmv.f.s writes fd NaN-boxed, vfmv.s.f writes element 0 of vd.
+    fn vfop_move_scalar(&mut self, v: &VInst, cfg: &VConfig) -> bool {
+        match v.funct3 {
+            OPFVF if v.vm && v.vs2 == 0 => {
+                if cfg.vl > 0 {
+                    let data = self.vf_scalar(v.vs1, cfg.sew);
+                    self.vreg_set(v.vd, 0, cfg.sew, data);
+                }
+            }
+            _ if v.vm && v.vs1 == 0 => {
+                let data = self.vreg_get(v.vs2, 0, cfg.sew);
+                self.m_fregs[v.vd] = match cfg.sew {
+                    16 => Self::nan_boxing_h(data as u16),
+                    32 => data | 0xffffffff_00000000,
+                    _ => data,
+                };
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // VFUNARY0 : vs1[4:3] selects single-width, widening or narrowing, vs1[2:0] the conversion.
+    fn vfop_convert(&mut self, v: &VInst, cfg: &VConfig, rm: RoundingMode) -> bool {
+        let elen = self.m_arg_config.elen;
+        let kind = v.vs1 & 0b111;
+        let (ssew, dsew, semul8, demul8) = match v.vs1 >> 3 {
+            0b00 if kind != 0b100 && kind != 0b101 => (cfg.sew, cfg.sew, cfg.lmul8, cfg.lmul8),
+            0b01 if kind != 0b101 => (cfg.sew, cfg.sew * 2, cfg.lmul8, cfg.lmul8 * 2),
+            0b10 => (cfg.sew * 2, cfg.sew, cfg.lmul8 * 2, cfg.lmul8),
+            _ => return false,
+        };
+        let widths_ok = match kind {
+            0b010 | 0b011 => vf_width_ok(dsew, elen) && ssew <= elen,      // integer to float
+            0b100 | 0b101 => vf_width_ok(ssew, elen) && vf_width_ok(dsew, elen),
+            _ => vf_width_ok(ssew, elen) && dsew <= elen,                  // float to integer
+        };
+        if !widths_ok || !vreg_group_ok(v.vd, demul8) || !vreg_group_ok(v.vs2, semul8) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        let signed = kind & 0x1 != 0;
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let a = self.vreg_get(v.vs2, idx, ssew);
+            let data = match kind {
+                0b000 | 0b001 => vf_sew!(ssew, vf_to_int(a, signed, dsew, rm)),
+                0b110 | 0b111 => vf_sew!(ssew, vf_to_int(a, signed, dsew, RoundingMode::TowardZero)),
+                0b010 | 0b011 => vf_sew!(dsew, vf_from_int(a, ssew, signed, rm)),
+                _ => vf_sew!(ssew, vf_to_float(a, dsew, rm, kind == 0b101)),
+            };
+            res.push((idx, data));
+        }
+        self.vwrite(v.vd, dsew, res);
+        true
+    }
+
+    // VFUNARY1 : vfsqrt / vfrsqrt7 / vfrec7 / vfclass
+    fn vfop_unary(&mut self, v: &VInst, cfg: &VConfig, rm: RoundingMode) -> bool {
+        if !matches!(v.vs1, 0b00000 | 0b00100 | 0b00101 | 0b10000) || !vreg_group_ok(v.vd, cfg.lmul8) ||
+            !vreg_group_ok(v.vs2, cfg.lmul8) || (!v.vm && v.vd == 0) {
+            return false;
+        }
+        fn sqrt<T: Float>(x: u64, rm: RoundingMode) -> u64 {
+            vf_bits(vf_from::<T>(x).sqrt(rm))
+        }
+        let mut res = Vec::with_capacity(cfg.vl);
+        for idx in 0..cfg.vl {
+            if !self.vactive(v.vm, idx) {
+                continue;
+            }
+            let a = self.vreg_get(v.vs2, idx, cfg.sew);
+            let data = match v.vs1 {
+                0b00000 => vf_sew!(cfg.sew, sqrt(a, rm)),
+                0b00100 => vf_rsqrt7(a, cfg.sew),
+                0b00101 => vf_rec7(a, cfg.sew, rm),
+                _ => vf_sew!(cfg.sew, vf_class(a)),
+            };
+            res.push((idx, data));
+        }
+        self.vwrite(v.vd, cfg.sew, res);
+        true
+    }
+}
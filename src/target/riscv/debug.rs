@@ -0,0 +1,237 @@
+use crate::emu_env::{EmuEnv, MachineStatus};
+use crate::target::riscv::riscv::PrivMode;
+use crate::target::riscv::riscv_csr::{CsrAddr, RiscvCsr};
+use crate::target::riscv::riscv_csr_def;
+
+// dcsr.cause
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum DebugCause {
+    Ebreak = 1,
+    Trigger = 2,
+    HaltReq = 3,
+    Step = 4,
+    ResetHaltReq = 5,
+}
+
+// dcsr.xdebugver : external debug support following the ratified spec
+const DCSR_XDEBUGVER: i64 = 4;
+
+// Writable fields of dcsr. xdebugver, cause and nmip are read-only, v depends on H extension.
+const DCSR_WMASK: i64 = (1 << riscv_csr_def::SYSREG_DCSR_EBREAKVS_LSB) | (1 << riscv_csr_def::SYSREG_DCSR_EBREAKVU_LSB) |
+    (1 << riscv_csr_def::SYSREG_DCSR_EBREAKM_LSB) | (1 << riscv_csr_def::SYSREG_DCSR_EBREAKS_LSB) |
+    (1 << riscv_csr_def::SYSREG_DCSR_EBREAKU_LSB) | (1 << riscv_csr_def::SYSREG_DCSR_STEPIE_LSB) |
+    (1 << riscv_csr_def::SYSREG_DCSR_STOPCOUNT_LSB) | (1 << riscv_csr_def::SYSREG_DCSR_STOPTIME_LSB) |
+    (1 << riscv_csr_def::SYSREG_DCSR_MPRVEN_LSB) | (1 << riscv_csr_def::SYSREG_DCSR_STEP_LSB) |
+    (0x3 << riscv_csr_def::SYSREG_DCSR_PRV_LSB);
+
+// Register number of abstract command "Access Register"
+const REGNO_CSR_LAST: u16 = 0x0fff;
+const REGNO_GPR_BASE: u16 = 0x1000;
+const REGNO_FPR_BASE: u16 = 0x1020;
+
+impl RiscvCsr<i64> {
+    fn dcsr_field(&self, msb: u8, lsb: u8) -> i64 {
+        EmuEnv::extract_bit_field(self.m_dcsr.m_csr, msb, lsb)
+    }
+
+    // WARL : reserved privilege (2) keeps the previous value.
+    pub fn dcsr_write(&mut self, data: i64) -> i64 {
+        let ret_val = self.m_dcsr.m_csr;
+        let mut wmask = DCSR_WMASK;
+        if (self.m_misa.m_csr & (1 << ('H' as u8 - 'A' as u8))) != 0 {
+            wmask |= 1 << riscv_csr_def::SYSREG_DCSR_V_LSB;
+        }
+        let prv = EmuEnv::extract_bit_field(data, riscv_csr_def::SYSREG_DCSR_PRV_MSB, riscv_csr_def::SYSREG_DCSR_PRV_LSB);
+        if prv == PrivMode::Hypervisor as i64 {
+            wmask &= !(0x3 << riscv_csr_def::SYSREG_DCSR_PRV_LSB);
+        }
+        self.m_dcsr.m_csr = (ret_val & !wmask) | (data & wmask);
+        ret_val
+    }
+}
+
+impl EmuEnv {
+    // Halt the hart in Debug Mode. The instruction at dpc has not been executed.
+    pub fn enter_debug_mode(&mut self, guest_pc: u64, cause: DebugCause) {
+        self.hpm_block_stop(guest_pc);
+        // xdebugver and cause are read-only from the debugger, update the storage directly.
+        let mut dcsr = self.m_csr.m_dcsr.m_csr;
+        dcsr = Self::set_bit_field(dcsr, DCSR_XDEBUGVER, riscv_csr_def::SYSREG_DCSR_XDEBUGVER_MSB, riscv_csr_def::SYSREG_DCSR_XDEBUGVER_LSB);
+        dcsr = Self::set_bit_field(dcsr, cause as i64, riscv_csr_def::SYSREG_DCSR_CAUSE_MSB, riscv_csr_def::SYSREG_DCSR_CAUSE_LSB);
+        dcsr = Self::set_bit_field(dcsr, self.m_virt as i64, riscv_csr_def::SYSREG_DCSR_V_MSB, riscv_csr_def::SYSREG_DCSR_V_LSB);
+        dcsr = Self::set_bit_field(dcsr, self.m_priv as i64, riscv_csr_def::SYSREG_DCSR_PRV_MSB, riscv_csr_def::SYSREG_DCSR_PRV_LSB);
+        self.m_csr.m_dcsr.m_csr = dcsr;
+        self.m_csr.m_dpc.m_csr = guest_pc as i64;
+
+        if self.m_arg_config.debug {
+            eprintln!("<Info: Enter Debug Mode. cause={} dpc={:016x}>", cause as u32, guest_pc);
+        }
+        // Debug Mode runs with M-mode privilege
+        self.m_priv = PrivMode::Machine;
+        self.m_virt = false;
+        self.m_csr.m_debug_mode = true;
+        self.m_pc[0] = guest_pc;
+        self.m_machine_status = MachineStatus::Halted;
+        self.check_tlb_context();
+    }
+
+    // DRET : resume at dpc with the privilege in dcsr.prv / dcsr.v.
+    pub fn leave_debug_mode(&mut self) {
+        let next_priv = PrivMode::from_u8(self.m_csr.dcsr_field(riscv_csr_def::SYSREG_DCSR_PRV_MSB, riscv_csr_def::SYSREG_DCSR_PRV_LSB) as u8);
+        let next_virt = next_priv != PrivMode::Machine &&
+            self.m_csr.dcsr_field(riscv_csr_def::SYSREG_DCSR_V_MSB, riscv_csr_def::SYSREG_DCSR_V_LSB) != 0;
+        if next_priv != PrivMode::Machine {
+            let mstatus = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+            let mstatus = Self::set_bit_field(mstatus, 0, riscv_csr_def::SYSREG_MSTATUS_MPRV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPRV_LSB);
+            self.m_csr.csrrw(CsrAddr::Mstatus, mstatus);
+        }
+        if self.m_arg_config.debug {
+            eprintln!("<Info: Leave Debug Mode. dpc={:016x}>", self.m_csr.m_dpc.m_csr);
+        }
+        self.m_priv = next_priv;
+        self.m_virt = next_virt;
+        self.m_csr.m_debug_mode = false;
+        self.m_pc[0] = self.m_csr.m_dpc.m_csr as u64;
+        if self.m_machine_status == MachineStatus::Halted {
+            self.m_machine_status = MachineStatus::Running;
+        }
+        self.check_tlb_context();
+    }
+
+    // dcsr.ebreak{m,s,u,vs,vu} : EBREAK in the mode halts the hart instead of raising breakpoint exception.
+    pub fn ebreak_enters_debug_mode(&self) -> bool {
+        let (msb, lsb) = match (self.m_priv, self.m_virt) {
+            (PrivMode::Machine, _) => (riscv_csr_def::SYSREG_DCSR_EBREAKM_MSB, riscv_csr_def::SYSREG_DCSR_EBREAKM_LSB),
+            (PrivMode::User, true) => (riscv_csr_def::SYSREG_DCSR_EBREAKVU_MSB, riscv_csr_def::SYSREG_DCSR_EBREAKVU_LSB),
+            (_, true) => (riscv_csr_def::SYSREG_DCSR_EBREAKVS_MSB, riscv_csr_def::SYSREG_DCSR_EBREAKVS_LSB),
+            (PrivMode::User, false) => (riscv_csr_def::SYSREG_DCSR_EBREAKU_MSB, riscv_csr_def::SYSREG_DCSR_EBREAKU_LSB),
+            (_, false) => (riscv_csr_def::SYSREG_DCSR_EBREAKS_MSB, riscv_csr_def::SYSREG_DCSR_EBREAKS_LSB),
+        };
+        self.m_csr.dcsr_field(msb, lsb) != 0
+    }
+
+    // dcsr.step : the next block is translated with a single instruction.
+    pub fn debug_single_step(&self) -> bool {
+        !self.m_csr.m_debug_mode &&
+            self.m_csr.dcsr_field(riscv_csr_def::SYSREG_DCSR_STEP_MSB, riscv_csr_def::SYSREG_DCSR_STEP_LSB) != 0
+    }
+
+    // Interrupts are disabled while single stepping unless dcsr.stepie is set.
+    pub fn debug_mask_interrupts(&self) -> bool {
+        self.debug_single_step() &&
+            self.m_csr.dcsr_field(riscv_csr_def::SYSREG_DCSR_STEPIE_MSB, riscv_csr_def::SYSREG_DCSR_STEPIE_LSB) == 0
+    }
+
+    // Called at each block boundary of the run loop. Returns true when the hart is halted.
+    pub fn debug_check_halt(&mut self) -> bool {
+        if self.m_debug_haltreq && !self.m_csr.m_debug_mode {
+            self.m_debug_haltreq = false;
+            self.enter_debug_mode(self.m_pc[0], DebugCause::HaltReq);
+        }
+        self.m_csr.m_debug_mode
+    }
+
+    //
+    // Debugger front end interface, equivalent to the operations of the debug module.
+    //
+
+    pub fn debug_halted(&self) -> bool {
+        self.m_csr.m_debug_mode
+    }
+
+    // dmcontrol.haltreq : the hart halts at the next block boundary.
+    pub fn debug_halt_request(&mut self) {
+        self.m_debug_haltreq = true;
+    }
+
+    // dmcontrol.resumereq : run until the hart halts again or the machine stops.
+    pub fn debug_resume(&mut self) -> MachineStatus {
+        if self.m_csr.m_debug_mode {
+            self.leave_debug_mode();
+        }
+        self.run_loop();
+        self.m_machine_status
+    }
+
+    // Resume with dcsr.step set, and restore dcsr.step after the hart halted again.
+    pub fn debug_step(&mut self) -> MachineStatus {
+        let dcsr = self.m_csr.m_dcsr.m_csr;
+        self.m_csr.dcsr_write(dcsr | (1 << riscv_csr_def::SYSREG_DCSR_STEP_LSB));
+        let status = self.debug_resume();
+        let step = Self::extract_bit_field(dcsr, riscv_csr_def::SYSREG_DCSR_STEP_MSB, riscv_csr_def::SYSREG_DCSR_STEP_LSB);
+        let dcsr = self.m_csr.m_dcsr.m_csr;
+        self.m_csr.dcsr_write(Self::set_bit_field(dcsr, step, riscv_csr_def::SYSREG_DCSR_STEP_MSB, riscv_csr_def::SYSREG_DCSR_STEP_LSB));
+        status
+    }
+
+    // Abstract command "Access Register" : 0x0000-0x0fff CSRs, 0x1000-0x101f GPRs, 0x1020-0x103f FPRs.
+    // Only available while the hart is halted. The PC is read / written through dpc.
+    pub fn debug_read_register(&mut self, regno: u16) -> Option<u64> {
+        if !self.m_csr.m_debug_mode {
+            return None;
+        }
+        match regno {
+            0..=REGNO_CSR_LAST => match CsrAddr::from_u64(regno as u64) {
+                CsrAddr::None => None,
+                csr => Some(self.m_csr.csrrs(csr, 0) as u64),
+            },
+            REGNO_GPR_BASE..=0x101f => Some(self.m_iregs[(regno - REGNO_GPR_BASE) as usize]),
+            REGNO_FPR_BASE..=0x103f => Some(self.m_fregs[(regno - REGNO_FPR_BASE) as usize]),
+            _ => None,
+        }
+    }
+
+    pub fn debug_write_register(&mut self, regno: u16, data: u64) -> bool {
+        if !self.m_csr.m_debug_mode {
+            return false;
+        }
+        match regno {
+            0..=REGNO_CSR_LAST => match CsrAddr::from_u64(regno as u64) {
+                CsrAddr::None => return false,
+                csr => { self.m_csr.csrrw(csr, data as i64); }
+            },
+            REGNO_GPR_BASE => {}
+            0x1001..=0x101f => self.m_iregs[(regno - REGNO_GPR_BASE) as usize] = data,
+            REGNO_FPR_BASE..=0x103f => self.m_fregs[(regno - REGNO_FPR_BASE) as usize] = data,
+            _ => return false,
+        }
+        true
+    }
+
+    // System bus access : physical address, size of 1, 2, 4 or 8 bytes.
+    pub fn debug_read_memory(&mut self, guest_phy_addr: u64, size: usize) -> Option<u64> {
+        if let Some(data) = self.mmio_read(guest_phy_addr, size) {
+            return Some(data);
+        }
+        if !self.is_dram_addr(guest_phy_addr, size as u64) {
+            return None;
+        }
+        match size {
+            1 => Some(self.read_mem_1byte(guest_phy_addr) as u64),
+            2 => Some(self.read_mem_2byte(guest_phy_addr) as u64),
+            4 => Some(self.read_mem_4byte(guest_phy_addr) as u64),
+            8 => Some(self.read_mem_8byte(guest_phy_addr)),
+            _ => None,
+        }
+    }
+
+    // Memory may hold the guest code (e.g. software breakpoint), translated blocks are dropped.
+    pub fn debug_write_memory(&mut self, guest_phy_addr: u64, size: usize, data: u64) -> bool {
+        if self.mmio_write(guest_phy_addr, size, data) {
+            return true;
+        }
+        if !self.is_dram_addr(guest_phy_addr, size as u64) {
+            return false;
+        }
+        match size {
+            1 => self.write_mem_1byte(guest_phy_addr, data as u8),
+            2 => self.write_mem_2byte(guest_phy_addr, data as u16),
+            4 => self.write_mem_4byte(guest_phy_addr, data as u32),
+            8 => self.write_mem_8byte(guest_phy_addr, data),
+            _ => return false,
+        }
+        self.flush_translated_blocks();
+        true
+    }
+}
@@ -0,0 +1,325 @@
+use crate::emu_env::EmuEnv;
+use crate::target::riscv::riscv::{ExceptCode, PrivMode};
+use crate::target::riscv::riscv_csr::{CsrAddr, RiscvCsr};
+use crate::target::riscv::riscv_inst_id::RiscvInstId;
+
+// Number of programmable counters : mhpmcounter3 .. mhpmcounter31
+pub const HPM_NUM: usize = 29;
+const HPM_BASE: usize = 3;
+
+// Counter index of cycle / time / instret in mcountinhibit, mcounteren and friends
+const CNT_CY: usize = 0;
+const CNT_TM: usize = 1;
+const CNT_IR: usize = 2;
+
+// mcountinhibit has no TM bit
+const MCOUNTINHIBIT_MASK: i64 = 0xffff_fffd;
+
+// mhpmevent bits (Sscofpmf)
+const MHPMEVENT_OF: i64 = 1 << 63;
+const MHPMEVENT_MINH: i64 = 1 << 62;
+const MHPMEVENT_SINH: i64 = 1 << 61;
+const MHPMEVENT_UINH: i64 = 1 << 60;
+const MHPMEVENT_VSINH: i64 = 1 << 59;
+const MHPMEVENT_VUINH: i64 = 1 << 58;
+const MHPMEVENT_EVENT_MASK: i64 = 0xff;
+const MHPMEVENT_WMASK: i64 = MHPMEVENT_OF | MHPMEVENT_MINH | MHPMEVENT_SINH | MHPMEVENT_UINH |
+    MHPMEVENT_VSINH | MHPMEVENT_VUINH | MHPMEVENT_EVENT_MASK;
+
+// Local counter overflow interrupt pending
+pub const MIP_LCOFIP: i64 = 1 << 13;
+
+// Events selected by mhpmevent[7:0]. Other values count nothing.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum HpmEvent {
+    None = 0,
+    Load = 1,
+    Store = 2,
+    Branch = 3,       // Conditional branches and jumps
+    TlbMiss = 4,      // Page table walks
+}
+const HPM_EVENT_NUM: usize = 5;
+
+impl HpmEvent {
+    fn from_mhpmevent(mhpmevent: i64) -> HpmEvent {
+        match mhpmevent & MHPMEVENT_EVENT_MASK {
+            1 => HpmEvent::Load,
+            2 => HpmEvent::Store,
+            3 => HpmEvent::Branch,
+            4 => HpmEvent::TlbMiss,
+            _ => HpmEvent::None,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << (self as u8)
+    }
+
+    // Events retired with the instruction. AMOs are both load and store.
+    pub fn of_inst(id: RiscvInstId) -> u8 {
+        use RiscvInstId::*;
+        let load = HpmEvent::Load.bit();
+        let store = HpmEvent::Store.bit();
+        match id {
+            LB | LH | LW | LD | LBU | LHU | LWU | FLW | FLD |
+            C_LW | C_LD | C_FLD | C_LWSP | C_LDSP | C_FLDSP |
+            LR_W | LR_D |
+            HLV_B | HLV_BU | HLV_H | HLV_HU | HLV_W | HLV_WU | HLV_D | HLVX_HU | HLVX_WU |
+            VLE_V | VLSE_V | VLUXEI_V | VLOXEI_V => load,
+            SB | SH | SW | SD | FSW | FSD |
+            C_SW | C_SD | C_FSD | C_SWSP | C_SDSP | C_FSDSP |
+            SC_W | SC_D |
+            HSV_B | HSV_H | HSV_W | HSV_D |
+            VSE_V | VSSE_V | VSUXEI_V | VSOXEI_V => store,
+            AMOSWAP_W | AMOADD_W | AMOXOR_W | AMOAND_W | AMOOR_W | AMOMIN_W | AMOMAX_W | AMOMINU_W | AMOMAXU_W |
+            AMOSWAP_D | AMOADD_D | AMOXOR_D | AMOAND_D | AMOOR_D | AMOMIN_D | AMOMAX_D | AMOMINU_D | AMOMAXU_D => load | store,
+            BEQ | BNE | BLT | BGE | BLTU | BGEU | JAL | JALR |
+            C_BEQZ | C_BNEZ | C_J | C_JAL | C_JR | C_JALR => HpmEvent::Branch.bit(),
+            _ => 0,
+        }
+    }
+}
+
+// Instruction of a translated block : byte offset from the block head and events it retires with.
+#[derive(Copy, Clone)]
+pub struct TbInst {
+    pub offset: u16,
+    pub events: u8,
+}
+
+// Block being executed. Instructions are retired in bulk when the block exits,
+// or up to a CSR access which observes the counters.
+#[derive(Copy, Clone)]
+pub struct HpmBlock {
+    key: usize,
+    init_pc: u64,
+    done: usize,              // Number of instructions already retired
+    priv_mode: PrivMode,
+    virt: bool,
+    running: bool,
+    stop_pc: Option<u64>,     // Instruction which raised exception or entered Debug Mode : not retired
+}
+
+impl HpmBlock {
+    pub fn new() -> HpmBlock {
+        HpmBlock { key: 0, init_pc: 0, done: 0, priv_mode: PrivMode::Machine, virt: false, running: false, stop_pc: None }
+    }
+}
+
+impl CsrAddr {
+    // Index of cycle(0) / time(1) / instret(2) / hpmcounter3..31 for counter CSRs,
+    // and whether it is the machine mode view.
+    fn counter_index(self) -> Option<(bool, usize)> {
+        let addr = self as u64;
+        match addr {
+            0xc00..=0xc1f => Some((false, (addr - 0xc00) as usize)),
+            0xb00..=0xb1f => Some((true, (addr - 0xb00) as usize)),
+            _ => None,
+        }
+    }
+
+    pub fn is_hpm(self) -> bool {
+        let addr = self as u64;
+        self.counter_index().is_some() || (0x320..=0x33f).contains(&addr) || addr == CsrAddr::Scountovf as u64
+    }
+}
+
+impl RiscvCsr<i64> {
+    fn counter_read(&self, idx: usize) -> i64 {
+        match idx {
+            CNT_CY => self.m_mcycle.m_csr,
+            CNT_TM => self.m_time.m_csr,
+            CNT_IR => self.m_minstret.m_csr,
+            _ => self.m_mhpmcounter[idx - HPM_BASE].m_csr,
+        }
+    }
+
+    pub fn hpm_read(&self, addr: CsrAddr) -> i64 {
+        let csr = addr as u64;
+        match addr {
+            CsrAddr::Mcountinhibit => self.m_mcountinhibit.m_csr,
+            CsrAddr::Scountovf => {
+                let mut ovf = 0;
+                for idx in 0..HPM_NUM {
+                    if (self.m_mhpmevent[idx].m_csr & MHPMEVENT_OF) != 0 {
+                        ovf |= 1 << (idx + HPM_BASE);
+                    }
+                }
+                ovf & self.m_mcounteren.m_csr
+            }
+            _ => match addr.counter_index() {
+                Some((_, idx)) => self.counter_read(idx),
+                None if (0x323..=0x33f).contains(&csr) => self.m_mhpmevent[(csr - 0x323) as usize].m_csr,
+                None => 0,
+            },
+        }
+    }
+
+    // User mode counters and scountovf are read-only. mcycle / minstret / mhpmcounter are writable from M-mode.
+    pub fn hpm_write(&mut self, addr: CsrAddr, data: i64) {
+        let csr = addr as u64;
+        match addr {
+            CsrAddr::Mcountinhibit => self.m_mcountinhibit.m_csr = data & MCOUNTINHIBIT_MASK,
+            _ => match addr.counter_index() {
+                Some((true, CNT_CY)) => self.m_mcycle.m_csr = data,
+                Some((true, CNT_IR)) => self.m_minstret.m_csr = data,
+                Some((true, idx)) if idx >= HPM_BASE => self.m_mhpmcounter[idx - HPM_BASE].m_csr = data,
+                Some(_) => {}
+                None if (0x323..=0x33f).contains(&csr) => self.m_mhpmevent[(csr - 0x323) as usize].m_csr = data & MHPMEVENT_WMASK,
+                None => {}
+            },
+        }
+        self.m_hpm_active = 0;
+        for idx in 0..HPM_NUM {
+            let inhibit = (self.m_mcountinhibit.m_csr >> (idx + HPM_BASE)) & 1 != 0;
+            if !inhibit && HpmEvent::from_mhpmevent(self.m_mhpmevent[idx].m_csr) != HpmEvent::None {
+                self.m_hpm_active |= 1 << idx;
+            }
+        }
+    }
+
+    // Mode filtering of Sscofpmf
+    fn hpm_inhibited(mhpmevent: i64, priv_mode: PrivMode, virt: bool) -> bool {
+        let inh = match (priv_mode, virt) {
+            (PrivMode::Machine, _) => MHPMEVENT_MINH,
+            (PrivMode::Supervisor, false) => MHPMEVENT_SINH,
+            (PrivMode::Supervisor, true) => MHPMEVENT_VSINH,
+            (_, false) => MHPMEVENT_UINH,
+            (_, true) => MHPMEVENT_VUINH,
+        };
+        (mhpmevent & inh) != 0
+    }
+
+    // Count n events on mhpmcounters selecting the event.
+    // Wrapping to zero sets OF and raises local counter overflow interrupt unless OF is already set.
+    pub fn hpm_add(&mut self, event: HpmEvent, n: u64, priv_mode: PrivMode, virt: bool) {
+        if n == 0 {
+            return;
+        }
+        for idx in 0..HPM_NUM {
+            if (self.m_hpm_active >> idx) & 1 == 0 {
+                continue;
+            }
+            let mhpmevent = self.m_mhpmevent[idx].m_csr;
+            if HpmEvent::from_mhpmevent(mhpmevent) != event || Self::hpm_inhibited(mhpmevent, priv_mode, virt) {
+                continue;
+            }
+            let (val, overflow) = (self.m_mhpmcounter[idx].m_csr as u64).overflowing_add(n);
+            self.m_mhpmcounter[idx].m_csr = val as i64;
+            if overflow && (mhpmevent & MHPMEVENT_OF) == 0 {
+                self.m_mhpmevent[idx].m_csr |= MHPMEVENT_OF;
+                self.m_mip.m_csr |= MIP_LCOFIP;
+            }
+        }
+    }
+
+    // Advance time and cycle by the cycle model, and instret by retired instructions.
+    fn hpm_tick(&mut self, cycles: u64, insts: u64) {
+        let inhibit = self.m_mcountinhibit.m_csr;
+        self.m_time.m_csr = self.m_time.m_csr.wrapping_add(cycles as i64);
+        if (inhibit >> CNT_CY) & 1 == 0 {
+            self.m_mcycle.m_csr = self.m_mcycle.m_csr.wrapping_add(cycles as i64);
+        }
+        if (inhibit >> CNT_IR) & 1 == 0 {
+            self.m_minstret.m_csr = self.m_minstret.m_csr.wrapping_add(insts as i64);
+        }
+    }
+}
+
+impl EmuEnv {
+    pub fn hpm_block_start(&mut self, key: usize) {
+        self.m_hpm_block = HpmBlock {
+            key: key,
+            init_pc: self.m_tb_text_hash_address[key],
+            done: 0,
+            priv_mode: self.m_priv,
+            virt: self.m_virt,
+            running: true,
+            stop_pc: None,
+        };
+    }
+
+    // Instruction at guest_pc did not complete (exception, Debug Mode entry).
+    pub fn hpm_block_stop(&mut self, guest_pc: u64) {
+        if self.m_hpm_block.running && self.m_hpm_block.stop_pc.is_none() {
+            self.m_hpm_block.stop_pc = Some(guest_pc);
+        }
+    }
+
+    pub fn hpm_block_end(&mut self) {
+        self.hpm_retire(self.m_hpm_block.stop_pc);
+        self.m_hpm_block.running = false;
+    }
+
+    // Retire instructions of the running block before end_pc (or all of them).
+    fn hpm_retire(&mut self, end_pc: Option<u64>) {
+        let block = self.m_hpm_block;
+        if !block.running {
+            return;
+        }
+        let insts = &self.m_tb_text_hash_insts[block.key];
+        let mut counts = [0u64; HPM_EVENT_NUM];
+        let mut done = block.done;
+        while done < insts.len() {
+            let inst = insts[done];
+            if let Some(end_pc) = end_pc {
+                if block.init_pc + inst.offset as u64 >= end_pc {
+                    break;
+                }
+            }
+            for (ev, count) in counts.iter_mut().enumerate() {
+                *count += ((inst.events >> ev) & 1) as u64;
+            }
+            done += 1;
+        }
+        let retired = (done - block.done) as u64;
+        self.m_hpm_block.done = done;
+
+        self.m_csr.hpm_tick(retired * self.m_arg_config.cycle_per_inst, retired);
+        if self.m_csr.m_hpm_active != 0 {
+            for ev in [HpmEvent::Load, HpmEvent::Store, HpmEvent::Branch] {
+                self.m_csr.hpm_add(ev, counts[ev as usize], block.priv_mode, block.virt);
+            }
+        }
+    }
+
+    // Page table walk on TLB miss
+    pub fn hpm_tlb_miss(&mut self) {
+        self.m_csr.hpm_tick(self.m_arg_config.tlb_miss_cycles, 0);
+        self.m_csr.hpm_add(HpmEvent::TlbMiss, 1, self.m_priv, self.m_virt);
+    }
+
+    // Counter access from U/S/VS/VU-mode is allowed by mcounteren, hcounteren and scounteren.
+    pub fn counter_access_fault(&self, csr_addr: u64) -> Option<ExceptCode> {
+        let idx = match CsrAddr::from_u64(csr_addr).counter_index() {
+            Some((false, idx)) => idx,
+            _ => return None,
+        };
+        if self.m_priv == PrivMode::Machine {
+            return None;
+        }
+        let enabled = |counteren: i64| (counteren >> idx) & 1 != 0;
+        if !enabled(self.m_csr.m_mcounteren.m_csr) {
+            Some(ExceptCode::IllegalInst)
+        } else if self.m_virt && !enabled(self.m_csr.m_hcounteren.m_csr) {
+            Some(ExceptCode::VirtualInst)
+        } else if self.m_priv == PrivMode::User && !enabled(self.m_csr.m_scounteren.m_csr) {
+            Some(if self.m_virt { ExceptCode::VirtualInst } else { ExceptCode::IllegalInst })
+        } else {
+            None
+        }
+    }
+
+    // CSR instruction on counters : instructions before it are retired first so that the value is exact,
+    // and the access itself is retired before the write so that a written value is not incremented.
+    pub fn csr_op(&mut self, addr: CsrAddr, guest_pc: u64, op: fn(&mut RiscvCsr<i64>, CsrAddr, i64) -> i64, data: i64) -> i64 {
+        if !addr.is_hpm() || !self.m_hpm_block.running {
+            return op(&mut self.m_csr, addr, data);
+        }
+        self.hpm_retire(Some(guest_pc));
+        let ret_val = self.m_csr.hpm_read(addr);
+        self.hpm_retire(Some(guest_pc + 1));
+        op(&mut self.m_csr, addr, data);
+        ret_val
+    }
+}
@@ -0,0 +1,111 @@
+use crate::emu_env::EmuEnv;
+use crate::target::riscv::mmu::MemAccType;
+use crate::target::riscv::riscv::PrivMode;
+use crate::target::riscv::riscv_csr::{TVEC_MODE_TABLE, TVEC_MODE_VECTORED};
+use crate::target::riscv::riscv_csr_def;
+
+// Interrupt causes (xcause without the interrupt bit)
+pub const IRQ_S_SOFT: u64 = 1;
+pub const IRQ_VS_SOFT: u64 = 2;
+pub const IRQ_M_SOFT: u64 = 3;
+pub const IRQ_S_TIMER: u64 = 5;
+pub const IRQ_VS_TIMER: u64 = 6;
+pub const IRQ_M_TIMER: u64 = 7;
+pub const IRQ_S_EXT: u64 = 9;
+pub const IRQ_VS_EXT: u64 = 10;
+pub const IRQ_M_EXT: u64 = 11;
+pub const IRQ_LCOF: u64 = 13;
+
+// xcause[XLEN-1] : trap is caused by an interrupt
+pub const CAUSE_INTERRUPT: i64 = 1 << 63;
+
+// VS-level interrupts (VSSI VSTI VSEI)
+const IRQ_VS_BITS: i64 = (1 << IRQ_VS_SOFT) | (1 << IRQ_VS_TIMER) | (1 << IRQ_VS_EXT);
+
+// Priority among interrupts to the same privilege mode, highest first
+const IRQ_PRIORITY: [u64; 10] = [
+    IRQ_M_EXT, IRQ_M_SOFT, IRQ_M_TIMER,
+    IRQ_S_EXT, IRQ_S_SOFT, IRQ_S_TIMER,
+    IRQ_VS_EXT, IRQ_VS_SOFT, IRQ_VS_TIMER,
+    IRQ_LCOF,
+];
+
+impl EmuEnv {
+    fn highest_irq(irqs: i64) -> Option<u64> {
+        IRQ_PRIORITY.iter().copied().find(|irq| (irqs & (1 << irq)) != 0)
+    }
+
+    // Interrupt to be taken before the next block, if any.
+    // Interrupts to a more privileged mode are taken first, then the priority order within the mode.
+    pub fn pending_interrupt(&self) -> Option<u64> {
+        let csr = &self.m_csr;
+        let mut pending = csr.m_mip.m_csr;
+        let mut enabled = csr.m_mie.m_csr;
+        if self.m_arg_config.hypervisor {
+            pending |= (csr.m_hip.m_csr | csr.m_hvip.m_csr) & IRQ_VS_BITS;
+            enabled |= csr.m_hie.m_csr & IRQ_VS_BITS;
+        }
+        let irqs = pending & enabled;
+        if irqs == 0 {
+            return None;
+        }
+
+        let mideleg = csr.mideleg();
+        let hideleg = csr.m_hideleg.m_csr;
+        let mstatus = csr.m_mstatus.m_csr;
+        let mie = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MIE_MSB, riscv_csr_def::SYSREG_MSTATUS_MIE_LSB) != 0;
+        let sie = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_SIE_MSB, riscv_csr_def::SYSREG_MSTATUS_SIE_LSB) != 0;
+        let vsie = Self::extract_bit_field(csr.m_vsstatus.m_csr, riscv_csr_def::SYSREG_SSTATUS_SIE_MSB, riscv_csr_def::SYSREG_SSTATUS_SIE_LSB) != 0;
+
+        // M-level : masked only in M-mode by mstatus.MIE
+        if self.m_priv != PrivMode::Machine || mie {
+            if let Some(irq) = Self::highest_irq(irqs & !mideleg) {
+                return Some(irq);
+            }
+        }
+        // HS-level : masked only in HS-mode by sstatus.SIE
+        let s_enabled = match self.m_priv {
+            PrivMode::Machine => false,
+            PrivMode::Supervisor => self.m_virt || sie,
+            _ => true,
+        };
+        if s_enabled {
+            if let Some(irq) = Self::highest_irq(irqs & mideleg & !hideleg) {
+                return Some(irq);
+            }
+        }
+        // VS-level : only in VS/VU-mode, masked in VS-mode by vsstatus.SIE
+        if self.m_virt && (self.m_priv == PrivMode::User || vsie) {
+            return Self::highest_irq(irqs & mideleg & hideleg);
+        }
+        None
+    }
+
+    // Take the interrupt before executing the instruction at the current PC.
+    pub fn generate_interrupt(&mut self, irq: u64) {
+        if self.m_arg_config.debug {
+            eprintln!("<Info: Generate Interrupt Cause={}, PC={:016x}>", irq, self.m_pc[0]);
+        }
+        let epc = self.m_pc[0];
+        self.take_trap(epc, irq, true, 0);
+    }
+
+    // Handler address following xtvec.MODE. Exceptions always go to BASE.
+    // In table mode, the handler address of the interrupt is fetched from xtvt with the privilege of the handler.
+    pub fn trap_vector(&mut self, tvec: i64, tvt: i64, cause: u64, interrupt: bool) -> u64 {
+        let base = (tvec & !0x3) as u64;
+        match tvec & 0x3 {
+            TVEC_MODE_VECTORED if interrupt => base + 4 * cause,
+            TVEC_MODE_TABLE if interrupt => {
+                let entry = (tvt as u64).wrapping_add(8 * cause);
+                match self.convert_physical_address(entry, entry, MemAccType::Fetch) {
+                    Ok(phy_addr) => self.read_mem_8byte(phy_addr) & !0x1,
+                    // Fault on the table entry has already redirected the PC to the exception handler.
+                    Err(_) => self.m_pc[0],
+                }
+            }
+            TVEC_MODE_TABLE => base & !0x3f,
+            _ => base,
+        }
+    }
+}
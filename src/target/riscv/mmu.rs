@@ -0,0 +1,961 @@
+use crate::emu_env::{tlb_index, tlb_tag, EmuEnv, TLB_INVALID_TAG, TLB_SIZE};
+use crate::target::riscv::pmp::{PMP_R, PMP_W};
+use crate::target::riscv::riscv_csr::{CsrAddr};
+use crate::target::riscv::riscv_csr_def;
+use crate::target::riscv::riscv::{ExceptCode, PrivMode};
+
+#[derive(Copy, Clone)]
+pub enum MemAccType {
+    Fetch,
+    Write,
+    Read,
+}
+
+#[derive(PartialEq, Eq)]
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum MemResult {
+    NoExcept = 0,
+    MisAlign = 1 << 0,
+    NotDefined = 1 << 1,
+    NewRegion = 1 << 2,
+    TlbError = 1 << 3,
+    AccessFault = 1 << 4,
+    TriggerHit = 1 << 5,
+}
+
+#[derive(PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum VMMode {
+    Mbare = 0,
+    Sv32 = 1,
+    Sv39 = 8,
+    Sv48 = 9,
+    Sv57 = 10,
+    Sv64 = 11,
+}
+impl VMMode {
+    pub fn from(x: i64) -> VMMode {
+        match x {
+            0 => VMMode::Mbare,
+            1 => VMMode::Sv32,
+            8 => VMMode::Sv39,
+            9 => VMMode::Sv48,
+            10 => VMMode::Sv57,
+            11 => VMMode::Sv64,
+            _ => panic!("Intelnal Error: Unknown VMMode = {:}", x),
+        }
+    }
+}
+
+
+
+// PTE bits
+const PTE_R: u8 = 1 << 1;
+const PTE_W: u8 = 1 << 2;
+const PTE_X: u8 = 1 << 3;
+const PTE_U: u8 = 1 << 4;
+const PTE_G: u8 = 1 << 5;
+const PTE_A: u8 = 1 << 6;
+const PTE_D: u8 = 1 << 7;
+// PTE[63:54] of 64-bit PTE
+const PTE_N_SHIFT: u64 = 63;
+const PTE_PBMT_SHIFT: u64 = 61;
+const PTE_RESERVED_MASK: u64 = 0x7f << 54;
+// Svnapot : only 64KiB (ppn[3:0] = 4'b1000) is defined
+const PTE_NAPOT_64K_PPN: u64 = 0b1000;
+
+// Translation block context : [15:0] ASID, [16] global page, [17] bare, [19:18] privilege,
+// [20] virtualization mode, [34:21] VMID
+const TB_CTX_ASID_MASK: u64 = 0xffff;
+const TB_CTX_GLOBAL: u64 = 1 << 16;
+const TB_CTX_BARE: u64 = 1 << 17;
+const TB_CTX_PRIV_SHIFT: u64 = 18;
+const TB_CTX_VIRT: u64 = 1 << 20;
+const TB_CTX_VMID_SHIFT: u64 = 21;
+const TB_CTX_VMID_MASK: u64 = 0x3fff;
+
+// Stage of the page table walk.
+// VS-stage and G-stage are used in virtualization mode (V=1) or by HLV / HSV.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum WalkStage {
+    Single,
+    VS,
+    G,
+}
+
+// Parameters of a page table walk which are not taken from the page table itself.
+#[derive(Copy, Clone)]
+struct WalkCtx {
+    stage: WalkStage,
+    root_ppn: u64,
+    priv_mode: PrivMode,    // G-stage is always checked as U-mode
+    sum: bool,
+    mxr: bool,
+    hlvx: bool,             // HLVX : read requires execute permission
+    virt: bool,             // tval is a guest virtual address
+    gva: u64,               // Address reported in tval
+    fault_acc: MemAccType,  // Original access type, selects exception code
+}
+
+// Software side of the inline TLB entry.
+// JIT code only compares the tags in m_tlb_vec / m_tlb_wr_vec, which are
+// built from these entries for the current translation context.
+#[derive(Copy, Clone)]
+pub struct TlbEntry {
+    pub valid: bool,
+    pub bare: bool,         // Filled without page table (M-mode or satp.MODE = Bare)
+    pub vaddr: u64,
+    pub page_mask: u64,     // Superpage leaves cover more than a 4KB entry
+    pub asid: u16,
+    pub perm: u8,           // PTE[7:0]
+    pub pbmt: u8,           // Svpbmt memory type (0 : PMA, 1 : NC, 2 : IO)
+    pub pmp_perm: u8,       // PMP permission of the whole physical page
+    pub pmp_machine: bool,  // pmp_perm is evaluated for M-mode
+    pub virt: bool,         // Guest translation. bare means vsatp.MODE = Bare
+    pub vmid: u16,
+    pub g_perm: u8,         // G-stage PTE[7:0]
+}
+
+impl TlbEntry {
+    pub const fn invalid() -> TlbEntry {
+        TlbEntry { valid: false, bare: false, vaddr: 0, page_mask: !0xfff, asid: 0, perm: 0, pbmt: 0, pmp_perm: 0, pmp_machine: false,
+                   virt: false, vmid: 0, g_perm: 0 }
+    }
+
+    fn bare() -> TlbEntry {
+        TlbEntry { valid: true, bare: true, vaddr: 0, page_mask: !0xfff, asid: 0, perm: 0, pbmt: 0, pmp_perm: 0, pmp_machine: false,
+                   virt: false, vmid: 0, g_perm: 0 }
+    }
+
+    pub fn is_global(&self) -> bool {
+        !self.bare && (self.perm & PTE_G) != 0
+    }
+}
+
+// Everything in CSRs which changes the result of the data access translation.
+#[derive(Copy, Clone, PartialEq)]
+pub struct TlbContext {
+    pub priv_mode: PrivMode,
+    pub bare: bool,
+    pub asid: u16,
+    pub sum: bool,
+    pub mxr: bool,
+    pub virt: bool,
+    pub vmid: u16,
+    pub g_mxr: bool,        // mstatus.MXR applied to G-stage
+    pub trig_load: bool,    // Load / store address triggers are armed : always take the slow path
+    pub trig_store: bool,
+}
+
+impl TlbContext {
+    pub fn machine() -> TlbContext {
+        TlbContext { priv_mode: PrivMode::Machine, bare: true, asid: 0, sum: false, mxr: false, virt: false, vmid: 0, g_mxr: false,
+                     trig_load: false, trig_store: false }
+    }
+
+    // Returns (readable, writable) of the entry in this context.
+    fn permits(&self, entry: &TlbEntry) -> (bool, bool) {
+        let (readable, writable) = self.permits_page(entry);
+        (readable && !self.trig_load, writable && !self.trig_store)
+    }
+
+    fn permits_page(&self, entry: &TlbEntry) -> (bool, bool) {
+        if !entry.valid || entry.bare != self.bare || entry.virt != self.virt {
+            return (false, false);
+        }
+        if self.virt && entry.vmid != self.vmid {
+            return (false, false);
+        }
+        // PMP only differs between M-mode and others
+        if entry.pmp_machine != (self.priv_mode == PrivMode::Machine) {
+            return (false, false);
+        }
+        let mut pmp_readable = (entry.pmp_perm & PMP_R) != 0;
+        let mut pmp_writable = (entry.pmp_perm & PMP_W) != 0;
+        // G-stage permission is folded into the physical side of the check.
+        if self.virt {
+            pmp_readable &= (entry.g_perm & PTE_R) != 0 || (self.g_mxr && (entry.g_perm & PTE_X) != 0);
+            pmp_writable &= (entry.g_perm & PTE_W) != 0 && (entry.g_perm & PTE_D) != 0;
+        }
+        if entry.bare {
+            return (pmp_readable, pmp_writable);
+        }
+        if !entry.is_global() && entry.asid != self.asid {
+            return (false, false);
+        }
+        let is_user_page = (entry.perm & PTE_U) != 0;
+        match self.priv_mode {
+            PrivMode::User => if !is_user_page { return (false, false); },
+            _ => if is_user_page && !self.sum { return (false, false); },
+        }
+        let readable = (entry.perm & PTE_R) != 0 || (self.mxr && (entry.perm & PTE_X) != 0);
+        // Store to clean page must walk the page table to update PTE.D
+        let writable = (entry.perm & PTE_W) != 0 && (entry.perm & PTE_D) != 0;
+        (readable && pmp_readable, writable && pmp_writable)
+    }
+}
+
+impl EmuEnv {
+
+    pub fn convert_physical_address(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType) -> Result<u64, MemResult> {
+        let (priv_mode, virt) = match acc_type {
+            MemAccType::Fetch => (self.m_priv, self.m_virt),
+            _ => self.data_access_mode(),
+        };
+
+        if self.m_arg_config.mmu_debug { 
+            println!("<Convert_Virtual_Address. virtual_addr={:016x} : vm_mode = {}, priv_mode = {}, virt = {}>",
+                 virtual_addr, self.get_vm_mode() as u32, priv_mode as u32, virt);
+        }
+
+        self.check_tlb_context();
+        self.check_data_trigger(guest_pc, virtual_addr, acc_type)?;
+        self.translate_checked(guest_pc, virtual_addr, acc_type, acc_type, priv_mode, virt, false)
+    }
+
+    // cbo.clean / flush / inval : permitted where a load is, but faults are reported as store faults.
+    pub fn convert_cbo_address(&mut self, guest_pc: u64, virtual_addr: u64) -> Result<u64, MemResult> {
+        let (priv_mode, virt) = self.data_access_mode();
+
+        self.check_tlb_context();
+        self.translate_checked(guest_pc, virtual_addr, MemAccType::Read, MemAccType::Write, priv_mode, virt, false)
+    }
+
+    // HLV / HLVX / HSV : access to the guest address space as hstatus.SPVP privilege.
+    pub fn convert_guest_physical_address(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, hlvx: bool) -> Result<u64, MemResult> {
+        let hstatus = self.m_csr.csrrs(CsrAddr::Hstatus, 0);
+        let spvp = Self::extract_bit_field(hstatus, riscv_csr_def::SYSREG_HSTATUS_SPVP_MSB, riscv_csr_def::SYSREG_HSTATUS_SPVP_LSB);
+        let priv_mode = if spvp != 0 { PrivMode::Supervisor } else { PrivMode::User };
+
+        self.check_tlb_context();
+        self.check_data_trigger(guest_pc, virtual_addr, acc_type)?;
+        self.translate_checked(guest_pc, virtual_addr, acc_type, acc_type, priv_mode, true, hlvx)
+    }
+
+    // acc_type is checked against permissions, fault_acc selects the exception code.
+    fn translate_checked(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, fault_acc: MemAccType,
+                         priv_mode: PrivMode, virt: bool, hlvx: bool) -> Result<u64, MemResult> {
+        self.m_tlb_fill = TlbEntry::bare();
+        let phy_addr = if virt {
+            self.translate_guest_address(guest_pc, virtual_addr, acc_type, fault_acc, priv_mode, hlvx)?
+        } else {
+            self.translate_address(guest_pc, virtual_addr, acc_type, fault_acc, priv_mode)?
+        };
+
+        if !self.pmp_allowed(phy_addr, 1, acc_type, priv_mode) {
+            if self.m_arg_config.mmu_debug {
+                println!("<Access Fault : PMP denied PAddr = 0x{:016x}>", phy_addr);
+            }
+            self.raise_access_fault(guest_pc, virtual_addr, fault_acc, virt);
+            return Err(MemResult::AccessFault);
+        }
+        Ok(phy_addr)
+    }
+
+    // Privilege and virtualization mode of loads / stores, changed by mstatus.MPRV.
+    fn data_access_mode(&mut self) -> (PrivMode, bool) {
+        let mstatus: i64 = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let mprv = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MPRV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPRV_LSB);
+        if mprv == 0 {
+            return (self.m_priv, self.m_virt);
+        }
+        let mpp = PrivMode::from_u8(Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MPP_MSB, riscv_csr_def::SYSREG_MSTATUS_MPP_LSB) as u8);
+        let mpv = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MPV_MSB, riscv_csr_def::SYSREG_MSTATUS_MPV_LSB) != 0;
+        (mpp, mpv && mpp != PrivMode::Machine)
+    }
+
+    fn translate_address(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, fault_acc: MemAccType,
+                         priv_mode: PrivMode) -> Result<u64, MemResult> {
+        if priv_mode != PrivMode::Supervisor && priv_mode != PrivMode::User {
+            return Ok(virtual_addr);
+        }
+
+        let satp = self.m_csr.csrrs(CsrAddr::Satp, 0);
+        let vm_mode = VMMode::from(Self::extract_bit_field(satp, 63, 60));
+        if vm_mode == VMMode::Mbare {
+            return Ok(virtual_addr);
+        }
+        self.hpm_tlb_miss();
+        let mstatus: i64 = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let ctx = WalkCtx {
+            stage: WalkStage::Single,
+            root_ppn: Self::extract_bit_field(satp, 43, 0) as u64,
+            priv_mode: priv_mode,
+            sum: Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_SUM_MSB, riscv_csr_def::SYSREG_MSTATUS_SUM_LSB) != 0,
+            mxr: Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MXR_MSB, riscv_csr_def::SYSREG_MSTATUS_MXR_LSB) != 0,
+            hlvx: false,
+            virt: false,
+            gva: virtual_addr,
+            fault_acc: fault_acc,
+        };
+        let (phy_addr, pte_val, page_mask) = self.walk_vm_mode(guest_pc, virtual_addr, acc_type, vm_mode, &ctx)?;
+
+        self.m_tlb_fill = TlbEntry {
+            valid: true,
+            bare: false,
+            vaddr: virtual_addr & !0xfff,
+            page_mask: page_mask,
+            asid: self.current_asid(false),
+            perm: pte_val as u8,
+            pbmt: Self::pte_pbmt(pte_val),
+            pmp_perm: 0,            // Evaluated by update_tlb
+            pmp_machine: false,
+            virt: false,
+            vmid: 0,
+            g_perm: 0,
+        };
+        Ok(phy_addr)
+    }
+
+    // Two-stage translation : VS-stage (vsatp) gives guest physical address,
+    // and G-stage (hgatp) translates it and every VS-stage page table access to physical address.
+    fn translate_guest_address(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, fault_acc: MemAccType,
+                               priv_mode: PrivMode, hlvx: bool) -> Result<u64, MemResult> {
+        self.hpm_tlb_miss();
+        let mstatus: i64 = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let vsstatus: i64 = self.m_csr.csrrs(CsrAddr::Vsstatus, 0);
+        let vsatp = self.m_csr.csrrs(CsrAddr::Vsatp, 0);
+        let mxr = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MXR_MSB, riscv_csr_def::SYSREG_MSTATUS_MXR_LSB) != 0;
+
+        let vs_mode = VMMode::from(Self::extract_bit_field(vsatp, 63, 60));
+        let vs_bare = vs_mode == VMMode::Mbare;
+        // Bare stage is reported with page_mask = 0 : it does not limit the page size.
+        let (guest_phy_addr, vs_pte, vs_mask) = if vs_bare {
+            (virtual_addr, 0, 0)
+        } else {
+            let ctx = WalkCtx {
+                stage: WalkStage::VS,
+                root_ppn: Self::extract_bit_field(vsatp, 43, 0) as u64,
+                priv_mode: priv_mode,
+                sum: Self::extract_bit_field(vsstatus, riscv_csr_def::SYSREG_SSTATUS_SUM_MSB, riscv_csr_def::SYSREG_SSTATUS_SUM_LSB) != 0,
+                // MXR of either vsstatus or mstatus makes executable pages readable
+                mxr: mxr || Self::extract_bit_field(vsstatus, riscv_csr_def::SYSREG_SSTATUS_MXR_MSB, riscv_csr_def::SYSREG_SSTATUS_MXR_LSB) != 0,
+                hlvx: hlvx,
+                virt: true,
+                gva: virtual_addr,
+                fault_acc: fault_acc,
+            };
+            self.walk_vm_mode(guest_pc, virtual_addr, acc_type, vs_mode, &ctx)?
+        };
+        let (phy_addr, g_pte, g_mask) = self.translate_g_stage(guest_pc, virtual_addr, guest_phy_addr, acc_type, fault_acc, hlvx)?;
+
+        let vs_pbmt = Self::pte_pbmt(vs_pte);
+        self.m_tlb_fill = TlbEntry {
+            valid: true,
+            bare: vs_bare,
+            vaddr: virtual_addr & !0xfff,
+            page_mask: if (vs_mask | g_mask) == 0 { !0xfff } else { vs_mask | g_mask },
+            asid: self.current_asid(true),
+            perm: vs_pte as u8,
+            pbmt: if vs_pbmt != 0 { vs_pbmt } else { Self::pte_pbmt(g_pte) },
+            pmp_perm: 0,            // Evaluated by update_tlb
+            pmp_machine: false,
+            virt: true,
+            vmid: self.current_vmid(),
+            g_perm: g_pte as u8,
+        };
+        Ok(phy_addr)
+    }
+
+    // G-stage translation of guest physical address.
+    // Returns (physical address, G-stage PTE, page mask). Bare hgatp grants everything.
+    fn translate_g_stage(&mut self, guest_pc: u64, gva: u64, guest_phy_addr: u64, acc_type: MemAccType,
+                         fault_acc: MemAccType, hlvx: bool) -> Result<(u64, i64, u64), MemResult> {
+        let hgatp = self.m_csr.csrrs(CsrAddr::Hgatp, 0);
+        let g_mode = VMMode::from(Self::extract_bit_field(hgatp, riscv_csr_def::SYSREG_HGATP_MODE_MSB, riscv_csr_def::SYSREG_HGATP_MODE_LSB));
+        if g_mode == VMMode::Mbare {
+            return Ok((guest_phy_addr, (PTE_R | PTE_W | PTE_X | PTE_U | PTE_A | PTE_D) as i64, 0));
+        }
+        let mstatus: i64 = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let ctx = WalkCtx {
+            stage: WalkStage::G,
+            root_ppn: Self::extract_bit_field(hgatp, riscv_csr_def::SYSREG_HGATP_PPN_MSB, riscv_csr_def::SYSREG_HGATP_PPN_LSB) as u64,
+            priv_mode: PrivMode::User,
+            sum: false,
+            mxr: Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MXR_MSB, riscv_csr_def::SYSREG_MSTATUS_MXR_LSB) != 0,
+            hlvx: hlvx,
+            virt: true,
+            gva: gva,
+            fault_acc: fault_acc,
+        };
+        self.walk_vm_mode(guest_pc, guest_phy_addr, acc_type, g_mode, &ctx)
+    }
+
+    fn walk_vm_mode(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, vm_mode: VMMode,
+                    ctx: &WalkCtx) -> Result<(u64, i64, u64), MemResult> {
+        let (init_level, ppn_idx, pte_len, pte_idx, mut vpn_len, vpn_idx, ptesize): (u32, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, u32) =
+            match vm_mode {
+                VMMode::Sv32 => (2, vec![12, 22], vec![10, 12], vec![10, 20], vec![10, 10], vec![12, 22], 4),
+                VMMode::Sv39 => (3, vec![12, 21, 30], vec![9, 9, 26], vec![10, 19, 28], vec![9, 9, 9], vec![12, 21, 30], 8),
+                VMMode::Sv48 => (4, vec![12, 21, 30, 39], vec![9, 9, 9, 17], vec![10, 19, 28, 37], vec![9, 9, 9, 9], vec![12, 21, 30, 39], 8),
+                VMMode::Sv57 => (5, vec![12, 21, 30, 39, 48], vec![9, 9, 9, 9, 8], vec![10, 19, 28, 37, 46], vec![9, 9, 9, 9, 9], vec![12, 21, 30, 39, 48], 8),
+                _ => return Ok((virtual_addr, 0, 0)),
+            };
+        // Sv39x4 / Sv48x4 / Sv57x4 : root page table is indexed by 2 more bits.
+        if ctx.stage == WalkStage::G {
+            vpn_len[(init_level - 1) as usize] += 2;
+        }
+        let pagesize: u32 = 4096;
+        self.walk_page_table(guest_pc, virtual_addr, acc_type, ctx, init_level, ppn_idx, pte_len, pte_idx, vpn_len, vpn_idx, pagesize, ptesize)
+    }
+
+    fn pte_pbmt(pte_val: i64) -> u8 {
+        (((pte_val as u64) >> PTE_PBMT_SHIFT) & 0x3) as u8
+    }
+
+    fn current_asid(&mut self, virt: bool) -> u16 {
+        let satp = self.m_csr.csrrs(if virt { CsrAddr::Vsatp } else { CsrAddr::Satp }, 0);
+        Self::extract_bit_field(satp, 59, 44) as u16
+    }
+
+    fn current_vmid(&mut self) -> u16 {
+        let hgatp = self.m_csr.csrrs(CsrAddr::Hgatp, 0);
+        Self::extract_bit_field(hgatp, riscv_csr_def::SYSREG_HGATP_VMID_MSB, riscv_csr_def::SYSREG_HGATP_VMID_LSB) as u16
+    }
+
+    fn satp_mode(&mut self, virt: bool) -> VMMode {
+        let satp = self.m_csr.csrrs(if virt { CsrAddr::Vsatp } else { CsrAddr::Satp }, 0);
+        VMMode::from(Self::extract_bit_field(satp, 63, 60))
+    }
+
+    fn tlb_context(&mut self) -> TlbContext {
+        let mstatus: i64 = self.m_csr.csrrs(CsrAddr::Mstatus, 0);
+        let (priv_mode, virt) = self.data_access_mode();
+        let mxr = Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_MXR_MSB, riscv_csr_def::SYSREG_MSTATUS_MXR_LSB) != 0;
+        if virt {
+            let vsstatus: i64 = self.m_csr.csrrs(CsrAddr::Vsstatus, 0);
+            return TlbContext {
+                priv_mode: priv_mode,
+                bare: self.satp_mode(true) == VMMode::Mbare,
+                asid: self.current_asid(true),
+                sum: Self::extract_bit_field(vsstatus, riscv_csr_def::SYSREG_SSTATUS_SUM_MSB, riscv_csr_def::SYSREG_SSTATUS_SUM_LSB) != 0,
+                mxr: mxr || Self::extract_bit_field(vsstatus, riscv_csr_def::SYSREG_SSTATUS_MXR_MSB, riscv_csr_def::SYSREG_SSTATUS_MXR_LSB) != 0,
+                virt: true,
+                vmid: self.current_vmid(),
+                g_mxr: mxr,
+                trig_load: self.trigger_armed(MemAccType::Read, self.m_priv, self.m_virt),
+                trig_store: self.trigger_armed(MemAccType::Write, self.m_priv, self.m_virt),
+            };
+        }
+        TlbContext {
+            priv_mode: priv_mode,
+            bare: priv_mode == PrivMode::Machine || self.satp_mode(false) == VMMode::Mbare,
+            asid: self.current_asid(false),
+            sum: Self::extract_bit_field(mstatus, riscv_csr_def::SYSREG_MSTATUS_SUM_MSB, riscv_csr_def::SYSREG_MSTATUS_SUM_LSB) != 0,
+            mxr: mxr,
+            virt: false,
+            vmid: 0,
+            g_mxr: false,
+            trig_load: self.trigger_armed(MemAccType::Read, self.m_priv, self.m_virt),
+            trig_store: self.trigger_armed(MemAccType::Write, self.m_priv, self.m_virt),
+        }
+    }
+
+    fn set_tlb_tags(&mut self, idx: usize) {
+        let entry = self.m_tlb_entry_vec[idx];
+        let (readable, writable) = self.m_tlb_ctx.permits(&entry);
+        self.m_tlb_vec[idx]    = if readable { tlb_tag(entry.vaddr) } else { TLB_INVALID_TAG };
+        self.m_tlb_wr_vec[idx] = if writable { tlb_tag(entry.vaddr) } else { TLB_INVALID_TAG };
+    }
+
+    // Called whenever privilege, satp, mstatus, PMP or triggers may have been changed.
+    // Entries are kept, only the tags visible from JIT code are rebuilt.
+    // PMP update drops all the entries and translated blocks.
+    // Trigger update drops translated blocks, which have execute triggers built in.
+    pub fn check_tlb_context(&mut self) {
+        if self.m_csr.m_pmp_updated {
+            self.m_csr.m_pmp_updated = false;
+            self.flush_tlb_if(|_| true, |_, _, _| true);
+        }
+        if self.m_csr.m_trig_updated {
+            self.m_csr.m_trig_updated = false;
+            self.flush_tlb_if(|_| false, |_, _, _| true);
+        }
+        let ctx = self.tlb_context();
+        if ctx != self.m_tlb_ctx {
+            self.m_tlb_ctx = ctx;
+            for idx in 0..TLB_SIZE {
+                self.set_tlb_tags(idx);
+            }
+        }
+    }
+
+    // Register the last translation (convert_physical_address) to TLB.
+    pub fn update_tlb(&mut self, vaddr: u64, guest_phy_addr: u64) {
+        let idx = tlb_index(vaddr);
+        let mut entry = self.m_tlb_fill;
+        entry.vaddr = vaddr & !0xfff;
+        // Page partially covered by PMP entry always goes through the slow path.
+        entry.pmp_perm = self.m_csr.pmp_perm(guest_phy_addr & !0xfff, 0x1000, self.m_tlb_ctx.priv_mode);
+        entry.pmp_machine = self.m_tlb_ctx.priv_mode == PrivMode::Machine;
+        self.m_tlb_entry_vec[idx] = entry;
+        self.m_tlb_addr_vec[idx] = guest_phy_addr & !0xfff;
+        self.set_tlb_tags(idx);
+    }
+
+    // Drop TLB entries and translated blocks selected by the filters.
+    // tb_hit receives (context, start address, page mask) of the translated block.
+    fn flush_tlb_if<F, G>(&mut self, entry_hit: F, tb_hit: G)
+    where
+        F: Fn(&TlbEntry) -> bool,
+        G: Fn(u64, u64, u64) -> bool,
+    {
+        for idx in 0..TLB_SIZE {
+            let entry = self.m_tlb_entry_vec[idx];
+            if entry.valid && entry_hit(&entry) {
+                self.m_tlb_entry_vec[idx].valid = false;
+                self.m_tlb_vec[idx] = TLB_INVALID_TAG;
+                self.m_tlb_wr_vec[idx] = TLB_INVALID_TAG;
+            }
+        }
+
+        for key in 0..self.m_tb_text_hash_address.len() {
+            if tb_hit(self.m_tb_text_hash_ctx[key], self.m_tb_text_hash_address[key], self.m_tb_text_hash_page_mask[key]) {
+                self.m_tb_text_hash_address[key] = 0xdeadbeef;
+            }
+        }
+    }
+
+    // SFENCE.VMA : None operand means all addresses / all ASIDs.
+    // Global entries are not flushed by ASID, bare entries only by the full flush.
+    // Guest entries are only flushed by HFENCE.
+    pub fn sfence_vma(&mut self, vaddr: Option<u64>, asid: Option<u16>) {
+        self.fence_vma(false, 0, vaddr, asid);
+    }
+
+    // HFENCE.VVMA, and SFENCE.VMA in VS-mode : VS-stage entries of the current VMID.
+    pub fn hfence_vvma(&mut self, vaddr: Option<u64>, asid: Option<u16>) {
+        let vmid = self.current_vmid();
+        self.fence_vma(true, vmid, vaddr, asid);
+    }
+
+    fn fence_vma(&mut self, virt: bool, vmid: u16, vaddr: Option<u64>, asid: Option<u16>) {
+        let flush_all = vaddr.is_none() && asid.is_none();
+        self.flush_tlb_if(
+            |entry| {
+                entry.virt == virt && (!virt || entry.vmid == vmid) &&
+                (flush_all || (!entry.bare &&
+                    vaddr.map_or(true, |va| (va & entry.page_mask) == (entry.vaddr & entry.page_mask)) &&
+                    asid.map_or(true, |asid| !entry.is_global() && entry.asid == asid)))
+            },
+            |tb_ctx, tb_addr, mask| {
+                ((tb_ctx & TB_CTX_VIRT) != 0) == virt &&
+                (!virt || ((tb_ctx >> TB_CTX_VMID_SHIFT) & TB_CTX_VMID_MASK) == vmid as u64) &&
+                (flush_all || ((tb_ctx & TB_CTX_BARE) == 0 &&
+                    vaddr.map_or(true, |va| (va & mask) == (tb_addr & mask)) &&
+                    asid.map_or(true, |asid| (tb_ctx & TB_CTX_GLOBAL) == 0 && (tb_ctx & TB_CTX_ASID_MASK) == asid as u64)))
+            });
+    }
+
+    // HFENCE.GVMA : guest physical address is not kept in TLB,
+    // so all the guest entries of VMID (or all VMIDs) are flushed.
+    pub fn hfence_gvma(&mut self, vmid: Option<u16>) {
+        self.flush_tlb_if(
+            |entry| entry.virt && vmid.map_or(true, |vmid| entry.vmid == vmid),
+            |tb_ctx, _, _| {
+                (tb_ctx & TB_CTX_VIRT) != 0 &&
+                vmid.map_or(true, |vmid| ((tb_ctx >> TB_CTX_VMID_SHIFT) & TB_CTX_VMID_MASK) == vmid as u64)
+            });
+    }
+
+    // Guest code was modified from outside of the hart (e.g. software breakpoint by the debugger).
+    pub fn flush_translated_blocks(&mut self) {
+        self.flush_tlb_if(|_| false, |_, _, _| true);
+    }
+
+    // Translation blocks are tagged with fetch privilege and address space.
+    pub fn tb_context(&mut self, global: bool) -> u64 {
+        let mut ctx = (self.m_priv as u64) << TB_CTX_PRIV_SHIFT;
+        if self.m_virt {
+            ctx |= TB_CTX_VIRT | ((self.current_vmid() as u64) << TB_CTX_VMID_SHIFT);
+        }
+        if self.get_vm_mode() == VMMode::Mbare {
+            ctx |= TB_CTX_BARE;
+        } else {
+            ctx |= self.current_asid(self.m_virt) as u64;
+            if global {
+                ctx |= TB_CTX_GLOBAL;
+            }
+        }
+        ctx
+    }
+
+    pub fn tb_context_match(&mut self, tb_ctx: u64) -> bool {
+        let curr_ctx = self.tb_context(false);
+        if (tb_ctx ^ curr_ctx) & !(TB_CTX_ASID_MASK | TB_CTX_GLOBAL) != 0 {
+            return false;
+        }
+        (tb_ctx & (TB_CTX_BARE | TB_CTX_GLOBAL)) != 0 || (tb_ctx & TB_CTX_ASID_MASK) == (curr_ctx & TB_CTX_ASID_MASK)
+    }
+
+    // Raise page fault of the access type. virt : tval is a guest virtual address.
+    fn raise_page_fault(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, virt: bool) {
+        let code = match acc_type {
+            MemAccType::Fetch => ExceptCode::InstPageFault,
+            MemAccType::Read => ExceptCode::LoadPageFault,
+            MemAccType::Write => ExceptCode::StorePageFault,
+        };
+        self.m_excp_gva = virt;
+        self.generate_exception(guest_pc, code, virtual_addr as i64);
+    }
+
+    // Raise access fault of the access type. (PTE is not placed on main memory, or PMP violation)
+    fn raise_access_fault(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, virt: bool) {
+        let code = match acc_type {
+            MemAccType::Fetch => ExceptCode::InstAccessFault,
+            MemAccType::Read => ExceptCode::LoadAccessFault,
+            MemAccType::Write => ExceptCode::StoreAccessFault,
+        };
+        self.m_excp_gva = virt;
+        self.generate_exception(guest_pc, code, virtual_addr as i64);
+    }
+
+    // Raise guest page fault : G-stage translation of guest_phy_addr failed.
+    fn raise_guest_page_fault(&mut self, guest_pc: u64, virtual_addr: u64, guest_phy_addr: u64, acc_type: MemAccType) {
+        let code = match acc_type {
+            MemAccType::Fetch => ExceptCode::InstGuestPageFault,
+            MemAccType::Read => ExceptCode::LoadGuestPageFault,
+            MemAccType::Write => ExceptCode::StoreGuestPageFault,
+        };
+        self.m_excp_gva = true;
+        self.m_excp_tval2 = guest_phy_addr >> 2;
+        self.generate_exception(guest_pc, code, virtual_addr as i64);
+    }
+
+    // Page fault found while walking : G-stage reports guest page fault of the translated address.
+    fn raise_walk_page_fault(&mut self, guest_pc: u64, ctx: &WalkCtx, addr: u64) {
+        if ctx.stage == WalkStage::G {
+            self.raise_guest_page_fault(guest_pc, ctx.gva, addr, ctx.fault_acc);
+        } else {
+            self.raise_page_fault(guest_pc, ctx.gva, ctx.fault_acc, ctx.virt);
+        }
+    }
+
+    fn read_pte(&self, pte_addr: u64, ptesize: u32) -> i64 {
+        if ptesize == 8 {
+            self.read_mem_8byte(pte_addr) as i64
+        } else {
+            self.read_mem_4byte(pte_addr) as i64
+        }
+    }
+
+    fn write_pte(&self, pte_addr: u64, ptesize: u32, pte_val: i64) {
+        if ptesize == 8 {
+            self.write_mem_8byte(pte_addr, pte_val as u64);
+        } else {
+            self.write_mem_4byte(pte_addr, pte_val as u32);
+        }
+    }
+
+    // Returns (translated address, leaf PTE, page mask).
+    fn walk_page_table(&mut self, guest_pc: u64, virtual_addr: u64, acc_type: MemAccType, ctx: &WalkCtx, init_level: u32, 
+        ppn_idx: Vec<u8>, pte_len: Vec<u8>, pte_idx: Vec<u8>, vpn_len: Vec<u8>, vpn_idx: Vec<u8>, 
+        pagesize: u32, ptesize: u32) -> Result<(u64, i64, u64), MemResult> {
+        let is_write_access = match acc_type {
+            MemAccType::Write => true,
+            _ => false,
+        };
+
+        //===================
+        // Simple TLB Search
+        //===================
+        // let virtual_addr_vpn: u64 = (virtual_addr >> 12);
+        // let virtual_addr_tag: u8 = virtual_addr_vpn & (tlb_width-1);
+        // if (m_tlb_en[virtual_addr_tag] && m_tlb_tag[virtual_addr_tag] == virtual_addr_vpn) {
+        //     let paddr:u64 = (m_tlb_addr[virtual_addr_tag] & !0x0fff) + (virtual_addr & 0x0fff);
+        //     let pte_val:u64 = m_tlb_addr[virtual_addr_tag] & 0x0ff;
+        //
+        //     if (!is_allowed_access ((pte_val >> 1) & 0x0f, acc_type, self.m_priv)) {
+        //         if self.m_arg_config.mmu_debug { println! ("<Page Access Failed. Allowed Access Failed PTE_VAL=%016lx>", pte_val);
+        //         return Err(MemResult::TlbError);
+        //     }
+        //     if (((pte_val & 0x40) == 0) || // PTE.A
+        //         ((acc_type == MemAccType::Write) && (pte_val & 0x80) == 0)) { // PTE.D
+        //         if self.m_arg_config.mmu_debug { println!("<Access Fault : Page Permission Fault {:01x}>", (pte_val >> 1) & 0x0f);
+        //         if (acc_type == MemAccType::Fetch) {
+        //             generate_exception (self, ExceptCode::InstPageFault, virtual_addr as i64);
+        //         }
+        //         return Err(MemResult::TlbError);
+        //     }
+        //     return Ok(paddr);
+        // }
+
+        // Virtual address must be sign-extended from the top of VPN.
+        // Guest physical address must be zero-extended.
+        if ptesize == 8 {
+            let va_bits = vpn_idx[(init_level - 1) as usize] + vpn_len[(init_level - 1) as usize];
+            let upper = if ctx.stage == WalkStage::G {
+                (virtual_addr >> va_bits) as i64
+            } else {
+                (virtual_addr as i64) >> (va_bits - 1)
+            };
+            if upper != 0 && upper != -1 {
+                if self.m_arg_config.mmu_debug {
+                    println!("<Page Table Error : VAddr = 0x{:016x} is not canonical for {}-bit VA>", virtual_addr, va_bits);
+                }
+                self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                return Err(MemResult::TlbError);
+            }
+        }
+
+        let pte_base = ctx.root_ppn as i64;
+
+        let mut pte_val: i64 = 0;
+        let mut pte_addr: u64 = (pte_base * pagesize as i64) as u64;
+        let mut pte_phy_addr: u64 = pte_addr;
+        let mut level: usize = 0;
+
+        for curr_level in (0..init_level as usize).rev() {
+            level = curr_level;
+            let va_vpn_i: u64 =
+                (virtual_addr >> vpn_idx[level as usize]) & ((1 << vpn_len[level as usize]) - 1);
+            pte_addr += (va_vpn_i * (ptesize as u64)) as u64;
+
+            // VS-stage page table is placed on guest physical address.
+            pte_phy_addr = if ctx.stage == WalkStage::VS {
+                self.translate_g_stage(guest_pc, ctx.gva, pte_addr, MemAccType::Read, ctx.fault_acc, false)?.0
+            } else {
+                pte_addr
+            };
+
+            if !self.is_dram_addr(pte_phy_addr, ptesize as u64) {
+                if self.m_arg_config.mmu_debug {
+                    println!("<Access Fault : PTEAddr = 0x{:016x} is not on main memory>", pte_phy_addr);
+                }
+                self.raise_access_fault(guest_pc, ctx.gva, ctx.fault_acc, ctx.virt);
+                return Err(MemResult::AccessFault);
+            }
+            // Implicit access to page table is checked by PMP as S-mode.
+            if !self.pmp_allowed(pte_phy_addr, ptesize as u64, MemAccType::Read, PrivMode::Supervisor) {
+                if self.m_arg_config.mmu_debug {
+                    println!("<Access Fault : PMP denied PTEAddr = 0x{:016x}>", pte_phy_addr);
+                }
+                self.raise_access_fault(guest_pc, ctx.gva, ctx.fault_acc, ctx.virt);
+                return Err(MemResult::AccessFault);
+            }
+            pte_val = self.read_pte(pte_phy_addr, ptesize);
+
+            if self.m_arg_config.mmu_debug {
+                println!("<Info: VAddr = 0x{:016x} PTEAddr = 0x{:016x} : PPTE = 0x{:08x}>",virtual_addr, pte_addr, pte_val);
+            }
+
+            // 3. If pte:v = 0, or if pte:r = 0 and pte:w = 1, or if any bits or encodings
+            //    that are reserved for future standard use are set, stop and raise a page-fault exception.
+            let pte_reserved = ptesize == 8 && ((pte_val as u64) & PTE_RESERVED_MASK) != 0;
+            if (pte_val & 0x01) == 0 || (((pte_val & 0x02) == 0) && ((pte_val & 0x04) == 0x04)) || pte_reserved {
+                // let bit_length: u32 = m_bit_mode == RiscvBitMode_t::Bit32 ? 8 : 16;
+                if self.m_arg_config.mmu_debug { 
+                    println!("<Page Table Error : 0x{:016x} = 0x{:08x} is not valid Page Table. Generate Exception>",
+                         pte_addr, pte_val);
+                }
+                
+                self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                return Err(MemResult::TlbError);
+            }
+
+            // If pte:r = 1 or pte:x = 1, go to step 5. Otherwise, this PTE is a
+            // pointer to the next level of the page table. Let i = i − 1. If i < 0, stop and raise a page-fault
+            // exception. Otherwise, let a = pte:ppn × pagesize and go to step 2.
+            if ((pte_val & 0x08) == 0x08) || ((pte_val & 0x02) == 0x02) {
+                break;
+            } else {
+                // N and PBMT are reserved on non-leaf PTE
+                if ptesize == 8 && ((pte_val as u64) >> PTE_PBMT_SHIFT) != 0 {
+                    if self.m_arg_config.mmu_debug {
+                        println!("<Page Table Error : 0x{:016x} = 0x{:016x} non-leaf PTE has N/PBMT>", pte_addr, pte_val);
+                    }
+                    self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                    return Err(MemResult::TlbError);
+                }
+                if level == 0 {
+                    if self.m_arg_config.mmu_debug { println!(
+                        "<Access Fault : Tried to Access to Page {:01x}>",
+                        ((pte_val >> 1) & 0x0f)
+                    );
+                }
+                    self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                    return Err(MemResult::TlbError);
+                }
+            }
+            let pte_ppn: u64 = Self::extract_bit_field(
+                pte_val as i64,
+                pte_len[(init_level - 1) as usize] + pte_idx[(init_level - 1) as usize] - 1,
+                pte_idx[0],
+            ) as u64;
+            pte_addr = pte_ppn * (pagesize as u64);
+        }
+
+        let pbmt = if ptesize == 8 { Self::pte_pbmt(pte_val) } else { 0 };
+        if pbmt != 0 {
+            let menvcfg = self.m_csr.csrrs(CsrAddr::Menvcfg, 0);
+            let mut pbmte = Self::extract_bit_field(menvcfg, riscv_csr_def::SYSREG_MENVCFG_PBMTE_MSB, riscv_csr_def::SYSREG_MENVCFG_PBMTE_LSB);
+            // VS-stage is also controlled by henvcfg.PBMTE
+            if ctx.stage == WalkStage::VS {
+                let henvcfg = self.m_csr.csrrs(CsrAddr::Henvcfg, 0);
+                pbmte &= Self::extract_bit_field(henvcfg, riscv_csr_def::SYSREG_MENVCFG_PBMTE_MSB, riscv_csr_def::SYSREG_MENVCFG_PBMTE_LSB);
+            }
+            // PBMT = 3 is reserved
+            if pbmte == 0 || pbmt == 3 {
+                if self.m_arg_config.mmu_debug {
+                    println!("<Page Table Error : PBMT={} is not allowed PTE_VAL={:016x}>", pbmt, pte_val);
+                }
+                self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                return Err(MemResult::TlbError);
+            }
+        }
+
+        let napot = ptesize == 8 && ((pte_val as u64) >> PTE_N_SHIFT) != 0;
+        if napot {
+            let ppn_low = Self::extract_bit_field(pte_val, pte_idx[0] + 3, pte_idx[0]) as u64;
+            if !self.m_arg_config.svnapot || level != 0 || ppn_low != PTE_NAPOT_64K_PPN {
+                if self.m_arg_config.mmu_debug {
+                    println!("<Page Table Error : Unsupported NAPOT PTE at level {} PTE_VAL={:016x}>", level, pte_val);
+                }
+                self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                return Err(MemResult::TlbError);
+            }
+        }
+
+        if !self.is_allowed_access(
+            ((pte_val >> 1) & 0x0f) as u8,
+            acc_type.clone(),
+            ctx,
+        ) {
+            if self.m_arg_config.mmu_debug { 
+                println!("<Page Access Failed. Allowed Access Failed PTE_VAL={:016x}>",pte_val);
+            }
+            self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+            return Err(MemResult::TlbError);
+        }
+
+        if level != 0
+            && Self::extract_bit_field(
+                pte_val as i64,
+                pte_len[level - 1] + pte_idx[level - 1] - 1,
+                pte_idx[0],
+            ) != 0
+        {
+            // 6. If i > 0 and pa:ppn[i−1:0] != 0, this is a misaligned superpage
+            // stop and raise a page-fault exception.
+            if self.m_arg_config.mmu_debug {
+                println!("<Page Access Failed. Misaligned superpage at level {} PTE_VAL={:016x}>", level, pte_val);
+            }
+            self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+            return Err(MemResult::TlbError);
+        }
+
+        if ((pte_val & 0x40) == 0) || // PTE.A
+            (is_write_access && (pte_val & 0x80) == 0)
+        {
+            // PTE.D
+            if !self.m_arg_config.svadu {
+                if self.m_arg_config.mmu_debug { println!(
+                    "<Access Fault : Page Permission Fault {:01x}",
+                    ((pte_val >> 1) & 0x0f)
+                   );
+                }
+                self.raise_walk_page_fault(guest_pc, ctx, virtual_addr);
+                return Err(MemResult::TlbError);
+            }
+            // Svadu : set A (and D for store) bits by hardware
+            // Update of VS-stage PTE is a store to guest physical address.
+            if ctx.stage == WalkStage::VS {
+                pte_phy_addr = self.translate_g_stage(guest_pc, ctx.gva, pte_addr, MemAccType::Write, ctx.fault_acc, false)?.0;
+            }
+            if !self.pmp_allowed(pte_phy_addr, ptesize as u64, MemAccType::Write, PrivMode::Supervisor) {
+                if self.m_arg_config.mmu_debug {
+                    println!("<Access Fault : PMP denied PTE update PTEAddr = 0x{:016x}>", pte_phy_addr);
+                }
+                self.raise_access_fault(guest_pc, ctx.gva, ctx.fault_acc, ctx.virt);
+                return Err(MemResult::AccessFault);
+            }
+            pte_val |= 0x40;
+            if is_write_access {
+                pte_val |= 0x80;
+            }
+            self.write_pte(pte_phy_addr, ptesize, pte_val);
+            if self.m_arg_config.mmu_debug {
+                println!("<Info: Update A/D bits PTEAddr = 0x{:016x} : PPTE = 0x{:08x}>", pte_phy_addr, pte_val);
+            }
+        }
+
+        let page_mask = if napot { !0xffff } else { !((1u64 << ppn_idx[level]) - 1) };
+
+        let mut phy_addr: u64 = (Self::extract_bit_field(
+            pte_val as i64,
+            pte_len[(init_level - 1) as usize] + pte_idx[(init_level - 1) as usize] - 1,
+            pte_idx[level],
+        ) << ppn_idx[level]) as u64;
+
+        // Superpage : lower VPNs and page offset are taken from virtual address as is.
+        phy_addr |= Self::extract_bit_field(virtual_addr as i64, ppn_idx[level] - 1, 0) as u64;
+        // Svnapot : ppn[3:0] is replaced with vpn[0][3:0]
+        if napot {
+            phy_addr = (phy_addr & !0xffff) | (virtual_addr & 0xffff);
+        }
+        if self.m_arg_config.mmu_debug && pbmt != 0 {
+            println!("<Info: VAddr = 0x{:016x} memory type PBMT = {}>", virtual_addr, pbmt);
+        }
+
+        //==========================
+        // Update Simple TLB Search
+        //==========================
+        // if self.m_arg_config.mmu_debug { println!(
+        //     "<Info: TLB[{:d}] <= 0x{:016x}(0x{:016x})>",
+        //     virtual_addr as i64_tag,
+        //     virtual_addr as i64_vpn,
+        //     *paddr & !0x0fff
+        // );
+        // m_tlb_en  [virtual_addr_tag] = true;
+        // m_tlb_tag [virtual_addr_tag] = virtual_addr_vpn;
+        // m_tlb_addr[virtual_addr_tag] = (*paddr & !0x0fff) | (pte_val & 0x0ff);
+
+        // if self.m_arg_config.mmu_debug { println!("<Converted Virtual Address = {:08x}>", phy_addr);
+        return Ok((phy_addr, pte_val, page_mask));
+    }
+
+    fn is_allowed_access(&self, i_type: u8, acc_type: MemAccType, ctx: &WalkCtx) -> bool {
+        let is_user_page = (i_type & 0x08) != 0;
+        match ctx.priv_mode {
+            PrivMode::User => {
+                if !is_user_page {
+                    return false;
+                }
+            }
+            _ => {
+                // Supervisor can access U-page only when SUM = 1, and never execute it.
+                if is_user_page {
+                    match acc_type {
+                        MemAccType::Fetch => return false,
+                        _ => if !ctx.sum { return false; },
+                    }
+                }
+            }
+        }
+        let allowed_access = match acc_type {
+            MemAccType::Fetch => (i_type & 0x04) != 0,
+            MemAccType::Write => ((i_type & 0x01) != 0) && ((i_type & 0x02) != 0),
+            // HLVX reads executable pages regardless of read permission
+            MemAccType::Read if ctx.hlvx => (i_type & 0x04) != 0,
+            MemAccType::Read => ((i_type & 0x01) != 0) || (ctx.mxr && (i_type & 0x04) != 0),
+        };
+        return allowed_access;
+    }
+
+    fn get_vm_mode(&mut self) -> VMMode {
+        let satp_val = self.m_csr.csrrs(if self.m_virt { CsrAddr::Vsatp } else { CsrAddr::Satp }, 0); // SATP / VSATP
+        let mode = Self::extract_bit_field(satp_val, 63, 60);
+        return if self.m_priv == PrivMode::Machine {
+            VMMode::Mbare
+        } else {
+            let v_mode = VMMode::from(mode);
+            if v_mode == VMMode::Mbare || v_mode == VMMode::Sv32 ||
+                v_mode == VMMode::Sv39 || v_mode == VMMode::Sv48 || v_mode == VMMode::Sv57 || v_mode == VMMode::Sv64 {
+                return v_mode
+            } else {
+                panic!("Error: illegal VM Mode in SATP {:}", mode)
+            }
+        };
+    }
+}
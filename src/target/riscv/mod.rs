@@ -0,0 +1,21 @@
+#[macro_use]
+pub mod riscv;
+pub mod riscv_csr;
+pub mod riscv_csr_def;
+pub mod riscv_decoder;
+pub mod riscv_decoder_extra;
+pub mod riscv_inst_id;
+pub mod riscv_disassemble;
+pub mod mmu;
+pub mod pmp;
+pub mod trigger;
+pub mod debug;
+pub mod hpm;
+pub mod interrupt;
+mod translate_riscv_int;
+mod translate_riscv_priv;
+mod translate_riscv_fp;
+mod translate_riscv_c;
+mod translate_riscv_bitmanip;
+mod translate_riscv_vector;
+mod translate_riscv_crypto;
@@ -0,0 +1,181 @@
+use crate::emu_env::EmuEnv;
+use crate::target::riscv::mmu::MemAccType;
+use crate::target::riscv::riscv::PrivMode;
+use crate::target::riscv::riscv_csr::{CsrAddr, RiscvCsr};
+
+// Number of implemented PMP entries. pmpcfg / pmpaddr above this are read-only zero.
+pub const PMP_NUM: usize = 16;
+
+// pmpcfg bits
+pub const PMP_R: u8 = 1 << 0;
+pub const PMP_W: u8 = 1 << 1;
+pub const PMP_X: u8 = 1 << 2;
+const PMP_A_SHIFT: u8 = 3;
+const PMP_A_MASK: u8 = 3 << PMP_A_SHIFT;
+const PMP_L: u8 = 1 << 7;
+const PMP_CFG_MASK: u8 = PMP_L | PMP_A_MASK | PMP_X | PMP_W | PMP_R;
+
+// pmpaddr holds PA[55:2]
+const PMP_ADDR_MASK: u64 = (1 << 54) - 1;
+
+#[derive(PartialEq, Eq)]
+enum PmpMatch {
+    Off = 0,
+    Tor = 1,
+    Na4 = 2,
+    Napot = 3,
+}
+
+impl PmpMatch {
+    fn from_cfg(cfg: u8) -> PmpMatch {
+        match (cfg & PMP_A_MASK) >> PMP_A_SHIFT {
+            1 => PmpMatch::Tor,
+            2 => PmpMatch::Na4,
+            3 => PmpMatch::Napot,
+            _ => PmpMatch::Off,
+        }
+    }
+}
+
+impl CsrAddr {
+    // Returns (is pmpcfg, index) of PMP CSRs.
+    // For pmpcfg the index is the first entry held by the register.
+    fn pmp_index(self) -> Option<(bool, usize)> {
+        let addr = self as u64;
+        match addr {
+            0x3a0..=0x3af => Some((true, ((addr - 0x3a0) * 4) as usize)),
+            0x3b0..=0x3ef => Some((false, (addr - 0x3b0) as usize)),
+            _ => None,
+        }
+    }
+
+    pub fn is_pmp(self) -> bool {
+        self.pmp_index().is_some()
+    }
+}
+
+impl RiscvCsr<i64> {
+    pub fn pmp_cfg(&self, idx: usize) -> u8 {
+        (self.m_pmpcfg[idx / 8].m_csr >> ((idx % 8) * 8)) as u8
+    }
+
+    fn pmp_locked(&self, idx: usize) -> bool {
+        (self.pmp_cfg(idx) & PMP_L) != 0
+    }
+
+    pub fn pmp_read(&self, addr: CsrAddr) -> i64 {
+        match addr.pmp_index() {
+            Some((true, idx)) if idx < PMP_NUM => self.m_pmpcfg[idx / 8].m_csr,
+            Some((false, idx)) if idx < PMP_NUM => self.m_pmpaddr[idx].m_csr,
+            _ => 0,
+        }
+    }
+
+    // Locked entries ignore writes, and so does the pmpaddr used as the bottom of a locked TOR entry.
+    pub fn pmp_write(&mut self, addr: CsrAddr, data: i64) {
+        match addr.pmp_index() {
+            Some((true, base)) if base < PMP_NUM => {
+                let mut cfg_val = self.m_pmpcfg[base / 8].m_csr;
+                for idx in base..base + 8 {
+                    if self.pmp_locked(idx) {
+                        continue;
+                    }
+                    let mut cfg = (data >> ((idx % 8) * 8)) as u8 & PMP_CFG_MASK;
+                    // R=0 / W=1 is reserved
+                    if (cfg & (PMP_R | PMP_W)) == PMP_W {
+                        cfg &= !PMP_W;
+                    }
+                    cfg_val &= !(0xff << ((idx % 8) * 8));
+                    cfg_val |= (cfg as i64) << ((idx % 8) * 8);
+                }
+                if cfg_val != self.m_pmpcfg[base / 8].m_csr {
+                    self.m_pmpcfg[base / 8].m_csr = cfg_val;
+                    self.m_pmp_updated = true;
+                }
+            }
+            Some((false, idx)) if idx < PMP_NUM => {
+                if self.pmp_locked(idx) {
+                    return;
+                }
+                if idx + 1 < PMP_NUM
+                    && self.pmp_locked(idx + 1)
+                    && PmpMatch::from_cfg(self.pmp_cfg(idx + 1)) == PmpMatch::Tor
+                {
+                    return;
+                }
+                let addr_val = data & PMP_ADDR_MASK as i64;
+                if addr_val != self.m_pmpaddr[idx].m_csr {
+                    self.m_pmpaddr[idx].m_csr = addr_val;
+                    self.m_pmp_updated = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Physical address range [start, end) of the entry, None when disabled.
+    fn pmp_range(&self, idx: usize) -> Option<(u64, u64)> {
+        let pmpaddr = self.m_pmpaddr[idx].m_csr as u64;
+        match PmpMatch::from_cfg(self.pmp_cfg(idx)) {
+            PmpMatch::Off => None,
+            PmpMatch::Tor => {
+                let start = if idx == 0 { 0 } else { (self.m_pmpaddr[idx - 1].m_csr as u64) << 2 };
+                Some((start, pmpaddr << 2))
+            }
+            PmpMatch::Na4 => Some((pmpaddr << 2, (pmpaddr << 2) + 4)),
+            PmpMatch::Napot => {
+                // pmpaddr = y..y01..1 : region size is 2^(trailing ones + 3)
+                let size_bits = pmpaddr.trailing_ones() + 3;
+                let size = 1u64 << size_bits;
+                let start = (pmpaddr << 2) & !(size - 1);
+                Some((start, start.wrapping_add(size)))
+            }
+        }
+    }
+
+    fn pmp_active(&self) -> bool {
+        (0..PMP_NUM).any(|idx| PmpMatch::from_cfg(self.pmp_cfg(idx)) != PmpMatch::Off)
+    }
+
+    // Permission (PMP_R / PMP_W / PMP_X) granted to the whole range [paddr, paddr + size).
+    // Range partially matched by the highest priority entry gets no permission.
+    pub fn pmp_perm(&self, paddr: u64, size: u64, priv_mode: PrivMode) -> u8 {
+        let last = paddr + size - 1;
+        for idx in 0..PMP_NUM {
+            let (start, end) = match self.pmp_range(idx) {
+                Some(range) => range,
+                None => continue,
+            };
+            if end <= start || last < start || paddr >= end {
+                continue;
+            }
+            if paddr < start || last >= end {
+                return 0;
+            }
+            let cfg = self.pmp_cfg(idx);
+            // M-mode is only restricted by locked entries
+            if priv_mode == PrivMode::Machine && (cfg & PMP_L) == 0 {
+                return PMP_R | PMP_W | PMP_X;
+            }
+            return cfg & (PMP_R | PMP_W | PMP_X);
+        }
+        // No match : M-mode succeeds. S/U-mode fails once PMP is programmed.
+        if priv_mode == PrivMode::Machine || !self.pmp_active() {
+            PMP_R | PMP_W | PMP_X
+        } else {
+            0
+        }
+    }
+}
+
+impl EmuEnv {
+    pub fn pmp_allowed(&self, paddr: u64, size: u64, acc_type: MemAccType, priv_mode: PrivMode) -> bool {
+        let perm = self.m_csr.pmp_perm(paddr, size, priv_mode);
+        let required = match acc_type {
+            MemAccType::Fetch => PMP_X,
+            MemAccType::Read => PMP_R,
+            MemAccType::Write => PMP_W,
+        };
+        (perm & required) != 0
+    }
+}
@@ -0,0 +1,1205 @@
+use crate::device::entropy::EntropySource;
+use crate::target::riscv::hpm::HPM_NUM;
+use crate::target::riscv::pmp::PMP_NUM;
+use crate::target::riscv::riscv::PrivMode;
+use crate::target::riscv::trigger::{TDATA1_DISABLED, TRIG_NUM};
+
+pub struct RiscvCsrBase<W> {
+    pub m_csr: W,
+}
+
+impl RiscvCsrBase<i64> {
+    pub fn new() -> RiscvCsrBase<i64> {
+        RiscvCsrBase { m_csr: 0x0 }
+    }
+
+    // WARL : bits out of wmask keep their value.
+    fn write_masked(&mut self, data: i64, wmask: i64) {
+        self.m_csr = (self.m_csr & !wmask) | (data & wmask);
+    }
+}
+
+// Access descriptor of a CSR. Bits out of rmask read as zero and bits out of wmask ignore writes.
+// CSRs with csr[11:10] = 3 are read-only regardless of wmask.
+#[derive(Clone, Copy)]
+pub struct CsrDesc {
+    pub priv_mode: PrivMode,    // Lowest privilege mode allowed to access
+    pub rmask: i64,
+    pub wmask: i64,
+}
+
+const ALL: i64 = -1;
+
+// mstatus : SIE MIE SPIE MPIE SPP VS MPP FS MPRV SUM MXR TVM TW TSR, and GVA MPV with H extension
+const MSTATUS_WMASK: i64 = 0x7e_7faa;
+const MSTATUS_H_WMASK: i64 = 0xc0_0000_0000;
+// sstatus : SIE SPIE SPP VS FS SUM MXR are writable, XS UXL SD are read-only
+const SSTATUS_WMASK: i64 = 0xc_6722;
+const SSTATUS_RMASK: i64 = SSTATUS_WMASK | 0x1_8000 | 0x3_0000_0000 | (1 << 63);
+const MSTATUS_MPP_MASK: i64 = 0x1800;
+const MSTATUS_FS_MASK: i64 = 0x6000;
+const MSTATUS_VS_MASK: i64 = 0x600;
+const MSTATUS_XS_MASK: i64 = 0x1_8000;
+const MSTATUS_SD: i64 = 1 << 63;
+// UXL = SXL = VSXL = 2 : XLEN 64
+const MSTATUS_RESET: i64 = 0xa_0000_0000;
+const SSTATUS_RESET: i64 = 0x2_0000_0000;
+const HSTATUS_RESET: i64 = 0x2_0000_0000;
+// hstatus : GVA SPV SPVP HU VTVM VTW VTSR
+const HSTATUS_WMASK: i64 = 0x70_03c0;
+
+// Interrupt bits of S-mode (SSI STI SEI LCOFI), M-mode (MSI MTI MEI) and VS-mode (VSSI VSTI VSEI SGEI)
+const IRQ_S_MASK: i64 = 0x2222;
+const IRQ_M_MASK: i64 = 0x0888;
+const IRQ_VS_MASK: i64 = 0x1444;
+// Pending bits set by software. Others follow the interrupt sources.
+const MIP_WMASK: i64 = 0x2222;
+const SIP_WMASK: i64 = 0x2002;
+const HIP_WMASK: i64 = 0x0004;
+const HVIP_WMASK: i64 = 0x0444;
+
+// Exceptions which can be delegated : all but ECALL from M-mode, and ECALL from VS-mode / guest faults with H extension
+const MEDELEG_WMASK: i64 = 0xb3ff;
+const MEDELEG_H_WMASK: i64 = 0xf0_0400;
+const HEDELEG_WMASK: i64 = 0xb1ff;
+
+const FFLAGS_MASK: i64 = 0x1f;
+const FRM_MASK: i64 = 0x7;
+const FCSR_MASK: i64 = 0xff;
+const VXSAT_MASK: i64 = 0x1;
+const VXRM_MASK: i64 = 0x3;
+const VCSR_MASK: i64 = 0x7;
+// vtype.vill : the last vsetvl{i} requested an unsupported configuration
+pub const VTYPE_VILL: i64 = 1 << 63;
+
+// xtvec.MODE >= 2 is reserved, xepc[0] is always zero
+const TVEC_WMASK: i64 = !0x2;
+// xtvec.MODE = 3 selects the CLIC-style vector table in xtvt
+pub const TVEC_MODE_DIRECT: i64 = 0;
+pub const TVEC_MODE_VECTORED: i64 = 1;
+pub const TVEC_MODE_TABLE: i64 = 3;
+// xtvt holds XLEN-bit handler addresses and is 64-byte aligned
+const TVT_WMASK: i64 = !0x3f;
+const EPC_WMASK: i64 = !0x1;
+// mseccfg : SSEED USEED
+const MSECCFG_WMASK: i64 = 0x300;
+// xenvcfg : CBIE CBCFE CBZE of Zicbom / Zicboz
+pub const ENVCFG_CBO_WMASK: i64 = 0xf0;
+const ENVCFG_CBIE_MASK: i64 = 0x30;
+// seed.OPST : ES16 returns 16 bits of entropy, DEAD is an unrecoverable self-test error
+const SEED_OPST_ES16: i64 = 0b10 << 30;
+const SEED_OPST_DEAD: i64 = 0b11 << 30;
+
+impl CsrAddr {
+    pub fn from_u64(n: u64) -> CsrAddr {
+        match n {
+            0x001 => CsrAddr::FFlags,
+            0x002 => CsrAddr::Frm,
+            0x003 => CsrAddr::Fcsr,
+            0x008 => CsrAddr::Vstart,
+            0x009 => CsrAddr::Vxsat,
+            0x00a => CsrAddr::Vxrm,
+            0x00f => CsrAddr::Vcsr,
+            0x015 => CsrAddr::Seed,
+            0xc20 => CsrAddr::Vl,
+            0xc21 => CsrAddr::Vtype,
+            0xc22 => CsrAddr::Vlenb,
+            0xc00 => CsrAddr::Cycle,
+            0xc01 => CsrAddr::Time,
+            0xc02 => CsrAddr::Instret,
+            0xc03 => CsrAddr::Hpmcounter3,
+            0xc04 => CsrAddr::Hpmcounter4,
+            0xc05 => CsrAddr::Hpmcounter5,
+            0xc06 => CsrAddr::Hpmcounter6,
+            0xc07 => CsrAddr::Hpmcounter7,
+            0xc08 => CsrAddr::Hpmcounter8,
+            0xc09 => CsrAddr::Hpmcounter9,
+            0xc0a => CsrAddr::Hpmcounter10,
+            0xc0b => CsrAddr::Hpmcounter11,
+            0xc0c => CsrAddr::Hpmcounter12,
+            0xc0d => CsrAddr::Hpmcounter13,
+            0xc0e => CsrAddr::Hpmcounter14,
+            0xc0f => CsrAddr::Hpmcounter15,
+            0xc10 => CsrAddr::Hpmcounter16,
+            0xc11 => CsrAddr::Hpmcounter17,
+            0xc12 => CsrAddr::Hpmcounter18,
+            0xc13 => CsrAddr::Hpmcounter19,
+            0xc14 => CsrAddr::Hpmcounter20,
+            0xc15 => CsrAddr::Hpmcounter21,
+            0xc16 => CsrAddr::Hpmcounter22,
+            0xc17 => CsrAddr::Hpmcounter23,
+            0xc18 => CsrAddr::Hpmcounter24,
+            0xc19 => CsrAddr::Hpmcounter25,
+            0xc1a => CsrAddr::Hpmcounter26,
+            0xc1b => CsrAddr::Hpmcounter27,
+            0xc1c => CsrAddr::Hpmcounter28,
+            0xc1d => CsrAddr::Hpmcounter29,
+            0xc1e => CsrAddr::Hpmcounter30,
+            0xc1f => CsrAddr::Hpmcounter31,
+            0x300 => CsrAddr::Mstatus,
+            0x301 => CsrAddr::Misa,
+            0x302 => CsrAddr::Medeleg,
+            0x303 => CsrAddr::Mideleg,
+            0x304 => CsrAddr::Mie,
+            0x305 => CsrAddr::Mtvec,
+            0x340 => CsrAddr::Mscratch,
+            0x306 => CsrAddr::Mcounteren,
+            0x307 => CsrAddr::Mtvt,
+            0x341 => CsrAddr::Mepc,
+            0x342 => CsrAddr::Mcause,
+            0x343 => CsrAddr::Mtval,
+            0x344 => CsrAddr::Mip,
+            0x30a => CsrAddr::Menvcfg,
+            0x747 => CsrAddr::Mseccfg,
+            0x3a0 => CsrAddr::Pmpcfg0,
+            0x3a2 => CsrAddr::Pmpcfg2,
+            0x3a4 => CsrAddr::Pmpcfg4,
+            0x3a6 => CsrAddr::Pmpcfg6,
+            0x3a8 => CsrAddr::Pmpcfg8,
+            0x3aa => CsrAddr::Pmpcfg10,
+            0x3ac => CsrAddr::Pmpcfg12,
+            0x3ae => CsrAddr::Pmpcfg14,
+            0x3b0 => CsrAddr::Pmpaddr0,
+            0x3b1 => CsrAddr::Pmpaddr1,
+            0x3b2 => CsrAddr::Pmpaddr2,
+            0x3b3 => CsrAddr::Pmpaddr3,
+            0x3b4 => CsrAddr::Pmpaddr4,
+            0x3b5 => CsrAddr::Pmpaddr5,
+            0x3b6 => CsrAddr::Pmpaddr6,
+            0x3b7 => CsrAddr::Pmpaddr7,
+            0x3b8 => CsrAddr::Pmpaddr8,
+            0x3b9 => CsrAddr::Pmpaddr9,
+            0x3ba => CsrAddr::Pmpaddr10,
+            0x3bb => CsrAddr::Pmpaddr11,
+            0x3bc => CsrAddr::Pmpaddr12,
+            0x3bd => CsrAddr::Pmpaddr13,
+            0x3be => CsrAddr::Pmpaddr14,
+            0x3bf => CsrAddr::Pmpaddr15,
+            0x3c0 => CsrAddr::Pmpaddr16,
+            0x3c1 => CsrAddr::Pmpaddr17,
+            0x3c2 => CsrAddr::Pmpaddr18,
+            0x3c3 => CsrAddr::Pmpaddr19,
+            0x3c4 => CsrAddr::Pmpaddr20,
+            0x3c5 => CsrAddr::Pmpaddr21,
+            0x3c6 => CsrAddr::Pmpaddr22,
+            0x3c7 => CsrAddr::Pmpaddr23,
+            0x3c8 => CsrAddr::Pmpaddr24,
+            0x3c9 => CsrAddr::Pmpaddr25,
+            0x3ca => CsrAddr::Pmpaddr26,
+            0x3cb => CsrAddr::Pmpaddr27,
+            0x3cc => CsrAddr::Pmpaddr28,
+            0x3cd => CsrAddr::Pmpaddr29,
+            0x3ce => CsrAddr::Pmpaddr30,
+            0x3cf => CsrAddr::Pmpaddr31,
+            0x3d0 => CsrAddr::Pmpaddr32,
+            0x3d1 => CsrAddr::Pmpaddr33,
+            0x3d2 => CsrAddr::Pmpaddr34,
+            0x3d3 => CsrAddr::Pmpaddr35,
+            0x3d4 => CsrAddr::Pmpaddr36,
+            0x3d5 => CsrAddr::Pmpaddr37,
+            0x3d6 => CsrAddr::Pmpaddr38,
+            0x3d7 => CsrAddr::Pmpaddr39,
+            0x3d8 => CsrAddr::Pmpaddr40,
+            0x3d9 => CsrAddr::Pmpaddr41,
+            0x3da => CsrAddr::Pmpaddr42,
+            0x3db => CsrAddr::Pmpaddr43,
+            0x3dc => CsrAddr::Pmpaddr44,
+            0x3dd => CsrAddr::Pmpaddr45,
+            0x3de => CsrAddr::Pmpaddr46,
+            0x3df => CsrAddr::Pmpaddr47,
+            0x3e0 => CsrAddr::Pmpaddr48,
+            0x3e1 => CsrAddr::Pmpaddr49,
+            0x3e2 => CsrAddr::Pmpaddr50,
+            0x3e3 => CsrAddr::Pmpaddr51,
+            0x3e4 => CsrAddr::Pmpaddr52,
+            0x3e5 => CsrAddr::Pmpaddr53,
+            0x3e6 => CsrAddr::Pmpaddr54,
+            0x3e7 => CsrAddr::Pmpaddr55,
+            0x3e8 => CsrAddr::Pmpaddr56,
+            0x3e9 => CsrAddr::Pmpaddr57,
+            0x3ea => CsrAddr::Pmpaddr58,
+            0x3eb => CsrAddr::Pmpaddr59,
+            0x3ec => CsrAddr::Pmpaddr60,
+            0x3ed => CsrAddr::Pmpaddr61,
+            0x3ee => CsrAddr::Pmpaddr62,
+            0x3ef => CsrAddr::Pmpaddr63,
+            0x7a0 => CsrAddr::Tselect,
+            0x7a1 => CsrAddr::Tdata1,
+            0x7a2 => CsrAddr::Tdata2,
+            0x7a3 => CsrAddr::Tdata3,
+            0x7a4 => CsrAddr::Tinfo,
+            0x7a5 => CsrAddr::Tcontrol,
+            0x7b0 => CsrAddr::Dcsr,
+            0x7b1 => CsrAddr::Dpc,
+            0x7b2 => CsrAddr::Dscratch,
+            0xb00 => CsrAddr::Mcycle,
+            0xb02 => CsrAddr::Minstret,
+            0xb03 => CsrAddr::Mhpmcounter3,
+            0xb04 => CsrAddr::Mhpmcounter4,
+            0xb05 => CsrAddr::Mhpmcounter5,
+            0xb06 => CsrAddr::Mhpmcounter6,
+            0xb07 => CsrAddr::Mhpmcounter7,
+            0xb08 => CsrAddr::Mhpmcounter8,
+            0xb09 => CsrAddr::Mhpmcounter9,
+            0xb0a => CsrAddr::Mhpmcounter10,
+            0xb0b => CsrAddr::Mhpmcounter11,
+            0xb0c => CsrAddr::Mhpmcounter12,
+            0xb0d => CsrAddr::Mhpmcounter13,
+            0xb0e => CsrAddr::Mhpmcounter14,
+            0xb0f => CsrAddr::Mhpmcounter15,
+            0xb10 => CsrAddr::Mhpmcounter16,
+            0xb11 => CsrAddr::Mhpmcounter17,
+            0xb12 => CsrAddr::Mhpmcounter18,
+            0xb13 => CsrAddr::Mhpmcounter19,
+            0xb14 => CsrAddr::Mhpmcounter20,
+            0xb15 => CsrAddr::Mhpmcounter21,
+            0xb16 => CsrAddr::Mhpmcounter22,
+            0xb17 => CsrAddr::Mhpmcounter23,
+            0xb18 => CsrAddr::Mhpmcounter24,
+            0xb19 => CsrAddr::Mhpmcounter25,
+            0xb1a => CsrAddr::Mhpmcounter26,
+            0xb1b => CsrAddr::Mhpmcounter27,
+            0xb1c => CsrAddr::Mhpmcounter28,
+            0xb1d => CsrAddr::Mhpmcounter29,
+            0xb1e => CsrAddr::Mhpmcounter30,
+            0xb1f => CsrAddr::Mhpmcounter31,
+            0x320 => CsrAddr::Mcountinhibit,
+            0x323 => CsrAddr::Mhpmevent3,
+            0x324 => CsrAddr::Mhpmevent4,
+            0x325 => CsrAddr::Mhpmevent5,
+            0x326 => CsrAddr::Mhpmevent6,
+            0x327 => CsrAddr::Mhpmevent7,
+            0x328 => CsrAddr::Mhpmevent8,
+            0x329 => CsrAddr::Mhpmevent9,
+            0x32a => CsrAddr::Mhpmevent10,
+            0x32b => CsrAddr::Mhpmevent11,
+            0x32c => CsrAddr::Mhpmevent12,
+            0x32d => CsrAddr::Mhpmevent13,
+            0x32e => CsrAddr::Mhpmevent14,
+            0x32f => CsrAddr::Mhpmevent15,
+            0x330 => CsrAddr::Mhpmevent16,
+            0x331 => CsrAddr::Mhpmevent17,
+            0x332 => CsrAddr::Mhpmevent18,
+            0x333 => CsrAddr::Mhpmevent19,
+            0x334 => CsrAddr::Mhpmevent20,
+            0x335 => CsrAddr::Mhpmevent21,
+            0x336 => CsrAddr::Mhpmevent22,
+            0x337 => CsrAddr::Mhpmevent23,
+            0x338 => CsrAddr::Mhpmevent24,
+            0x339 => CsrAddr::Mhpmevent25,
+            0x33a => CsrAddr::Mhpmevent26,
+            0x33b => CsrAddr::Mhpmevent27,
+            0x33c => CsrAddr::Mhpmevent28,
+            0x33d => CsrAddr::Mhpmevent29,
+            0x33e => CsrAddr::Mhpmevent30,
+            0x33f => CsrAddr::Mhpmevent31,
+            0xf11 => CsrAddr::Mvendorid,
+            0xf12 => CsrAddr::Marchid,
+            0xf13 => CsrAddr::Mimpid,
+            0xf14 => CsrAddr::Mhartid,
+            // 0xc80 => CsrAddr::Cycleh       ,
+            // 0xc82 => CsrAddr::Instreth     ,
+            // 0xc83 => CsrAddr::Hpmcounter3h ,
+            // 0xc84 => CsrAddr::Hpmcounter4h ,
+            // 0xc85 => CsrAddr::Hpmcounter5h ,
+            // 0xc86 => CsrAddr::Hpmcounter6h ,
+            // 0xc87 => CsrAddr::Hpmcounter7h ,
+            // 0xc88 => CsrAddr::Hpmcounter8h ,
+            // 0xc89 => CsrAddr::Hpmcounter9h ,
+            // 0xc8a => CsrAddr::Hpmcounter10h,
+            // 0xc8b => CsrAddr::Hpmcounter11h,
+            // 0xc8c => CsrAddr::Hpmcounter12h,
+            // 0xc8d => CsrAddr::Hpmcounter13h,
+            // 0xc8e => CsrAddr::Hpmcounter14h,
+            // 0xc8f => CsrAddr::Hpmcounter15h,
+            // 0xc90 => CsrAddr::Hpmcounter16h,
+            // 0xc91 => CsrAddr::Hpmcounter17h,
+            // 0xc92 => CsrAddr::Hpmcounter18h,
+            // 0xc93 => CsrAddr::Hpmcounter19h,
+            // 0xc94 => CsrAddr::Hpmcounter20h,
+            // 0xc95 => CsrAddr::Hpmcounter21h,
+            // 0xc96 => CsrAddr::Hpmcounter22h,
+            // 0xc97 => CsrAddr::Hpmcounter23h,
+            // 0xc98 => CsrAddr::Hpmcounter24h,
+            // 0xc99 => CsrAddr::Hpmcounter25h,
+            // 0xc9a => CsrAddr::Hpmcounter26h,
+            // 0xc9b => CsrAddr::Hpmcounter27h,
+            // 0xc9c => CsrAddr::Hpmcounter28h,
+            // 0xc9d => CsrAddr::Hpmcounter29h,
+            // 0xc9e => CsrAddr::Hpmcounter30h,
+            // 0xc9f => CsrAddr::Hpmcounter31h,
+            // 0xb80 => CsrAddr::Mcycleh      ,
+            // 0xb82 => CsrAddr::Minstreth    ,
+            // 0xb83 => CsrAddr::Mhpmcounter3h,
+            // 0xb84 => CsrAddr::Mhpmcounter4h,
+            // 0xb85 => CsrAddr::Mhpmcounter5h,
+            // 0xb86 => CsrAddr::Mhpmcounter6h,
+            // 0xb87 => CsrAddr::Mhpmcounter7h,
+            // 0xb88 => CsrAddr::Mhpmcounter8h,
+            // 0xb89 => CsrAddr::Mhpmcounter9h,
+            // 0xb8a => CsrAddr::Mhpmcounter10h,
+            // 0xb8b => CsrAddr::Mhpmcounter11h,
+            // 0xb8c => CsrAddr::Mhpmcounter12h,
+            // 0xb8d => CsrAddr::Mhpmcounter13h,
+            // 0xb8e => CsrAddr::Mhpmcounter14h,
+            // 0xb8f => CsrAddr::Mhpmcounter15h,
+            // 0xb90 => CsrAddr::Mhpmcounter16h,
+            // 0xb91 => CsrAddr::Mhpmcounter17h,
+            // 0xb92 => CsrAddr::Mhpmcounter18h,
+            // 0xb93 => CsrAddr::Mhpmcounter19h,
+            // 0xb94 => CsrAddr::Mhpmcounter20h,
+            // 0xb95 => CsrAddr::Mhpmcounter21h,
+            // 0xb96 => CsrAddr::Mhpmcounter22h,
+            // 0xb97 => CsrAddr::Mhpmcounter23h,
+            // 0xb98 => CsrAddr::Mhpmcounter24h,
+            // 0xb99 => CsrAddr::Mhpmcounter25h,
+            // 0xb9a => CsrAddr::Mhpmcounter26h,
+            // 0xb9b => CsrAddr::Mhpmcounter27h,
+            // 0xb9c => CsrAddr::Mhpmcounter28h,
+            // 0xb9d => CsrAddr::Mhpmcounter29h,
+            // 0xb9e => CsrAddr::Mhpmcounter30h,
+            // 0xb9f => CsrAddr::Mhpmcounter31h,
+            0x100 => CsrAddr::Sstatus,
+            0x104 => CsrAddr::Sie,
+            0x105 => CsrAddr::Stvec,
+            0x106 => CsrAddr::Scounteren,
+            0x10a => CsrAddr::Senvcfg,
+            0x107 => CsrAddr::Stvt,
+            0x140 => CsrAddr::Sscratch,
+            0x141 => CsrAddr::Sepc,
+            0x142 => CsrAddr::Scause,
+            0x143 => CsrAddr::Stval,
+            0x144 => CsrAddr::Sip,
+            0x180 => CsrAddr::Satp,
+
+            0x34a => CsrAddr::Mtinst,
+            0x34b => CsrAddr::Mtval2,
+            0x600 => CsrAddr::Hstatus,
+            0x602 => CsrAddr::Hedeleg,
+            0x603 => CsrAddr::Hideleg,
+            0x604 => CsrAddr::Hie,
+            0x605 => CsrAddr::Htimedelta,
+            0x606 => CsrAddr::Hcounteren,
+            0x607 => CsrAddr::Hgeie,
+            0x60a => CsrAddr::Henvcfg,
+            0x643 => CsrAddr::Htval,
+            0x644 => CsrAddr::Hip,
+            0x645 => CsrAddr::Hvip,
+            0x64a => CsrAddr::Htinst,
+            0x680 => CsrAddr::Hgatp,
+            0xe12 => CsrAddr::Hgeip,
+            0xda0 => CsrAddr::Scountovf,
+            0x200 => CsrAddr::Vsstatus,
+            0x204 => CsrAddr::Vsie,
+            0x205 => CsrAddr::Vstvec,
+            0x240 => CsrAddr::Vsscratch,
+            0x241 => CsrAddr::Vsepc,
+            0x242 => CsrAddr::Vscause,
+            0x243 => CsrAddr::Vstval,
+            0x244 => CsrAddr::Vsip,
+            0x280 => CsrAddr::Vsatp,
+            _ => CsrAddr::None,
+        }
+    }
+}
+
+pub struct RiscvCsr<W> {
+    pub m_fflags: RiscvCsrBase<W>,
+    pub m_frm: RiscvCsrBase<W>,
+    pub m_vstart: RiscvCsrBase<W>,
+    pub m_vxsat: RiscvCsrBase<W>,
+    pub m_vxrm: RiscvCsrBase<W>,
+    pub m_vl: RiscvCsrBase<W>,
+    pub m_vtype: RiscvCsrBase<W>,
+    pub m_vlenb: RiscvCsrBase<W>,   // VLEN / 8, set from the configuration
+    pub m_mcycle: RiscvCsrBase<W>,
+    pub m_minstret: RiscvCsrBase<W>,
+    pub m_mimpid: RiscvCsrBase<W>,
+    pub m_marchid: RiscvCsrBase<W>,
+    pub m_mvendorid: RiscvCsrBase<W>,
+    pub m_misa: RiscvCsrBase<W>,
+    pub m_mstatus: RiscvCsrBase<W>,
+    pub m_mtvec: RiscvCsrBase<W>,
+    pub m_mip: RiscvCsrBase<W>,
+    pub m_mie: RiscvCsrBase<W>,
+    pub m_mscratch: RiscvCsrBase<W>,
+    pub m_mepc: RiscvCsrBase<W>,
+    pub m_mtval: RiscvCsrBase<W>,
+    pub m_mcause: RiscvCsrBase<W>,
+    pub m_mhartid: RiscvCsrBase<W>,
+    pub m_dcsr: RiscvCsrBase<W>,
+    pub m_dpc: RiscvCsrBase<W>,
+    pub m_dscratch: RiscvCsrBase<W>,
+    pub m_medeleg: RiscvCsrBase<W>,
+    pub m_mideleg: RiscvCsrBase<W>,
+    pub m_mcounteren: RiscvCsrBase<W>,
+    pub m_menvcfg: RiscvCsrBase<W>,
+    pub m_menvcfg_wmask: W,         // Bits of enabled extensions
+    pub m_mtvt: RiscvCsrBase<W>,
+    pub m_tvt_enabled: bool,        // xtvec.MODE = 3 and xtvt are implemented
+    pub m_mseccfg: RiscvCsrBase<W>,
+    pub m_entropy: Option<EntropySource>,   // Zkr : seed CSR is implemented when set
+
+    pub m_stvec: RiscvCsrBase<W>,
+    pub m_scounteren: RiscvCsrBase<W>,
+    pub m_senvcfg: RiscvCsrBase<W>,
+    pub m_stvt: RiscvCsrBase<W>,
+    pub m_sscratch: RiscvCsrBase<W>,
+    pub m_sepc: RiscvCsrBase<W>,
+    pub m_scause: RiscvCsrBase<W>,
+    pub m_stval: RiscvCsrBase<W>,
+    pub m_satp: RiscvCsrBase<W>,
+
+    pub m_mtinst: RiscvCsrBase<W>,
+    pub m_mtval2: RiscvCsrBase<W>,
+    pub m_hstatus: RiscvCsrBase<W>,
+    pub m_hedeleg: RiscvCsrBase<W>,
+    pub m_hideleg: RiscvCsrBase<W>,
+    pub m_hie: RiscvCsrBase<W>,
+    pub m_htimedelta: RiscvCsrBase<W>,
+    pub m_hcounteren: RiscvCsrBase<W>,
+    pub m_hgeie: RiscvCsrBase<W>,
+    pub m_henvcfg: RiscvCsrBase<W>,
+    pub m_htval: RiscvCsrBase<W>,
+    pub m_hip: RiscvCsrBase<W>,
+    pub m_hvip: RiscvCsrBase<W>,
+    pub m_htinst: RiscvCsrBase<W>,
+    pub m_hgatp: RiscvCsrBase<W>,
+    pub m_hgeip: RiscvCsrBase<W>,
+    pub m_vsstatus: RiscvCsrBase<W>,
+    pub m_vsie: RiscvCsrBase<W>,
+    pub m_vstvec: RiscvCsrBase<W>,
+    pub m_vsscratch: RiscvCsrBase<W>,
+    pub m_vsepc: RiscvCsrBase<W>,
+    pub m_vscause: RiscvCsrBase<W>,
+    pub m_vstval: RiscvCsrBase<W>,
+    pub m_vsip: RiscvCsrBase<W>,
+    pub m_vsatp: RiscvCsrBase<W>,
+
+    pub m_pmpcfg: [RiscvCsrBase<W>; PMP_NUM / 8],
+    pub m_pmpaddr: [RiscvCsrBase<W>; PMP_NUM],
+    pub m_pmp_updated: bool,        // PMP has been changed since the last TLB check
+
+    pub m_tselect: RiscvCsrBase<W>,
+    pub m_tdata1: [RiscvCsrBase<W>; TRIG_NUM],
+    pub m_tdata2: [RiscvCsrBase<W>; TRIG_NUM],
+    pub m_tcontrol: RiscvCsrBase<W>,
+    pub m_trig_updated: bool,       // Triggers have been changed since the last TLB check
+
+    pub m_debug_mode: bool,         // Hart is halted in Debug Mode
+
+    pub m_time: RiscvCsrBase<W>,
+    pub m_mcountinhibit: RiscvCsrBase<W>,
+    pub m_mhpmcounter: [RiscvCsrBase<W>; HPM_NUM],
+    pub m_mhpmevent: [RiscvCsrBase<W>; HPM_NUM],
+    pub m_hpm_active: u32,          // mhpmcounters which have an event selected and not inhibited
+}
+
+#[derive(Clone, Copy)]
+pub enum CsrAddr {
+    None = 0x000,
+    FFlags = 0x001,
+    Frm = 0x002,
+    Fcsr = 0x003,
+    Vstart = 0x008,
+    Vxsat = 0x009,
+    Vxrm = 0x00a,
+    Vcsr = 0x00f,
+    Seed = 0x015,
+    Vl = 0xc20,
+    Vtype = 0xc21,
+    Vlenb = 0xc22,
+    Cycle = 0xc00,
+    Time = 0xc01,
+    Instret = 0xc02,
+    Hpmcounter3 = 0xc03,
+    Hpmcounter4 = 0xc04,
+    Hpmcounter5 = 0xc05,
+    Hpmcounter6 = 0xc06,
+    Hpmcounter7 = 0xc07,
+    Hpmcounter8 = 0xc08,
+    Hpmcounter9 = 0xc09,
+    Hpmcounter10 = 0xc0a,
+    Hpmcounter11 = 0xc0b,
+    Hpmcounter12 = 0xc0c,
+    Hpmcounter13 = 0xc0d,
+    Hpmcounter14 = 0xc0e,
+    Hpmcounter15 = 0xc0f,
+    Hpmcounter16 = 0xc10,
+    Hpmcounter17 = 0xc11,
+    Hpmcounter18 = 0xc12,
+    Hpmcounter19 = 0xc13,
+    Hpmcounter20 = 0xc14,
+    Hpmcounter21 = 0xc15,
+    Hpmcounter22 = 0xc16,
+    Hpmcounter23 = 0xc17,
+    Hpmcounter24 = 0xc18,
+    Hpmcounter25 = 0xc19,
+    Hpmcounter26 = 0xc1a,
+    Hpmcounter27 = 0xc1b,
+    Hpmcounter28 = 0xc1c,
+    Hpmcounter29 = 0xc1d,
+    Hpmcounter30 = 0xc1e,
+    Hpmcounter31 = 0xc1f,
+    Mstatus = 0x300,
+    Misa = 0x301,
+    Medeleg = 0x302,
+    Mideleg = 0x303,
+    Mie = 0x304,
+    Mtvec = 0x305,
+    Mscratch = 0x340,
+    Mcounteren = 0x306,
+    Mtvt = 0x307,
+    Mepc = 0x341,
+    Mcause = 0x342,
+    Mtval = 0x343,
+    Mip = 0x344,
+    Menvcfg = 0x30a,
+    Mseccfg = 0x747,
+    Pmpcfg0 = 0x3a0,
+    Pmpcfg2 = 0x3a2,
+    Pmpcfg4 = 0x3a4,
+    Pmpcfg6 = 0x3a6,
+    Pmpcfg8 = 0x3a8,
+    Pmpcfg10 = 0x3aa,
+    Pmpcfg12 = 0x3ac,
+    Pmpcfg14 = 0x3ae,
+    Pmpaddr0 = 0x3b0,
+    Pmpaddr1 = 0x3b1,
+    Pmpaddr2 = 0x3b2,
+    Pmpaddr3 = 0x3b3,
+    Pmpaddr4 = 0x3b4,
+    Pmpaddr5 = 0x3b5,
+    Pmpaddr6 = 0x3b6,
+    Pmpaddr7 = 0x3b7,
+    Pmpaddr8 = 0x3b8,
+    Pmpaddr9 = 0x3b9,
+    Pmpaddr10 = 0x3ba,
+    Pmpaddr11 = 0x3bb,
+    Pmpaddr12 = 0x3bc,
+    Pmpaddr13 = 0x3bd,
+    Pmpaddr14 = 0x3be,
+    Pmpaddr15 = 0x3bf,
+    Pmpaddr16 = 0x3c0,
+    Pmpaddr17 = 0x3c1,
+    Pmpaddr18 = 0x3c2,
+    Pmpaddr19 = 0x3c3,
+    Pmpaddr20 = 0x3c4,
+    Pmpaddr21 = 0x3c5,
+    Pmpaddr22 = 0x3c6,
+    Pmpaddr23 = 0x3c7,
+    Pmpaddr24 = 0x3c8,
+    Pmpaddr25 = 0x3c9,
+    Pmpaddr26 = 0x3ca,
+    Pmpaddr27 = 0x3cb,
+    Pmpaddr28 = 0x3cc,
+    Pmpaddr29 = 0x3cd,
+    Pmpaddr30 = 0x3ce,
+    Pmpaddr31 = 0x3cf,
+    Pmpaddr32 = 0x3d0,
+    Pmpaddr33 = 0x3d1,
+    Pmpaddr34 = 0x3d2,
+    Pmpaddr35 = 0x3d3,
+    Pmpaddr36 = 0x3d4,
+    Pmpaddr37 = 0x3d5,
+    Pmpaddr38 = 0x3d6,
+    Pmpaddr39 = 0x3d7,
+    Pmpaddr40 = 0x3d8,
+    Pmpaddr41 = 0x3d9,
+    Pmpaddr42 = 0x3da,
+    Pmpaddr43 = 0x3db,
+    Pmpaddr44 = 0x3dc,
+    Pmpaddr45 = 0x3dd,
+    Pmpaddr46 = 0x3de,
+    Pmpaddr47 = 0x3df,
+    Pmpaddr48 = 0x3e0,
+    Pmpaddr49 = 0x3e1,
+    Pmpaddr50 = 0x3e2,
+    Pmpaddr51 = 0x3e3,
+    Pmpaddr52 = 0x3e4,
+    Pmpaddr53 = 0x3e5,
+    Pmpaddr54 = 0x3e6,
+    Pmpaddr55 = 0x3e7,
+    Pmpaddr56 = 0x3e8,
+    Pmpaddr57 = 0x3e9,
+    Pmpaddr58 = 0x3ea,
+    Pmpaddr59 = 0x3eb,
+    Pmpaddr60 = 0x3ec,
+    Pmpaddr61 = 0x3ed,
+    Pmpaddr62 = 0x3ee,
+    Pmpaddr63 = 0x3ef,
+    Tselect = 0x7a0,
+    Tdata1 = 0x7a1,
+    Tdata2 = 0x7a2,
+    Tdata3 = 0x7a3,
+    Tinfo = 0x7a4,
+    Tcontrol = 0x7a5,
+    Dcsr = 0x7b0,
+    Dpc = 0x7b1,
+    Dscratch = 0x7b2,
+    Mcycle = 0xb00,
+    Minstret = 0xb02,
+    Mhpmcounter3 = 0xb03,
+    Mhpmcounter4 = 0xb04,
+    Mhpmcounter5 = 0xb05,
+    Mhpmcounter6 = 0xb06,
+    Mhpmcounter7 = 0xb07,
+    Mhpmcounter8 = 0xb08,
+    Mhpmcounter9 = 0xb09,
+    Mhpmcounter10 = 0xb0a,
+    Mhpmcounter11 = 0xb0b,
+    Mhpmcounter12 = 0xb0c,
+    Mhpmcounter13 = 0xb0d,
+    Mhpmcounter14 = 0xb0e,
+    Mhpmcounter15 = 0xb0f,
+    Mhpmcounter16 = 0xb10,
+    Mhpmcounter17 = 0xb11,
+    Mhpmcounter18 = 0xb12,
+    Mhpmcounter19 = 0xb13,
+    Mhpmcounter20 = 0xb14,
+    Mhpmcounter21 = 0xb15,
+    Mhpmcounter22 = 0xb16,
+    Mhpmcounter23 = 0xb17,
+    Mhpmcounter24 = 0xb18,
+    Mhpmcounter25 = 0xb19,
+    Mhpmcounter26 = 0xb1a,
+    Mhpmcounter27 = 0xb1b,
+    Mhpmcounter28 = 0xb1c,
+    Mhpmcounter29 = 0xb1d,
+    Mhpmcounter30 = 0xb1e,
+    Mhpmcounter31 = 0xb1f,
+    Mcountinhibit = 0x320,
+    Mhpmevent3 = 0x323,
+    Mhpmevent4 = 0x324,
+    Mhpmevent5 = 0x325,
+    Mhpmevent6 = 0x326,
+    Mhpmevent7 = 0x327,
+    Mhpmevent8 = 0x328,
+    Mhpmevent9 = 0x329,
+    Mhpmevent10 = 0x32a,
+    Mhpmevent11 = 0x32b,
+    Mhpmevent12 = 0x32c,
+    Mhpmevent13 = 0x32d,
+    Mhpmevent14 = 0x32e,
+    Mhpmevent15 = 0x32f,
+    Mhpmevent16 = 0x330,
+    Mhpmevent17 = 0x331,
+    Mhpmevent18 = 0x332,
+    Mhpmevent19 = 0x333,
+    Mhpmevent20 = 0x334,
+    Mhpmevent21 = 0x335,
+    Mhpmevent22 = 0x336,
+    Mhpmevent23 = 0x337,
+    Mhpmevent24 = 0x338,
+    Mhpmevent25 = 0x339,
+    Mhpmevent26 = 0x33a,
+    Mhpmevent27 = 0x33b,
+    Mhpmevent28 = 0x33c,
+    Mhpmevent29 = 0x33d,
+    Mhpmevent30 = 0x33e,
+    Mhpmevent31 = 0x33f,
+    Mvendorid = 0xf11,
+    Marchid = 0xf12,
+    Mimpid = 0xf13,
+    Mhartid = 0xf14,
+    // Cycleh         = 0xc80,
+    // Instreth       = 0xc82,
+    // Hpmcounter3h   = 0xc83,
+    // Hpmcounter4h   = 0xc84,
+    // Hpmcounter5h   = 0xc85,
+    // Hpmcounter6h   = 0xc86,
+    // Hpmcounter7h   = 0xc87,
+    // Hpmcounter8h   = 0xc88,
+    // Hpmcounter9h   = 0xc89,
+    // Hpmcounter10h  = 0xc8a,
+    // Hpmcounter11h  = 0xc8b,
+    // Hpmcounter12h  = 0xc8c,
+    // Hpmcounter13h  = 0xc8d,
+    // Hpmcounter14h  = 0xc8e,
+    // Hpmcounter15h  = 0xc8f,
+    // Hpmcounter16h  = 0xc90,
+    // Hpmcounter17h  = 0xc91,
+    // Hpmcounter18h  = 0xc92,
+    // Hpmcounter19h  = 0xc93,
+    // Hpmcounter20h  = 0xc94,
+    // Hpmcounter21h  = 0xc95,
+    // Hpmcounter22h  = 0xc96,
+    // Hpmcounter23h  = 0xc97,
+    // Hpmcounter24h  = 0xc98,
+    // Hpmcounter25h  = 0xc99,
+    // Hpmcounter26h  = 0xc9a,
+    // Hpmcounter27h  = 0xc9b,
+    // Hpmcounter28h  = 0xc9c,
+    // Hpmcounter29h  = 0xc9d,
+    // Hpmcounter30h  = 0xc9e,
+    // Hpmcounter31h  = 0xc9f,
+    // Mcycleh        = 0xb80,
+    // Minstreth      = 0xb82,
+    // Mhpmcounter3h  = 0xb83,
+    // Mhpmcounter4h  = 0xb84,
+    // Mhpmcounter5h  = 0xb85,
+    // Mhpmcounter6h  = 0xb86,
+    // Mhpmcounter7h  = 0xb87,
+    // Mhpmcounter8h  = 0xb88,
+    // Mhpmcounter9h  = 0xb89,
+    // Mhpmcounter10h = 0xb8a,
+    // Mhpmcounter11h = 0xb8b,
+    // Mhpmcounter12h = 0xb8c,
+    // Mhpmcounter13h = 0xb8d,
+    // Mhpmcounter14h = 0xb8e,
+    // Mhpmcounter15h = 0xb8f,
+    // Mhpmcounter16h = 0xb90,
+    // Mhpmcounter17h = 0xb91,
+    // Mhpmcounter18h = 0xb92,
+    // Mhpmcounter19h = 0xb93,
+    // Mhpmcounter20h = 0xb94,
+    // Mhpmcounter21h = 0xb95,
+    // Mhpmcounter22h = 0xb96,
+    // Mhpmcounter23h = 0xb97,
+    // Mhpmcounter24h = 0xb98,
+    // Mhpmcounter25h = 0xb99,
+    // Mhpmcounter26h = 0xb9a,
+    // Mhpmcounter27h = 0xb9b,
+    // Mhpmcounter28h = 0xb9c,
+    // Mhpmcounter29h = 0xb9d,
+    // Mhpmcounter30h = 0xb9e,
+    // Mhpmcounter31h = 0xb9f,
+    Sstatus = 0x100,
+    Sie = 0x104,
+    Stvec = 0x105,
+    Scounteren = 0x106,
+    Senvcfg = 0x10a,
+    Stvt = 0x107,
+    Sscratch = 0x140,
+    Sepc = 0x141,
+    Scause = 0x142,
+    Stval = 0x143,
+    Sip = 0x144,
+    Satp = 0x180,
+
+    Mtinst = 0x34a,
+    Mtval2 = 0x34b,
+    Hstatus = 0x600,
+    Hedeleg = 0x602,
+    Hideleg = 0x603,
+    Hie = 0x604,
+    Htimedelta = 0x605,
+    Hcounteren = 0x606,
+    Hgeie = 0x607,
+    Henvcfg = 0x60a,
+    Htval = 0x643,
+    Hip = 0x644,
+    Hvip = 0x645,
+    Htinst = 0x64a,
+    Hgatp = 0x680,
+    Hgeip = 0xe12,
+    Scountovf = 0xda0,
+    Vsstatus = 0x200,
+    Vsie = 0x204,
+    Vstvec = 0x205,
+    Vsscratch = 0x240,
+    Vsepc = 0x241,
+    Vscause = 0x242,
+    Vstval = 0x243,
+    Vsip = 0x244,
+    Vsatp = 0x280,
+}
+
+impl RiscvCsr<i64> {
+    pub fn new() -> RiscvCsr<i64> {
+        RiscvCsr {
+            m_fflags: RiscvCsrBase::<i64>::new(),
+            m_frm: RiscvCsrBase::<i64>::new(),
+            m_vstart: RiscvCsrBase::<i64>::new(),
+            m_vxsat: RiscvCsrBase::<i64>::new(),
+            m_vxrm: RiscvCsrBase::<i64>::new(),
+            m_vl: RiscvCsrBase::<i64>::new(),
+            m_vtype: RiscvCsrBase { m_csr: VTYPE_VILL },
+            m_vlenb: RiscvCsrBase::<i64>::new(),
+            m_mcycle: RiscvCsrBase::<i64>::new(),
+            m_minstret: RiscvCsrBase::<i64>::new(),
+            m_mimpid: RiscvCsrBase::<i64>::new(),
+            m_marchid: RiscvCsrBase::<i64>::new(),
+            m_mvendorid: RiscvCsrBase::<i64>::new(),
+            m_misa: RiscvCsrBase::<i64>::new(),
+            m_mstatus: RiscvCsrBase { m_csr: MSTATUS_RESET },
+            m_mtvec: RiscvCsrBase::<i64>::new(),
+            m_mip: RiscvCsrBase::<i64>::new(),
+            m_mie: RiscvCsrBase::<i64>::new(),
+            m_mscratch: RiscvCsrBase::<i64>::new(),
+            m_mepc: RiscvCsrBase::<i64>::new(),
+            m_mtval: RiscvCsrBase::<i64>::new(),
+            m_mcause: RiscvCsrBase::<i64>::new(),
+            m_mhartid: RiscvCsrBase::<i64>::new(),
+            m_dcsr: RiscvCsrBase::<i64>::new(),
+            m_dpc: RiscvCsrBase::<i64>::new(),
+            m_dscratch: RiscvCsrBase::<i64>::new(),
+            m_medeleg: RiscvCsrBase::<i64>::new(),
+            m_mideleg: RiscvCsrBase::<i64>::new(),
+            m_mcounteren: RiscvCsrBase::<i64>::new(),
+            m_menvcfg: RiscvCsrBase::<i64>::new(),
+            m_menvcfg_wmask: ENVCFG_CBO_WMASK,     // Zicbom / Zicboz are always implemented
+            m_mtvt: RiscvCsrBase::<i64>::new(),
+            m_tvt_enabled: false,
+            m_mseccfg: RiscvCsrBase::<i64>::new(),
+            m_entropy: None,
+
+            m_stvec: RiscvCsrBase::<i64>::new(),
+            m_scounteren: RiscvCsrBase::<i64>::new(),
+            m_senvcfg: RiscvCsrBase::<i64>::new(),
+            m_stvt: RiscvCsrBase::<i64>::new(),
+            m_sscratch: RiscvCsrBase::<i64>::new(),
+            m_sepc: RiscvCsrBase::<i64>::new(),
+            m_scause: RiscvCsrBase::<i64>::new(),
+            m_stval: RiscvCsrBase::<i64>::new(),
+            m_satp: RiscvCsrBase::<i64>::new(),
+
+            m_mtinst: RiscvCsrBase::<i64>::new(),
+            m_mtval2: RiscvCsrBase::<i64>::new(),
+            m_hstatus: RiscvCsrBase { m_csr: HSTATUS_RESET },
+            m_hedeleg: RiscvCsrBase::<i64>::new(),
+            m_hideleg: RiscvCsrBase::<i64>::new(),
+            m_hie: RiscvCsrBase::<i64>::new(),
+            m_htimedelta: RiscvCsrBase::<i64>::new(),
+            m_hcounteren: RiscvCsrBase::<i64>::new(),
+            m_hgeie: RiscvCsrBase::<i64>::new(),
+            m_henvcfg: RiscvCsrBase::<i64>::new(),
+            m_htval: RiscvCsrBase::<i64>::new(),
+            m_hip: RiscvCsrBase::<i64>::new(),
+            m_hvip: RiscvCsrBase::<i64>::new(),
+            m_htinst: RiscvCsrBase::<i64>::new(),
+            m_hgatp: RiscvCsrBase::<i64>::new(),
+            m_hgeip: RiscvCsrBase::<i64>::new(),
+            m_vsstatus: RiscvCsrBase { m_csr: SSTATUS_RESET },
+            m_vsie: RiscvCsrBase::<i64>::new(),
+            m_vstvec: RiscvCsrBase::<i64>::new(),
+            m_vsscratch: RiscvCsrBase::<i64>::new(),
+            m_vsepc: RiscvCsrBase::<i64>::new(),
+            m_vscause: RiscvCsrBase::<i64>::new(),
+            m_vstval: RiscvCsrBase::<i64>::new(),
+            m_vsip: RiscvCsrBase::<i64>::new(),
+            m_vsatp: RiscvCsrBase::<i64>::new(),
+
+            m_pmpcfg: std::array::from_fn(|_| RiscvCsrBase::<i64>::new()),
+            m_pmpaddr: std::array::from_fn(|_| RiscvCsrBase::<i64>::new()),
+            m_pmp_updated: false,
+
+            m_tselect: RiscvCsrBase::<i64>::new(),
+            m_tdata1: std::array::from_fn(|_| RiscvCsrBase { m_csr: TDATA1_DISABLED }),
+            m_tdata2: std::array::from_fn(|_| RiscvCsrBase::<i64>::new()),
+            m_tcontrol: RiscvCsrBase::<i64>::new(),
+            m_trig_updated: false,
+
+            m_debug_mode: false,
+
+            m_time: RiscvCsrBase::<i64>::new(),
+            m_mcountinhibit: RiscvCsrBase::<i64>::new(),
+            m_mhpmcounter: std::array::from_fn(|_| RiscvCsrBase::<i64>::new()),
+            m_mhpmevent: std::array::from_fn(|_| RiscvCsrBase::<i64>::new()),
+            m_hpm_active: 0,
+        }
+    }
+
+    fn has_ext(&self, ext: char) -> bool {
+        (self.m_misa.m_csr & (1 << (ext as u8 - b'A'))) != 0
+    }
+
+    // Descriptor of the CSR. None for CSRs not implemented in this configuration.
+    pub fn csr_desc(&self, addr: CsrAddr) -> Option<CsrDesc> {
+        let h = self.has_ext('H');
+        // Vector CSRs are not accessible while mstatus.VS is Off
+        let vs = (self.m_mstatus.m_csr & MSTATUS_VS_MASK) != 0;
+        let zkr = self.m_entropy.is_some();
+        let (rmask, wmask) = match addr {
+            CsrAddr::None => return None,
+            CsrAddr::FFlags => (FFLAGS_MASK, FFLAGS_MASK),
+            CsrAddr::Frm => (FRM_MASK, FRM_MASK),
+            CsrAddr::Fcsr => (FCSR_MASK, FCSR_MASK),
+            // vstart holds an element index less than VLMAX with SEW = 8 and LMUL = 8, i.e. VLEN
+            CsrAddr::Vstart if vs => (ALL, self.m_vlenb.m_csr * 8 - 1),
+            CsrAddr::Vxsat if vs => (VXSAT_MASK, VXSAT_MASK),
+            CsrAddr::Vxrm if vs => (VXRM_MASK, VXRM_MASK),
+            CsrAddr::Vcsr if vs => (VCSR_MASK, VCSR_MASK),
+            CsrAddr::Vl | CsrAddr::Vtype | CsrAddr::Vlenb if vs => (ALL, 0),
+            // Writes to seed are ignored. Privilege is checked by mseccfg on the access.
+            CsrAddr::Seed if zkr => (ALL, 0),
+
+            CsrAddr::Mvendorid | CsrAddr::Marchid | CsrAddr::Mimpid | CsrAddr::Mhartid => (ALL, 0),
+            CsrAddr::Misa => (ALL, 0),
+            CsrAddr::Mstatus => (ALL, MSTATUS_WMASK | if h { MSTATUS_H_WMASK } else { 0 }),
+            CsrAddr::Medeleg => (ALL, MEDELEG_WMASK | if h { MEDELEG_H_WMASK } else { 0 }),
+            CsrAddr::Mideleg => (ALL, IRQ_S_MASK),
+            CsrAddr::Mie => (ALL, IRQ_S_MASK | IRQ_M_MASK | if h { IRQ_VS_MASK } else { 0 }),
+            CsrAddr::Mip => (ALL, MIP_WMASK | if h { HIP_WMASK } else { 0 }),
+            CsrAddr::Mtvec => (ALL, TVEC_WMASK),
+            CsrAddr::Mepc => (ALL, EPC_WMASK),
+            CsrAddr::Mcounteren => (ALL, 0xffff_ffff),
+            CsrAddr::Mtvt if self.m_tvt_enabled => (ALL, TVT_WMASK),
+            CsrAddr::Menvcfg => (ALL, self.m_menvcfg_wmask),
+            CsrAddr::Mseccfg if zkr => (ALL, MSECCFG_WMASK),
+            CsrAddr::Mscratch | CsrAddr::Mcause | CsrAddr::Mtval => (ALL, ALL),
+
+            CsrAddr::Sstatus => (SSTATUS_RMASK, SSTATUS_WMASK),
+            CsrAddr::Sie => (IRQ_S_MASK, IRQ_S_MASK),
+            CsrAddr::Sip => (IRQ_S_MASK, SIP_WMASK),
+            CsrAddr::Stvec => (ALL, TVEC_WMASK),
+            CsrAddr::Sepc => (ALL, EPC_WMASK),
+            CsrAddr::Scounteren => (ALL, 0xffff_ffff),
+            CsrAddr::Senvcfg => (ALL, ENVCFG_CBO_WMASK),
+            CsrAddr::Stvt if self.m_tvt_enabled => (ALL, TVT_WMASK),
+            CsrAddr::Sscratch | CsrAddr::Scause | CsrAddr::Stval | CsrAddr::Satp => (ALL, ALL),
+
+            CsrAddr::Mtinst | CsrAddr::Mtval2 if h => (ALL, ALL),
+            CsrAddr::Hstatus if h => (ALL, HSTATUS_WMASK),
+            CsrAddr::Hedeleg if h => (ALL, HEDELEG_WMASK),
+            CsrAddr::Hideleg if h => (ALL, IRQ_VS_MASK & !0x1000),
+            CsrAddr::Hie if h => (ALL, IRQ_VS_MASK),
+            CsrAddr::Hip if h => (ALL, HIP_WMASK),
+            CsrAddr::Hvip if h => (ALL, HVIP_WMASK),
+            CsrAddr::Hcounteren if h => (ALL, 0xffff_ffff),
+            CsrAddr::Henvcfg if h => (ALL, self.m_menvcfg_wmask),
+            CsrAddr::Hgeie | CsrAddr::Hgeip if h => (ALL, 0),     // No guest external interrupt (GEILEN = 0)
+            CsrAddr::Htimedelta | CsrAddr::Htval | CsrAddr::Htinst | CsrAddr::Hgatp if h => (ALL, ALL),
+            CsrAddr::Vsstatus if h => (SSTATUS_RMASK, SSTATUS_WMASK),
+            CsrAddr::Vsie | CsrAddr::Vsip if h => (IRQ_S_MASK, IRQ_S_MASK),
+            CsrAddr::Vstvec if h => (ALL, TVEC_WMASK),
+            CsrAddr::Vsepc if h => (ALL, EPC_WMASK),
+            CsrAddr::Vsscratch | CsrAddr::Vscause | CsrAddr::Vstval | CsrAddr::Vsatp if h => (ALL, ALL),
+
+            // PMP, triggers, Debug Mode and counters check the value themselves
+            CsrAddr::Dcsr | CsrAddr::Dpc | CsrAddr::Dscratch => (ALL, ALL),
+            _ if addr.is_pmp() || addr.is_trigger() || addr.is_hpm() => (ALL, ALL),
+            _ => return None,
+        };
+        // csr[9:8] is the lowest privilege. Hypervisor CSRs are accessible from HS-mode.
+        let priv_mode = match (addr as u64 >> 8) & 0x3 {
+            0 => PrivMode::User,
+            3 => PrivMode::Machine,
+            _ => PrivMode::Supervisor,
+        };
+        Some(CsrDesc { priv_mode: priv_mode, rmask: rmask, wmask: wmask })
+    }
+
+    fn csr_storage(&mut self, addr: CsrAddr) -> Option<&mut RiscvCsrBase<i64>> {
+        let csr = match addr {
+            CsrAddr::FFlags => &mut self.m_fflags,
+            CsrAddr::Frm => &mut self.m_frm,
+            CsrAddr::Vstart => &mut self.m_vstart,
+            CsrAddr::Vxsat => &mut self.m_vxsat,
+            CsrAddr::Vxrm => &mut self.m_vxrm,
+            CsrAddr::Vl => &mut self.m_vl,
+            CsrAddr::Vtype => &mut self.m_vtype,
+            CsrAddr::Vlenb => &mut self.m_vlenb,
+            CsrAddr::Mimpid => &mut self.m_mimpid,
+            CsrAddr::Marchid => &mut self.m_marchid,
+            CsrAddr::Mvendorid => &mut self.m_mvendorid,
+            CsrAddr::Misa => &mut self.m_misa,
+            CsrAddr::Mstatus => &mut self.m_mstatus,
+            CsrAddr::Mtvec => &mut self.m_mtvec,
+            CsrAddr::Mip => &mut self.m_mip,
+            CsrAddr::Mie => &mut self.m_mie,
+            CsrAddr::Mscratch => &mut self.m_mscratch,
+            CsrAddr::Mepc => &mut self.m_mepc,
+            CsrAddr::Mtval => &mut self.m_mtval,
+            CsrAddr::Mcause => &mut self.m_mcause,
+            CsrAddr::Mhartid => &mut self.m_mhartid,
+            CsrAddr::Dcsr => &mut self.m_dcsr,
+            CsrAddr::Dpc => &mut self.m_dpc,
+            CsrAddr::Dscratch => &mut self.m_dscratch,
+            CsrAddr::Medeleg => &mut self.m_medeleg,
+            CsrAddr::Mideleg => &mut self.m_mideleg,
+            CsrAddr::Mcounteren => &mut self.m_mcounteren,
+            CsrAddr::Menvcfg => &mut self.m_menvcfg,
+            CsrAddr::Mtvt => &mut self.m_mtvt,
+            CsrAddr::Mseccfg => &mut self.m_mseccfg,
+
+            CsrAddr::Stvec => &mut self.m_stvec,
+            CsrAddr::Scounteren => &mut self.m_scounteren,
+            CsrAddr::Senvcfg => &mut self.m_senvcfg,
+            CsrAddr::Stvt => &mut self.m_stvt,
+            CsrAddr::Sscratch => &mut self.m_sscratch,
+            CsrAddr::Sepc => &mut self.m_sepc,
+            CsrAddr::Scause => &mut self.m_scause,
+            CsrAddr::Stval => &mut self.m_stval,
+            CsrAddr::Satp => &mut self.m_satp,
+
+            CsrAddr::Mtinst => &mut self.m_mtinst,
+            CsrAddr::Mtval2 => &mut self.m_mtval2,
+            CsrAddr::Hstatus => &mut self.m_hstatus,
+            CsrAddr::Hedeleg => &mut self.m_hedeleg,
+            CsrAddr::Hideleg => &mut self.m_hideleg,
+            CsrAddr::Hie => &mut self.m_hie,
+            CsrAddr::Htimedelta => &mut self.m_htimedelta,
+            CsrAddr::Hcounteren => &mut self.m_hcounteren,
+            CsrAddr::Hgeie => &mut self.m_hgeie,
+            CsrAddr::Henvcfg => &mut self.m_henvcfg,
+            CsrAddr::Htval => &mut self.m_htval,
+            CsrAddr::Hip => &mut self.m_hip,
+            CsrAddr::Hvip => &mut self.m_hvip,
+            CsrAddr::Htinst => &mut self.m_htinst,
+            CsrAddr::Hgatp => &mut self.m_hgatp,
+            CsrAddr::Hgeip => &mut self.m_hgeip,
+            CsrAddr::Vsstatus => &mut self.m_vsstatus,
+            CsrAddr::Vsie => &mut self.m_vsie,
+            CsrAddr::Vstvec => &mut self.m_vstvec,
+            CsrAddr::Vsscratch => &mut self.m_vsscratch,
+            CsrAddr::Vsepc => &mut self.m_vsepc,
+            CsrAddr::Vscause => &mut self.m_vscause,
+            CsrAddr::Vstval => &mut self.m_vstval,
+            CsrAddr::Vsip => &mut self.m_vsip,
+            CsrAddr::Vsatp => &mut self.m_vsatp,
+            _ => return None,
+        };
+        Some(csr)
+    }
+
+    // SD summarizes dirty state of FS, VS and XS.
+    fn status_with_sd(status: i64) -> i64 {
+        let dirty = (status & MSTATUS_FS_MASK) == MSTATUS_FS_MASK || (status & MSTATUS_VS_MASK) == MSTATUS_VS_MASK ||
+                    (status & MSTATUS_XS_MASK) == MSTATUS_XS_MASK;
+        if dirty { status | MSTATUS_SD } else { status & !MSTATUS_SD }
+    }
+
+    // Interrupts delegated to S-mode. VS-level interrupts are always delegated with H extension.
+    pub fn mideleg(&self) -> i64 {
+        self.m_mideleg.m_csr | if self.has_ext('H') { IRQ_VS_MASK } else { 0 }
+    }
+
+    // Translation modes other than Bare / Sv39 / Sv48 / Sv57 are not supported, and such writes are ignored.
+    fn atp_mode_supported(data: i64) -> bool {
+        matches!((data as u64) >> 60, 0 | 8 | 9 | 10)
+    }
+
+    // Each read of seed takes new 16 bits from the entropy source.
+    fn seed_read(&mut self) -> i64 {
+        let mut data = [0u8; 2];
+        let filled = match &mut self.m_entropy {
+            Some(entropy) => entropy.fill(&mut data),
+            None => false,
+        };
+        if filled { SEED_OPST_ES16 | u16::from_le_bytes(data) as i64 } else { SEED_OPST_DEAD }
+    }
+
+    pub fn csr_read(&mut self, addr: CsrAddr) -> i64 {
+        let desc = match self.csr_desc(addr) {
+            Some(desc) => desc,
+            None => return 0,
+        };
+        let value = if addr.is_pmp() {
+            self.pmp_read(addr)
+        } else if addr.is_trigger() {
+            self.trigger_read(addr)
+        } else if addr.is_hpm() {
+            self.hpm_read(addr)
+        } else {
+            match addr {
+                CsrAddr::Fcsr => (self.m_frm.m_csr << 5) | self.m_fflags.m_csr,
+                CsrAddr::Vcsr => (self.m_vxrm.m_csr << 1) | self.m_vxsat.m_csr,
+                CsrAddr::Mstatus | CsrAddr::Sstatus => Self::status_with_sd(self.m_mstatus.m_csr),
+                CsrAddr::Vsstatus => Self::status_with_sd(self.m_vsstatus.m_csr),
+                CsrAddr::Mideleg => self.mideleg(),
+                CsrAddr::Sie => self.m_mie.m_csr & self.mideleg(),
+                CsrAddr::Sip => self.m_mip.m_csr & self.mideleg(),
+                CsrAddr::Seed => self.seed_read(),
+                _ => match self.csr_storage(addr) {
+                    Some(csr) => csr.m_csr,
+                    None => 0,
+                },
+            }
+        };
+        value & desc.rmask
+    }
+
+    pub fn csr_write(&mut self, addr: CsrAddr, data: i64) {
+        let desc = match self.csr_desc(addr) {
+            Some(desc) => desc,
+            None => return,
+        };
+        if addr.is_pmp() {
+            return self.pmp_write(addr, data);
+        }
+        if addr.is_trigger() {
+            return self.trigger_write(addr, data);
+        }
+        if addr.is_hpm() {
+            return self.hpm_write(addr, data);
+        }
+        let mut wmask = desc.wmask;
+        match addr {
+            CsrAddr::Dcsr => {
+                self.dcsr_write(data);
+            }
+            CsrAddr::Fcsr => {
+                self.m_fflags.write_masked(data, FFLAGS_MASK);
+                self.m_frm.write_masked(data >> 5, FRM_MASK);
+            }
+            CsrAddr::Vcsr => {
+                self.m_vxsat.write_masked(data, VXSAT_MASK);
+                self.m_vxrm.write_masked(data >> 1, VXRM_MASK);
+                self.set_vs_dirty();
+            }
+            // Views of M-mode registers
+            CsrAddr::Sstatus => self.m_mstatus.write_masked(data, wmask),
+            CsrAddr::Sie => {
+                wmask &= self.mideleg();
+                self.m_mie.write_masked(data, wmask)
+            }
+            CsrAddr::Sip => {
+                wmask &= self.mideleg();
+                self.m_mip.write_masked(data, wmask)
+            }
+            _ => {
+                match addr {
+                    // mstatus.MPP = 2 is reserved : keep the previous value.
+                    CsrAddr::Mstatus if (data & MSTATUS_MPP_MASK) == (PrivMode::Hypervisor as i64) << 11 => {
+                        wmask &= !MSTATUS_MPP_MASK;
+                    }
+                    // xenvcfg.CBIE = 2 is reserved : keep the previous value.
+                    CsrAddr::Menvcfg | CsrAddr::Senvcfg | CsrAddr::Henvcfg if (data & ENVCFG_CBIE_MASK) == 0x20 => {
+                        wmask &= !ENVCFG_CBIE_MASK;
+                    }
+                    CsrAddr::Satp | CsrAddr::Vsatp | CsrAddr::Hgatp if !Self::atp_mode_supported(data) => return,
+                    // Table mode is only for mtvec / stvec with the vector table implemented. MODE = 2 keeps the previous value.
+                    CsrAddr::Mtvec | CsrAddr::Stvec if self.m_tvt_enabled => {
+                        wmask = if (data & 0x3) == 2 { wmask & !0x3 } else { wmask | 0x2 };
+                    }
+                    _ => {}
+                }
+                if let Some(csr) = self.csr_storage(addr) {
+                    csr.write_masked(data, wmask);
+                }
+                if matches!(addr, CsrAddr::Vstart | CsrAddr::Vxsat | CsrAddr::Vxrm) {
+                    self.set_vs_dirty();
+                }
+            }
+        }
+    }
+
+    // Vector state has been updated by a CSR write or a vector instruction.
+    pub fn set_vs_dirty(&mut self) {
+        self.m_mstatus.m_csr |= MSTATUS_VS_MASK;
+    }
+
+    pub fn csrrw(&mut self, addr: CsrAddr, data: i64) -> i64 {
+        let ret_val = self.csr_read(addr);
+        self.csr_write(addr, data);
+        ret_val
+    }
+
+    // Set / clear with zero does not write the CSR.
+    pub fn csrrs(&mut self, addr: CsrAddr, data: i64) -> i64 {
+        let ret_val = self.csr_read(addr);
+        if data != 0 {
+            self.csr_write(addr, ret_val | data);
+        }
+        ret_val
+    }
+
+    pub fn csrrc(&mut self, addr: CsrAddr, data: i64) -> i64 {
+        let ret_val = self.csr_read(addr);
+        if data != 0 {
+            self.csr_write(addr, ret_val & !data);
+        }
+        ret_val
+    }
+}
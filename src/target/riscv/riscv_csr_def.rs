@@ -0,0 +1,314 @@
+// pub const SYSREG_FCSR_FRM_MSB: u8 = 7;
+// pub const SYSREG_FCSR_FRM_LSB: u8 = 5;
+// pub const SYSREG_FCSR_FFLAGS_MSB: u8 = 4;
+// pub const SYSREG_FCSR_FFLAGS_LSB: u8 = 0;
+// pub const SYSREG_SSTATUS_SD_MSB: u8 = 63;
+// pub const SYSREG_SSTATUS_SD_LSB: u8 = 63;
+// pub const SYSREG_SSTATUS_UXL_MSB: u8 = 33;
+// pub const SYSREG_SSTATUS_UXL_LSB: u8 = 32;
+pub const SYSREG_SSTATUS_MXR_MSB: u8 = 19;
+pub const SYSREG_SSTATUS_MXR_LSB: u8 = 19;
+pub const SYSREG_SSTATUS_SUM_MSB: u8 = 18;
+pub const SYSREG_SSTATUS_SUM_LSB: u8 = 18;
+// pub const SYSREG_SSTATUS_XS_MSB: u8 = 16;
+// pub const SYSREG_SSTATUS_XS_LSB: u8 = 15;
+// pub const SYSREG_SSTATUS_FS_MSB: u8 = 14;
+// pub const SYSREG_SSTATUS_FS_LSB: u8 = 13;
+pub const SYSREG_SSTATUS_SPP_MSB: u8 = 8;
+pub const SYSREG_SSTATUS_SPP_LSB: u8 = 8;
+pub const SYSREG_SSTATUS_SPIE_MSB: u8 = 5;
+pub const SYSREG_SSTATUS_SPIE_LSB: u8 = 5;
+// pub const SYSREG_SSTATUS_UPIE_MSB: u8 = 4;
+// pub const SYSREG_SSTATUS_UPIE_LSB: u8 = 4;
+pub const SYSREG_SSTATUS_SIE_MSB: u8 = 1;
+pub const SYSREG_SSTATUS_SIE_LSB: u8 = 1;
+// pub const SYSREG_SSTATUS_UIE_MSB: u8 = 0;
+// pub const SYSREG_SSTATUS_UIE_LSB: u8 = 0;
+// pub const SYSREG_SCOUNTEREN_HPM31_MSB: u8 = 31;
+// pub const SYSREG_SCOUNTEREN_HPM31_LSB: u8 = 31;
+// pub const SYSREG_SCOUNTEREN_HPM30_MSB: u8 = 30;
+// pub const SYSREG_SCOUNTEREN_HPM30_LSB: u8 = 30;
+// pub const SYSREG_SCOUNTEREN_HPM29_MSB: u8 = 29;
+// pub const SYSREG_SCOUNTEREN_HPM29_LSB: u8 = 29;
+// pub const SYSREG_SCOUNTEREN_HPM28_MSB: u8 = 28;
+// pub const SYSREG_SCOUNTEREN_HPM28_LSB: u8 = 28;
+// pub const SYSREG_SCOUNTEREN_HPM27_MSB: u8 = 27;
+// pub const SYSREG_SCOUNTEREN_HPM27_LSB: u8 = 27;
+// pub const SYSREG_SCOUNTEREN_HPM26_MSB: u8 = 26;
+// pub const SYSREG_SCOUNTEREN_HPM26_LSB: u8 = 26;
+// pub const SYSREG_SCOUNTEREN_HPM25_MSB: u8 = 25;
+// pub const SYSREG_SCOUNTEREN_HPM25_LSB: u8 = 25;
+// pub const SYSREG_SCOUNTEREN_HPM24_MSB: u8 = 24;
+// pub const SYSREG_SCOUNTEREN_HPM24_LSB: u8 = 24;
+// pub const SYSREG_SCOUNTEREN_HPM23_MSB: u8 = 23;
+// pub const SYSREG_SCOUNTEREN_HPM23_LSB: u8 = 23;
+// pub const SYSREG_SCOUNTEREN_HPM22_MSB: u8 = 22;
+// pub const SYSREG_SCOUNTEREN_HPM22_LSB: u8 = 22;
+// pub const SYSREG_SCOUNTEREN_HPM21_MSB: u8 = 21;
+// pub const SYSREG_SCOUNTEREN_HPM21_LSB: u8 = 21;
+// pub const SYSREG_SCOUNTEREN_HPM20_MSB: u8 = 20;
+// pub const SYSREG_SCOUNTEREN_HPM20_LSB: u8 = 20;
+// pub const SYSREG_SCOUNTEREN_HPM19_MSB: u8 = 19;
+// pub const SYSREG_SCOUNTEREN_HPM19_LSB: u8 = 19;
+// pub const SYSREG_SCOUNTEREN_HPM18_MSB: u8 = 18;
+// pub const SYSREG_SCOUNTEREN_HPM18_LSB: u8 = 18;
+// pub const SYSREG_SCOUNTEREN_HPM17_MSB: u8 = 17;
+// pub const SYSREG_SCOUNTEREN_HPM17_LSB: u8 = 17;
+// pub const SYSREG_SCOUNTEREN_HPM16_MSB: u8 = 16;
+// pub const SYSREG_SCOUNTEREN_HPM16_LSB: u8 = 16;
+// pub const SYSREG_SCOUNTEREN_HPM15_MSB: u8 = 15;
+// pub const SYSREG_SCOUNTEREN_HPM15_LSB: u8 = 15;
+// pub const SYSREG_SCOUNTEREN_HPM14_MSB: u8 = 14;
+// pub const SYSREG_SCOUNTEREN_HPM14_LSB: u8 = 14;
+// pub const SYSREG_SCOUNTEREN_HPM13_MSB: u8 = 13;
+// pub const SYSREG_SCOUNTEREN_HPM13_LSB: u8 = 13;
+// pub const SYSREG_SCOUNTEREN_HPM12_MSB: u8 = 12;
+// pub const SYSREG_SCOUNTEREN_HPM12_LSB: u8 = 12;
+// pub const SYSREG_SCOUNTEREN_HPM11_MSB: u8 = 11;
+// pub const SYSREG_SCOUNTEREN_HPM11_LSB: u8 = 11;
+// pub const SYSREG_SCOUNTEREN_HPM10_MSB: u8 = 10;
+// pub const SYSREG_SCOUNTEREN_HPM10_LSB: u8 = 10;
+// pub const SYSREG_SCOUNTEREN_HPM9_MSB: u8 = 9;
+// pub const SYSREG_SCOUNTEREN_HPM9_LSB: u8 = 9;
+// pub const SYSREG_SCOUNTEREN_HPM8_MSB: u8 = 8;
+// pub const SYSREG_SCOUNTEREN_HPM8_LSB: u8 = 8;
+// pub const SYSREG_SCOUNTEREN_HPM7_MSB: u8 = 7;
+// pub const SYSREG_SCOUNTEREN_HPM7_LSB: u8 = 7;
+// pub const SYSREG_SCOUNTEREN_HPM6_MSB: u8 = 6;
+// pub const SYSREG_SCOUNTEREN_HPM6_LSB: u8 = 6;
+// pub const SYSREG_SCOUNTEREN_HPM5_MSB: u8 = 5;
+// pub const SYSREG_SCOUNTEREN_HPM5_LSB: u8 = 5;
+// pub const SYSREG_SCOUNTEREN_HPM4_MSB: u8 = 4;
+// pub const SYSREG_SCOUNTEREN_HPM4_LSB: u8 = 4;
+// pub const SYSREG_SCOUNTEREN_HPM3_MSB: u8 = 3;
+// pub const SYSREG_SCOUNTEREN_HPM3_LSB: u8 = 3;
+// pub const SYSREG_SCOUNTEREN_IR_MSB: u8 = 2;
+// pub const SYSREG_SCOUNTEREN_IR_LSB: u8 = 2;
+// pub const SYSREG_SCOUNTEREN_TM_MSB: u8 = 1;
+// pub const SYSREG_SCOUNTEREN_TM_LSB: u8 = 1;
+// pub const SYSREG_SCOUNTEREN_CY_MSB: u8 = 0;
+// pub const SYSREG_SCOUNTEREN_CY_LSB: u8 = 0;
+pub const SYSREG_SATP_MODE_MSB: u8 = 63;
+pub const SYSREG_SATP_MODE_LSB: u8 = 60;
+// pub const SYSREG_SATP_ASID_MSB: u8 = 30;
+// pub const SYSREG_SATP_ASID_LSB: u8 = 22;
+pub const SYSREG_SATP_PPN_MSB: u8 = 43;
+pub const SYSREG_SATP_PPN_LSB: u8 = 0;
+pub const SYSREG_MENVCFG_PBMTE_MSB: u8 = 62;
+pub const SYSREG_MENVCFG_PBMTE_LSB: u8 = 62;
+pub const SYSREG_MENVCFG_CBZE_MSB: u8 = 7;
+pub const SYSREG_MENVCFG_CBZE_LSB: u8 = 7;
+pub const SYSREG_MENVCFG_CBCFE_MSB: u8 = 6;
+pub const SYSREG_MENVCFG_CBCFE_LSB: u8 = 6;
+pub const SYSREG_MENVCFG_CBIE_MSB: u8 = 5;
+pub const SYSREG_MENVCFG_CBIE_LSB: u8 = 4;
+pub const SYSREG_MSECCFG_SSEED_MSB: u8 = 9;
+pub const SYSREG_MSECCFG_SSEED_LSB: u8 = 9;
+pub const SYSREG_MSECCFG_USEED_MSB: u8 = 8;
+pub const SYSREG_MSECCFG_USEED_LSB: u8 = 8;
+pub const SYSREG_HSTATUS_VTSR_MSB: u8 = 22;
+pub const SYSREG_HSTATUS_VTSR_LSB: u8 = 22;
+pub const SYSREG_HSTATUS_VTW_MSB: u8 = 21;
+pub const SYSREG_HSTATUS_VTW_LSB: u8 = 21;
+pub const SYSREG_HSTATUS_VTVM_MSB: u8 = 20;
+pub const SYSREG_HSTATUS_VTVM_LSB: u8 = 20;
+pub const SYSREG_HSTATUS_HU_MSB: u8 = 9;
+pub const SYSREG_HSTATUS_HU_LSB: u8 = 9;
+pub const SYSREG_HSTATUS_SPVP_MSB: u8 = 8;
+pub const SYSREG_HSTATUS_SPVP_LSB: u8 = 8;
+pub const SYSREG_HSTATUS_SPV_MSB: u8 = 7;
+pub const SYSREG_HSTATUS_SPV_LSB: u8 = 7;
+pub const SYSREG_HSTATUS_GVA_MSB: u8 = 6;
+pub const SYSREG_HSTATUS_GVA_LSB: u8 = 6;
+pub const SYSREG_HGATP_MODE_MSB: u8 = 63;
+pub const SYSREG_HGATP_MODE_LSB: u8 = 60;
+pub const SYSREG_HGATP_VMID_MSB: u8 = 57;
+pub const SYSREG_HGATP_VMID_LSB: u8 = 44;
+pub const SYSREG_HGATP_PPN_MSB: u8 = 43;
+pub const SYSREG_HGATP_PPN_LSB: u8 = 0;
+pub const SYSREG_DCSR_XDEBUGVER_MSB: u8 = 31;
+pub const SYSREG_DCSR_XDEBUGVER_LSB: u8 = 28;
+pub const SYSREG_DCSR_EBREAKVS_MSB: u8 = 17;
+pub const SYSREG_DCSR_EBREAKVS_LSB: u8 = 17;
+pub const SYSREG_DCSR_EBREAKVU_MSB: u8 = 16;
+pub const SYSREG_DCSR_EBREAKVU_LSB: u8 = 16;
+pub const SYSREG_DCSR_EBREAKM_MSB: u8 = 15;
+pub const SYSREG_DCSR_EBREAKM_LSB: u8 = 15;
+pub const SYSREG_DCSR_EBREAKS_MSB: u8 = 13;
+pub const SYSREG_DCSR_EBREAKS_LSB: u8 = 13;
+pub const SYSREG_DCSR_EBREAKU_MSB: u8 = 12;
+pub const SYSREG_DCSR_EBREAKU_LSB: u8 = 12;
+pub const SYSREG_DCSR_STEPIE_MSB: u8 = 11;
+pub const SYSREG_DCSR_STEPIE_LSB: u8 = 11;
+pub const SYSREG_DCSR_STOPCOUNT_MSB: u8 = 10;
+pub const SYSREG_DCSR_STOPCOUNT_LSB: u8 = 10;
+pub const SYSREG_DCSR_STOPTIME_MSB: u8 = 9;
+pub const SYSREG_DCSR_STOPTIME_LSB: u8 = 9;
+pub const SYSREG_DCSR_CAUSE_MSB: u8 = 8;
+pub const SYSREG_DCSR_CAUSE_LSB: u8 = 6;
+pub const SYSREG_DCSR_V_MSB: u8 = 5;
+pub const SYSREG_DCSR_V_LSB: u8 = 5;
+pub const SYSREG_DCSR_MPRVEN_MSB: u8 = 4;
+pub const SYSREG_DCSR_MPRVEN_LSB: u8 = 4;
+pub const SYSREG_DCSR_STEP_MSB: u8 = 2;
+pub const SYSREG_DCSR_STEP_LSB: u8 = 2;
+pub const SYSREG_DCSR_PRV_MSB: u8 = 1;
+pub const SYSREG_DCSR_PRV_LSB: u8 = 0;
+// pub const SYSREG_MIMPID_IMPLEMENTATION_MSB: u8 = 63;
+// pub const SYSREG_MIMPID_IMPLEMENTATION_LSB: u8 = 16;
+// pub const SYSREG_MIMPID_SOURCE_MSB: u8 = 15;
+// pub const SYSREG_MIMPID_SOURCE_LSB: u8 = 0;
+// pub const SYSREG_MSTATUS_SD_MSB: u8 = 63;
+// pub const SYSREG_MSTATUS_SD_LSB: u8 = 63;
+pub const SYSREG_MSTATUS_MPV_MSB: u8 = 39;
+pub const SYSREG_MSTATUS_MPV_LSB: u8 = 39;
+pub const SYSREG_MSTATUS_GVA_MSB: u8 = 38;
+pub const SYSREG_MSTATUS_GVA_LSB: u8 = 38;
+// pub const SYSREG_MSTATUS_SXL_MSB: u8 = 35;
+// pub const SYSREG_MSTATUS_SXL_LSB: u8 = 34;
+// pub const SYSREG_MSTATUS_UXL_MSB: u8 = 33;
+// pub const SYSREG_MSTATUS_UXL_LSB: u8 = 32;
+pub const SYSREG_MSTATUS_TSR_MSB: u8 = 22;
+pub const SYSREG_MSTATUS_TSR_LSB: u8 = 22;
+// pub const SYSREG_MSTATUS_TW_MSB: u8 = 21;
+// pub const SYSREG_MSTATUS_TW_LSB: u8 = 21;
+pub const SYSREG_MSTATUS_TVM_MSB: u8 = 20;
+pub const SYSREG_MSTATUS_TVM_LSB: u8 = 20;
+pub const SYSREG_MSTATUS_MXR_MSB: u8 = 19;
+pub const SYSREG_MSTATUS_MXR_LSB: u8 = 19;
+pub const SYSREG_MSTATUS_SUM_MSB: u8 = 18;
+pub const SYSREG_MSTATUS_SUM_LSB: u8 = 18;
+pub const SYSREG_MSTATUS_MPRV_MSB: u8 = 17;
+pub const SYSREG_MSTATUS_MPRV_LSB: u8 = 17;
+// pub const SYSREG_MSTATUS_XS_MSB: u8 = 16;
+// pub const SYSREG_MSTATUS_XS_LSB: u8 = 15;
+// pub const SYSREG_MSTATUS_FS_MSB: u8 = 14;
+// pub const SYSREG_MSTATUS_FS_LSB: u8 = 13;
+pub const SYSREG_MSTATUS_MPP_MSB: u8 = 12;
+pub const SYSREG_MSTATUS_MPP_LSB: u8 = 11;
+pub const SYSREG_MSTATUS_VS_MSB: u8 = 10;
+pub const SYSREG_MSTATUS_VS_LSB: u8 = 9;
+pub const SYSREG_MSTATUS_SPP_MSB: u8 = 8;
+pub const SYSREG_MSTATUS_SPP_LSB: u8 = 8;
+pub const SYSREG_MSTATUS_MPIE_MSB: u8 = 7;
+pub const SYSREG_MSTATUS_MPIE_LSB: u8 = 7;
+pub const SYSREG_MSTATUS_SPIE_MSB: u8 = 5;
+pub const SYSREG_MSTATUS_SPIE_LSB: u8 = 5;
+// pub const SYSREG_MSTATUS_UPIE_MSB: u8 = 4;
+// pub const SYSREG_MSTATUS_UPIE_LSB: u8 = 4;
+pub const SYSREG_MSTATUS_MIE_MSB: u8 = 3;
+pub const SYSREG_MSTATUS_MIE_LSB: u8 = 3;
+pub const SYSREG_MSTATUS_SIE_MSB: u8 = 1;
+pub const SYSREG_MSTATUS_SIE_LSB: u8 = 1;
+// pub const SYSREG_MSTATUS_UIE_MSB: u8 = 0;
+// pub const SYSREG_MSTATUS_UIE_LSB: u8 = 0;
+// pub const SYSREG_MISA_MXL_MSB: u8 = 63;
+// pub const SYSREG_MISA_MXL_LSB: u8 = 62;
+// pub const SYSREG_MISA_EXTENSIONS_MSB: u8 = 25;
+// pub const SYSREG_MISA_EXTENSIONS_LSB: u8 = 0;
+// pub const SYSREG_MIE_MEIE_MSB: u8 = 11;
+// pub const SYSREG_MIE_MEIE_LSB: u8 = 11;
+// pub const SYSREG_MIE_SEIE_MSB: u8 = 9;
+// pub const SYSREG_MIE_SEIE_LSB: u8 = 9;
+// pub const SYSREG_MIE_UEIE_MSB: u8 = 8;
+// pub const SYSREG_MIE_UEIE_LSB: u8 = 8;
+// pub const SYSREG_MIE_MTIE_MSB: u8 = 7;
+// pub const SYSREG_MIE_MTIE_LSB: u8 = 7;
+// pub const SYSREG_MIE_STIE_MSB: u8 = 5;
+// pub const SYSREG_MIE_STIE_LSB: u8 = 5;
+// pub const SYSREG_MIE_UTIE_MSB: u8 = 4;
+// pub const SYSREG_MIE_UTIE_LSB: u8 = 4;
+// pub const SYSREG_MIE_MSIE_MSB: u8 = 3;
+// pub const SYSREG_MIE_MSIE_LSB: u8 = 3;
+// pub const SYSREG_MIE_SSIE_MSB: u8 = 1;
+// pub const SYSREG_MIE_SSIE_LSB: u8 = 1;
+// pub const SYSREG_MIE_USIE_MSB: u8 = 0;
+// pub const SYSREG_MIE_USIE_LSB: u8 = 0;
+// pub const SYSREG_MTVEC_TRAP_VECTOR_BASE_ADDRESS_MSB: u8 = 63;
+// pub const SYSREG_MTVEC_TRAP_VECTOR_BASE_ADDRESS_LSB: u8 = 2;
+// pub const SYSREG_MCOUNTEREN_HPM31_MSB: u8 = 31;
+// pub const SYSREG_MCOUNTEREN_HPM31_LSB: u8 = 31;
+// pub const SYSREG_MCOUNTEREN_HPM30_MSB: u8 = 30;
+// pub const SYSREG_MCOUNTEREN_HPM30_LSB: u8 = 30;
+// pub const SYSREG_MCOUNTEREN_HPM29_MSB: u8 = 29;
+// pub const SYSREG_MCOUNTEREN_HPM29_LSB: u8 = 29;
+// pub const SYSREG_MCOUNTEREN_HPM28_MSB: u8 = 28;
+// pub const SYSREG_MCOUNTEREN_HPM28_LSB: u8 = 28;
+// pub const SYSREG_MCOUNTEREN_HPM27_MSB: u8 = 27;
+// pub const SYSREG_MCOUNTEREN_HPM27_LSB: u8 = 27;
+// pub const SYSREG_MCOUNTEREN_HPM26_MSB: u8 = 26;
+// pub const SYSREG_MCOUNTEREN_HPM26_LSB: u8 = 26;
+// pub const SYSREG_MCOUNTEREN_HPM25_MSB: u8 = 25;
+// pub const SYSREG_MCOUNTEREN_HPM25_LSB: u8 = 25;
+// pub const SYSREG_MCOUNTEREN_HPM24_MSB: u8 = 24;
+// pub const SYSREG_MCOUNTEREN_HPM24_LSB: u8 = 24;
+// pub const SYSREG_MCOUNTEREN_HPM23_MSB: u8 = 23;
+// pub const SYSREG_MCOUNTEREN_HPM23_LSB: u8 = 23;
+// pub const SYSREG_MCOUNTEREN_HPM22_MSB: u8 = 22;
+// pub const SYSREG_MCOUNTEREN_HPM22_LSB: u8 = 22;
+// pub const SYSREG_MCOUNTEREN_HPM21_MSB: u8 = 21;
+// pub const SYSREG_MCOUNTEREN_HPM21_LSB: u8 = 21;
+// pub const SYSREG_MCOUNTEREN_HPM20_MSB: u8 = 20;
+// pub const SYSREG_MCOUNTEREN_HPM20_LSB: u8 = 20;
+// pub const SYSREG_MCOUNTEREN_HPM19_MSB: u8 = 19;
+// pub const SYSREG_MCOUNTEREN_HPM19_LSB: u8 = 19;
+// pub const SYSREG_MCOUNTEREN_HPM18_MSB: u8 = 18;
+// pub const SYSREG_MCOUNTEREN_HPM18_LSB: u8 = 18;
+// pub const SYSREG_MCOUNTEREN_HPM17_MSB: u8 = 17;
+// pub const SYSREG_MCOUNTEREN_HPM17_LSB: u8 = 17;
+// pub const SYSREG_MCOUNTEREN_HPM16_MSB: u8 = 16;
+// pub const SYSREG_MCOUNTEREN_HPM16_LSB: u8 = 16;
+// pub const SYSREG_MCOUNTEREN_HPM15_MSB: u8 = 15;
+// pub const SYSREG_MCOUNTEREN_HPM15_LSB: u8 = 15;
+// pub const SYSREG_MCOUNTEREN_HPM14_MSB: u8 = 14;
+// pub const SYSREG_MCOUNTEREN_HPM14_LSB: u8 = 14;
+// pub const SYSREG_MCOUNTEREN_HPM13_MSB: u8 = 13;
+// pub const SYSREG_MCOUNTEREN_HPM13_LSB: u8 = 13;
+// pub const SYSREG_MCOUNTEREN_HPM12_MSB: u8 = 12;
+// pub const SYSREG_MCOUNTEREN_HPM12_LSB: u8 = 12;
+// pub const SYSREG_MCOUNTEREN_HPM11_MSB: u8 = 11;
+// pub const SYSREG_MCOUNTEREN_HPM11_LSB: u8 = 11;
+// pub const SYSREG_MCOUNTEREN_HPM10_MSB: u8 = 10;
+// pub const SYSREG_MCOUNTEREN_HPM10_LSB: u8 = 10;
+// pub const SYSREG_MCOUNTEREN_HPM9_MSB: u8 = 9;
+// pub const SYSREG_MCOUNTEREN_HPM9_LSB: u8 = 9;
+// pub const SYSREG_MCOUNTEREN_HPM8_MSB: u8 = 8;
+// pub const SYSREG_MCOUNTEREN_HPM8_LSB: u8 = 8;
+// pub const SYSREG_MCOUNTEREN_HPM7_MSB: u8 = 7;
+// pub const SYSREG_MCOUNTEREN_HPM7_LSB: u8 = 7;
+// pub const SYSREG_MCOUNTEREN_HPM6_MSB: u8 = 6;
+// pub const SYSREG_MCOUNTEREN_HPM6_LSB: u8 = 6;
+// pub const SYSREG_MCOUNTEREN_HPM5_MSB: u8 = 5;
+// pub const SYSREG_MCOUNTEREN_HPM5_LSB: u8 = 5;
+// pub const SYSREG_MCOUNTEREN_HPM4_MSB: u8 = 4;
+// pub const SYSREG_MCOUNTEREN_HPM4_LSB: u8 = 4;
+// pub const SYSREG_MCOUNTEREN_HPM3_MSB: u8 = 3;
+// pub const SYSREG_MCOUNTEREN_HPM3_LSB: u8 = 3;
+// pub const SYSREG_MCOUNTEREN_IR_MSB: u8 = 2;
+// pub const SYSREG_MCOUNTEREN_IR_LSB: u8 = 2;
+// pub const SYSREG_MCOUNTEREN_TM_MSB: u8 = 1;
+// pub const SYSREG_MCOUNTEREN_TM_LSB: u8 = 1;
+// pub const SYSREG_MCOUNTEREN_CY_MSB: u8 = 0;
+// pub const SYSREG_MCOUNTEREN_CY_LSB: u8 = 0;
+// pub const SYSREG_MCAUSE_INTERRUPT_MSB: u8 = 63;
+// pub const SYSREG_MCAUSE_INTERRUPT_LSB: u8 = 63;
+// pub const SYSREG_MCAUSE_EXCEPTION_CODE_MSB: u8 = 3;
+// pub const SYSREG_MCAUSE_EXCEPTION_CODE_LSB: u8 = 0;
+// pub const SYSREG_MIP_MEIP_MSB: u8 = 11;
+// pub const SYSREG_MIP_MEIP_LSB: u8 = 11;
+// pub const SYSREG_MIP_SEIP_MSB: u8 = 9;
+// pub const SYSREG_MIP_SEIP_LSB: u8 = 9;
+// pub const SYSREG_MIP_UEIP_MSB: u8 = 8;
+// pub const SYSREG_MIP_UEIP_LSB: u8 = 8;
+// pub const SYSREG_MIP_MTIP_MSB: u8 = 7;
+// pub const SYSREG_MIP_MTIP_LSB: u8 = 7;
+// pub const SYSREG_MIP_STIP_MSB: u8 = 5;
+// pub const SYSREG_MIP_STIP_LSB: u8 = 5;
+// pub const SYSREG_MIP_UTIP_MSB: u8 = 4;
+// pub const SYSREG_MIP_UTIP_LSB: u8 = 4;
+// pub const SYSREG_MIP_MSIP_MSB: u8 = 3;
+// pub const SYSREG_MIP_MSIP_LSB: u8 = 3;
+// pub const SYSREG_MIP_SSIP_MSB: u8 = 1;
+// pub const SYSREG_MIP_SSIP_LSB: u8 = 1;
+// pub const SYSREG_MIP_USIP_MSB: u8 = 0;
+// pub const SYSREG_MIP_USIP_LSB: u8 = 0;
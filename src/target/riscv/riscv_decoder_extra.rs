@@ -0,0 +1,442 @@
+use super::riscv_inst_id::RiscvInstId;
+
+pub fn decode_inst_ld_10_f3_110_r3_00000_f2_00_r2_00000_r1_00000_rd_00001_op_00000 (_inst: u32) -> Option<(RiscvInstId, usize)> {
+  // if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit32) {
+  //   return Some((RiscvInstId::C_FLWSP;
+  // } else if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit64) {
+    return Some((RiscvInstId::C_LDSP, 2));
+  // } else {
+  //   return Some((RiscvInstId::SENTINEL_MAX;
+  // }
+
+}
+
+
+pub fn decode_inst_ld_10_f3_110_r3_00000_f2_00_r2_00000_r1_00001_rd_00000_op_00000 (_inst: u32) -> Option<(RiscvInstId, usize)> {
+  // if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit32) {
+  //   return Some((RiscvInstId::C_FSWSP;
+  // } else if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit64) {
+    return Some((RiscvInstId::C_SDSP, 2));
+  // } else {
+  //   return Some((RiscvInstId::SENTINEL_MAX;
+  // }
+
+}
+
+
+pub fn decode_inst_ld_00_r3_00000_f2_00_r2_00000_r1_00000_f3_110_rd_00000_op_00000 (_inst: u32) -> Option<(RiscvInstId, usize)> {
+  // if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit32) {
+  //   return Some((RiscvInstId::C_FLW;
+  // } else if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit64) {
+    return Some((RiscvInstId::C_LD, 2));
+  // } else {
+  //   return Some((RiscvInstId::SENTINEL_MAX;
+  // }
+
+}
+
+
+pub fn decode_inst_ld_00_r3_00000_f2_00_r2_00000_r1_00001_f3_110_rd_00000_op_00000 (_inst: u32) -> Option<(RiscvInstId, usize)> {
+  // if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit32) {
+  //   return Some((RiscvInstId::C_FSW;
+  // } else if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit64) {
+    return Some((RiscvInstId::C_SD, 2));
+  // } else {
+  //   return Some((RiscvInstId::SENTINEL_MAX;
+  // }
+
+}
+
+
+pub fn decode_inst_ld_01_f3_010_r3_00000_f2_00_r2_00000_r1_00000_rd_00000_op_00000 (_inst: u32) -> Option<(RiscvInstId, usize)> {
+  // if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit32) {
+  //   return Some((RiscvInstId::C_JAL;
+  // } else if (m_pe_thread->GetBitMode() == RiscvBitMode_t::Bit64) {
+    return Some((RiscvInstId::C_ADDIW, 2));
+  // } else {
+  //   return Some((RiscvInstId::SENTINEL_MAX;
+  // }
+
+}
+
+
+// Instructions which are not covered by the generated decoder.
+// Called only when decode_inst() could not find the instruction.
+pub fn decode_inst_ext (inst: u32) -> Option<(RiscvInstId, usize)> {
+    if inst & 0x3 != 0x3 {
+        return decode_inst_zcb(inst & 0xffff);
+    }
+
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let rd     = (inst >> 7) & 0x1f;
+    let rs2    = (inst >> 20) & 0x1f;
+    let funct7 = inst >> 25;
+
+    if inst == 0x7b200073 {
+        return Some((RiscvInstId::DRET, 4));
+    }
+    match (opcode, funct3) {
+        (0x73, 0b000) if rd == 0 => match funct7 {
+            0b0010001 => Some((RiscvInstId::HFENCE_VVMA, 4)),
+            0b0110001 => Some((RiscvInstId::HFENCE_GVMA, 4)),
+            _ => None,
+        },
+        (0x73, 0b100) => decode_inst_hypervisor_ldst(funct7, rs2, rd),
+        (0x33, 0b101) if funct7 == 0b0000111 => Some((RiscvInstId::CZERO_EQZ, 4)),
+        (0x33, 0b111) if funct7 == 0b0000111 => Some((RiscvInstId::CZERO_NEZ, 4)),
+        // Zicbom / Zicboz. (prefetch.i / r / w of Zicbop are ORI hints to x0, nothing to decode)
+        (0x0f, 0b010) if rd == 0 => match inst >> 20 {
+            0 => Some((RiscvInstId::CBO_INVAL, 4)),
+            1 => Some((RiscvInstId::CBO_CLEAN, 4)),
+            2 => Some((RiscvInstId::CBO_FLUSH, 4)),
+            4 => Some((RiscvInstId::CBO_ZERO, 4)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn decode_inst_zcb (inst: u32) -> Option<(RiscvInstId, usize)> {
+    let quadrant = inst & 0x3;
+    let funct6   = inst >> 10;
+    let funct2   = (inst >> 5) & 0x3;
+    let low      = (inst >> 2) & 0x7;
+
+    match (quadrant, funct6) {
+        (0b00, 0b100000) => Some((RiscvInstId::C_LBU, 2)),
+        (0b00, 0b100001) if funct2 & 0x2 == 0 => Some((RiscvInstId::C_LHU, 2)),
+        (0b00, 0b100001) => Some((RiscvInstId::C_LH, 2)),
+        (0b00, 0b100010) => Some((RiscvInstId::C_SB, 2)),
+        (0b00, 0b100011) if funct2 & 0x2 == 0 => Some((RiscvInstId::C_SH, 2)),
+        (0b01, 0b100111) => match (funct2, low) {
+            (0b10, _)     => Some((RiscvInstId::C_MUL, 2)),
+            (0b11, 0b000) => Some((RiscvInstId::C_ZEXT_B, 2)),
+            (0b11, 0b001) => Some((RiscvInstId::C_SEXT_B, 2)),
+            (0b11, 0b010) => Some((RiscvInstId::C_ZEXT_H, 2)),
+            (0b11, 0b011) => Some((RiscvInstId::C_SEXT_H, 2)),
+            (0b11, 0b100) => Some((RiscvInstId::C_ZEXT_W, 2)),
+            (0b11, 0b101) => Some((RiscvInstId::C_NOT, 2)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Zfh.
+// Called before decode_inst() : the generated decoder does not check rs2 of FCVT.S.D / FCVT.D.S
+// and would take fcvt.s.h / fcvt.d.h for them.
+pub fn decode_inst_zfh (inst: u32) -> Option<(RiscvInstId, usize)> {
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let rs2    = (inst >> 20) & 0x1f;
+    let fmt    = (inst >> 25) & 0x3;
+    let funct7 = inst >> 25;
+
+    match opcode {
+        0x07 if funct3 == 0b001 => Some((RiscvInstId::FLH, 4)),
+        0x27 if funct3 == 0b001 => Some((RiscvInstId::FSH, 4)),
+        0x43 if fmt == 0b10 => Some((RiscvInstId::FMADD_H, 4)),
+        0x47 if fmt == 0b10 => Some((RiscvInstId::FMSUB_H, 4)),
+        0x4b if fmt == 0b10 => Some((RiscvInstId::FNMSUB_H, 4)),
+        0x4f if fmt == 0b10 => Some((RiscvInstId::FNMADD_H, 4)),
+        0x53 => match (funct7, rs2, funct3) {
+            (0b0000010, _, _) => Some((RiscvInstId::FADD_H, 4)),
+            (0b0000110, _, _) => Some((RiscvInstId::FSUB_H, 4)),
+            (0b0001010, _, _) => Some((RiscvInstId::FMUL_H, 4)),
+            (0b0001110, _, _) => Some((RiscvInstId::FDIV_H, 4)),
+            (0b0101110, 0b00000, _) => Some((RiscvInstId::FSQRT_H, 4)),
+            (0b0010010, _, 0b000) => Some((RiscvInstId::FSGNJ_H, 4)),
+            (0b0010010, _, 0b001) => Some((RiscvInstId::FSGNJN_H, 4)),
+            (0b0010010, _, 0b010) => Some((RiscvInstId::FSGNJX_H, 4)),
+            (0b0010110, _, 0b000) => Some((RiscvInstId::FMIN_H, 4)),
+            (0b0010110, _, 0b001) => Some((RiscvInstId::FMAX_H, 4)),
+            (0b0100000, 0b00010, _) => Some((RiscvInstId::FCVT_S_H, 4)),
+            (0b0100001, 0b00010, _) => Some((RiscvInstId::FCVT_D_H, 4)),
+            (0b0100010, 0b00000, _) => Some((RiscvInstId::FCVT_H_S, 4)),
+            (0b0100010, 0b00001, _) => Some((RiscvInstId::FCVT_H_D, 4)),
+            (0b1100010, 0b00000, _) => Some((RiscvInstId::FCVT_W_H, 4)),
+            (0b1100010, 0b00001, _) => Some((RiscvInstId::FCVT_WU_H, 4)),
+            (0b1100010, 0b00010, _) => Some((RiscvInstId::FCVT_L_H, 4)),
+            (0b1100010, 0b00011, _) => Some((RiscvInstId::FCVT_LU_H, 4)),
+            (0b1101010, 0b00000, _) => Some((RiscvInstId::FCVT_H_W, 4)),
+            (0b1101010, 0b00001, _) => Some((RiscvInstId::FCVT_H_WU, 4)),
+            (0b1101010, 0b00010, _) => Some((RiscvInstId::FCVT_H_L, 4)),
+            (0b1101010, 0b00011, _) => Some((RiscvInstId::FCVT_H_LU, 4)),
+            (0b1110010, 0b00000, 0b000) => Some((RiscvInstId::FMV_X_H, 4)),
+            (0b1110010, 0b00000, 0b001) => Some((RiscvInstId::FCLASS_H, 4)),
+            (0b1111010, 0b00000, 0b000) => Some((RiscvInstId::FMV_H_X, 4)),
+            (0b1010010, _, 0b010) => Some((RiscvInstId::FEQ_H, 4)),
+            (0b1010010, _, 0b001) => Some((RiscvInstId::FLT_H, 4)),
+            (0b1010010, _, 0b000) => Some((RiscvInstId::FLE_H, 4)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Zfa.
+// Called before decode_inst() : fli / fround share funct7 with FMV.W.X / FCVT.S.D, which the generated
+// decoder matches without checking rs2. fmvh.x.d / fmvp.d.x are defined for RV32 and accepted here as well.
+pub fn decode_inst_zfa (inst: u32) -> Option<(RiscvInstId, usize)> {
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let rs2    = (inst >> 20) & 0x1f;
+    let funct7 = inst >> 25;
+
+    if opcode != 0x53 {
+        return None;
+    }
+    match (funct7, rs2, funct3) {
+        (0b1111000, 0b00001, 0b000) => Some((RiscvInstId::FLI_S, 4)),
+        (0b1111001, 0b00001, 0b000) => Some((RiscvInstId::FLI_D, 4)),
+        (0b1111010, 0b00001, 0b000) => Some((RiscvInstId::FLI_H, 4)),
+        (0b0010100, _, 0b010) => Some((RiscvInstId::FMINM_S, 4)),
+        (0b0010100, _, 0b011) => Some((RiscvInstId::FMAXM_S, 4)),
+        (0b0010101, _, 0b010) => Some((RiscvInstId::FMINM_D, 4)),
+        (0b0010101, _, 0b011) => Some((RiscvInstId::FMAXM_D, 4)),
+        (0b0010110, _, 0b010) => Some((RiscvInstId::FMINM_H, 4)),
+        (0b0010110, _, 0b011) => Some((RiscvInstId::FMAXM_H, 4)),
+        (0b0100000, 0b00100, _) => Some((RiscvInstId::FROUND_S, 4)),
+        (0b0100000, 0b00101, _) => Some((RiscvInstId::FROUNDNX_S, 4)),
+        (0b0100001, 0b00100, _) => Some((RiscvInstId::FROUND_D, 4)),
+        (0b0100001, 0b00101, _) => Some((RiscvInstId::FROUNDNX_D, 4)),
+        (0b0100010, 0b00100, _) => Some((RiscvInstId::FROUND_H, 4)),
+        (0b0100010, 0b00101, _) => Some((RiscvInstId::FROUNDNX_H, 4)),
+        (0b1100001, 0b01000, 0b001) => Some((RiscvInstId::FCVTMOD_W_D, 4)),
+        (0b1010000, _, 0b100) => Some((RiscvInstId::FLEQ_S, 4)),
+        (0b1010000, _, 0b101) => Some((RiscvInstId::FLTQ_S, 4)),
+        (0b1010001, _, 0b100) => Some((RiscvInstId::FLEQ_D, 4)),
+        (0b1010001, _, 0b101) => Some((RiscvInstId::FLTQ_D, 4)),
+        (0b1010010, _, 0b100) => Some((RiscvInstId::FLEQ_H, 4)),
+        (0b1010010, _, 0b101) => Some((RiscvInstId::FLTQ_H, 4)),
+        (0b1110001, 0b00001, 0b000) => Some((RiscvInstId::FMVH_X_D, 4)),
+        (0b1011001, _, 0b000) => Some((RiscvInstId::FMVP_D_X, 4)),
+        _ => None,
+    }
+}
+
+// V.
+// Vector loads / stores share LOAD-FP / STORE-FP with the scalar ones and are told apart by the width field.
+// OP-V instructions are decoded by operand type only : the helpers decode funct6.
+pub fn decode_inst_vector (inst: u32) -> Option<(RiscvInstId, usize)> {
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let umop   = (inst >> 20) & 0x1f;
+    let mop    = (inst >> 26) & 0x3;
+    let mew    = (inst >> 28) & 0x1;
+    let nf     = inst >> 29;
+
+    match opcode {
+        0x07 | 0x27 if matches!(funct3, 0b000 | 0b101 | 0b110 | 0b111) && mew == 0 => {
+            let is_load = opcode == 0x07;
+            match (mop, is_load) {
+                (0b00, _) => {
+                    let valid = match umop {
+                        0b00000 => true,
+                        0b01000 => (nf + 1).is_power_of_two() && (is_load || funct3 == 0b000),   // whole register
+                        0b01011 => funct3 == 0b000 && nf == 0,                                   // vlm.v / vsm.v
+                        0b10000 => is_load,                                                      // fault-only-first
+                        _ => false,
+                    };
+                    if !valid {
+                        return None;
+                    }
+                    if is_load { Some((RiscvInstId::VLE_V, 4)) } else { Some((RiscvInstId::VSE_V, 4)) }
+                }
+                (0b01, true) => Some((RiscvInstId::VLUXEI_V, 4)),
+                (0b01, false) => Some((RiscvInstId::VSUXEI_V, 4)),
+                (0b10, true) => Some((RiscvInstId::VLSE_V, 4)),
+                (0b10, false) => Some((RiscvInstId::VSSE_V, 4)),
+                (_, true) => Some((RiscvInstId::VLOXEI_V, 4)),
+                (_, false) => Some((RiscvInstId::VSOXEI_V, 4)),
+            }
+        }
+        0x57 => match funct3 {
+            0b000 => Some((RiscvInstId::OPIVV, 4)),
+            0b001 => Some((RiscvInstId::OPFVV, 4)),
+            0b010 => Some((RiscvInstId::OPMVV, 4)),
+            0b011 => Some((RiscvInstId::OPIVI, 4)),
+            0b100 => Some((RiscvInstId::OPIVX, 4)),
+            0b101 => Some((RiscvInstId::OPFVF, 4)),
+            0b110 => Some((RiscvInstId::OPMVX, 4)),
+            _ => match inst >> 30 {
+                0b00 | 0b01 => Some((RiscvInstId::VSETVLI, 4)),
+                0b11 => Some((RiscvInstId::VSETIVLI, 4)),
+                _ if inst >> 25 == 0b1000000 => Some((RiscvInstId::VSETVL, 4)),
+                _ => None,
+            },
+        },
+        _ => None,
+    }
+}
+
+// Zkn / Zks / Zbkb / Zbkx.
+// Called before decode_inst() : the generated decoder does not check the upper bits of SLLI / SRLI
+// and would take aes64im, sha256sum0, brev8 etc. for shifts.
+pub fn decode_inst_crypto (inst: u32) -> Option<(RiscvInstId, usize)> {
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let funct5 = (inst >> 25) & 0x1f;
+    let funct7 = inst >> 25;
+    let imm12  = inst >> 20;
+
+    match (opcode, funct3) {
+        // OP-IMM
+        (0x13, 0b001) => match imm12 {
+            0x100 => Some((RiscvInstId::SHA256SUM0, 4)),
+            0x101 => Some((RiscvInstId::SHA256SUM1, 4)),
+            0x102 => Some((RiscvInstId::SHA256SIG0, 4)),
+            0x103 => Some((RiscvInstId::SHA256SIG1, 4)),
+            0x104 => Some((RiscvInstId::SHA512SUM0, 4)),
+            0x105 => Some((RiscvInstId::SHA512SUM1, 4)),
+            0x106 => Some((RiscvInstId::SHA512SIG0, 4)),
+            0x107 => Some((RiscvInstId::SHA512SIG1, 4)),
+            0x108 => Some((RiscvInstId::SM3P0, 4)),
+            0x109 => Some((RiscvInstId::SM3P1, 4)),
+            0x300 => Some((RiscvInstId::AES64IM, 4)),
+            // rnum > 0xA is reserved
+            0x310..=0x31a => Some((RiscvInstId::AES64KS1I, 4)),
+            _ => None,
+        },
+        (0x13, 0b101) if imm12 == 0x687 => Some((RiscvInstId::BREV8, 4)),
+        // OP
+        (0x33, 0b000) => match (funct5, funct7) {
+            (_, 0b0011001) => Some((RiscvInstId::AES64ES, 4)),
+            (_, 0b0011011) => Some((RiscvInstId::AES64ESM, 4)),
+            (_, 0b0011101) => Some((RiscvInstId::AES64DS, 4)),
+            (_, 0b0011111) => Some((RiscvInstId::AES64DSM, 4)),
+            (_, 0b0111111) => Some((RiscvInstId::AES64KS2, 4)),
+            // bs is in inst[31:30]
+            (0b11000, _) => Some((RiscvInstId::SM4ED, 4)),
+            (0b11010, _) => Some((RiscvInstId::SM4KS, 4)),
+            _ => None,
+        },
+        (0x33, _) => match (funct7, funct3) {
+            (0b0000100, 0b100) => Some((RiscvInstId::PACK, 4)),
+            (0b0000100, 0b111) => Some((RiscvInstId::PACKH, 4)),
+            (0b0010100, 0b010) => Some((RiscvInstId::XPERM4, 4)),
+            (0b0010100, 0b100) => Some((RiscvInstId::XPERM8, 4)),
+            _ => None,
+        },
+        // OP-32 : packw with rs2 = x0 is zext.h, which decode_inst_bitmanip takes first
+        (0x3b, 0b100) if funct7 == 0b0000100 => Some((RiscvInstId::PACKW, 4)),
+        _ => None,
+    }
+}
+
+// Zcmp.
+// Called before decode_inst() when --zcmp is given : Zcmp takes over the C.FSDSP encodings.
+pub fn decode_inst_zcmp (inst: u32) -> Option<(RiscvInstId, usize)> {
+    if inst & 0xe003 != 0xa002 {
+        return None;
+    }
+    let rlist = (inst >> 4) & 0xf;
+
+    match ((inst >> 8) & 0x1f, (inst >> 5) & 0x3) {
+        (0b11000, _) if rlist >= 4 => Some((RiscvInstId::CM_PUSH, 2)),
+        (0b11010, _) if rlist >= 4 => Some((RiscvInstId::CM_POP, 2)),
+        (0b11100, _) if rlist >= 4 => Some((RiscvInstId::CM_POPRETZ, 2)),
+        (0b11110, _) if rlist >= 4 => Some((RiscvInstId::CM_POPRET, 2)),
+        (f5, 0b01) if f5 & 0x1c == 0x0c => Some((RiscvInstId::CM_MVSA01, 2)),
+        (f5, 0b11) if f5 & 0x1c == 0x0c => Some((RiscvInstId::CM_MVA01S, 2)),
+        _ => None,
+    }
+}
+
+fn decode_inst_hypervisor_ldst (funct7: u32, rs2: u32, rd: u32) -> Option<(RiscvInstId, usize)> {
+    match (funct7, rs2) {
+        (0b0110000, 0b00000) => Some((RiscvInstId::HLV_B, 4)),
+        (0b0110000, 0b00001) => Some((RiscvInstId::HLV_BU, 4)),
+        (0b0110010, 0b00000) => Some((RiscvInstId::HLV_H, 4)),
+        (0b0110010, 0b00001) => Some((RiscvInstId::HLV_HU, 4)),
+        (0b0110010, 0b00011) => Some((RiscvInstId::HLVX_HU, 4)),
+        (0b0110100, 0b00000) => Some((RiscvInstId::HLV_W, 4)),
+        (0b0110100, 0b00001) => Some((RiscvInstId::HLV_WU, 4)),
+        (0b0110100, 0b00011) => Some((RiscvInstId::HLVX_WU, 4)),
+        (0b0110110, 0b00000) => Some((RiscvInstId::HLV_D, 4)),
+        (0b0110001, _) if rd == 0 => Some((RiscvInstId::HSV_B, 4)),
+        (0b0110011, _) if rd == 0 => Some((RiscvInstId::HSV_H, 4)),
+        (0b0110101, _) if rd == 0 => Some((RiscvInstId::HSV_W, 4)),
+        (0b0110111, _) if rd == 0 => Some((RiscvInstId::HSV_D, 4)),
+        _ => None,
+    }
+}
+
+// Zba / Zbb / Zbc / Zbs.
+// Called before decode_inst() : the generated decoder does not check the upper bits of
+// SLLI / SLLIW and would take clz, bseti, slli.uw etc. for shifts.
+pub fn decode_inst_bitmanip (inst: u32) -> Option<(RiscvInstId, usize)> {
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let rs2    = (inst >> 20) & 0x1f;
+    let funct6 = inst >> 26;
+    let funct7 = inst >> 25;
+    let imm12  = inst >> 20;
+
+    match (opcode, funct3) {
+        // OP-IMM
+        (0x13, 0b001) => match (funct6, imm12) {
+            (_, 0x600) => Some((RiscvInstId::CLZ, 4)),
+            (_, 0x601) => Some((RiscvInstId::CTZ, 4)),
+            (_, 0x602) => Some((RiscvInstId::CPOP, 4)),
+            (_, 0x604) => Some((RiscvInstId::SEXT_B, 4)),
+            (_, 0x605) => Some((RiscvInstId::SEXT_H, 4)),
+            (0b001010, _) => Some((RiscvInstId::BSETI, 4)),
+            (0b010010, _) => Some((RiscvInstId::BCLRI, 4)),
+            (0b011010, _) => Some((RiscvInstId::BINVI, 4)),
+            _ => None,
+        },
+        (0x13, 0b101) => match (funct6, imm12) {
+            (_, 0x287) => Some((RiscvInstId::ORC_B, 4)),
+            (_, 0x6b8) => Some((RiscvInstId::REV8, 4)),
+            (0b011000, _) => Some((RiscvInstId::RORI, 4)),
+            (0b010010, _) => Some((RiscvInstId::BEXTI, 4)),
+            _ => None,
+        },
+        // OP-IMM-32
+        (0x1b, 0b001) => match (funct6, imm12) {
+            (_, 0x600) => Some((RiscvInstId::CLZW, 4)),
+            (_, 0x601) => Some((RiscvInstId::CTZW, 4)),
+            (_, 0x602) => Some((RiscvInstId::CPOPW, 4)),
+            (0b000010, _) => Some((RiscvInstId::SLLI_UW, 4)),
+            _ => None,
+        },
+        (0x1b, 0b101) if funct7 == 0b0110000 => Some((RiscvInstId::RORIW, 4)),
+        // OP
+        (0x33, _) => match (funct7, funct3) {
+            (0b0010000, 0b010) => Some((RiscvInstId::SH1ADD, 4)),
+            (0b0010000, 0b100) => Some((RiscvInstId::SH2ADD, 4)),
+            (0b0010000, 0b110) => Some((RiscvInstId::SH3ADD, 4)),
+            (0b0100000, 0b111) => Some((RiscvInstId::ANDN, 4)),
+            (0b0100000, 0b110) => Some((RiscvInstId::ORN, 4)),
+            (0b0100000, 0b100) => Some((RiscvInstId::XNOR, 4)),
+            (0b0000101, 0b001) => Some((RiscvInstId::CLMUL, 4)),
+            (0b0000101, 0b010) => Some((RiscvInstId::CLMULR, 4)),
+            (0b0000101, 0b011) => Some((RiscvInstId::CLMULH, 4)),
+            (0b0000101, 0b100) => Some((RiscvInstId::MIN, 4)),
+            (0b0000101, 0b101) => Some((RiscvInstId::MINU, 4)),
+            (0b0000101, 0b110) => Some((RiscvInstId::MAX, 4)),
+            (0b0000101, 0b111) => Some((RiscvInstId::MAXU, 4)),
+            (0b0110000, 0b001) => Some((RiscvInstId::ROL, 4)),
+            (0b0110000, 0b101) => Some((RiscvInstId::ROR, 4)),
+            (0b0100100, 0b001) => Some((RiscvInstId::BCLR, 4)),
+            (0b0100100, 0b101) => Some((RiscvInstId::BEXT, 4)),
+            (0b0110100, 0b001) => Some((RiscvInstId::BINV, 4)),
+            (0b0010100, 0b001) => Some((RiscvInstId::BSET, 4)),
+            _ => None,
+        },
+        // OP-32
+        (0x3b, _) => match (funct7, funct3) {
+            (0b0000100, 0b000) => Some((RiscvInstId::ADD_UW, 4)),
+            (0b0000100, 0b100) if rs2 == 0 => Some((RiscvInstId::ZEXT_H, 4)),
+            (0b0010000, 0b010) => Some((RiscvInstId::SH1ADD_UW, 4)),
+            (0b0010000, 0b100) => Some((RiscvInstId::SH2ADD_UW, 4)),
+            (0b0010000, 0b110) => Some((RiscvInstId::SH3ADD_UW, 4)),
+            (0b0110000, 0b001) => Some((RiscvInstId::ROLW, 4)),
+            (0b0110000, 0b101) => Some((RiscvInstId::RORW, 4)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,455 @@
+use super::super::super::tcg::tcg::{TCGOp, TCGOpcode, TCGv};
+use super::super::super::instr_info::InstrInfo;
+
+use super::super::super::get_rs1_addr;
+use super::super::super::get_rs2_addr;
+use super::super::super::get_rd_addr;
+
+use super::riscv::TranslateRiscv;
+
+impl TranslateRiscv {
+    // rd = op(rs1)
+    fn translate_rr(&mut self, op: TCGOpcode, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::new_2op(op, source1, source1),
+            TCGOp::tcg_set_gpr(rd_addr, source1),
+        ];
+
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    // rd = rs1 op ~rs2
+    fn translate_rrr_inv(&mut self, op: TCGOpcode, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rs2_addr = get_rs2_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::tcg_get_gpr(source2, rs2_addr),
+            TCGOp::new_3op(TCGOpcode::XOR_64BIT, source2, source2, TCGv::new_imm(u64::MAX)),
+            TCGOp::new_3op(op, source1, source1, source2),
+            TCGOp::tcg_set_gpr(rd_addr, source1),
+        ];
+
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    // rd = (rs1 << shamt) + rs2, rs1 is zero-extended from 32-bit for the .uw forms
+    fn translate_shadd(&mut self, shamt: u64, zext: bool, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rs2_addr = get_rs2_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+
+        let mut tcg_list = vec![];
+
+        tcg_list.push(TCGOp::tcg_get_gpr(source1, rs1_addr));
+        tcg_list.push(TCGOp::tcg_get_gpr(source2, rs2_addr));
+        if zext {
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(32)));
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SRL_64BIT, source1, source1, TCGv::new_imm(32 - shamt)));
+        } else {
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(shamt)));
+        }
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::ADD_64BIT, source1, source1, source2));
+        tcg_list.push(TCGOp::tcg_set_gpr(rd_addr, source1));
+
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    // rd = (rs1 << shift) >> shift
+    fn translate_ext(&mut self, shift: u64, shift_op: TCGOpcode, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(shift)),
+            TCGOp::new_3op(shift_op, source1, source1, TCGv::new_imm(shift)),
+            TCGOp::tcg_set_gpr(rd_addr, source1),
+        ];
+
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    // rd = (rs1 < rs2) ? rs1 : rs2 with cmp_op, operands are swapped for max
+    fn translate_minmax(&mut self, cmp_op: TCGOpcode, is_max: bool, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rs2_addr = get_rs2_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+        let mask = self.tcg_temp_new();
+
+        let mut tcg_list = vec![];
+
+        tcg_list.push(TCGOp::tcg_get_gpr(source1, rs1_addr));
+        tcg_list.push(TCGOp::tcg_get_gpr(source2, rs2_addr));
+        if is_max {
+            tcg_list.push(TCGOp::new_3op(cmp_op, mask, source2, source1));
+        } else {
+            tcg_list.push(TCGOp::new_3op(cmp_op, mask, source1, source2));
+        }
+        // mask = 0 to select rs1, all ones to select rs2
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::SUB_64BIT, mask, mask, TCGv::new_imm(1)));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::XOR_64BIT, source2, source2, source1));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::AND_64BIT, source2, source2, mask));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::XOR_64BIT, source1, source1, source2));
+        tcg_list.push(TCGOp::tcg_set_gpr(rd_addr, source1));
+
+        self.tcg_temp_free(mask);
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    // rd = rs1 op (1 << rs2[5:0]), the bit is inverted for bclr
+    fn translate_bit_r(&mut self, op: TCGOpcode, invert: bool, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rs2_addr = get_rs2_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+        let bit = self.tcg_temp_new();
+
+        let mut tcg_list = vec![];
+
+        tcg_list.push(TCGOp::tcg_get_gpr(source1, rs1_addr));
+        tcg_list.push(TCGOp::tcg_get_gpr(source2, rs2_addr));
+        tcg_list.push(TCGOp::new_2op(TCGOpcode::MOV_IMM_64BIT, bit, TCGv::new_imm(1)));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::ROL_64BIT, bit, bit, source2));
+        if invert {
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::XOR_64BIT, bit, bit, TCGv::new_imm(u64::MAX)));
+        }
+        tcg_list.push(TCGOp::new_3op(op, source1, source1, bit));
+        tcg_list.push(TCGOp::tcg_set_gpr(rd_addr, source1));
+
+        self.tcg_temp_free(bit);
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    // rd = rs1 op (1 << shamt), the bit is inverted for bclri
+    fn translate_bit_i(&mut self, op: TCGOpcode, invert: bool, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let shamt = ((inst.inst >> 20) & 0x3f) as u64;
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let bit = if invert { !(1u64 << shamt) } else { 1u64 << shamt };
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::new_2op(TCGOpcode::MOV_IMM_64BIT, source2, TCGv::new_imm(bit)),
+            TCGOp::new_3op(op, source1, source1, source2),
+            TCGOp::tcg_set_gpr(rd_addr, source1),
+        ];
+
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    /* Zba */
+    pub fn translate_add_uw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(0, true, inst)
+    }
+    pub fn translate_sh1add(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(1, false, inst)
+    }
+    pub fn translate_sh2add(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(2, false, inst)
+    }
+    pub fn translate_sh3add(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(3, false, inst)
+    }
+    pub fn translate_sh1add_uw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(1, true, inst)
+    }
+    pub fn translate_sh2add_uw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(2, true, inst)
+    }
+    pub fn translate_sh3add_uw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shadd(3, true, inst)
+    }
+
+    pub fn translate_slli_uw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let shamt = ((inst.inst >> 20) & 0x3f) as u64;
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+
+        let mut tcg_list = vec![];
+
+        tcg_list.push(TCGOp::tcg_get_gpr(source1, rs1_addr));
+        if shamt < 32 {
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(32)));
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SRL_64BIT, source1, source1, TCGv::new_imm(32 - shamt)));
+        } else {
+            // upper 32-bits of rs1 are shifted out anyway
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(shamt)));
+        }
+        tcg_list.push(TCGOp::tcg_set_gpr(rd_addr, source1));
+
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    /* Zbb */
+    pub fn translate_andn(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr_inv(TCGOpcode::AND_64BIT, inst)
+    }
+    pub fn translate_orn(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr_inv(TCGOpcode::OR_64BIT, inst)
+    }
+    pub fn translate_xnor(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr_inv(TCGOpcode::XOR_64BIT, inst)
+    }
+
+    pub fn translate_clz(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::CLZ_64BIT, inst)
+    }
+    pub fn translate_ctz(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::CTZ_64BIT, inst)
+    }
+    pub fn translate_cpop(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::CPOP_64BIT, inst)
+    }
+    pub fn translate_clzw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::CLZ_32BIT, inst)
+    }
+    pub fn translate_ctzw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::CTZ_32BIT, inst)
+    }
+    pub fn translate_cpopw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::CPOP_32BIT, inst)
+    }
+
+    pub fn translate_max(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_minmax(TCGOpcode::SLT_64BIT, true, inst)
+    }
+    pub fn translate_maxu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_minmax(TCGOpcode::SLTU_64BIT, true, inst)
+    }
+    pub fn translate_min(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_minmax(TCGOpcode::SLT_64BIT, false, inst)
+    }
+    pub fn translate_minu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_minmax(TCGOpcode::SLTU_64BIT, false, inst)
+    }
+
+    pub fn translate_sext_b(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_ext(56, TCGOpcode::SRA_64BIT, inst)
+    }
+    pub fn translate_sext_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_ext(48, TCGOpcode::SRA_64BIT, inst)
+    }
+    pub fn translate_zext_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_ext(48, TCGOpcode::SRL_64BIT, inst)
+    }
+
+    pub fn translate_rol(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr(TCGOpcode::ROL_64BIT, inst)
+    }
+    pub fn translate_ror(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr(TCGOpcode::ROR_64BIT, inst)
+    }
+    pub fn translate_rori(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shift_i(TCGOpcode::ROR_64BIT, inst)
+    }
+    pub fn translate_rolw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr_32bit(TCGOpcode::ROL_32BIT, inst)
+    }
+    pub fn translate_rorw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr_32bit(TCGOpcode::ROR_32BIT, inst)
+    }
+    pub fn translate_roriw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_shift_i(TCGOpcode::ROR_32BIT, inst)
+    }
+
+    pub fn translate_orc_b(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let result = self.tcg_temp_new();
+        let mask = self.tcg_temp_new();
+
+        // Bit 7 of each byte is set if the byte is not zero : ((x & 0x7f) + 0x7f) | x
+        // Then spread bit 7 to the whole byte by multiplying 0xff.
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::new_2op(TCGOpcode::MOV_IMM_64BIT, result, TCGv::new_imm(0x7f7f_7f7f_7f7f_7f7f)),
+            TCGOp::new_2op(TCGOpcode::MOV_IMM_64BIT, mask, TCGv::new_imm(0x7f7f_7f7f_7f7f_7f7f)),
+            TCGOp::new_3op(TCGOpcode::AND_64BIT, result, result, source1),
+            TCGOp::new_3op(TCGOpcode::ADD_64BIT, result, result, mask),
+            TCGOp::new_3op(TCGOpcode::OR_64BIT, result, result, source1),
+            TCGOp::new_2op(TCGOpcode::MOV_IMM_64BIT, mask, TCGv::new_imm(0x8080_8080_8080_8080)),
+            TCGOp::new_3op(TCGOpcode::AND_64BIT, result, result, mask),
+            TCGOp::new_3op(TCGOpcode::SRL_64BIT, result, result, TCGv::new_imm(7)),
+            TCGOp::new_2op(TCGOpcode::MOV_IMM_64BIT, mask, TCGv::new_imm(0xff)),
+            TCGOp::new_3op(TCGOpcode::MUL_64BIT, result, result, mask),
+            TCGOp::tcg_set_gpr(rd_addr, result),
+        ];
+
+        self.tcg_temp_free(mask);
+        self.tcg_temp_free(result);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    pub fn translate_rev8(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rr(TCGOpcode::BSWAP_64BIT, inst)
+    }
+
+    /* Zbc */
+    pub fn translate_clmul(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr(TCGOpcode::CLMUL_64BIT, inst)
+    }
+    pub fn translate_clmulh(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr(TCGOpcode::CLMULH_64BIT, inst)
+    }
+    pub fn translate_clmulr(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_rrr(TCGOpcode::CLMULR_64BIT, inst)
+    }
+
+    /* Zbs */
+    pub fn translate_bclr(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_bit_r(TCGOpcode::AND_64BIT, true, inst)
+    }
+    pub fn translate_bset(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_bit_r(TCGOpcode::OR_64BIT, false, inst)
+    }
+    pub fn translate_binv(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_bit_r(TCGOpcode::XOR_64BIT, false, inst)
+    }
+    pub fn translate_bclri(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_bit_i(TCGOpcode::AND_64BIT, true, inst)
+    }
+    pub fn translate_bseti(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_bit_i(TCGOpcode::OR_64BIT, false, inst)
+    }
+    pub fn translate_binvi(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_bit_i(TCGOpcode::XOR_64BIT, false, inst)
+    }
+
+    pub fn translate_bext(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rs2_addr = get_rs2_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::tcg_get_gpr(source2, rs2_addr),
+            TCGOp::new_3op(TCGOpcode::ROR_64BIT, source1, source1, source2),
+            TCGOp::new_3op(TCGOpcode::AND_64BIT, source1, source1, TCGv::new_imm(1)),
+            TCGOp::tcg_set_gpr(rd_addr, source1),
+        ];
+
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    pub fn translate_bexti(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let shamt = ((inst.inst >> 20) & 0x3f) as u64;
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+
+        let tcg_list = vec![
+            TCGOp::tcg_get_gpr(source1, rs1_addr),
+            TCGOp::new_3op(TCGOpcode::SRL_64BIT, source1, source1, TCGv::new_imm(shamt)),
+            TCGOp::new_3op(TCGOpcode::AND_64BIT, source1, source1, TCGv::new_imm(1)),
+            TCGOp::tcg_set_gpr(rd_addr, source1),
+        ];
+
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+}
@@ -0,0 +1,104 @@
+use super::super::super::tcg::tcg::{TCGOp, TCGOpcode, TCGv};
+use super::super::super::instr_info::InstrInfo;
+use super::riscv::{CallCryptoIdx, CALL_HELPER_IDX, CRYPTO_IMM_SHIFT};
+
+use super::super::super::get_rs1_addr;
+use super::super::super::get_rs2_addr;
+use super::super::super::get_rd_addr;
+
+use super::riscv::TranslateRiscv;
+
+impl TranslateRiscv {
+    // Done by the helper on register indices. The helper does not write x0.
+    fn translate_crypto(&mut self, op: CallCryptoIdx, imm: u64, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rs2 = TCGv::new_reg(get_rs2_addr!(inst.inst) as u64);
+        let crypto_op = TCGv::new_imm(op as u64 | imm << CRYPTO_IMM_SHIFT);
+        vec![TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_CRYPTO_IDX as usize, crypto_op, rd, rs1, rs2)]
+    }
+
+    // rd = rs2[bits-1:0] : rs1[bits-1:0], sign-extended from 32-bit for packw
+    fn translate_pack_bits(&mut self, bits: u64, sext_32: bool, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1_addr = get_rs1_addr!(inst.inst);
+        let rs2_addr = get_rs2_addr!(inst.inst);
+        let rd_addr = get_rd_addr!(inst.inst);
+
+        if rd_addr == 0 {
+            return vec![];
+        }
+
+        let source1 = self.tcg_temp_new();
+        let source2 = self.tcg_temp_new();
+
+        let mut tcg_list = vec![];
+
+        tcg_list.push(TCGOp::tcg_get_gpr(source1, rs1_addr));
+        tcg_list.push(TCGOp::tcg_get_gpr(source2, rs2_addr));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(64 - bits)));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::SRL_64BIT, source1, source1, TCGv::new_imm(64 - bits)));
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source2, source2, TCGv::new_imm(64 - bits)));
+        if bits < 32 {
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SRL_64BIT, source2, source2, TCGv::new_imm(64 - 2 * bits)));
+        }
+        tcg_list.push(TCGOp::new_3op(TCGOpcode::OR_64BIT, source1, source1, source2));
+        if sext_32 {
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SLL_64BIT, source1, source1, TCGv::new_imm(32)));
+            tcg_list.push(TCGOp::new_3op(TCGOpcode::SRA_64BIT, source1, source1, TCGv::new_imm(32)));
+        }
+        tcg_list.push(TCGOp::tcg_set_gpr(rd_addr, source1));
+
+        self.tcg_temp_free(source2);
+        self.tcg_temp_free(source1);
+
+        tcg_list
+    }
+
+    /* Zkne / Zknd */
+    pub fn translate_aes64es  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::AES64ES , 0, inst) }
+    pub fn translate_aes64esm (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::AES64ESM, 0, inst) }
+    pub fn translate_aes64ds  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::AES64DS , 0, inst) }
+    pub fn translate_aes64dsm (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::AES64DSM, 0, inst) }
+    pub fn translate_aes64im  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::AES64IM , 0, inst) }
+    pub fn translate_aes64ks2 (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::AES64KS2, 0, inst) }
+    pub fn translate_aes64ks1i(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rnum = ((inst.inst >> 20) & 0xf) as u64;
+        self.translate_crypto(CallCryptoIdx::AES64KS1I, rnum, inst)
+    }
+
+    /* Zknh */
+    pub fn translate_sha256sig0(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA256SIG0, 0, inst) }
+    pub fn translate_sha256sig1(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA256SIG1, 0, inst) }
+    pub fn translate_sha256sum0(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA256SUM0, 0, inst) }
+    pub fn translate_sha256sum1(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA256SUM1, 0, inst) }
+    pub fn translate_sha512sig0(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA512SIG0, 0, inst) }
+    pub fn translate_sha512sig1(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA512SIG1, 0, inst) }
+    pub fn translate_sha512sum0(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA512SUM0, 0, inst) }
+    pub fn translate_sha512sum1(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SHA512SUM1, 0, inst) }
+
+    /* Zksh / Zksed */
+    pub fn translate_sm3p0(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SM3P0, 0, inst) }
+    pub fn translate_sm3p1(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::SM3P1, 0, inst) }
+    pub fn translate_sm4ed(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let bs = ((inst.inst >> 30) & 0x3) as u64;
+        self.translate_crypto(CallCryptoIdx::SM4ED, bs, inst)
+    }
+    pub fn translate_sm4ks(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let bs = ((inst.inst >> 30) & 0x3) as u64;
+        self.translate_crypto(CallCryptoIdx::SM4KS, bs, inst)
+    }
+
+    /* Zbkb / Zbkx : rol, ror, andn, rev8 etc. are shared with Zbb */
+    pub fn translate_pack(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_pack_bits(32, false, inst)
+    }
+    pub fn translate_packh(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_pack_bits(8, false, inst)
+    }
+    pub fn translate_packw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_pack_bits(16, true, inst)
+    }
+    pub fn translate_brev8 (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::BREV8 , 0, inst) }
+    pub fn translate_xperm4(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::XPERM4, 0, inst) }
+    pub fn translate_xperm8(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_crypto(CallCryptoIdx::XPERM8, 0, inst) }
+}
@@ -0,0 +1,842 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::super::super::tcg::tcg::{TCGOp, TCGOpcode, TCGv, TCGLabel};
+use super::super::super::instr_info::InstrInfo;
+use super::riscv::{CALL_HELPER_IDX, CallFcvtIdx};
+
+use super::super::super::get_rs1_addr;
+use super::super::super::get_rs2_addr;
+use super::super::super::get_rs3_addr;
+use super::super::super::get_rd_addr;
+
+use super::riscv::TranslateRiscv;
+
+impl TranslateRiscv {
+    pub fn translate_fld(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let imm_const: u64 = ((inst.inst as i32) >> 20) as u64;
+        
+
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let imm = Box::new(TCGv::new_imm(imm_const));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FLOAT_LOAD64_IDX as usize, *rd, *rs1, *imm, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));        
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+
+        // Self::translate_float_rri(TCGOpcode::LOAD_FLOAT_64BIT, inst)
+    }
+    pub fn translate_flw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let imm_const: u64 = ((inst.inst as i32) >> 20) as u64;
+        
+
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let imm = Box::new(TCGv::new_imm(imm_const));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FLOAT_LOAD32_IDX as usize, *rd, *rs1, *imm, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));        
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+
+        // Self::translate_float_rri(TCGOpcode::LOAD_FLOAT_32BIT, inst)
+    }
+    pub fn translate_fsd(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let imm_const: u64 = ((inst.inst as i32) >> 20) as u64;
+        
+
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let imm = Box::new(TCGv::new_imm(imm_const));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FLOAT_STORE64_IDX as usize, *rd, *rs1, *imm, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));        
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+
+        // Self::translate_store(TCGOpcode::STORE_FLOAT_64BIT, inst)
+    }
+    pub fn translate_fsw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let imm_const: u64 = ((inst.inst as i32) >> 20) as u64;
+        
+
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let imm = Box::new(TCGv::new_imm(imm_const));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FLOAT_STORE32_IDX as usize, *rd, *rs1, *imm, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));        
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+
+        // Self::translate_store(TCGOpcode::STORE_FLOAT_32BIT, inst)
+    }
+
+    pub fn translate_fadd_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fadd_d =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FADD_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![fadd_d]
+    }
+
+    pub fn translate_fsub_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fsub_d =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FSUB_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![fsub_d]
+    }
+
+    pub fn translate_fmul_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fmul_d =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FMUL_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![fmul_d]
+    }
+
+    pub fn translate_fmadd_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FMADD_D_IDX as usize, *rd, *rs1, *rs2, *rs3);        vec![fop]
+    }
+
+    pub fn translate_fmsub_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FMSUB_D_IDX as usize, *rd, *rs1, *rs2, *rs3);        vec![fop]
+    }
+
+    pub fn translate_fnmsub_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FNMSUB_D_IDX as usize, *rd, *rs1, *rs2, *rs3);        vec![fop]
+    }
+
+    pub fn translate_fnmadd_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FNMADD_D_IDX as usize, *rd, *rs1, *rs2, *rs3);        vec![fop]
+    }
+
+    pub fn translate_fdiv_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fdiv_d =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FDIV_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![fdiv_d]
+    }
+
+    pub fn translate_fsqrt_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fdiv_d =
+            TCGOp::new_helper_call_arg2(CALL_HELPER_IDX::CALL_FSQRT_D_IDX as usize, *rd, *rs1);
+        vec![fdiv_d]
+    }
+
+    pub fn translate_fmv_x_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let mov_x_d = TCGOp::new_2op(TCGOpcode::MOVE_TO_INT_FROM_FLOAT, *rd, *rs1);
+        vec![mov_x_d]
+    }
+
+
+    pub fn translate_fmv_d_x(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let mov_x_d = TCGOp::new_2op(TCGOpcode::MOVE_TO_FLOAT_FROM_INT, *rd, *rs1);
+        vec![mov_x_d]
+    }
+
+    pub fn translate_feq_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FEQ_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+    pub fn translate_flt_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FLT_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+    pub fn translate_fle_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FLE_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fclass_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg2(CALL_HELPER_IDX::CALL_FCLASS_D_IDX as usize, *rd, *rs1);
+        vec![op]
+    }
+
+    pub fn translate_fmax_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FMAX_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fmin_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FMIN_D_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fsgnj_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_3op(TCGOpcode::SGNJ_64BIT, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fsgnjn_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_3op(TCGOpcode::SGNJN_64BIT, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fsgnjx_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_3op(TCGOpcode::SGNJX_64BIT, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fadd_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fadd_s =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FADD_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![fadd_s]
+    }
+
+    pub fn translate_fsub_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fsub_s =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FSUB_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![fsub_s]
+    }
+
+    pub fn translate_fmul_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fmul_s =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FMUL_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![fmul_s]
+    }
+
+    pub fn translate_fmadd_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FMADD_S_IDX as usize, *rd, *rs1, *rs2, *rs3);        
+        vec![fop]
+    }
+
+    pub fn translate_fmsub_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FMSUB_S_IDX as usize, *rd, *rs1, *rs2, *rs3);       
+        vec![fop]
+    }
+
+    pub fn translate_fnmsub_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FNMSUB_S_IDX as usize, *rd, *rs1, *rs2, *rs3);
+        vec![fop]
+    }
+
+    pub fn translate_fnmadd_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rs3 = Box::new(TCGv::new_reg(get_rs3_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fop = TCGOp::new_helper_call_arg4(
+            CALL_HELPER_IDX::CALL_FNMADD_S_IDX as usize, *rd, *rs1, *rs2, *rs3);
+        vec![fop]
+    }
+
+    pub fn translate_fdiv_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fdiv_s =
+            TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FDIV_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![fdiv_s]
+    }
+
+    pub fn translate_fsqrt_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let fdiv_s =
+            TCGOp::new_helper_call_arg2(CALL_HELPER_IDX::CALL_FSQRT_S_IDX as usize, *rd, *rs1);
+        vec![fdiv_s]
+    }
+
+    pub fn translate_fmv_x_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let mov_x_s = TCGOp::new_2op(TCGOpcode::MOVE_TO_INT_FROM_FLOAT, *rd, *rs1);
+        vec![mov_x_s]
+    }
+
+
+    pub fn translate_fmv_s_x(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let mov_x_s = TCGOp::new_2op(TCGOpcode::MOVE_TO_FLOAT_FROM_INT, *rd, *rs1);
+        vec![mov_x_s]
+    }
+
+    pub fn translate_feq_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FEQ_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+    pub fn translate_flt_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FLT_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+    pub fn translate_fle_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FLE_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fclass_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg2(CALL_HELPER_IDX::CALL_FCLASS_S_IDX as usize, *rd, *rs1);
+        vec![op]
+    }
+
+    pub fn translate_fmv_x_w(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let mov_x_d = TCGOp::new_2op(TCGOpcode::MOVE_TO_INT_FROM_FLOAT_32BIT, *rd, *rs1);
+        vec![mov_x_d]
+    }
+
+
+    pub fn translate_fmv_w_x(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let mov_x_d = TCGOp::new_2op(TCGOpcode::MOVE_TO_FLOAT_FROM_INT_32BIT, *rd, *rs1);
+        vec![mov_x_d]
+    }
+
+    pub fn translate_fmax_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FMAX_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fmin_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FMIN_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fsgnj_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FSGNJ_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fsgnjn_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FSGNJN_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fsgnjx_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst)as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let op = TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FSGNJX_S_IDX as usize, *rd, *rs1, *rs2);
+        vec![op]
+    }
+
+    pub fn translate_fcvt_w_s (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::W_S as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx, rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_wu_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::WU_S as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_s_w (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::S_W as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_s_wu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::S_WU as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_s_d (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::S_D as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_d_s (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::D_S as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_w_d (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::W_D as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_wu_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::WU_D as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_d_w (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::D_W as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_d_wu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::D_WU as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_l_s (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::L_S as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_lu_s(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::LU_S as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_s_l (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::S_L as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_s_lu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::S_LU as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_l_d (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::L_D as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_lu_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::LU_D as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_d_l (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::D_L as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_fcvt_d_lu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { 
+        let mut tcg_lists = vec![];
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(CallFcvtIdx::D_LU as u64);
+        tcg_lists.push(TCGOp::new_helper_call_arg3(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx,  rd, rs1));
+        tcg_lists 
+    }
+
+    pub fn translate_flh(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let imm_const: u64 = ((inst.inst as i32) >> 20) as u64;
+
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let imm = Box::new(TCGv::new_imm(imm_const));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FLOAT_LOAD16_IDX as usize, *rd, *rs1, *imm, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+    pub fn translate_fsh(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let imm_const: u64 = get_s_imm_field!(inst.inst);
+        let imm_const = ((imm_const as i32) << (32 - 12)) >> (32 - 12);
+
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let imm = Box::new(TCGv::new_imm(imm_const as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst) as u64));
+
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FLOAT_STORE16_IDX as usize, *rs2, *rs1, *imm, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+
+    // Operations done entirely by a helper : (rd, rs1, rs2) of the instruction are passed as-is.
+    fn translate_float_call_rr(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+
+        vec![TCGOp::new_helper_call_arg2(helper_idx as usize, rd, rs1)]
+    }
+    fn translate_float_call_rrr(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rs2 = TCGv::new_reg(get_rs2_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+
+        vec![TCGOp::new_helper_call_arg3(helper_idx as usize, rd, rs1, rs2)]
+    }
+    fn translate_fcvt_h(&mut self, fcvt_idx: CallFcvtIdx, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let fcvt_helper_idx = TCGv::new_imm(fcvt_idx as u64);
+        let rm  = TCGv::new_imm(((inst.inst >> 12) & 0x7) as u64);
+
+        vec![TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_FCVT_IDX as usize, fcvt_helper_idx, rd, rs1, rm)]
+    }
+    // Zfh arithmetic takes the rm field of the instruction as well, FMA packs it above rs3.
+    fn translate_float_h_call_rr_rm(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let rm  = TCGv::new_imm(((inst.inst >> 12) & 0x7) as u64);
+
+        vec![TCGOp::new_helper_call_arg3(helper_idx as usize, rd, rs1, rm)]
+    }
+    fn translate_float_h_call_rrr_rm(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rs2 = TCGv::new_reg(get_rs2_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let rm  = TCGv::new_imm(((inst.inst >> 12) & 0x7) as u64);
+
+        vec![TCGOp::new_helper_call_arg4(helper_idx as usize, rd, rs1, rs2, rm)]
+    }
+    fn translate_float_h_call_rrrr_rm(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rs2 = TCGv::new_reg(get_rs2_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let rs3_rm = TCGv::new_imm(get_rs3_addr!(inst.inst) as u64 | (((inst.inst >> 12) & 0x7) << 5) as u64);
+
+        vec![TCGOp::new_helper_call_arg4(helper_idx as usize, rd, rs1, rs2, rs3_rm)]
+    }
+
+    pub fn translate_fadd_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrr_rm(CALL_HELPER_IDX::CALL_FADD_H_IDX, inst) }
+    pub fn translate_fsub_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrr_rm(CALL_HELPER_IDX::CALL_FSUB_H_IDX, inst) }
+    pub fn translate_fmul_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrr_rm(CALL_HELPER_IDX::CALL_FMUL_H_IDX, inst) }
+    pub fn translate_fdiv_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrr_rm(CALL_HELPER_IDX::CALL_FDIV_H_IDX, inst) }
+    pub fn translate_fmadd_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrrr_rm(CALL_HELPER_IDX::CALL_FMADD_H_IDX, inst) }
+    pub fn translate_fmsub_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrrr_rm(CALL_HELPER_IDX::CALL_FMSUB_H_IDX, inst) }
+    pub fn translate_fnmsub_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrrr_rm(CALL_HELPER_IDX::CALL_FNMSUB_H_IDX, inst) }
+    pub fn translate_fnmadd_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rrrr_rm(CALL_HELPER_IDX::CALL_FNMADD_H_IDX, inst) }
+    pub fn translate_fsqrt_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_h_call_rr_rm(CALL_HELPER_IDX::CALL_FSQRT_H_IDX, inst) }
+    pub fn translate_feq_h   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FEQ_H_IDX, inst) }
+    pub fn translate_flt_h   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FLT_H_IDX, inst) }
+    pub fn translate_fle_h   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FLE_H_IDX, inst) }
+    pub fn translate_fclass_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rr(CALL_HELPER_IDX::CALL_FCLASS_H_IDX, inst) }
+    pub fn translate_fmax_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FMAX_H_IDX, inst) }
+    pub fn translate_fmin_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FMIN_H_IDX, inst) }
+    pub fn translate_fsgnj_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FSGNJ_H_IDX, inst) }
+    pub fn translate_fsgnjn_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FSGNJN_H_IDX, inst) }
+    pub fn translate_fsgnjx_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FSGNJX_H_IDX, inst) }
+    pub fn translate_fmv_x_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rr(CALL_HELPER_IDX::CALL_FMV_X_H_IDX, inst) }
+    pub fn translate_fmv_h_x (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rr(CALL_HELPER_IDX::CALL_FMV_H_X_IDX, inst) }
+
+    pub fn translate_fcvt_s_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::S_H, inst) }
+    pub fn translate_fcvt_h_s  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::H_S, inst) }
+    pub fn translate_fcvt_d_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::D_H, inst) }
+    pub fn translate_fcvt_h_d  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::H_D, inst) }
+    pub fn translate_fcvt_w_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::W_H, inst) }
+    pub fn translate_fcvt_wu_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::WU_H, inst) }
+    pub fn translate_fcvt_l_h  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::L_H, inst) }
+    pub fn translate_fcvt_lu_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::LU_H, inst) }
+    pub fn translate_fcvt_h_w  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::H_W, inst) }
+    pub fn translate_fcvt_h_wu (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::H_WU, inst) }
+    pub fn translate_fcvt_h_l  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::H_L, inst) }
+    pub fn translate_fcvt_h_lu (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fcvt_h(CallFcvtIdx::H_LU, inst) }
+
+    // Zfa
+    fn translate_fli(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let idx = TCGv::new_imm(get_rs1_addr!(inst.inst) as u64);
+
+        vec![TCGOp::new_helper_call_arg2(helper_idx as usize, rd, idx)]
+    }
+    // The 4th argument selects the variant : fmaxm, fltq or froundnx.
+    fn translate_float_rrr_sel(&mut self, helper_idx: CALL_HELPER_IDX, sel: u64, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rs2 = TCGv::new_reg(get_rs2_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+
+        vec![TCGOp::new_helper_call_arg4(helper_idx as usize, rd, rs1, rs2, TCGv::new_imm(sel))]
+    }
+    fn translate_fround(&mut self, helper_idx: CALL_HELPER_IDX, exact: u64, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        let rd  = TCGv::new_reg(get_rd_addr!(inst.inst) as u64);
+        let rm  = TCGv::new_imm(((inst.inst >> 12) & 0x7) as u64);
+
+        vec![TCGOp::new_helper_call_arg4(helper_idx as usize, rd, rs1, rm, TCGv::new_imm(exact))]
+    }
+
+    pub fn translate_fli_s      (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fli(CALL_HELPER_IDX::CALL_FLI_S_IDX, inst) }
+    pub fn translate_fli_d      (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fli(CALL_HELPER_IDX::CALL_FLI_D_IDX, inst) }
+    pub fn translate_fli_h      (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fli(CALL_HELPER_IDX::CALL_FLI_H_IDX, inst) }
+    pub fn translate_fminm_s    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FMINMAXM_S_IDX, 0, inst) }
+    pub fn translate_fmaxm_s    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FMINMAXM_S_IDX, 1, inst) }
+    pub fn translate_fminm_d    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FMINMAXM_D_IDX, 0, inst) }
+    pub fn translate_fmaxm_d    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FMINMAXM_D_IDX, 1, inst) }
+    pub fn translate_fminm_h    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FMINMAXM_H_IDX, 0, inst) }
+    pub fn translate_fmaxm_h    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FMINMAXM_H_IDX, 1, inst) }
+    pub fn translate_fround_s   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fround(CALL_HELPER_IDX::CALL_FROUND_S_IDX, 0, inst) }
+    pub fn translate_froundnx_s (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fround(CALL_HELPER_IDX::CALL_FROUND_S_IDX, 1, inst) }
+    pub fn translate_fround_d   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fround(CALL_HELPER_IDX::CALL_FROUND_D_IDX, 0, inst) }
+    pub fn translate_froundnx_d (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fround(CALL_HELPER_IDX::CALL_FROUND_D_IDX, 1, inst) }
+    pub fn translate_fround_h   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fround(CALL_HELPER_IDX::CALL_FROUND_H_IDX, 0, inst) }
+    pub fn translate_froundnx_h (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_fround(CALL_HELPER_IDX::CALL_FROUND_H_IDX, 1, inst) }
+    pub fn translate_fleq_s     (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FCMPQ_S_IDX, 0, inst) }
+    pub fn translate_fltq_s     (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FCMPQ_S_IDX, 1, inst) }
+    pub fn translate_fleq_d     (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FCMPQ_D_IDX, 0, inst) }
+    pub fn translate_fltq_d     (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FCMPQ_D_IDX, 1, inst) }
+    pub fn translate_fleq_h     (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FCMPQ_H_IDX, 0, inst) }
+    pub fn translate_fltq_h     (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_rrr_sel(CALL_HELPER_IDX::CALL_FCMPQ_H_IDX, 1, inst) }
+    pub fn translate_fcvtmod_w_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rr(CALL_HELPER_IDX::CALL_FCVTMOD_W_D_IDX, inst) }
+    pub fn translate_fmvh_x_d   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rr(CALL_HELPER_IDX::CALL_FMVH_X_D_IDX, inst) }
+    pub fn translate_fmvp_d_x   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_float_call_rrr(CALL_HELPER_IDX::CALL_FMVP_D_X_IDX, inst) }
+}
@@ -0,0 +1,213 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::super::super::tcg::tcg::{TCGOp, TCGOpcode, TCGv, TCGLabel};
+use super::super::super::instr_info::InstrInfo;
+use super::riscv::{CALL_HELPER_IDX, HYP_LDST_EXEC, HYP_LDST_UNSIGNED, CBO_INVAL, CBO_CLEAN, CBO_FLUSH, CBO_ZERO};
+
+use super::super::super::get_rs1_addr;
+use super::super::super::get_rs2_addr;
+use super::super::super::get_rd_addr;
+
+use super::riscv::TranslateRiscv;
+
+impl TranslateRiscv {
+    // CSR helper raises illegal / virtual instruction exception and returns non-zero.
+    fn translate_csr_common(&mut self, helper_idx: CALL_HELPER_IDX, rs1: TCGv, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+        let csr = Box::new(TCGv::new_imm(get_imm12!(inst.inst)));
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let csr_op = TCGOp::new_helper_call_arg4(helper_idx as usize, *rd, rs1, *csr, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rd, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![csr_op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+    pub fn translate_csrrw(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        self.translate_csr_common(CALL_HELPER_IDX::CALL_CSRRW_IDX, rs1, inst)
+    }
+    pub fn translate_csrrs(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        self.translate_csr_common(CALL_HELPER_IDX::CALL_CSRRS_IDX, rs1, inst)
+    }
+    pub fn translate_csrrc(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_reg(get_rs1_addr!(inst.inst) as u64);
+        self.translate_csr_common(CALL_HELPER_IDX::CALL_CSRRC_IDX, rs1, inst)
+    }
+    pub fn translate_csrrwi(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_imm(get_rs1_addr!(inst.inst) as u64);
+        self.translate_csr_common(CALL_HELPER_IDX::CALL_CSRRWI_IDX, rs1, inst)
+    }
+    pub fn translate_csrrsi(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_imm(get_rs1_addr!(inst.inst) as u64);
+        self.translate_csr_common(CALL_HELPER_IDX::CALL_CSRRSI_IDX, rs1, inst)
+    }
+    pub fn translate_csrrci(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = TCGv::new_imm(get_rs1_addr!(inst.inst) as u64);
+        self.translate_csr_common(CALL_HELPER_IDX::CALL_CSRRCI_IDX, rs1, inst)
+    }
+
+    pub fn translate_fence(&mut self, _inst: &InstrInfo) -> Vec<TCGOp> {
+        vec![]
+    }
+    pub fn translate_fence_i(&mut self, _inst: &InstrInfo) -> Vec<TCGOp> {
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        vec![exit_tb]
+    }
+    // Fence helpers take (rs1, rs2, -, pc) and may raise exception.
+    fn translate_fence_vma_common(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst) as u64));
+        let dummy = Box::new(TCGv::new_imm(0));
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let op = TCGOp::new_helper_call_arg4(helper_idx as usize, *rs1, *rs2, *dummy, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+    pub fn translate_sfence_vma(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_fence_vma_common(CALL_HELPER_IDX::CALL_SFENCE_VMA_IDX, inst)
+    }
+    pub fn translate_hfence_vvma(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_fence_vma_common(CALL_HELPER_IDX::CALL_HFENCE_VVMA_IDX, inst)
+    }
+    pub fn translate_hfence_gvma(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_fence_vma_common(CALL_HELPER_IDX::CALL_HFENCE_GVMA_IDX, inst)
+    }
+
+    // Cache-block operations run in a helper : it checks the envcfg enables and translates the block address.
+    fn translate_cbo_common(&mut self, op: u64, inst: &InstrInfo) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let tcg_op = Box::new(TCGv::new_imm(op));
+        let dummy = Box::new(TCGv::new_imm(0));
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let cbo_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_CBO_IDX as usize, *rs1, *tcg_op, *dummy, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, *zero, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![cbo_op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+    pub fn translate_cbo_inval(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_cbo_common(CBO_INVAL, inst)
+    }
+    pub fn translate_cbo_clean(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_cbo_common(CBO_CLEAN, inst)
+    }
+    pub fn translate_cbo_flush(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_cbo_common(CBO_FLUSH, inst)
+    }
+    pub fn translate_cbo_zero(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        self.translate_cbo_common(CBO_ZERO, inst)
+    }
+
+    pub fn translate_mret(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let mret_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_MRET_IDX as usize,
+                                                  TCGv::new_imm(0), TCGv::new_imm(0), TCGv::new_imm(0),
+                                                  TCGv::new_imm(inst.addr));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        vec![mret_op, exit_tb]
+    }
+
+    pub fn translate_ecall(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let ecall_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_ECALL_IDX as usize,
+                                                   TCGv::new_imm(0), TCGv::new_imm(0), TCGv::new_imm(0),
+                                                   TCGv::new_imm(inst.addr));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        vec![ecall_op, exit_tb]
+    }
+
+    // EBREAK either raises breakpoint exception or halts the hart in Debug Mode.
+    pub fn translate_ebreak(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let ebreak_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_EBREAK_IDX as usize,
+                                                    TCGv::new_imm(0), TCGv::new_imm(0), TCGv::new_imm(0),
+                                                    TCGv::new_imm(inst.addr));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        vec![ebreak_op, exit_tb]
+    }
+
+    pub fn translate_dret(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let dret_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_DRET_IDX as usize,
+                                                  TCGv::new_imm(0), TCGv::new_imm(0), TCGv::new_imm(0),
+                                                  TCGv::new_imm(inst.addr));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        vec![dret_op, exit_tb]
+    }
+
+    pub fn translate_sret(&mut self, inst: &InstrInfo) -> Vec<TCGOp> {
+        let mret_op = TCGOp::new_helper_call_arg4(CALL_HELPER_IDX::CALL_SRET_IDX as usize,
+                                                  TCGv::new_imm(0), TCGv::new_imm(0), TCGv::new_imm(0),
+                                                  TCGv::new_imm(inst.addr));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        vec![mret_op, exit_tb]
+    }
+
+    // HLV / HLVX : helper(rd, rs1, access kind, pc)
+    fn translate_hlv_common(&mut self, inst: &InstrInfo, kind: u64) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rd = Box::new(TCGv::new_reg(get_rd_addr!(inst.inst) as u64));
+        self.translate_hyp_ldst(CALL_HELPER_IDX::CALL_HLV_IDX, *rd, *rs1, kind, inst)
+    }
+    // HSV : helper(rs2, rs1, access kind, pc)
+    fn translate_hsv_common(&mut self, inst: &InstrInfo, kind: u64) -> Vec<TCGOp> {
+        let rs1 = Box::new(TCGv::new_reg(get_rs1_addr!(inst.inst) as u64));
+        let rs2 = Box::new(TCGv::new_reg(get_rs2_addr!(inst.inst) as u64));
+        self.translate_hyp_ldst(CALL_HELPER_IDX::CALL_HSV_IDX, *rs2, *rs1, kind, inst)
+    }
+    fn translate_hyp_ldst(&mut self, helper_idx: CALL_HELPER_IDX, reg: TCGv, rs1: TCGv, kind: u64, inst: &InstrInfo) -> Vec<TCGOp> {
+        let tcg_kind = Box::new(TCGv::new_imm(kind));
+        let tcg_inst_addr = Box::new(TCGv::new_imm(inst.addr));
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(helper_idx as usize, reg, rs1, *tcg_kind, *tcg_inst_addr);
+
+        let zero = Box::new(TCGv::new_reg(0 as u64));
+        let dummy_addr = Box::new(TCGv::new_imm(0));
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, rs1, *zero, *dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+
+    pub fn translate_hlv_b(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 1) }
+    pub fn translate_hlv_bu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 1 | HYP_LDST_UNSIGNED) }
+    pub fn translate_hlv_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 2) }
+    pub fn translate_hlv_hu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 2 | HYP_LDST_UNSIGNED) }
+    pub fn translate_hlvx_hu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 2 | HYP_LDST_UNSIGNED | HYP_LDST_EXEC) }
+    pub fn translate_hlv_w(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 4) }
+    pub fn translate_hlv_wu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 4 | HYP_LDST_UNSIGNED) }
+    pub fn translate_hlvx_wu(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 4 | HYP_LDST_UNSIGNED | HYP_LDST_EXEC) }
+    pub fn translate_hlv_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hlv_common(inst, 8) }
+    pub fn translate_hsv_b(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hsv_common(inst, 1) }
+    pub fn translate_hsv_h(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hsv_common(inst, 2) }
+    pub fn translate_hsv_w(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hsv_common(inst, 4) }
+    pub fn translate_hsv_d(&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_hsv_common(inst, 8) }
+}
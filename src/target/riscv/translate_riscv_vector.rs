@@ -0,0 +1,36 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::super::super::tcg::tcg::{TCGOp, TCGOpcode, TCGv, TCGLabel};
+use super::super::super::instr_info::InstrInfo;
+use super::riscv::CALL_HELPER_IDX;
+
+use super::riscv::TranslateRiscv;
+
+impl TranslateRiscv {
+    // Vector instructions are done by a helper which decodes the whole instruction.
+    // The helper returns non-zero when it raised an exception, and the block exits.
+    fn translate_vector_call(&mut self, helper_idx: CALL_HELPER_IDX, inst: &InstrInfo) -> Vec<TCGOp> {
+        let tcg_inst = TCGv::new_imm(inst.inst as u64);
+        let dummy = TCGv::new_imm(0);
+        let tcg_inst_addr = TCGv::new_imm(inst.addr);
+
+        let label = Rc::new(RefCell::new(TCGLabel::new()));
+
+        let tcg_call_op = TCGOp::new_helper_call_arg4(helper_idx as usize, tcg_inst, dummy, dummy, tcg_inst_addr);
+
+        let zero = TCGv::new_reg(0);
+        let dummy_addr = TCGv::new_imm(0);
+
+        let result_cmp_op = TCGOp::new_4op(TCGOpcode::EQ_EAX_64BIT, zero, zero, dummy_addr, Rc::clone(&label));
+        let exit_tb = TCGOp::new_0op(TCGOpcode::EXIT_TB, None);
+        let tcg_set_label = TCGOp::new_label(Rc::clone(&label));
+
+        vec![tcg_call_op, result_cmp_op, exit_tb, tcg_set_label]
+    }
+
+    pub fn translate_vsetvl (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_vector_call(CALL_HELPER_IDX::CALL_VSETVL_IDX, inst) }
+    pub fn translate_vldst  (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_vector_call(CALL_HELPER_IDX::CALL_VLDST_IDX, inst) }
+    pub fn translate_vop    (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_vector_call(CALL_HELPER_IDX::CALL_VOP_IDX, inst) }
+    pub fn translate_vfop   (&mut self, inst: &InstrInfo) -> Vec<TCGOp> { self.translate_vector_call(CALL_HELPER_IDX::CALL_VFOP_IDX, inst) }
+}
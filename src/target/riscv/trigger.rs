@@ -0,0 +1,264 @@
+use crate::emu_env::EmuEnv;
+use crate::target::riscv::debug::DebugCause;
+use crate::target::riscv::mmu::{MemAccType, MemResult};
+use crate::target::riscv::riscv::{ExceptCode, PrivMode};
+use crate::target::riscv::riscv_csr::{CsrAddr, RiscvCsr};
+
+// Number of implemented triggers. tselect above this is ignored.
+pub const TRIG_NUM: usize = 4;
+
+// tdata1.type
+const TDATA1_TYPE_SHIFT: u64 = 60;
+const TDATA1_TYPE_MCONTROL6: i64 = 6;
+const TDATA1_TYPE_DISABLED: i64 = 15;
+pub const TDATA1_DISABLED: i64 = TDATA1_TYPE_DISABLED << TDATA1_TYPE_SHIFT;
+const TDATA1_DMODE: i64 = 1 << 59;
+
+// mcontrol6 fields
+const MCONTROL6_VS: i64 = 1 << 24;
+const MCONTROL6_VU: i64 = 1 << 23;
+const MCONTROL6_HIT0: i64 = 1 << 22;
+const MCONTROL6_ACTION_SHIFT: u64 = 12;
+const MCONTROL6_ACTION_MASK: i64 = 0xf << MCONTROL6_ACTION_SHIFT;
+const MCONTROL6_CHAIN: i64 = 1 << 11;
+const MCONTROL6_MATCH_SHIFT: u64 = 7;
+const MCONTROL6_MATCH_MASK: i64 = 0xf << MCONTROL6_MATCH_SHIFT;
+const MCONTROL6_M: i64 = 1 << 6;
+const MCONTROL6_S: i64 = 1 << 4;
+const MCONTROL6_U: i64 = 1 << 3;
+const MCONTROL6_EXECUTE: i64 = 1 << 2;
+const MCONTROL6_STORE: i64 = 1 << 1;
+const MCONTROL6_LOAD: i64 = 1 << 0;
+// size, select, uncertain and uncertainen are read-only zero : address match of any access size
+const MCONTROL6_WMASK: i64 = MCONTROL6_VS | MCONTROL6_VU | MCONTROL6_HIT0 | MCONTROL6_ACTION_MASK | MCONTROL6_CHAIN |
+    MCONTROL6_MATCH_MASK | MCONTROL6_M | MCONTROL6_S | MCONTROL6_U |
+    MCONTROL6_EXECUTE | MCONTROL6_STORE | MCONTROL6_LOAD;
+
+// tinfo : version 1, supports mcontrol6 and disabled
+const TINFO_VALUE: i64 = (1 << 24) | (1 << TDATA1_TYPE_MCONTROL6) | (1 << TDATA1_TYPE_DISABLED);
+
+// tcontrol : M-mode trigger enable, and its value before the last trap to M-mode
+const TCONTROL_MTE: i64 = 1 << 3;
+const TCONTROL_MPTE: i64 = 1 << 7;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum TriggerAction {
+    Breakpoint = 0,     // Raise breakpoint exception
+    DebugMode = 1,      // Enter Debug Mode
+}
+
+impl CsrAddr {
+    pub fn is_trigger(self) -> bool {
+        matches!(self, CsrAddr::Tselect | CsrAddr::Tdata1 | CsrAddr::Tdata2 |
+                       CsrAddr::Tdata3 | CsrAddr::Tinfo | CsrAddr::Tcontrol)
+    }
+}
+
+impl RiscvCsr<i64> {
+    fn trigger_selected(&self) -> usize {
+        self.m_tselect.m_csr as usize
+    }
+
+    pub fn trigger_read(&self, addr: CsrAddr) -> i64 {
+        let idx = self.trigger_selected();
+        match addr {
+            CsrAddr::Tselect => self.m_tselect.m_csr,
+            CsrAddr::Tdata1 => self.m_tdata1[idx].m_csr,
+            CsrAddr::Tdata2 => self.m_tdata2[idx].m_csr,
+            CsrAddr::Tinfo => TINFO_VALUE,
+            CsrAddr::Tcontrol => self.m_tcontrol.m_csr,
+            _ => 0,     // tdata3 (textra) is not implemented
+        }
+    }
+
+    // Triggers owned by Debug Mode (dmode = 1) are only writable from Debug Mode.
+    pub fn trigger_write(&mut self, addr: CsrAddr, data: i64) {
+        let idx = self.trigger_selected();
+        let writable = self.m_debug_mode || (self.m_tdata1[idx].m_csr & TDATA1_DMODE) == 0;
+        match addr {
+            CsrAddr::Tselect => {
+                if (data as u64) < TRIG_NUM as u64 {
+                    self.m_tselect.m_csr = data;
+                }
+            }
+            CsrAddr::Tdata1 if writable => {
+                let tdata1 = self.legalize_tdata1(data);
+                if tdata1 != self.m_tdata1[idx].m_csr {
+                    self.m_tdata1[idx].m_csr = tdata1;
+                    self.m_trig_updated = true;
+                }
+            }
+            CsrAddr::Tdata2 if writable => {
+                if data != self.m_tdata2[idx].m_csr {
+                    self.m_tdata2[idx].m_csr = data;
+                    self.m_trig_updated = true;
+                }
+            }
+            CsrAddr::Tcontrol => {
+                let tcontrol = data & (TCONTROL_MTE | TCONTROL_MPTE);
+                if tcontrol != self.m_tcontrol.m_csr {
+                    self.m_tcontrol.m_csr = tcontrol;
+                    self.m_trig_updated = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // WARL : unsupported type disables the trigger, unsupported match / action fall back to 0.
+    fn legalize_tdata1(&self, data: i64) -> i64 {
+        let trig_type = (data as u64 >> TDATA1_TYPE_SHIFT) as i64;
+        if trig_type != TDATA1_TYPE_MCONTROL6 {
+            return TDATA1_DISABLED;
+        }
+        let dmode = if self.m_debug_mode { data & TDATA1_DMODE } else { 0 };
+        let mut tdata1 = (TDATA1_TYPE_MCONTROL6 << TDATA1_TYPE_SHIFT) | dmode | (data & MCONTROL6_WMASK);
+        let match_type = (tdata1 & MCONTROL6_MATCH_MASK) >> MCONTROL6_MATCH_SHIFT;
+        if !matches!(match_type, 0..=5 | 8 | 9 | 12 | 13) {
+            tdata1 &= !MCONTROL6_MATCH_MASK;
+        }
+        let action = (tdata1 & MCONTROL6_ACTION_MASK) >> MCONTROL6_ACTION_SHIFT;
+        // Entering Debug Mode is only allowed for triggers owned by Debug Mode
+        if action > TriggerAction::DebugMode as i64 || (action == TriggerAction::DebugMode as i64 && dmode == 0) {
+            tdata1 &= !MCONTROL6_ACTION_MASK;
+        }
+        tdata1
+    }
+
+    fn trigger_addr_match(tdata1: i64, tdata2: u64, addr: u64) -> bool {
+        let match_type = (tdata1 & MCONTROL6_MATCH_MASK) >> MCONTROL6_MATCH_SHIFT;
+        let hit = match match_type & 0x7 {
+            0 => addr == tdata2,
+            1 => {
+                // NAPOT : trailing ones of tdata2 and one more bit are ignored
+                let bits = tdata2.trailing_ones() + 1;
+                let mask = if bits >= 64 { 0 } else { !0u64 << bits };
+                (addr & mask) == (tdata2 & mask)
+            }
+            2 => addr >= tdata2,
+            3 => addr < tdata2,
+            4 => ((addr as u32) & (tdata2 >> 32) as u32) == ((tdata2 as u32) & (tdata2 >> 32) as u32),
+            _ => (((addr >> 32) as u32) & (tdata2 >> 32) as u32) == ((tdata2 as u32) & (tdata2 >> 32) as u32),
+        };
+        if (match_type & 0x8) != 0 { !hit } else { hit }
+    }
+
+    // Trigger fires in the privilege mode, M-mode triggers which break are masked by tcontrol.mte.
+    fn trigger_enabled(&self, tdata1: i64, priv_mode: PrivMode, virt: bool) -> bool {
+        if (tdata1 as u64 >> TDATA1_TYPE_SHIFT) as i64 != TDATA1_TYPE_MCONTROL6 || self.m_debug_mode {
+            return false;
+        }
+        let mode_bit = match (priv_mode, virt) {
+            (PrivMode::Machine, _) => MCONTROL6_M,
+            (PrivMode::User, true) => MCONTROL6_VU,
+            (_, true) => MCONTROL6_VS,
+            (PrivMode::User, false) => MCONTROL6_U,
+            (_, false) => MCONTROL6_S,
+        };
+        if (tdata1 & mode_bit) == 0 {
+            return false;
+        }
+        let action = (tdata1 & MCONTROL6_ACTION_MASK) >> MCONTROL6_ACTION_SHIFT;
+        !(priv_mode == PrivMode::Machine && action == TriggerAction::Breakpoint as i64 &&
+          (self.m_tcontrol.m_csr & TCONTROL_MTE) == 0)
+    }
+
+    // Any trigger on the access type can fire in the mode.
+    // Used to send every load / store of the mode to the slow path.
+    pub fn trigger_armed(&self, kind: i64, priv_mode: PrivMode, virt: bool) -> bool {
+        (0..TRIG_NUM).any(|idx| {
+            let tdata1 = self.m_tdata1[idx].m_csr;
+            (tdata1 & kind) != 0 && self.trigger_enabled(tdata1, priv_mode, virt)
+        })
+    }
+
+    // Returns the trigger chain [first, last] which matches the access.
+    // Chained triggers fire only when all of them match.
+    fn trigger_match(&self, kind: i64, addr: u64, priv_mode: PrivMode, virt: bool) -> Option<(usize, usize)> {
+        let mut chain_start = 0;
+        let mut chain_hit = true;
+        for idx in 0..TRIG_NUM {
+            let tdata1 = self.m_tdata1[idx].m_csr;
+            chain_hit &= (tdata1 & kind) != 0 && self.trigger_enabled(tdata1, priv_mode, virt) &&
+                Self::trigger_addr_match(tdata1, self.m_tdata2[idx].m_csr as u64, addr);
+            if (tdata1 & MCONTROL6_CHAIN) != 0 && idx + 1 < TRIG_NUM {
+                continue;
+            }
+            if chain_hit {
+                return Some((chain_start, idx));
+            }
+            chain_start = idx + 1;
+            chain_hit = true;
+        }
+        None
+    }
+
+    // Trap to M-mode saves and clears tcontrol.mte, MRET restores it.
+    pub fn trigger_trap_to_machine(&mut self) {
+        let tcontrol = self.m_tcontrol.m_csr;
+        let next = if (tcontrol & TCONTROL_MTE) != 0 { TCONTROL_MPTE } else { 0 };
+        if next != tcontrol {
+            self.m_tcontrol.m_csr = next;
+            self.m_trig_updated = true;
+        }
+    }
+
+    pub fn trigger_mret(&mut self) {
+        let tcontrol = self.m_tcontrol.m_csr;
+        let mte = if (tcontrol & TCONTROL_MPTE) != 0 { TCONTROL_MTE } else { 0 };
+        if (tcontrol & TCONTROL_MTE) != mte {
+            self.m_tcontrol.m_csr = (tcontrol & !TCONTROL_MTE) | mte;
+            self.m_trig_updated = true;
+        }
+    }
+}
+
+impl EmuEnv {
+    pub fn trigger_armed(&self, acc_type: MemAccType, priv_mode: PrivMode, virt: bool) -> bool {
+        let kind = match acc_type {
+            MemAccType::Fetch => MCONTROL6_EXECUTE,
+            MemAccType::Read => MCONTROL6_LOAD,
+            MemAccType::Write => MCONTROL6_STORE,
+        };
+        self.m_csr.trigger_armed(kind, priv_mode, virt)
+    }
+
+    // Execute address trigger, checked when the instruction is translated.
+    // Cached blocks are dropped whenever triggers are updated.
+    pub fn exec_trigger_match(&self, guest_pc: u64) -> Option<(usize, usize)> {
+        self.m_csr.trigger_match(MCONTROL6_EXECUTE, guest_pc, self.m_priv, self.m_virt)
+    }
+
+    // Load / store address trigger. Fires before the memory access.
+    pub fn check_data_trigger(&mut self, guest_pc: u64, vaddr: u64, acc_type: MemAccType) -> Result<(), MemResult> {
+        let kind = match acc_type {
+            MemAccType::Fetch => return Ok(()),
+            MemAccType::Read => MCONTROL6_LOAD,
+            MemAccType::Write => MCONTROL6_STORE,
+        };
+        match self.m_csr.trigger_match(kind, vaddr, self.m_priv, self.m_virt) {
+            Some(chain) => {
+                self.fire_trigger(guest_pc, vaddr, chain);
+                Err(MemResult::TriggerHit)
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Set hit bits of the chain and take the action of the last trigger.
+    pub fn fire_trigger(&mut self, guest_pc: u64, tval: u64, chain: (usize, usize)) {
+        let (first, last) = chain;
+        for idx in first..=last {
+            self.m_csr.m_tdata1[idx].m_csr |= MCONTROL6_HIT0;
+        }
+        let action = (self.m_csr.m_tdata1[last].m_csr & MCONTROL6_ACTION_MASK) >> MCONTROL6_ACTION_SHIFT;
+        if self.m_arg_config.debug {
+            eprintln!("<Info: Trigger {}-{} fired. PC={:016x} TVAL={:016x} action={}>", first, last, guest_pc, tval, action);
+        }
+        if action == TriggerAction::DebugMode as i64 {
+            self.enter_debug_mode(guest_pc, DebugCause::Trigger);
+        } else {
+            self.generate_exception(guest_pc, ExceptCode::Breakpoint, tval as i64);
+        }
+    }
+}
@@ -14,6 +14,7 @@ pub enum TCGOpcode {
     MEM_LOAD,
     MEM_STORE,
     ADD_TLBIDX_OFFSET,
+    ADD_TLBWRIDX_OFFSET,
     ADD_TLBADDR_OFFSET,
     ADD_MEM_OFFSET,
 
@@ -99,6 +100,24 @@ pub enum TCGOpcode {
     REM_32BIT,
     REMU_32BIT,
 
+    CLZ_64BIT,
+    CTZ_64BIT,
+    CPOP_64BIT,
+    CLZ_32BIT,
+    CTZ_32BIT,
+    CPOP_32BIT,
+
+    ROL_64BIT,
+    ROR_64BIT,
+    ROL_32BIT,
+    ROR_32BIT,
+
+    BSWAP_64BIT,
+
+    CLMUL_64BIT,
+    CLMULH_64BIT,
+    CLMULR_64BIT,
+
     TLB_MATCH_CHECK,
     CMP_EQ,
     EXIT_TB,
@@ -391,6 +410,7 @@ pub trait TCG {
     fn tcg_gen_mem_store(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>, mem_size: MemOpType) -> usize;
 
     fn tcg_gen_tlbidx_offset(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
+    fn tcg_gen_tlbwridx_offset(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
     fn tcg_gen_tlbaddr_offset(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
     fn tcg_gen_mem_offset(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
 
@@ -405,6 +425,11 @@ pub trait TCG {
     fn tcg_gen_divu_64bit(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
     fn tcg_gen_rem_64bit(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
 
+    fn tcg_gen_bit_count(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
+    fn tcg_gen_rotate(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
+    fn tcg_gen_bswap_64bit(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
+    fn tcg_gen_clmul(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
+
     fn tcg_gen_jmpr(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
     fn tcg_gen_jmpim(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
     fn tcg_gen_eq_64bit(emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize;
@@ -92,6 +92,16 @@ enum X86Opcode {
 
     CQO = 0x99,
 
+    ROL_GV_CL = 0x00_d3,
+    ROR_GV_CL = 0x08_d3,
+    ROL_GV_IMM = 0x00_c1,
+    ROR_GV_IMM = 0x08_c1,
+
+    LZCNT = 0xbd_0f, // F3 prefix
+    TZCNT = 0xbc_0f, // F3 prefix
+    POPCNT = 0xb8_0f, // F3 prefix
+    SHLD_IMM = 0xa4_0f,
+
     SETB = 0x92_0f, // より下の場合バイトを設定します
     SETL = 0x9c_0f, // より小さい場合バイトを設定します
 
@@ -746,6 +756,46 @@ impl TCGX86 {
         gen_size
     }
 
+
+    // Bit manipulation instructions which are not in the x86-64 baseline.
+    // --no-host-bitmanip forces the portable fallback.
+    fn host_has_bitmanip(emu: &EmuEnv, op: TCGOpcode) -> bool {
+        if emu.m_arg_config.no_host_bitmanip {
+            return false;
+        }
+        match op {
+            TCGOpcode::CLZ_64BIT | TCGOpcode::CLZ_32BIT => is_x86_feature_detected!("lzcnt"),
+            TCGOpcode::CTZ_64BIT | TCGOpcode::CTZ_32BIT => is_x86_feature_detected!("bmi1"),
+            TCGOpcode::CPOP_64BIT | TCGOpcode::CPOP_32BIT => is_x86_feature_detected!("popcnt"),
+            TCGOpcode::CLMUL_64BIT | TCGOpcode::CLMULH_64BIT | TCGOpcode::CLMULR_64BIT => is_x86_feature_detected!("pclmulqdq"),
+            _ => true,
+        }
+    }
+
+    // dest = func(src1, src2) : call the portable implementation.
+    // TCG temporaries are caller-saved registers, keep them on the stack over the call.
+    fn tcg_gen_call_host_fn(emu: &mut EmuEnv, func: extern "C" fn(u64, u64) -> u64, dest: X86TargetRM, src1: X86TargetRM, src2: X86TargetRM, mc: &mut Vec<u8>) -> usize {
+        let saved_regs = [X86TargetRM::RCX, X86TargetRM::RDX, X86TargetRM::RSI, X86TargetRM::RDI];
+        let mut gen_size = 0;
+
+        for reg in saved_regs.iter() {
+            gen_size += Self::tcg_out(X86Opcode::PUSH as u64 + *reg as u64, 1, mc);
+        }
+        gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + src2 as u8, X86TargetRM::RAX as u8, mc);
+        gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + src1 as u8, X86TargetRM::RDI as u8, mc);
+        gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8, X86TargetRM::RSI as u8, mc);
+        gen_size += Self::tcg_gen_imm_u64(X86TargetRM::RAX, func as u64, mc);
+        // call   *%rax
+        gen_size += Self::tcg_out(0xd0_ff, 2, mc);
+        for reg in saved_regs.iter().rev() {
+            gen_size += Self::tcg_out(X86Opcode::POP as u64 + *reg as u64, 1, mc);
+        }
+        gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8, dest as u8, mc);
+
+        emu.m_x86reg_usage_list[dest as usize] = None;
+
+        gen_size
+    }
 }
 
 impl TCG for TCGX86 {
@@ -762,6 +812,7 @@ impl TCG for TCGX86 {
                     TCGOpcode::MEM_STORE => TCGX86::tcg_gen_mem_store(emu, pc_address, tcg, mc, MemOpType::STORE_64BIT),
 
                     TCGOpcode::ADD_TLBIDX_OFFSET => TCGX86::tcg_gen_tlbidx_offset(emu, pc_address, tcg, mc),             
+                    TCGOpcode::ADD_TLBWRIDX_OFFSET => TCGX86::tcg_gen_tlbwridx_offset(emu, pc_address, tcg, mc),
                     TCGOpcode::ADD_TLBADDR_OFFSET => TCGX86::tcg_gen_tlbaddr_offset(emu, pc_address, tcg, mc),                    
 
                     TCGOpcode::ADD_MEM_OFFSET => TCGX86::tcg_gen_mem_offset(emu, pc_address, tcg, mc),
@@ -788,6 +839,25 @@ impl TCG for TCGX86 {
                     TCGOpcode::REM_32BIT  => TCGX86::tcg_gen_rem_64bit(emu, pc_address, tcg, mc),
                     TCGOpcode::REMU_32BIT => TCGX86::tcg_gen_rem_64bit(emu, pc_address, tcg, mc),
 
+                    /* Bit manipulation */
+                    TCGOpcode::CLZ_64BIT  => TCGX86::tcg_gen_bit_count(emu, pc_address, tcg, mc),
+                    TCGOpcode::CTZ_64BIT  => TCGX86::tcg_gen_bit_count(emu, pc_address, tcg, mc),
+                    TCGOpcode::CPOP_64BIT => TCGX86::tcg_gen_bit_count(emu, pc_address, tcg, mc),
+                    TCGOpcode::CLZ_32BIT  => TCGX86::tcg_gen_bit_count(emu, pc_address, tcg, mc),
+                    TCGOpcode::CTZ_32BIT  => TCGX86::tcg_gen_bit_count(emu, pc_address, tcg, mc),
+                    TCGOpcode::CPOP_32BIT => TCGX86::tcg_gen_bit_count(emu, pc_address, tcg, mc),
+
+                    TCGOpcode::ROL_64BIT => TCGX86::tcg_gen_rotate(emu, pc_address, tcg, mc),
+                    TCGOpcode::ROR_64BIT => TCGX86::tcg_gen_rotate(emu, pc_address, tcg, mc),
+                    TCGOpcode::ROL_32BIT => TCGX86::tcg_gen_rotate(emu, pc_address, tcg, mc),
+                    TCGOpcode::ROR_32BIT => TCGX86::tcg_gen_rotate(emu, pc_address, tcg, mc),
+
+                    TCGOpcode::BSWAP_64BIT => TCGX86::tcg_gen_bswap_64bit(emu, pc_address, tcg, mc),
+
+                    TCGOpcode::CLMUL_64BIT  => TCGX86::tcg_gen_clmul(emu, pc_address, tcg, mc),
+                    TCGOpcode::CLMULH_64BIT => TCGX86::tcg_gen_clmul(emu, pc_address, tcg, mc),
+                    TCGOpcode::CLMULR_64BIT => TCGX86::tcg_gen_clmul(emu, pc_address, tcg, mc),
+
                     /* Shift operations */
                     TCGOpcode::SRL_64BIT => TCGX86::tcg_gen_srl_64bit(emu, pc_address, tcg, mc),
                     TCGOpcode::SLL_64BIT => TCGX86::tcg_gen_sll_64bit(emu, pc_address, tcg, mc),
@@ -1072,6 +1142,27 @@ impl TCG for TCGX86 {
     }
 
 
+    fn tcg_gen_tlbwridx_offset(emu: &EmuEnv, pc_address: u64, tcg: &tcg::TCGOp, mc: &mut Vec<u8>) -> usize {
+        let dest_reg = tcg.arg0.unwrap();
+        let src_reg = tcg.arg1.unwrap();
+
+        assert_eq!(dest_reg.t, TCGvType::TCGTemp);
+        assert_eq!(src_reg.t, TCGvType::TCGTemp);
+
+        let target_x86reg = Self::convert_x86_reg(dest_reg.value);
+        let source_x86reg = Self::convert_x86_reg(src_reg.value);
+
+        let mut gen_size = pc_address as usize;
+
+        if dest_reg.value != src_reg.value {
+            gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + source_x86reg as u8, target_x86reg as u8, mc);    
+        }
+        gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::ADD_GV_IMM, X86ModRM::MOD_11_DISP_RAX as u8 + target_x86reg as u8, 0, mc);
+        gen_size += Self::tcg_out(emu.calc_tlb_wr_relat_address() as u64, 4, mc);
+        return gen_size;
+    }
+
+
     fn tcg_gen_tlbaddr_offset(emu: &EmuEnv, pc_address: u64, tcg: &tcg::TCGOp, mc: &mut Vec<u8>) -> usize {
         let dest_reg = tcg.arg0.unwrap();
         let src_reg = tcg.arg1.unwrap();
@@ -1291,6 +1382,183 @@ impl TCG for TCGX86 {
         gen_size
     }
 
+    fn tcg_gen_bit_count(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize {
+        let dest = tcg.arg0.unwrap();
+        let src1 = tcg.arg1.unwrap();
+
+        assert_eq!(dest.t, TCGvType::TCGTemp);
+        assert_eq!(src1.t, TCGvType::TCGTemp);
+
+        let dest_x86reg = Self::convert_x86_reg(dest.value);
+        let src1_x86reg = Self::convert_x86_reg(src1.value);
+
+        let mut gen_size: usize = pc_address as usize;
+
+        let op = tcg.op.unwrap();
+        if !Self::host_has_bitmanip(emu, op) {
+            let func: extern "C" fn(u64, u64) -> u64 = match op {
+                TCGOpcode::CLZ_64BIT  => bitmanip_clz_64bit,
+                TCGOpcode::CTZ_64BIT  => bitmanip_ctz_64bit,
+                TCGOpcode::CPOP_64BIT => bitmanip_cpop_64bit,
+                TCGOpcode::CLZ_32BIT  => bitmanip_clz_32bit,
+                TCGOpcode::CTZ_32BIT  => bitmanip_ctz_32bit,
+                TCGOpcode::CPOP_32BIT => bitmanip_cpop_32bit,
+                _ => panic!("Unknown bit count operation {:?}", op),
+            };
+            return gen_size + Self::tcg_gen_call_host_fn(emu, func, dest_x86reg, src1_x86reg, src1_x86reg, mc);
+        }
+
+        let (x86_op, is_64bit) = match op {
+            TCGOpcode::CLZ_64BIT  => (X86Opcode::LZCNT, true),
+            TCGOpcode::CTZ_64BIT  => (X86Opcode::TZCNT, true),
+            TCGOpcode::CPOP_64BIT => (X86Opcode::POPCNT, true),
+            TCGOpcode::CLZ_32BIT  => (X86Opcode::LZCNT, false),
+            TCGOpcode::CTZ_32BIT  => (X86Opcode::TZCNT, false),
+            TCGOpcode::CPOP_32BIT => (X86Opcode::POPCNT, false),
+            _ => panic!("Unknown bit count operation {:?}", op),
+        };
+        gen_size += Self::tcg_out(0xf3, 1, mc);
+        if is_64bit {
+            gen_size += Self::tcg_modrm_2byte_64bit_raw_out(x86_op, X86ModRM::MOD_11_DISP_RAX as u8 + src1_x86reg as u8, dest_x86reg as u8, mc);
+        } else {
+            gen_size += Self::tcg_modrm_2byte_32bit_raw_out(x86_op, X86ModRM::MOD_11_DISP_RAX as u8 + src1_x86reg as u8, dest_x86reg as u8, mc);
+        }
+
+        emu.m_x86reg_usage_list[dest_x86reg as usize] = None;
+
+        gen_size
+    }
+
+    fn tcg_gen_rotate(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize {
+        let dest = tcg.arg0.unwrap();
+        let src1 = tcg.arg1.unwrap();
+        let src2 = tcg.arg2.unwrap();
+
+        assert_eq!(dest.t, TCGvType::TCGTemp);
+        assert_eq!(src1.t, TCGvType::TCGTemp);
+
+        let dest_x86reg = Self::convert_x86_reg(dest.value);
+        let src1_x86reg = Self::convert_x86_reg(src1.value);
+
+        let mut gen_size: usize = pc_address as usize;
+
+        let op = tcg.op.unwrap();
+        let is_64bit = op == TCGOpcode::ROL_64BIT || op == TCGOpcode::ROR_64BIT;
+        let is_left = op == TCGOpcode::ROL_64BIT || op == TCGOpcode::ROL_32BIT;
+
+        if src2.t == TCGvType::Immediate {
+            let x86_op = if is_left { X86Opcode::ROL_GV_IMM } else { X86Opcode::ROR_GV_IMM };
+            if dest.value != src1.value {
+                gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + src1_x86reg as u8, dest_x86reg as u8, mc);
+            }
+            if is_64bit {
+                gen_size += Self::tcg_modrm_64bit_raw_out(x86_op, X86ModRM::MOD_11_DISP_RAX as u8 + dest_x86reg as u8, 0, mc);
+            } else {
+                gen_size += Self::tcg_modrm_32bit_raw_out(x86_op, X86ModRM::MOD_11_DISP_RAX as u8 + dest_x86reg as u8, 0, mc);
+            }
+            gen_size += Self::tcg_out(src2.value, 1, mc);
+        } else {
+            // Rotate in RAX, RCX may hold any of the operands.
+            let x86_op = if is_left { X86Opcode::ROL_GV_CL } else { X86Opcode::ROR_GV_CL };
+            let src2_x86reg = Self::convert_x86_reg(src2.value);
+            gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + src1_x86reg as u8, X86TargetRM::RAX as u8, mc);
+            gen_size += Self::tcg_out(X86Opcode::PUSH as u64 + X86TargetRM::RCX as u64, 1, mc);
+            gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + src2_x86reg as u8, X86TargetRM::RCX as u8, mc);
+            if is_64bit {
+                gen_size += Self::tcg_modrm_64bit_raw_out(x86_op, X86ModRM::MOD_11_DISP_RAX as u8, 0, mc);
+            } else {
+                gen_size += Self::tcg_modrm_32bit_raw_out(x86_op, X86ModRM::MOD_11_DISP_RAX as u8, 0, mc);
+            }
+            gen_size += Self::tcg_out(X86Opcode::POP as u64 + X86TargetRM::RCX as u64, 1, mc);
+            gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8, dest_x86reg as u8, mc);
+        }
+
+        emu.m_x86reg_usage_list[dest_x86reg as usize] = None;
+
+        gen_size
+    }
+
+    fn tcg_gen_bswap_64bit(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize {
+        let dest = tcg.arg0.unwrap();
+        let src1 = tcg.arg1.unwrap();
+
+        assert_eq!(dest.t, TCGvType::TCGTemp);
+        assert_eq!(src1.t, TCGvType::TCGTemp);
+
+        let dest_x86reg = Self::convert_x86_reg(dest.value);
+        let src1_x86reg = Self::convert_x86_reg(src1.value);
+
+        let mut gen_size: usize = pc_address as usize;
+
+        if dest.value != src1.value {
+            gen_size += Self::tcg_modrm_64bit_raw_out(X86Opcode::MOV_GV_EV, X86ModRM::MOD_11_DISP_RAX as u8 + src1_x86reg as u8, dest_x86reg as u8, mc);
+        }
+        // bswap  dest
+        gen_size += Self::tcg_out(0xc8_0f_48 + ((dest_x86reg as u64) << 16), 3, mc);
+
+        emu.m_x86reg_usage_list[dest_x86reg as usize] = None;
+
+        gen_size
+    }
+
+    fn tcg_gen_clmul(emu: &mut EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize {
+        let dest = tcg.arg0.unwrap();
+        let src1 = tcg.arg1.unwrap();
+        let src2 = tcg.arg2.unwrap();
+
+        assert_eq!(dest.t, TCGvType::TCGTemp);
+        assert_eq!(src1.t, TCGvType::TCGTemp);
+        assert_eq!(src2.t, TCGvType::TCGTemp);
+
+        let dest_x86reg = Self::convert_x86_reg(dest.value);
+        let src1_x86reg = Self::convert_x86_reg(src1.value);
+        let src2_x86reg = Self::convert_x86_reg(src2.value);
+
+        let mut gen_size: usize = pc_address as usize;
+
+        let op = tcg.op.unwrap();
+        if !Self::host_has_bitmanip(emu, op) {
+            let func: extern "C" fn(u64, u64) -> u64 = match op {
+                TCGOpcode::CLMUL_64BIT  => bitmanip_clmul_64bit,
+                TCGOpcode::CLMULH_64BIT => bitmanip_clmulh_64bit,
+                TCGOpcode::CLMULR_64BIT => bitmanip_clmulr_64bit,
+                _ => panic!("Unknown carry-less multiply operation {:?}", op),
+            };
+            return gen_size + Self::tcg_gen_call_host_fn(emu, func, dest_x86reg, src1_x86reg, src2_x86reg, mc);
+        }
+
+        // movq   src1,%xmm0
+        gen_size += Self::tcg_out(0x6e_0f_48_66, 4, mc);
+        gen_size += Self::tcg_out(X86ModRM::MOD_11_DISP_RAX as u64 + src1_x86reg as u64, 1, mc);
+        // movq   src2,%xmm1
+        gen_size += Self::tcg_out(0x6e_0f_48_66, 4, mc);
+        gen_size += Self::tcg_out(X86ModRM::MOD_11_DISP_RAX as u64 + (1 << 3) + src2_x86reg as u64, 1, mc);
+        // pclmulqdq $0x0,%xmm1,%xmm0
+        gen_size += Self::tcg_out(0x00_c1_44_3a_0f_66, 6, mc);
+
+        if op == TCGOpcode::CLMULR_64BIT {
+            // movq   %xmm0,%rax
+            gen_size += Self::tcg_out(0x7e_0f_48_66, 4, mc);
+            gen_size += Self::tcg_out(X86ModRM::MOD_11_DISP_RAX as u64, 1, mc);
+        }
+        if op != TCGOpcode::CLMUL_64BIT {
+            // psrldq $0x8,%xmm0
+            gen_size += Self::tcg_out(0x08_d8_73_0f_66, 5, mc);
+        }
+        // movq   %xmm0,dest
+        gen_size += Self::tcg_out(0x7e_0f_48_66, 4, mc);
+        gen_size += Self::tcg_out(X86ModRM::MOD_11_DISP_RAX as u64 + dest_x86reg as u64, 1, mc);
+        if op == TCGOpcode::CLMULR_64BIT {
+            // shld   $0x1,%rax,dest : bits [126:63] of the product
+            gen_size += Self::tcg_modrm_2byte_64bit_raw_out(X86Opcode::SHLD_IMM, X86ModRM::MOD_11_DISP_RAX as u8 + dest_x86reg as u8, X86TargetRM::RAX as u8, mc);
+            gen_size += Self::tcg_out(1, 1, mc);
+        }
+
+        emu.m_x86reg_usage_list[dest_x86reg as usize] = None;
+
+        gen_size
+    }
+
     fn tcg_gen_sign_ext_32_64(_emu: &EmuEnv, pc_address: u64, tcg: &TCGOp, mc: &mut Vec<u8>) -> usize {
         let dest_reg = tcg.arg0.unwrap();
         let source1_reg = tcg.arg1.unwrap();
@@ -2351,3 +2619,44 @@ impl TCG for TCGX86 {
         return gen_size;
     }
 }
+
+// Portable implementation of the bit manipulation operations, used when the host lacks the instruction.
+extern "C" fn bitmanip_clz_64bit(a: u64, _b: u64) -> u64 {
+    a.leading_zeros() as u64
+}
+
+extern "C" fn bitmanip_ctz_64bit(a: u64, _b: u64) -> u64 {
+    a.trailing_zeros() as u64
+}
+
+extern "C" fn bitmanip_cpop_64bit(a: u64, _b: u64) -> u64 {
+    a.count_ones() as u64
+}
+
+extern "C" fn bitmanip_clz_32bit(a: u64, _b: u64) -> u64 {
+    (a as u32).leading_zeros() as u64
+}
+
+extern "C" fn bitmanip_ctz_32bit(a: u64, _b: u64) -> u64 {
+    (a as u32).trailing_zeros() as u64
+}
+
+extern "C" fn bitmanip_cpop_32bit(a: u64, _b: u64) -> u64 {
+    (a as u32).count_ones() as u64
+}
+
+fn bitmanip_clmul_128bit(a: u64, b: u64) -> u128 {
+    (0..64).filter(|i| (b >> i) & 1 != 0).fold(0, |acc, i| acc ^ ((a as u128) << i))
+}
+
+extern "C" fn bitmanip_clmul_64bit(a: u64, b: u64) -> u64 {
+    bitmanip_clmul_128bit(a, b) as u64
+}
+
+extern "C" fn bitmanip_clmulh_64bit(a: u64, b: u64) -> u64 {
+    (bitmanip_clmul_128bit(a, b) >> 64) as u64
+}
+
+extern "C" fn bitmanip_clmulr_64bit(a: u64, b: u64) -> u64 {
+    (bitmanip_clmul_128bit(a, b) >> 63) as u64
+}
@@ -0,0 +1,195 @@
+extern crate dydra;
+
+use dydra::emu_env::MachineStatus;
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+fn elf(name: &str) -> String {
+    format!("{}/tests/virt/{}.elf", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+// Test programs set a bit for each check that passed and report the bits as FAIL code.
+fn checks(name: &str, options: &[&str]) -> u16 {
+    match dydra::run_virt_test(&elf(name), options) {
+        MachineStatus::Fail(code) => code,
+        other => panic!("{} : unexpected machine status {:?}", name, other),
+    }
+}
+
+// Scratch file or directory for host side backends, unique to the test.
+fn scratch(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("dydra-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&path);
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]fn finisher_pass  () { assert_eq!(dydra::run_virt_test(&elf("finisher_pass"), &[]), MachineStatus::Pass); }
+#[test]fn finisher_fail  () { assert_eq!(dydra::run_virt_test(&elf("finisher"), &[]), MachineStatus::Fail(0x42)); }
+#[test]fn finisher_reset () { assert_eq!(dydra::run_virt_test(&elf("finisher_reset"), &[]), MachineStatus::Reset); }
+
+// The process exit status keeps only the low 8 bits of the Fail code.
+#[test]fn finisher_exit_code () {
+    assert_eq!(MachineStatus::Fail(0x42).exit_code(), 0x42);
+    assert_eq!(MachineStatus::Fail(0x100).exit_code(), 1);
+    assert_eq!(MachineStatus::Fail(0).exit_code(), 1);
+    assert_eq!(MachineStatus::Pass.exit_code(), 0);
+}
+
+#[test]fn virtio_blk () {
+    let drive = scratch("blk.img");
+    let mut image = vec![0u8; 4096];
+    image[0] = 0x2a;
+    fs::write(&drive, &image).unwrap();
+    assert_eq!(checks("virtio_blk", &["--drive", drive.to_str().unwrap()]), 0x1ff);
+}
+
+#[test]fn virtio_console () {
+    let socket = scratch("console.sock");
+    let path = socket.clone();
+    // Host side : send the input once the emulator listens, and receive the echo.
+    let client = thread::spawn(move || {
+        let mut stream = loop {
+            match UnixStream::connect(&path) {
+                Ok(stream) => break stream,
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        stream.write_all(b"hi").unwrap();
+        let mut echo = [0u8; 2];
+        stream.read_exact(&mut echo).unwrap();
+        echo
+    });
+    assert_eq!(checks("virtio_console", &["--virtio-console", "socket", "--console-socket", socket.to_str().unwrap()]), 0x7);
+    assert_eq!(&client.join().unwrap(), b"hi");
+}
+
+#[test]fn virtio_rng () { assert_eq!(checks("virtio_rng", &["--virtio-rng", "seeded", "--rng-seed", "1"]), 0xf); }
+
+#[test]fn virtio_9p () {
+    let root = scratch("9p");
+    let outside = scratch("9p-outside");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir(&outside).unwrap();
+    fs::write(root.join("file"), b"hello").unwrap();
+    fs::write(outside.join("secret"), b"secret").unwrap();
+    std::os::unix::fs::symlink(&outside, root.join("out")).unwrap();
+    std::os::unix::fs::symlink(outside.join("secret"), root.join("link")).unwrap();
+    std::os::unix::fs::symlink(outside.join("created"), root.join("dangle")).unwrap();
+    assert_eq!(checks("virtio_9p", &["--virtfs", root.to_str().unwrap()]), 0x3ff);
+    assert!(root.join("new").exists());
+    assert!(!outside.join("created").exists());
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+#[test]fn goldfish_rtc () { assert_eq!(checks("goldfish_rtc", &["--rtc-epoch", "1"]), 0x1f); }
+
+#[test]fn cfi_flash () {
+    let image = scratch("flash.img");
+    assert_eq!(checks("cfi_flash", &["--pflash0", image.to_str().unwrap()]), 0xff);
+    // Short image is extended with erased contents, and keeps what the guest programmed.
+    let data = fs::read(&image).unwrap();
+    assert_eq!(data.len(), 0x200_0000);
+    assert_eq!(&data[0x8..0x10], &[0xff; 8]);
+    assert_eq!(&data[0x10..0x14], &[0xa5; 4]);
+    assert_eq!(&data[0x1ff_fffc..], &[0; 4]);
+    fs::remove_file(&image).unwrap();
+}
+
+#[test]fn sv48_sv57 () { assert_eq!(checks("sv48_sv57", &[]), 0x3f); }
+
+#[test]fn page_walk       () { assert_eq!(checks("page_walk", &[]), 0x1fd7); }
+#[test]fn page_walk_svadu () { assert_eq!(checks("page_walk", &["--svadu"]), 0x1feb); }
+
+#[test]fn precise_trap () { assert_eq!(checks("precise_trap", &[]), 0x1f); }
+
+#[test]fn sfence_asid     () { assert_eq!(checks("sfence_asid", &[]), 0x7ff); }
+#[test]fn sfence_asid_hyp () { assert_eq!(checks("sfence_asid", &["--hypervisor"]), 0x7ff); }
+
+#[test]fn pmp () { assert_eq!(checks("pmp", &[]), 0x3ff); }
+
+#[test]fn napot_pbmt     () { assert_eq!(checks("napot_pbmt", &[]), 0x28); }
+#[test]fn napot_pbmt_ext () { assert_eq!(checks("napot_pbmt", &["--svpbmt", "--svnapot"]), 0x3f); }
+
+#[test]fn hypervisor () { assert_eq!(checks("hypervisor", &["--hypervisor"]), 0x3ff); }
+
+#[test]fn trigger () { assert_eq!(checks("trigger", &[]), 0x7ff); }
+
+// Debugger front end : halt request, single step, register / memory access and software breakpoint.
+#[test]fn debug_halt () {
+    const DCSR: u16 = 0x7b0;
+    const DPC : u16 = 0x7b1;
+    const A0  : u16 = 0x100a;
+    const A2  : u16 = 0x100c;
+    let dcsr_cause = |emu: &mut dydra::emu_env::EmuEnv| (emu.debug_read_register(DCSR).unwrap() >> 6) & 0x7;
+
+    let mut emu = dydra::virt_test_env(&elf("debug"), &[]);
+    emu.debug_halt_request();
+    assert_eq!(emu.run(), MachineStatus::Halted);
+    assert!(emu.debug_halted());
+    assert_eq!(emu.debug_read_register(DPC), Some(0x8000_0000));
+    assert_eq!(dcsr_cause(&mut emu), 3);
+
+    assert_eq!(emu.debug_step(), MachineStatus::Halted);
+    assert_eq!(emu.debug_read_register(A0), Some(1));
+    assert_eq!(emu.debug_read_register(DPC), Some(0x8000_0004));
+    assert_eq!(dcsr_cause(&mut emu), 4);
+    assert!(emu.debug_write_register(A0, 40));
+
+    // System bus access is limited to the devices and DRAM.
+    assert!(emu.debug_write_memory(0x8007_0000, 8, 0x1234));
+    assert_eq!(emu.debug_read_memory(0x8007_0000, 8), Some(0x1234));
+    assert_eq!(emu.debug_read_memory(0x8007_fffc, 8), None);
+    assert_eq!(emu.debug_read_memory(0x8008_0000, 4), None);
+    assert!(!emu.debug_write_memory(0x8007_fffc, 8, 0));
+    assert!(!emu.debug_write_memory(0x4000_0000, 4, 0));
+
+    // ebreak over the nop halts with dcsr.ebreakm.
+    let dcsr = emu.debug_read_register(DCSR).unwrap();
+    assert!(emu.debug_write_register(DCSR, dcsr | (1 << 15)));
+    assert!(emu.debug_write_memory(0x8000_000c, 4, 0x0010_0073));
+    assert_eq!(emu.debug_resume(), MachineStatus::Halted);
+    assert_eq!(emu.debug_read_register(DPC), Some(0x8000_000c));
+    assert_eq!(dcsr_cause(&mut emu), 1);
+    assert_eq!(emu.debug_read_register(A2), Some(42));
+
+    // Restore the nop and run to the end : registers are not accessible while running.
+    assert!(emu.debug_write_memory(0x8000_000c, 4, 0x0000_0013));
+    assert_eq!(emu.debug_resume(), MachineStatus::Fail(0x1234 + 42));
+    assert!(!emu.debug_halted());
+    assert_eq!(emu.debug_read_register(A0), None);
+}
+
+#[test]fn debug_traps () { assert_eq!(checks("debug_traps", &[]), 0xf); }
+
+#[test]fn hpm () { assert_eq!(checks("hpm", &[]), 0x7ff); }
+
+#[test]fn csr () { assert_eq!(checks("csr", &[]), 0x3ff); }
+
+#[test]fn xret () { assert_eq!(checks("xret", &[]), 0xff); }
+
+#[test]fn irq () { assert_eq!(checks("irq", &[]), 0xff); }
+#[test]fn irq_vector_table () { assert_eq!(checks("irq", &["--vector-table"]), 0x1ff); }
+
+#[test]fn bitmanip () { assert_eq!(checks("bitmanip", &[]), 0xffff); }
+#[test]fn bitmanip_no_host () { assert_eq!(checks("bitmanip", &["--no-host-bitmanip"]), 0xffff); }
+
+#[test]fn zc () { assert_eq!(checks("zc", &[]), 0x3f); }
+#[test]fn zc_zcmp () { assert_eq!(checks("zc", &["--zcmp"]), 0x7df); }
+
+#[test]fn fh () { assert_eq!(checks("fh", &[]), 0x3fff); }
+
+#[test]fn fa () { assert_eq!(checks("fa", &[]), 0x7f); }
+
+#[test]fn vector () { assert_eq!(checks("vector", &[]), 0x1fff); }
+
+#[test]fn zk () { assert_eq!(checks("zk", &["--zkr", "seeded", "--rng-seed", "1"]), 0xfff); }
+#[test]fn zk_no_host () { assert_eq!(checks("zk", &["--zkr", "urandom", "--no-host-crypto"]), 0xdff); }
+
+#[test]fn cbo () { assert_eq!(checks("cbo", &[]), 0xff); }
+#[test]fn cbo_block_4k () { assert_eq!(checks("cbo", &["--cbo-block-size", "4096"]), 0xff); }
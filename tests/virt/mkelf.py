@@ -0,0 +1,30 @@
+#!/usr/bin/env python3
+# Assemble a test program with llvm-mc and place its .text at 0x80000000 in a minimal ELF.
+# usage: mkelf.py test.S test.elf
+import os, struct, subprocess, sys, tempfile
+
+src, out = sys.argv[1], sys.argv[2]
+with tempfile.TemporaryDirectory() as tmp:
+    obj, bin = os.path.join(tmp, 't.o'), os.path.join(tmp, 't.bin')
+    subprocess.check_call(['llvm-mc', '-triple=riscv64', '-mattr=+m,+a,+f,+d,+c,+zfh,+zba,+zbb,+zbc,+zbs,+v',
+                           '-filetype=obj', src, '-o', obj])
+    subprocess.check_call(['llvm-objcopy', '-O', 'binary', '--only-section=.text', obj, bin])
+    text = open(bin, 'rb').read()
+
+base = 0x80000000
+shstr = b'\0.text\0.shstrtab\0'
+text_off = 0x1000
+shstr_off = text_off + len(text)
+shoff = (shstr_off + len(shstr) + 7) & ~7
+eh = b'\x7fELF' + bytes([2, 1, 1, 0]) + b'\0' * 8 + \
+     struct.pack('<HHIQQQIHHHHHH', 2, 243, 1, base, 64, shoff, 0, 64, 56, 1, 64, 3, 2)
+ph = struct.pack('<IIQQQQQQ', 1, 5, text_off, base, base, len(text), len(text), 0x1000)
+sh0 = b'\0' * 64
+sh1 = struct.pack('<IIQQQQIIQQ', 1, 1, 6, base, text_off, len(text), 0, 0, 4, 0)
+sh2 = struct.pack('<IIQQQQIIQQ', 7, 3, 0, 0, shstr_off, len(shstr), 0, 0, 1, 0)
+buf = bytearray(eh + ph)
+buf += b'\0' * (text_off - len(buf))
+buf += text + shstr
+buf += b'\0' * (shoff - len(buf))
+buf += sh0 + sh1 + sh2
+open(out, 'wb').write(buf)